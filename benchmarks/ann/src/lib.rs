@@ -0,0 +1,519 @@
+//! ANN Benchmark
+//!
+//! Benchmarks approximate nearest neighbor search on a Lance dataset of
+//! generated vectors across the vector index types Lance offers:
+//! - IVF_FLAT
+//! - IVF_PQ
+//! - IVF_HNSW_SQ (HNSW graphs within IVF partitions)
+//!
+//! Every index is searched with each `--nprobes` (and, for HNSW, each `--ef`)
+//! and recall@k is computed against exact neighbors found by brute force (see
+//! [`recall`]). The searches of an index that no other search beats on both
+//! recall and latency form its latency-vs-recall frontier, which is printed
+//! per index and across all of them.
+//!
+//! With `--append-fraction`, new vectors are appended after each index is
+//! built and every search is repeated before and after `optimize_indices`
+//! (see [`stale`]), showing what an index that lags behind its data costs.
+//! With `--maintenance-rounds`, vectors are instead appended in rounds with
+//! `optimize_indices` every few of them (see [`maintenance`]), tracking
+//! search latency and maintenance time as the dataset keeps growing.
+//!
+//! Runs as `lance-bench ann`.
+
+use anyhow::Result;
+use arrow::array::{AsArray, RecordBatchIterator};
+use arrow::datatypes::UInt64Type;
+use arrow::record_batch::RecordBatchReader;
+use arrow_array::Float32Array;
+use bench_core::cli::CommonArgs;
+use bench_core::data::{create_schema, generate_vector_batch, vector_for};
+use bench_core::stats::compute_statistics;
+use clap::{Parser, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use lance::index::vector::VectorIndexParams;
+use lance_index::vector::hnsw::builder::HnswBuildParams;
+use lance_index::vector::ivf::IvfBuildParams;
+use lance_index::vector::sq::builder::SQBuildParams;
+use lance_index::{DatasetIndexExt, IndexType};
+use lance_linalg::distance::DistanceType;
+use std::path::Path;
+use std::time::Instant;
+
+mod maintenance;
+pub mod recall;
+pub mod results;
+mod stale;
+
+use results::{IndexResult, SearchResult};
+
+/// Mixed into the seed for query vectors so they aren't dataset rows.
+const QUERY_SALT: u64 = 0x5EED_0F_0AE5;
+
+/// Rows per generated batch when writing the dataset.
+const BATCH_SIZE: usize = 10_000;
+
+/// Distance metric of the index and of the exact neighbors.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Distance {
+    L2,
+    Cosine,
+    Dot,
+}
+
+impl Distance {
+    fn distance_type(self) -> DistanceType {
+        match self {
+            Distance::L2 => DistanceType::L2,
+            Distance::Cosine => DistanceType::Cosine,
+            Distance::Dot => DistanceType::Dot,
+        }
+    }
+}
+
+/// Vector index types to benchmark.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IndexKind {
+    IvfFlat,
+    IvfPq,
+    IvfHnswSq,
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "ann-benchmark")]
+#[command(about = "Benchmark vector search latency and recall across index types")]
+pub struct Config {
+    /// URI of the Lance dataset, rewritten on every run
+    #[arg(long, default_value = "/tmp/ann-dataset")]
+    pub dataset_uri: String,
+
+    /// Number of vectors in the dataset
+    #[arg(long, default_value_t = 100_000)]
+    pub num_vectors: usize,
+
+    /// Dimensionality of each vector
+    #[arg(long, default_value_t = 128)]
+    pub vector_dim: usize,
+
+    /// Number of query vectors searched per configuration
+    #[arg(long, default_value_t = 100)]
+    pub num_queries: usize,
+
+    /// Neighbors returned per query, and the k of recall@k
+    #[arg(short, long, default_value_t = 10)]
+    pub k: usize,
+
+    /// Distance metric
+    #[arg(long, value_enum, default_value = "l2")]
+    pub distance: Distance,
+
+    /// Index types to benchmark (comma-separated)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "ivf-flat,ivf-pq,ivf-hnsw-sq"
+    )]
+    pub indexes: Vec<IndexKind>,
+
+    /// IVF partitions (default: sqrt of the number of vectors)
+    #[arg(long)]
+    pub num_partitions: Option<usize>,
+
+    /// PQ sub-vectors (default: dimension / 16)
+    #[arg(long)]
+    pub num_sub_vectors: Option<usize>,
+
+    /// Partitions probed per query (comma-separated), one search each
+    #[arg(long, value_delimiter = ',', default_value = "1,5,10,20,50")]
+    pub nprobes: Vec<usize>,
+
+    /// HNSW search breadths (comma-separated), searched with each --nprobes
+    #[arg(long, value_delimiter = ',', default_value = "50,100,200")]
+    pub ef: Vec<usize>,
+
+    /// Re-rank this many times k candidates with the full vectors
+    #[arg(long)]
+    pub refine_factor: Option<u32>,
+
+    /// After building each index, append this fraction of --num-vectors new
+    /// vectors and search again before and after optimize_indices
+    #[arg(long)]
+    pub append_fraction: Option<f64>,
+
+    /// After building each index, run this many rounds of appending vectors
+    /// and searching, optimizing the index every --optimize-every rounds
+    ///
+    /// Searches use the first --nprobes (and --ef).
+    #[arg(long)]
+    pub maintenance_rounds: Option<usize>,
+
+    /// Vectors appended per maintenance round (default: 1% of --num-vectors)
+    #[arg(long)]
+    pub maintenance_append: Option<usize>,
+
+    /// Maintenance rounds between optimize_indices runs
+    #[arg(long, default_value_t = 5)]
+    pub optimize_every: usize,
+
+    #[command(flatten)]
+    pub common: CommonArgs,
+}
+
+impl Config {
+    fn num_partitions(&self) -> usize {
+        self.num_partitions
+            .unwrap_or_else(|| ((self.num_vectors as f64).sqrt() as usize).max(1))
+    }
+
+    /// Vectors appended after each index is built.
+    fn appended_vectors(&self) -> usize {
+        self.append_fraction
+            .map_or(0, |f| (self.num_vectors as f64 * f).round() as usize)
+    }
+
+    /// Vectors appended per maintenance round.
+    fn maintenance_append(&self) -> usize {
+        self.maintenance_append
+            .unwrap_or((self.num_vectors / 100).max(1))
+    }
+
+    fn num_sub_vectors(&self) -> usize {
+        self.num_sub_vectors
+            .unwrap_or((self.vector_dim / 16).max(1))
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.common.resume {
+            anyhow::bail!("--resume is not supported by the ANN benchmark");
+        }
+        if self.num_vectors == 0 || self.num_queries == 0 || self.k == 0 {
+            anyhow::bail!("--num-vectors, --num-queries and -k must be positive");
+        }
+        if self.k > self.num_vectors {
+            anyhow::bail!(
+                "-k ({}) exceeds --num-vectors ({})",
+                self.k,
+                self.num_vectors
+            );
+        }
+        if self.vector_dim % self.num_sub_vectors() != 0 {
+            anyhow::bail!(
+                "--vector-dim ({}) must be a multiple of --num-sub-vectors ({})",
+                self.vector_dim,
+                self.num_sub_vectors()
+            );
+        }
+        if self
+            .append_fraction
+            .is_some_and(|f| f <= 0.0 || self.appended_vectors() == 0)
+        {
+            anyhow::bail!("--append-fraction must append at least one vector");
+        }
+        if self.maintenance_rounds == Some(0)
+            || self.maintenance_append == Some(0)
+            || self.optimize_every == 0
+        {
+            anyhow::bail!(
+                "--maintenance-rounds, --maintenance-append and --optimize-every must be positive"
+            );
+        }
+        if self.num_partitions() > self.num_vectors {
+            anyhow::bail!(
+                "--num-partitions ({}) exceeds --num-vectors ({})",
+                self.num_partitions(),
+                self.num_vectors
+            );
+        }
+        Ok(())
+    }
+
+    /// Index parameters and description of `kind`.
+    fn index_params(&self, kind: IndexKind) -> (VectorIndexParams, String) {
+        let partitions = self.num_partitions();
+        let distance = self.distance.distance_type();
+        match kind {
+            IndexKind::IvfFlat => (
+                VectorIndexParams::ivf_flat(partitions, distance),
+                format!("IVF_FLAT(partitions={})", partitions),
+            ),
+            IndexKind::IvfPq => (
+                VectorIndexParams::ivf_pq(partitions, 8, self.num_sub_vectors(), distance, 50),
+                format!(
+                    "IVF_PQ(partitions={},sub_vectors={})",
+                    partitions,
+                    self.num_sub_vectors()
+                ),
+            ),
+            IndexKind::IvfHnswSq => (
+                VectorIndexParams::with_ivf_hnsw_sq_params(
+                    distance,
+                    IvfBuildParams::new(partitions),
+                    HnswBuildParams::default(),
+                    SQBuildParams::default(),
+                ),
+                format!("IVF_HNSW_SQ(partitions={})", partitions),
+            ),
+        }
+    }
+
+    /// HNSW search breadths to sweep for `kind`.
+    fn ef_values(&self, kind: IndexKind) -> Vec<Option<usize>> {
+        match kind {
+            IndexKind::IvfHnswSq => self.ef.iter().copied().map(Some).collect(),
+            _ => vec![None],
+        }
+    }
+}
+
+fn print_plan(config: &Config) {
+    println!("{}", "=".repeat(60));
+    println!("ANN Benchmark");
+    println!("{}", "=".repeat(60));
+    println!("Dataset URI: {}", config.dataset_uri);
+    println!(
+        "Vectors: {} x {} dimensions, {:?} distance",
+        config.num_vectors, config.vector_dim, config.distance
+    );
+    println!(
+        "Queries: {} per search, k = {}",
+        config.num_queries, config.k
+    );
+    println!("Indexes: {:?}", config.indexes);
+    println!("nprobes: {:?}", config.nprobes);
+    if config.indexes.contains(&IndexKind::IvfHnswSq) {
+        println!("ef: {:?}", config.ef);
+    }
+    if let Some(refine) = config.refine_factor {
+        println!("Refine factor: {}", refine);
+    }
+    if config.append_fraction.is_some() {
+        println!(
+            "Appends: {} vectors after each index build",
+            config.appended_vectors()
+        );
+    }
+    if let Some(rounds) = config.maintenance_rounds {
+        println!(
+            "Maintenance: {} rounds of {} vectors, optimize_indices every {}",
+            rounds,
+            config.maintenance_append(),
+            config.optimize_every
+        );
+    }
+    println!("{}", "=".repeat(60));
+}
+
+/// Write the generated vectors to `config.dataset_uri`, replacing any dataset there.
+async fn write_dataset(config: &Config) -> Result<Dataset> {
+    println!(
+        "\nWriting {} vectors to {}",
+        config.num_vectors, config.dataset_uri
+    );
+    let params = WriteParams {
+        mode: WriteMode::Overwrite,
+        ..Default::default()
+    };
+    let reader = vector_batches(config, 0..config.num_vectors);
+    Ok(Dataset::write(reader, &config.dataset_uri, Some(params)).await?)
+}
+
+/// Generated vectors with the ids in `ids`.
+fn vector_batches(
+    config: &Config,
+    ids: std::ops::Range<usize>,
+) -> impl RecordBatchReader + Send + 'static {
+    let schema = create_schema(config.vector_dim);
+    let (dim, seed) = (config.vector_dim, config.common.seed);
+    let end = ids.end;
+    let batches = ids.step_by(BATCH_SIZE).map({
+        let schema = schema.clone();
+        move |start| {
+            let rows = BATCH_SIZE.min(end - start);
+            generate_vector_batch(schema.clone(), start, rows, dim, seed)
+        }
+    });
+    RecordBatchIterator::new(batches, schema)
+}
+
+/// Total size of the files under `path`, or 0 if it isn't a local directory.
+fn dir_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Run each query once, returning the ids found and each query's latency.
+async fn search(
+    dataset: &Dataset,
+    config: &Config,
+    queries: &[Vec<f32>],
+    nprobes: usize,
+    ef: Option<usize>,
+) -> Result<(Vec<Vec<u64>>, Vec<f64>)> {
+    let mut found = Vec::with_capacity(queries.len());
+    let mut latencies = Vec::with_capacity(queries.len());
+    for query in queries {
+        let query = Float32Array::from(query.clone());
+        let start = Instant::now();
+        let mut scanner = dataset.scan();
+        scanner
+            .project(&["id"])?
+            .nearest("vector", &query, config.k)?
+            .nprobes(nprobes);
+        if let Some(ef) = ef {
+            scanner.ef(ef);
+        }
+        if let Some(refine) = config.refine_factor {
+            scanner.refine(refine);
+        }
+        let batch = scanner.try_into_batch().await?;
+        latencies.push(start.elapsed().as_secs_f64());
+
+        let ids = batch
+            .column_by_name("id")
+            .ok_or_else(|| anyhow::anyhow!("Search results have no id column"))?
+            .as_primitive::<UInt64Type>();
+        found.push(ids.values().to_vec());
+    }
+    Ok((found, latencies))
+}
+
+/// Build the `kind` index and run every search configuration against it.
+async fn bench_index(
+    dataset: &mut Dataset,
+    config: &Config,
+    kind: IndexKind,
+    queries: &[Vec<f32>],
+    truth: &[Vec<u64>],
+) -> Result<IndexResult> {
+    let (params, index) = config.index_params(kind);
+    println!("\nBuilding {}", index);
+    let start = Instant::now();
+    dataset
+        .create_index_builder(&["vector"], IndexType::Vector, &params)
+        .replace(true)
+        .await?;
+    let build_time = start.elapsed().as_secs_f64();
+    let index_bytes = dir_size(&Path::new(&config.dataset_uri).join("_indices"));
+
+    let searches = sweep(dataset, config, kind, queries, truth).await?;
+    let appends = match config.append_fraction {
+        Some(_) => Some(stale::bench_appends(dataset, config, kind, queries).await?),
+        None => None,
+    };
+    let maintenance = match config.maintenance_rounds {
+        Some(_) => Some(maintenance::bench_maintenance(dataset, config, kind, queries).await?),
+        None => None,
+    };
+
+    Ok(IndexResult {
+        index,
+        build_time,
+        index_bytes,
+        searches,
+        appends,
+        maintenance,
+    })
+}
+
+/// Run every search configuration of `kind` against the current index.
+async fn sweep(
+    dataset: &Dataset,
+    config: &Config,
+    kind: IndexKind,
+    queries: &[Vec<f32>],
+    truth: &[Vec<u64>],
+) -> Result<Vec<SearchResult>> {
+    let ef_values = config.ef_values(kind);
+    let pb = ProgressBar::new((config.nprobes.len() * ef_values.len()) as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("  [{bar:40}] {pos}/{len} searches ({eta} remaining)")
+            .unwrap(),
+    );
+
+    let mut searches = Vec::new();
+    for &nprobes in &config.nprobes {
+        for &ef in &ef_values {
+            // Warm up the index cache so every configuration is measured hot
+            search(dataset, config, queries, nprobes, ef).await?;
+
+            let start = Instant::now();
+            let (found, latencies) = search(dataset, config, queries, nprobes, ef).await?;
+            let elapsed = start.elapsed().as_secs_f64();
+            let recall = found
+                .iter()
+                .zip(truth)
+                .map(|(found, truth)| recall::recall(found, truth))
+                .sum::<f64>()
+                / queries.len() as f64;
+            searches.push(SearchResult {
+                nprobes,
+                ef,
+                refine_factor: config.refine_factor,
+                recall,
+                stats: compute_statistics(&latencies),
+                throughput: queries.len() as f64 / elapsed,
+            });
+            pb.inc(1);
+        }
+    }
+    pb.finish_and_clear();
+    Ok(searches)
+}
+
+async fn run_async(config: &Config) -> Result<Vec<IndexResult>> {
+    let mut dataset = write_dataset(config).await?;
+
+    let queries: Vec<Vec<f32>> = (0..config.num_queries as u64)
+        .map(|i| vector_for(config.common.seed ^ QUERY_SALT, i, config.vector_dim))
+        .collect();
+    println!("Computing exact neighbors of {} queries", queries.len());
+    let start = Instant::now();
+    let truth = recall::exact_neighbors(
+        config.common.seed,
+        config.num_vectors,
+        config.vector_dim,
+        &queries,
+        config.k,
+        config.distance,
+    );
+    println!("  Done in {:.2}s", start.elapsed().as_secs_f64());
+
+    let mut results = Vec::new();
+    for &kind in &config.indexes {
+        let result = bench_index(&mut dataset, config, kind, &queries, &truth).await?;
+        results::print_index_result(&result, config.k);
+        results::print_append_result(&result, config.num_vectors);
+        results::print_maintenance_result(&result);
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Run the benchmark described by `config`, returning the results of every index.
+pub fn run(config: Config) -> Result<Vec<IndexResult>> {
+    config.validate()?;
+    print_plan(&config);
+    if config.common.dry_run {
+        println!("\nDry run: nothing written or searched");
+        return Ok(Vec::new());
+    }
+
+    let runtime = tokio::runtime::Runtime::new()?;
+    let results = runtime.block_on(run_async(&config))?;
+    results::print_frontiers(&results, config.k);
+
+    if let Some(output) = &config.common.output {
+        let distance = format!("{:?}", config.distance).to_lowercase();
+        results::write_results(output, config.k, &distance, &results)?;
+        println!("\nResults written to {}", output.display());
+    }
+    Ok(results)
+}
@@ -0,0 +1,110 @@
+//! Keeping an index up to date while data keeps arriving.
+//!
+//! Models a dataset that is served while it grows: each round appends a
+//! batch of vectors and searches, and every few rounds `optimize_indices`
+//! folds the appended vectors into the index. Search latency and recall
+//! drift as unindexed rows pile up and recover after each optimization,
+//! while the optimizations themselves cost time. Once done, the dataset is
+//! restored to the version the index was built on so the next index starts
+//! from the same data.
+
+use anyhow::Result;
+use bench_core::stats::compute_statistics;
+use lance::Dataset;
+use lance_index::optimize::OptimizeOptions;
+use lance_index::DatasetIndexExt;
+use std::time::Instant;
+
+use crate::results::{MaintenanceResult, RoundResult};
+use crate::{recall, search, vector_batches, Config, IndexKind};
+
+/// Run `config.maintenance_rounds` rounds of appends, optimizations and searches.
+pub(crate) async fn bench_maintenance(
+    dataset: &mut Dataset,
+    config: &Config,
+    kind: IndexKind,
+    queries: &[Vec<f32>],
+) -> Result<MaintenanceResult> {
+    let rounds = config.maintenance_rounds.unwrap_or(0);
+    let per_round = config.maintenance_append();
+    let nprobes = config.nprobes[0];
+    let ef = config.ef_values(kind)[0];
+    let version = dataset.version().version;
+    println!(
+        "  {} rounds of {} appended vectors, optimizing every {}",
+        rounds, per_round, config.optimize_every
+    );
+
+    let mut total = config.num_vectors;
+    let mut unindexed = 0;
+    let mut results = Vec::with_capacity(rounds);
+    for round in 1..=rounds {
+        let start = Instant::now();
+        dataset
+            .append(vector_batches(config, total..total + per_round), None)
+            .await?;
+        let append_time = start.elapsed().as_secs_f64();
+        total += per_round;
+        unindexed += per_round;
+
+        let optimize_time = if round % config.optimize_every == 0 {
+            let start = Instant::now();
+            dataset
+                .optimize_indices(&OptimizeOptions::default())
+                .await?;
+            unindexed = 0;
+            Some(start.elapsed().as_secs_f64())
+        } else {
+            None
+        };
+
+        let truth = recall::exact_neighbors(
+            config.common.seed,
+            total,
+            config.vector_dim,
+            queries,
+            config.k,
+            config.distance,
+        );
+        // Warm up, as every other search is measured hot
+        search(dataset, config, queries, nprobes, ef).await?;
+        let (found, latencies) = search(dataset, config, queries, nprobes, ef).await?;
+        let recall = found
+            .iter()
+            .zip(&truth)
+            .map(|(found, truth)| recall::recall(found, truth))
+            .sum::<f64>()
+            / queries.len() as f64;
+
+        let result = RoundResult {
+            round,
+            total_vectors: total,
+            unindexed,
+            append_time,
+            optimize_time,
+            recall,
+            stats: compute_statistics(&latencies),
+        };
+        println!(
+            "  Round {}: {} unindexed, recall {:.4}, p50 {:.3} ms{}",
+            round,
+            unindexed,
+            result.recall,
+            result.stats.p50 * 1000.0,
+            optimize_time.map_or(String::new(), |t| format!(", optimized in {:.2}s", t))
+        );
+        results.push(result);
+    }
+
+    let mut original = dataset.checkout_version(version).await?;
+    original.restore().await?;
+    *dataset = original;
+
+    Ok(MaintenanceResult {
+        nprobes,
+        ef,
+        appended_per_round: per_round,
+        optimize_every: config.optimize_every,
+        rounds: results,
+    })
+}
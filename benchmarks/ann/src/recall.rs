@@ -0,0 +1,136 @@
+//! Exact nearest neighbors and recall.
+//!
+//! The ground truth is computed by brute force over the generated vectors
+//! themselves (regenerated from the seed), not by asking Lance for a flat
+//! search, so a bug in Lance's distance computation can't hide in both sides.
+
+use bench_core::data::vector_for;
+use std::collections::BinaryHeap;
+
+use crate::Distance;
+
+/// A candidate neighbor, ordered by distance so the heap's top is the farthest.
+#[derive(PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: u64,
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.distance.total_cmp(&other.distance)
+    }
+}
+
+/// Distance between `a` and `b` for ranking: smaller is closer.
+///
+/// Only the order matters, so L2 skips the square root and cosine and dot
+/// are negated similarities.
+pub fn rank_distance(distance: Distance, a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    match distance {
+        Distance::L2 => a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum(),
+        Distance::Dot => -dot,
+        Distance::Cosine => {
+            let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            -dot / (norm(a) * norm(b))
+        }
+    }
+}
+
+/// The `k` vectors among `ids` closest to each query, closest first.
+fn nearest_in(
+    seed: u64,
+    ids: std::ops::Range<u64>,
+    dim: usize,
+    queries: &[Vec<f32>],
+    k: usize,
+    distance: Distance,
+) -> Vec<Vec<Candidate>> {
+    let mut heaps: Vec<BinaryHeap<Candidate>> = queries
+        .iter()
+        .map(|_| BinaryHeap::with_capacity(k + 1))
+        .collect();
+    for id in ids {
+        let vector = vector_for(seed, id, dim);
+        for (query, heap) in queries.iter().zip(&mut heaps) {
+            let distance = rank_distance(distance, query, &vector);
+            if heap.len() < k {
+                heap.push(Candidate { distance, id });
+            } else if heap.peek().is_some_and(|far| distance < far.distance) {
+                heap.pop();
+                heap.push(Candidate { distance, id });
+            }
+        }
+    }
+    heaps.into_iter().map(|h| h.into_sorted_vec()).collect()
+}
+
+/// Exact `k` nearest neighbors of each query among the dataset's
+/// `num_vectors` generated vectors, closest first.
+pub fn exact_neighbors(
+    seed: u64,
+    num_vectors: usize,
+    dim: usize,
+    queries: &[Vec<f32>],
+    k: usize,
+    distance: Distance,
+) -> Vec<Vec<u64>> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk = num_vectors.div_ceil(threads).max(1) as u64;
+    let partials: Vec<Vec<Vec<Candidate>>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_vectors as u64)
+            .step_by(chunk as usize)
+            .map(|start| {
+                let end = (start + chunk).min(num_vectors as u64);
+                scope.spawn(move || nearest_in(seed, start..end, dim, queries, k, distance))
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    (0..queries.len())
+        .map(|q| {
+            let mut merged: Vec<&Candidate> = partials.iter().flat_map(|p| &p[q]).collect();
+            merged.sort_by(|a, b| a.cmp(b));
+            merged.iter().take(k).map(|c| c.id).collect()
+        })
+        .collect()
+}
+
+/// Fraction of `truth` found in `found`.
+pub fn recall(found: &[u64], truth: &[u64]) -> f64 {
+    if truth.is_empty() {
+        return 1.0;
+    }
+    let hits = truth.iter().filter(|id| found.contains(id)).count();
+    hits as f64 / truth.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exact_neighbors_finds_itself() {
+        let (seed, dim) = (7, 8);
+        let queries = vec![vector_for(seed, 42, dim), vector_for(seed, 3, dim)];
+        let truth = exact_neighbors(seed, 100, dim, &queries, 5, Distance::L2);
+        assert_eq!(truth[0][0], 42);
+        assert_eq!(truth[1][0], 3);
+        assert_eq!(truth[0].len(), 5);
+    }
+
+    #[test]
+    fn test_recall() {
+        assert_eq!(recall(&[1, 2, 3, 4], &[1, 2, 5, 6]), 0.5);
+    }
+}
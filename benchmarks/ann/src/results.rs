@@ -0,0 +1,300 @@
+//! Per-index search results and the latency-vs-recall frontier.
+
+use anyhow::Result;
+use bench_core::results::write_results as write_results_file;
+use bench_core::stats::Statistics;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// One search configuration of an index.
+#[derive(Serialize, Deserialize)]
+pub struct SearchResult {
+    /// IVF partitions probed per query
+    pub nprobes: usize,
+    /// HNSW search breadth, for HNSW indices
+    pub ef: Option<usize>,
+    /// Candidates re-ranked with full vectors, as a multiple of k
+    pub refine_factor: Option<u32>,
+    /// Mean recall@k against the exact neighbors
+    pub recall: f64,
+    /// Latency of a single query (seconds)
+    pub stats: Statistics,
+    /// Queries per second, one query at a time
+    pub throughput: f64,
+}
+
+/// An index configuration and its searches.
+#[derive(Serialize, Deserialize)]
+pub struct IndexResult {
+    /// Index type and build parameters, e.g. "IVF_PQ(partitions=256,sub_vectors=8)"
+    pub index: String,
+    /// Time to build the index (seconds)
+    pub build_time: f64,
+    /// Size of the index files
+    pub index_bytes: u64,
+    pub searches: Vec<SearchResult>,
+    /// Searches after appending vectors, with `--append-fraction`
+    #[serde(default)]
+    pub appends: Option<AppendResult>,
+    /// Rounds of appends and optimizations, with `--maintenance-rounds`
+    #[serde(default)]
+    pub maintenance: Option<MaintenanceResult>,
+}
+
+/// The same searches after appending vectors the index doesn't cover.
+#[derive(Serialize, Deserialize)]
+pub struct AppendResult {
+    /// Vectors appended after the index was built
+    pub appended: usize,
+    /// Searches with the appended vectors unindexed
+    pub stale: Vec<SearchResult>,
+    /// Time of `optimize_indices` (seconds)
+    pub optimize_time: f64,
+    /// Searches after `optimize_indices`
+    pub optimized: Vec<SearchResult>,
+}
+
+/// Rounds of appends and periodic `optimize_indices` after the index was built.
+#[derive(Serialize, Deserialize)]
+pub struct MaintenanceResult {
+    /// IVF partitions probed by every search
+    pub nprobes: usize,
+    /// HNSW search breadth of every search, for HNSW indices
+    pub ef: Option<usize>,
+    pub appended_per_round: usize,
+    /// Rounds between optimizations
+    pub optimize_every: usize,
+    pub rounds: Vec<RoundResult>,
+}
+
+/// One round: an append, an optimization every few rounds, then searches.
+#[derive(Serialize, Deserialize)]
+pub struct RoundResult {
+    /// Round number, from 1
+    pub round: usize,
+    /// Vectors in the dataset after the append
+    pub total_vectors: usize,
+    /// Vectors appended since the index was last optimized (or built)
+    pub unindexed: usize,
+    /// Time of the append (seconds)
+    pub append_time: f64,
+    /// Time of `optimize_indices`, in rounds that ran it (seconds)
+    pub optimize_time: Option<f64>,
+    /// Mean recall@k against the exact neighbors of every vector so far
+    pub recall: f64,
+    /// Latency of a single query (seconds)
+    pub stats: Statistics,
+}
+
+/// Fields of the results file written with `--output`, next to the results.
+#[derive(Serialize)]
+struct Metadata<'a> {
+    k: usize,
+    distance: &'a str,
+}
+
+/// Write all index results to `path` as JSON.
+pub fn write_results(path: &Path, k: usize, distance: &str, results: &[IndexResult]) -> Result<()> {
+    write_results_file(path, "ann", Metadata { k, distance }, results)
+}
+
+/// Which searches are on the latency-vs-recall frontier.
+///
+/// A search is on the frontier unless another one has at least its recall
+/// at no more than its p50 latency, and is strictly better at one of them.
+pub fn frontier(searches: &[SearchResult]) -> Vec<bool> {
+    searches
+        .iter()
+        .map(|s| {
+            !searches.iter().any(|other| {
+                other.recall >= s.recall
+                    && other.stats.p50 <= s.stats.p50
+                    && (other.recall > s.recall || other.stats.p50 < s.stats.p50)
+            })
+        })
+        .collect()
+}
+
+/// Print every search of an index, marking the frontier.
+pub fn print_index_result(result: &IndexResult, k: usize) {
+    println!(
+        "\n{}: built in {:.2}s, {:.1} MB",
+        result.index,
+        result.build_time,
+        result.index_bytes as f64 / 1_000_000.0
+    );
+    let recall_header = format!("Recall@{}", k);
+    println!(
+        "  {:>8} {:>6} {:>7} {:>10} {:>10} {:>10} {:>10} {:>9}",
+        "nprobes", "ef", "refine", recall_header, "p50(ms)", "p99(ms)", "QPS", "Frontier"
+    );
+    println!("  {}", "-".repeat(78));
+    let on_frontier = frontier(&result.searches);
+    for (search, on_frontier) in result.searches.iter().zip(on_frontier) {
+        let optional = |v: Option<String>| v.unwrap_or_else(|| "-".to_string());
+        println!(
+            "  {:>8} {:>6} {:>7} {:>10.4} {:>10.3} {:>10.3} {:>10.1} {:>9}",
+            search.nprobes,
+            optional(search.ef.map(|ef| ef.to_string())),
+            optional(search.refine_factor.map(|r| r.to_string())),
+            search.recall,
+            search.stats.p50 * 1000.0,
+            search.stats.p99 * 1000.0,
+            search.throughput,
+            if on_frontier { "*" } else { "" }
+        );
+    }
+}
+
+/// Print how each search changed once vectors were appended, and after optimizing.
+pub fn print_append_result(result: &IndexResult, num_vectors: usize) {
+    let Some(appends) = &result.appends else {
+        return;
+    };
+    println!(
+        "\n{} after appending {} vectors ({:.1}% unindexed), optimize_indices took {:.2}s",
+        result.index,
+        appends.appended,
+        appends.appended as f64 * 100.0 / (num_vectors + appends.appended) as f64,
+        appends.optimize_time
+    );
+    println!("  {:>8} {:>6} {:>28} {:>28}", "", "", "Recall", "p50(ms)");
+    println!(
+        "  {:>8} {:>6} {:>8} {:>9} {:>9} {:>8} {:>9} {:>9}",
+        "nprobes", "ef", "indexed", "stale", "optimized", "indexed", "stale", "optimized"
+    );
+    println!("  {}", "-".repeat(76));
+    for ((indexed, stale), optimized) in result
+        .searches
+        .iter()
+        .zip(&appends.stale)
+        .zip(&appends.optimized)
+    {
+        println!(
+            "  {:>8} {:>6} {:>8.4} {:>9.4} {:>9.4} {:>8.3} {:>9.3} {:>9.3}",
+            indexed.nprobes,
+            indexed.ef.map_or("-".to_string(), |ef| ef.to_string()),
+            indexed.recall,
+            stale.recall,
+            optimized.recall,
+            indexed.stats.p50 * 1000.0,
+            stale.stats.p50 * 1000.0,
+            optimized.stats.p50 * 1000.0
+        );
+    }
+}
+
+/// Print every maintenance round, with the totals of appending and optimizing.
+pub fn print_maintenance_result(result: &IndexResult) {
+    let Some(maintenance) = &result.maintenance else {
+        return;
+    };
+    println!(
+        "\n{} with {} vectors appended per round, optimized every {} rounds (nprobes {}{})",
+        result.index,
+        maintenance.appended_per_round,
+        maintenance.optimize_every,
+        maintenance.nprobes,
+        maintenance
+            .ef
+            .map_or(String::new(), |ef| format!(", ef {}", ef))
+    );
+    println!(
+        "  {:>6} {:>10} {:>10} {:>11} {:>13} {:>8} {:>10} {:>10}",
+        "Round", "Vectors", "Unindexed", "Append(s)", "Optimize(s)", "Recall", "p50(ms)", "p99(ms)"
+    );
+    println!("  {}", "-".repeat(85));
+    for round in &maintenance.rounds {
+        println!(
+            "  {:>6} {:>10} {:>10} {:>11.3} {:>13} {:>8.4} {:>10.3} {:>10.3}",
+            round.round,
+            round.total_vectors,
+            round.unindexed,
+            round.append_time,
+            round
+                .optimize_time
+                .map_or("-".to_string(), |t| format!("{:.3}", t)),
+            round.recall,
+            round.stats.p50 * 1000.0,
+            round.stats.p99 * 1000.0
+        );
+    }
+    let append_total: f64 = maintenance.rounds.iter().map(|r| r.append_time).sum();
+    let optimize_total: f64 = maintenance
+        .rounds
+        .iter()
+        .filter_map(|r| r.optimize_time)
+        .sum();
+    println!(
+        "  Appending took {:.2}s and optimizing {:.2}s in total",
+        append_total, optimize_total
+    );
+}
+
+/// Print the frontier of every index in one table, ordered by recall.
+pub fn print_frontiers(results: &[IndexResult], k: usize) {
+    let mut points: Vec<(&IndexResult, &SearchResult)> = Vec::new();
+    for result in results {
+        let on_frontier = frontier(&result.searches);
+        points.extend(
+            result
+                .searches
+                .iter()
+                .zip(on_frontier)
+                .filter(|(_, on)| *on)
+                .map(|(s, _)| (result, s)),
+        );
+    }
+    points.sort_by(|a, b| a.1.recall.total_cmp(&b.1.recall));
+
+    println!("\n{}", "=".repeat(60));
+    println!("Latency vs recall@{} frontier per index", k);
+    println!("{}", "=".repeat(60));
+    println!(
+        "{:<40} {:>8} {:>6} {:>7} {:>10} {:>10}",
+        "Index", "nprobes", "ef", "refine", "Recall", "p50(ms)"
+    );
+    println!("{}", "-".repeat(86));
+    for (result, search) in points {
+        println!(
+            "{:<40} {:>8} {:>6} {:>7} {:>10.4} {:>10.3}",
+            result.index,
+            search.nprobes,
+            search.ef.map_or("-".to_string(), |ef| ef.to_string()),
+            search
+                .refine_factor
+                .map_or("-".to_string(), |r| r.to_string()),
+            search.recall,
+            search.stats.p50 * 1000.0
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bench_core::stats::compute_statistics;
+
+    fn search(recall: f64, p50: f64) -> SearchResult {
+        SearchResult {
+            nprobes: 1,
+            ef: None,
+            refine_factor: None,
+            recall,
+            stats: compute_statistics(&[p50]),
+            throughput: 1.0 / p50,
+        }
+    }
+
+    #[test]
+    fn test_frontier() {
+        let searches = [
+            search(0.80, 0.001),
+            search(0.90, 0.002),
+            // Slower than the 0.90 search for less recall
+            search(0.85, 0.003),
+            search(0.99, 0.010),
+        ];
+        assert_eq!(frontier(&searches), vec![true, true, false, true]);
+    }
+}
@@ -0,0 +1,65 @@
+//! Searching an index that lags behind appended data.
+//!
+//! Rows appended after an index is built aren't in it: searches scan them
+//! with brute force and merge the results, so recall holds up but latency
+//! grows with the unindexed share. `optimize_indices` adds the new rows to
+//! the index. Each index is searched with the appended rows unindexed and
+//! again after optimizing, then the dataset is restored to the version it
+//! was built on so the next index starts from the same data.
+
+use anyhow::Result;
+use lance::Dataset;
+use lance_index::optimize::OptimizeOptions;
+use lance_index::DatasetIndexExt;
+use std::time::Instant;
+
+use crate::results::AppendResult;
+use crate::{recall, sweep, vector_batches, Config, IndexKind};
+
+/// Append `config.appended_vectors()` vectors and search before and after optimizing.
+pub(crate) async fn bench_appends(
+    dataset: &mut Dataset,
+    config: &Config,
+    kind: IndexKind,
+    queries: &[Vec<f32>],
+) -> Result<AppendResult> {
+    let appended = config.appended_vectors();
+    let version = dataset.version().version;
+    println!("  Appending {} vectors", appended);
+    let total = config.num_vectors + appended;
+    dataset
+        .append(vector_batches(config, config.num_vectors..total), None)
+        .await?;
+
+    // The appended vectors can be the nearest neighbors too
+    let truth = recall::exact_neighbors(
+        config.common.seed,
+        total,
+        config.vector_dim,
+        queries,
+        config.k,
+        config.distance,
+    );
+
+    println!("  Searching with {} unindexed vectors", appended);
+    let stale = sweep(dataset, config, kind, queries, &truth).await?;
+
+    println!("  Optimizing indices");
+    let start = Instant::now();
+    dataset
+        .optimize_indices(&OptimizeOptions::default())
+        .await?;
+    let optimize_time = start.elapsed().as_secs_f64();
+    let optimized = sweep(dataset, config, kind, queries, &truth).await?;
+
+    let mut original = dataset.checkout_version(version).await?;
+    original.restore().await?;
+    *dataset = original;
+
+    Ok(AppendResult {
+        appended,
+        stale,
+        optimize_time,
+        optimized,
+    })
+}
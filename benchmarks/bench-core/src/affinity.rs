@@ -0,0 +1,124 @@
+//! CPU pinning for worker runtime threads.
+//!
+//! On multi-socket machines, letting the scheduler move workers across NUMA
+//! nodes adds cross-node memory traffic and run-to-run variance. Workers can
+//! instead be pinned to one core each or to all the cores of a NUMA node.
+
+use anyhow::Result;
+
+/// A list of CPU or NUMA node ids, parsed from the kernel's cpulist format
+/// (e.g. `0-3,8,10-11`).
+#[derive(Debug, Clone)]
+pub struct CpuList(pub Vec<usize>);
+
+/// Parse a cpulist such as `0-3,8,10-11`.
+pub fn parse_cpu_list(s: &str) -> Result<CpuList, String> {
+    let mut ids = Vec::new();
+    for part in s.trim().split(',').filter(|p| !p.is_empty()) {
+        let parse = |v: &str| {
+            v.trim()
+                .parse::<usize>()
+                .map_err(|_| format!("invalid cpu list '{}'", s))
+        };
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let (start, end) = (parse(start)?, parse(end)?);
+                if start > end {
+                    return Err(format!("invalid range '{}' in cpu list", part));
+                }
+                ids.extend(start..=end);
+            }
+            None => ids.push(parse(part)?),
+        }
+    }
+    if ids.is_empty() {
+        return Err("cpu list is empty".to_string());
+    }
+    Ok(CpuList(ids))
+}
+
+/// Read the CPUs that belong to a NUMA node from sysfs.
+fn numa_node_cpus(node: usize) -> Result<Vec<usize>> {
+    let path = format!("/sys/devices/system/node/node{}/cpulist", node);
+    let cpulist = std::fs::read_to_string(&path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path, e))?;
+    parse_cpu_list(&cpulist)
+        .map(|list| list.0)
+        .map_err(|e| anyhow::anyhow!("{}: {}", path, e))
+}
+
+/// The CPUs each worker runtime thread is pinned to.
+#[derive(Debug, Clone)]
+pub struct Pinning {
+    /// CPU set for each worker, indexed by worker index
+    worker_cpus: Vec<Vec<usize>>,
+}
+
+impl Pinning {
+    /// Pin worker `i` to core `cores[i % cores.len()]`.
+    pub fn cores(cores: &CpuList, num_workers: usize) -> Self {
+        Self {
+            worker_cpus: (0..num_workers)
+                .map(|i| vec![cores.0[i % cores.0.len()]])
+                .collect(),
+        }
+    }
+
+    /// Pin worker `i` to every CPU of NUMA node `nodes[i % nodes.len()]`.
+    pub fn numa_nodes(nodes: &CpuList, num_workers: usize) -> Result<Self> {
+        let node_cpus = nodes
+            .0
+            .iter()
+            .map(|&node| numa_node_cpus(node))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            worker_cpus: (0..num_workers)
+                .map(|i| node_cpus[i % node_cpus.len()].clone())
+                .collect(),
+        })
+    }
+
+    /// CPU set for each worker, indexed by worker index.
+    pub fn worker_cpus(&self) -> &[Vec<usize>] {
+        &self.worker_cpus
+    }
+
+    /// Pin the calling thread to the CPUs of worker `worker`.
+    pub fn apply(&self, worker: usize) -> Result<()> {
+        let cpus = &self.worker_cpus[worker % self.worker_cpus.len()];
+        let result = unsafe {
+            let mut set: libc::cpu_set_t = std::mem::zeroed();
+            libc::CPU_ZERO(&mut set);
+            for &cpu in cpus {
+                libc::CPU_SET(cpu, &mut set);
+            }
+            libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+        };
+        if result != 0 {
+            anyhow::bail!(
+                "Failed to pin worker {} to CPUs {:?}: {}",
+                worker,
+                cpus,
+                std::io::Error::last_os_error()
+            );
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(
+            parse_cpu_list("0-3,8,10-11").unwrap().0,
+            vec![0, 1, 2, 3, 8, 10, 11]
+        );
+        assert_eq!(parse_cpu_list("5\n").unwrap().0, vec![5]);
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+        assert!(parse_cpu_list("").is_err());
+    }
+}
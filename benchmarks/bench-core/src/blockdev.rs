@@ -0,0 +1,116 @@
+//! Block device I/O counters from `/sys/dev/block/*/stat`.
+//!
+//! A [`BlockSnapshot`] of the devices holding a phase's datasets is taken
+//! before the phase, and [`BlockSnapshot::delta`] afterwards gives the device
+//! I/O during the phase. The counters are device-wide, so anything else
+//! reading from the same devices is included.
+
+use serde::{Deserialize, Serialize};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Size of the sectors counted in `stat` files, whatever the device's own sector size.
+const SECTOR_BYTES: u64 = 512;
+
+/// Read I/O on the devices holding a phase's datasets.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct BlockStats {
+    /// Devices counted, as `major:minor`
+    pub devices: Vec<String>,
+    /// Completed read requests
+    pub reads: u64,
+    /// Reads merged into an adjacent request before reaching the device
+    pub read_merges: u64,
+    pub read_bytes: u64,
+    /// Time read requests spent queued and in service, summed over requests (ms)
+    pub read_time_ms: u64,
+    /// Time the devices had at least one request in flight (ms)
+    pub busy_ms: u64,
+    /// Time all requests spent queued and in service, summed over requests (ms)
+    pub queue_time_ms: u64,
+}
+
+impl BlockStats {
+    /// Mean size of a read request as issued to the device.
+    pub fn avg_read_bytes(&self) -> f64 {
+        if self.reads == 0 {
+            0.0
+        } else {
+            self.read_bytes as f64 / self.reads as f64
+        }
+    }
+
+    /// Mean time a read request spent queued and in service.
+    pub fn avg_read_ms(&self) -> f64 {
+        if self.reads == 0 {
+            0.0
+        } else {
+            self.read_time_ms as f64 / self.reads as f64
+        }
+    }
+}
+
+/// Counters of each device, in `stat` file order.
+pub struct BlockSnapshot {
+    devices: Vec<(String, Vec<u64>)>,
+}
+
+/// The `major:minor` of the block device holding `path`.
+fn device_of(path: &Path) -> Option<String> {
+    let dev = std::fs::metadata(path).ok()?.dev();
+    // glibc's encoding of dev_t
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    Some(format!("{}:{}", major, minor))
+}
+
+fn read_counters(device: &str) -> Option<Vec<u64>> {
+    let stat = std::fs::read_to_string(format!("/sys/dev/block/{}/stat", device)).ok()?;
+    let counters: Vec<u64> = stat
+        .split_whitespace()
+        .map(|field| field.parse().ok())
+        .collect::<Option<_>>()?;
+    // Every kernel since 2.6 reports at least these 11 fields
+    (counters.len() >= 11).then_some(counters)
+}
+
+impl BlockSnapshot {
+    /// Snapshot the devices holding `paths`.
+    ///
+    /// Paths that aren't on a block device (tmpfs, overlay, missing paths) are skipped.
+    pub fn take<P: AsRef<Path>>(paths: &[P]) -> Self {
+        let mut devices: Vec<(String, Vec<u64>)> = Vec::new();
+        for path in paths {
+            let Some(device) = device_of(path.as_ref()) else {
+                continue;
+            };
+            if devices.iter().any(|(d, _)| *d == device) {
+                continue;
+            }
+            if let Some(counters) = read_counters(&device) {
+                devices.push((device, counters));
+            }
+        }
+        Self { devices }
+    }
+
+    /// Read I/O since the snapshot, or None if no path was on a block device.
+    pub fn delta(&self) -> Option<BlockStats> {
+        if self.devices.is_empty() {
+            return None;
+        }
+        let mut stats = BlockStats::default();
+        for (device, before) in &self.devices {
+            let after = read_counters(device)?;
+            let diff = |i: usize| after[i].saturating_sub(before[i]);
+            stats.devices.push(device.clone());
+            stats.reads += diff(0);
+            stats.read_merges += diff(1);
+            stats.read_bytes += diff(2) * SECTOR_BYTES;
+            stats.read_time_ms += diff(3);
+            stats.busy_ms += diff(9);
+            stats.queue_time_ms += diff(10);
+        }
+        Some(stats)
+    }
+}
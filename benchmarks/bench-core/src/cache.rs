@@ -0,0 +1,388 @@
+//! Cache management utilities for dropping files from the kernel page cache.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use std::cell::Cell;
+use std::fs;
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+/// Offset, length and buffer alignment of `O_DIRECT` reads.
+///
+/// 4 KiB covers the logical block size of common disks and filesystems.
+pub const DIRECT_IO_ALIGNMENT: usize = 4096;
+
+/// How [`drop_directory_cache`] evicts files from the page cache.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheDropMode {
+    /// posix_fadvise(DONTNEED) on every file (best effort: mapped pages stay cached)
+    Fadvise,
+    /// fadvise, with each cold phase run in a child cgroup whose
+    /// `memory.high` caps how much of the datasets can stay cached, mapped
+    /// pages included
+    ///
+    /// Needs no root when the benchmark runs in a cgroup it owns, e.g. under
+    /// `systemd-run --user --scope -p Delegate=yes`.
+    Cgroup,
+    /// sync, then drop all clean pages system-wide through /proc/sys/vm/drop_caches (needs root)
+    #[value(name = "sync_drop_caches")]
+    SyncDropCaches,
+}
+
+static CACHE_DROP_MODE: Mutex<CacheDropMode> = Mutex::new(CacheDropMode::Fadvise);
+
+/// Cgroups set up for `CacheDropMode::Cgroup`.
+static TIMED_CGROUP: Mutex<Option<TimedCgroup>> = Mutex::new(None);
+
+/// Use `mode` for every cache drop from now on.
+///
+/// For `CacheDropMode::Cgroup`, this also moves this process into a cgroup
+/// of its own, under which every cold phase gets a cgroup allowed
+/// `cgroup_memory_high` bytes. Fails if this process can't drop caches that
+/// way.
+pub fn set_cache_drop_mode(mode: CacheDropMode, cgroup_memory_high: u64) -> Result<()> {
+    // Leave the cgroups of an earlier mode before setting up new ones
+    *TIMED_CGROUP.lock().unwrap() = None;
+    match mode {
+        CacheDropMode::Fadvise => {}
+        CacheDropMode::Cgroup => {
+            let cgroup = TimedCgroup::create(cgroup_memory_high).map_err(|e| {
+                anyhow::anyhow!(
+                    "--cache-drop-mode cgroup couldn't set up its cgroups ({:#}); run inside a delegated cgroup of its own, e.g. with `systemd-run --user --scope -p Delegate=yes`",
+                    e
+                )
+            })?;
+            *TIMED_CGROUP.lock().unwrap() = Some(cgroup);
+        }
+        CacheDropMode::SyncDropCaches => {
+            fs::OpenOptions::new()
+                .write(true)
+                .open(DROP_CACHES)
+                .map_err(|e| {
+                    anyhow::anyhow!(
+                        "--cache-drop-mode sync_drop_caches needs write access to {} ({}); run as root",
+                        DROP_CACHES,
+                        e
+                    )
+                })?;
+        }
+    }
+    *CACHE_DROP_MODE.lock().unwrap() = mode;
+    Ok(())
+}
+
+/// Go back to fadvise cache drops, removing any cold-phase cgroup.
+pub fn reset_cache_drop_mode() {
+    *TIMED_CGROUP.lock().unwrap() = None;
+    *CACHE_DROP_MODE.lock().unwrap() = CacheDropMode::Fadvise;
+}
+
+/// The mode cache drops currently use.
+pub fn cache_drop_mode() -> CacheDropMode {
+    *CACHE_DROP_MODE.lock().unwrap()
+}
+
+const DROP_CACHES: &str = "/proc/sys/vm/drop_caches";
+
+/// Directory of this process's cgroup (v2).
+fn own_cgroup() -> Result<PathBuf> {
+    let cgroups = fs::read_to_string("/proc/self/cgroup")?;
+    let path = cgroups
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| anyhow::anyhow!("This process is not in a cgroup v2 hierarchy"))?;
+    Ok(Path::new("/sys/fs/cgroup").join(path.trim_start_matches('/')))
+}
+
+/// This process's `harness` cgroup, a child of the cgroup it started in,
+/// where it runs outside cold phases.
+///
+/// Cgroup v2 only lets a cgroup without processes of its own hand the
+/// memory controller to its children, so only this process moves, and
+/// setup fails if other processes share the cgroup it started in. Cold
+/// phases run in cgroups of their own next to `harness`; see
+/// [`enter_timed_cgroup`].
+struct TimedCgroup {
+    parent: PathBuf,
+    harness: PathBuf,
+    memory_high: u64,
+    /// Whether the memory controller was enabled for `parent`'s children
+    /// here, and so is disabled again on drop
+    enabled_memory: bool,
+}
+
+impl TimedCgroup {
+    fn create(memory_high: u64) -> Result<Self> {
+        let parent = own_cgroup()?;
+        let harness = parent.join(format!("bench-harness-{}", std::process::id()));
+        fs::create_dir(&harness)?;
+        // Dropped on any error below, which moves this process back
+        let mut cgroup = Self {
+            parent,
+            harness,
+            memory_high,
+            enabled_memory: false,
+        };
+        move_process(&cgroup.harness, &std::process::id().to_string())?;
+
+        let subtree_control = cgroup.parent.join("cgroup.subtree_control");
+        let controllers = fs::read_to_string(&subtree_control)?;
+        if !controllers.split_whitespace().any(|c| c == "memory") {
+            fs::write(&subtree_control, "+memory").map_err(|e| {
+                anyhow::anyhow!(
+                    "Enabling the memory controller in {} ({}); other processes may share this cgroup",
+                    subtree_control.display(),
+                    e
+                )
+            })?;
+            cgroup.enabled_memory = true;
+        }
+        if !cgroup.harness.join("memory.reclaim").exists() {
+            anyhow::bail!("memory.reclaim is missing (it needs Linux 5.19 or later)");
+        }
+        Ok(cgroup)
+    }
+}
+
+impl Drop for TimedCgroup {
+    fn drop(&mut self) {
+        // A cgroup handing the memory controller to its children can't hold processes
+        if self.enabled_memory {
+            let _ = fs::write(self.parent.join("cgroup.subtree_control"), "-memory");
+        }
+        let left = move_process(&self.parent, &std::process::id().to_string())
+            .and_then(|()| fs::remove_dir(&self.harness).map_err(Into::into));
+        if let Err(e) = left {
+            println!(
+                "Warning: couldn't remove cgroup {}: {:#}",
+                self.harness.display(),
+                e
+            );
+        }
+    }
+}
+
+/// Move process `pid`, every thread of it, into `cgroup`.
+fn move_process(cgroup: &Path, pid: &str) -> Result<()> {
+    let procs = cgroup.join("cgroup.procs");
+    fs::write(&procs, pid)
+        .map_err(|e| anyhow::anyhow!("Moving process {} into {}: {}", pid, procs.display(), e))
+}
+
+/// Reclaim the page cache charged to `cgroup`, mapped pages included.
+///
+/// Best effort: the kernel reports EAGAIN when it couldn't reclaim as much as
+/// asked, e.g. for pages under writeback.
+fn reclaim_page_cache(cgroup: &Path) -> Result<()> {
+    let stat = fs::read_to_string(cgroup.join("memory.stat"))?;
+    let file_bytes = stat
+        .lines()
+        .find_map(|line| line.strip_prefix("file "))
+        .and_then(|bytes| bytes.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    if file_bytes == 0 {
+        return Ok(());
+    }
+    match fs::write(cgroup.join("memory.reclaim"), file_bytes.to_string()) {
+        Err(e) if e.raw_os_error() == Some(libc::EAGAIN) => Ok(()),
+        written => written.map_err(Into::into),
+    }
+}
+
+/// Keeps this process in a cold-phase cgroup until dropped, then removes it.
+pub struct TimedCgroupGuard {
+    harness: PathBuf,
+    timed: PathBuf,
+}
+
+impl Drop for TimedCgroupGuard {
+    fn drop(&mut self) {
+        let left = move_process(&self.harness, &std::process::id().to_string())
+            .and_then(|()| fs::remove_dir(&self.timed).map_err(Into::into));
+        if let Err(e) = left {
+            println!("Warning: couldn't leave the cold-phase cgroup: {:#}", e);
+        }
+    }
+}
+
+/// Run this process in a new cold-phase cgroup until the guard is dropped,
+/// with `CacheDropMode::Cgroup`; otherwise do nothing.
+///
+/// Pages charged before the phase, such as those read or mapped during
+/// warmup, stay charged to the harness cgroup, so they are reclaimed first.
+/// Only what is charged while inside counts against the phase cgroup's
+/// `memory.high`: the pages read and the memory allocated during the phase.
+/// Above it the kernel reclaims the phase's pages and throttles its
+/// allocations rather than OOM-killing the benchmark.
+pub fn enter_timed_cgroup() -> Result<Option<TimedCgroupGuard>> {
+    static PHASES: AtomicUsize = AtomicUsize::new(0);
+
+    let cgroup = TIMED_CGROUP.lock().unwrap();
+    let Some(cgroup) = cgroup.as_ref() else {
+        return Ok(None);
+    };
+    reclaim_page_cache(&cgroup.harness)?;
+    let timed = cgroup.parent.join(format!(
+        "bench-cold-{}-{}",
+        std::process::id(),
+        PHASES.fetch_add(1, Ordering::Relaxed)
+    ));
+    fs::create_dir(&timed)?;
+    // Dropped on any error below, which removes the new cgroup again
+    let guard = TimedCgroupGuard {
+        harness: cgroup.harness.clone(),
+        timed,
+    };
+    fs::write(
+        guard.timed.join("memory.high"),
+        cgroup.memory_high.to_string(),
+    )?;
+    move_process(&guard.timed, &std::process::id().to_string())?;
+    Ok(Some(guard))
+}
+
+thread_local! {
+    /// Inside [`eviction_point`]: whether a system-wide drop is still owed.
+    static DROP_OWED: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+/// Run `drops`, a series of cache drops that together make one eviction.
+///
+/// With `CacheDropMode::SyncDropCaches`, the page cache is then dropped once
+/// instead of once per [`drop_directory_cache`].
+pub fn eviction_point<T>(drops: impl FnOnce() -> Result<T>) -> Result<T> {
+    DROP_OWED.with(|owed| owed.set(Some(false)));
+    let dropped = drops();
+    if DROP_OWED.with(|owed| owed.take()) == Some(true) {
+        sync_drop_caches()?;
+        println!("    Dropped the whole page cache");
+    }
+    dropped
+}
+
+/// Write dirty pages back, then drop every clean page from the page cache.
+fn sync_drop_caches() -> Result<()> {
+    unsafe {
+        libc::sync();
+    }
+    fs::write(DROP_CACHES, "1")?;
+    Ok(())
+}
+
+/// Whether `uri` refers to local files, which go through the kernel page cache.
+///
+/// Object-store URIs (`s3://` and friends) have no local page cache to drop.
+pub fn is_local_uri(uri: &str) -> bool {
+    !uri.contains("://") || uri.starts_with("file://") || uri.starts_with("file+uring://")
+}
+
+pub fn drop_file_cache(file_path: &Path) -> Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::io::AsRawFd;
+
+        const POSIX_FADV_DONTNEED: libc::c_int = 4;
+
+        let file = fs::File::open(file_path)?;
+        let fd = file.as_raw_fd();
+        let metadata = file.metadata()?;
+        let file_size = metadata.len() as i64;
+
+        unsafe {
+            libc::posix_fadvise(fd, 0, file_size, POSIX_FADV_DONTNEED);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn drop_directory_cache(path: &Path) -> Result<()> {
+    if !path.exists() {
+        println!("    Warning: Path does not exist: {}", path.display());
+        return Ok(());
+    }
+
+    if cache_drop_mode() == CacheDropMode::SyncDropCaches {
+        if DROP_OWED.with(|owed| owed.get().is_some()) {
+            DROP_OWED.with(|owed| owed.set(Some(true)));
+        } else {
+            sync_drop_caches()?;
+            println!("    Dropped the whole page cache");
+        }
+        return Ok(());
+    }
+
+    let mut file_count = 0;
+    let mut total_size = 0u64;
+
+    for entry in walkdir::WalkDir::new(path) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            if let Ok(metadata) = entry.metadata() {
+                total_size += metadata.len();
+                let _ = drop_file_cache(entry.path());
+                file_count += 1;
+            }
+        }
+    }
+
+    println!(
+        "    Dropped {} files ({:.2} GB) from cache",
+        file_count,
+        total_size as f64 / 1024.0 / 1024.0 / 1024.0
+    );
+
+    Ok(())
+}
+
+/// Open a file for reads that bypass the page cache (`O_DIRECT`).
+///
+/// Reads must be aligned to [`DIRECT_IO_ALIGNMENT`]; use [`read_direct_at`].
+pub fn open_direct(file_path: &Path) -> io::Result<fs::File> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_DIRECT)
+            .open(file_path)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = file_path;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "O_DIRECT is only supported on Linux",
+        ))
+    }
+}
+
+/// Read `length` bytes at `offset` from a file opened with [`open_direct`].
+///
+/// The read is widened to aligned boundaries into an aligned buffer, and the
+/// requested bytes are copied out.
+pub fn read_direct_at(file: &fs::File, offset: u64, length: usize) -> io::Result<Vec<u8>> {
+    let align = DIRECT_IO_ALIGNMENT as u64;
+    let aligned_offset = offset / align * align;
+    let lead = (offset - aligned_offset) as usize;
+    let aligned_length = (lead + length).div_ceil(DIRECT_IO_ALIGNMENT) * DIRECT_IO_ALIGNMENT;
+
+    let mut storage = vec![0u8; aligned_length + DIRECT_IO_ALIGNMENT];
+    let start = storage.as_ptr().align_offset(DIRECT_IO_ALIGNMENT);
+    let buf = &mut storage[start..start + aligned_length];
+
+    // The last block may be cut short by the end of the file
+    let mut filled = 0;
+    while filled < lead + length {
+        let read = file.read_at(&mut buf[filled..], aligned_offset + filled as u64)?;
+        if read == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        filled += read;
+    }
+    Ok(buf[lead..lead + length].to_vec())
+}
@@ -0,0 +1,30 @@
+//! Command-line flags shared by every benchmark.
+
+use clap::Args;
+use std::path::PathBuf;
+
+/// Flags every benchmark accepts, flattened into each benchmark's own
+/// arguments so they are spelled the same everywhere.
+#[derive(Args, Debug, Clone)]
+pub struct CommonArgs {
+    /// Seed for the generated data and queries; every value is derived from
+    /// it and the row id, and every query from it and the query stream
+    #[arg(long, default_value_t = 42)]
+    pub seed: u64,
+
+    /// Write the results as JSON to this path or object-store URI (e.g. s3://bucket/results.json)
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Resume an interrupted run: keep the results already in --output, skip
+    /// the engines they cover and add the rest
+    ///
+    /// The rest of the configuration must match the interrupted run.
+    #[arg(long, requires = "output")]
+    pub resume: bool,
+
+    /// Validate the configuration and print the execution plan without
+    /// writing data or running queries
+    #[arg(long)]
+    pub dry_run: bool,
+}
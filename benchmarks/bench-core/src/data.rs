@@ -0,0 +1,231 @@
+//! Common data generation utilities for benchmarks.
+
+use anyhow::Result;
+use arrow::array::{AsArray, BooleanArray, FixedSizeListArray, Float32Array, UInt64Array};
+use arrow::datatypes::{DataType, Field, Float32Type, Schema, UInt64Type};
+use arrow::record_batch::RecordBatch;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rand_distr::{Distribution, StandardNormal};
+use std::sync::Arc;
+
+/// Creates the schema for the vector dataset.
+///
+/// `id` holds the row's position in the dataset, `flag` is true for every
+/// even `id` (for filtered takes) and `vector` holds random data.
+pub fn create_schema(dim: usize) -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::UInt64, false),
+        Field::new("flag", DataType::Boolean, false),
+        Field::new(
+            "vector",
+            DataType::FixedSizeList(
+                Arc::new(Field::new("item", DataType::Float32, true)),
+                dim as i32,
+            ),
+            true,
+        ),
+    ]))
+}
+
+/// Value of the `flag` column for the row with this id.
+pub fn flag_for(id: u64) -> bool {
+    id % 2 == 0
+}
+
+/// Random vector for the row with this id, derived from `seed` and the id alone.
+pub fn vector_for(seed: u64, id: u64, dim: usize) -> Vec<f32> {
+    let mut rng = StdRng::seed_from_u64(seed ^ id.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    (0..dim).map(|_| StandardNormal.sample(&mut rng)).collect()
+}
+
+/// Generates a batch of random vectors whose ids start at `start_row`.
+///
+/// Every value is derived from `seed` and the row id, so `verify_batch` can
+/// check any row returned by a take.
+pub fn generate_vector_batch(
+    schema: Arc<Schema>,
+    start_row: usize,
+    batch_size: usize,
+    dim: usize,
+    seed: u64,
+) -> Result<RecordBatch, arrow::error::ArrowError> {
+    let ids = UInt64Array::from_iter_values(start_row as u64..(start_row + batch_size) as u64);
+    let flags: BooleanArray = ids.values().iter().map(|&id| Some(flag_for(id))).collect();
+
+    let mut values: Vec<f32> = Vec::with_capacity(batch_size * dim);
+    for &id in ids.values() {
+        values.extend(vector_for(seed, id, dim));
+    }
+    let values_array = Float32Array::from(values);
+    let list_array = FixedSizeListArray::new(
+        Arc::new(Field::new("item", DataType::Float32, true)),
+        dim as i32,
+        Arc::new(values_array),
+        None,
+    );
+
+    RecordBatch::try_new(
+        schema,
+        vec![Arc::new(ids), Arc::new(flags), Arc::new(list_array)],
+    )
+}
+
+/// Seed of one stream of generated queries, derived from the data `seed` and
+/// the parts that identify the stream (query size, worker, wave and so on).
+pub fn query_seed(seed: u64, stream: &[u64]) -> u64 {
+    stream
+        .iter()
+        .fold(seed ^ 0x5155_4552_5953_5EED, |acc, &part| {
+            (acc ^ part)
+                .wrapping_mul(0x9E37_79B9_7F4A_7C15)
+                .rotate_left(31)
+        })
+}
+
+/// Generates random query indices from `seed`.
+pub fn generate_queries(
+    num_queries: usize,
+    rows_per_query: usize,
+    max_row: usize,
+    seed: u64,
+) -> Vec<Vec<u64>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut queries = Vec::with_capacity(num_queries);
+
+    for _ in 0..num_queries {
+        let mut query = Vec::with_capacity(rows_per_query);
+        for _ in 0..rows_per_query {
+            query.push(rng.gen_range(0..max_row as u64));
+        }
+        query.sort_unstable();
+        queries.push(query);
+    }
+
+    queries
+}
+
+/// Generates random query indices from `seed`, with no duplicates within a query.
+pub fn generate_unique_queries(
+    num_queries: usize,
+    rows_per_query: usize,
+    max_row: usize,
+    seed: u64,
+) -> Vec<Vec<u64>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..num_queries)
+        .map(|_| {
+            let mut query: Vec<u64> =
+                rand::seq::index::sample(&mut rng, max_row, rows_per_query.min(max_row))
+                    .into_iter()
+                    .map(|idx| idx as u64)
+                    .collect();
+            query.sort_unstable();
+            query
+        })
+        .collect()
+}
+
+/// Generates queries made of `num_ranges` contiguous runs of `rows_per_range`
+/// rows each, starting at random rows drawn from `seed`.
+pub fn generate_range_queries(
+    num_queries: usize,
+    num_ranges: usize,
+    rows_per_range: usize,
+    max_row: usize,
+    seed: u64,
+) -> Vec<Vec<u64>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut queries = Vec::with_capacity(num_queries);
+    let max_start = max_row.saturating_sub(rows_per_range).max(1) as u64;
+
+    for _ in 0..num_queries {
+        let mut query = Vec::with_capacity(num_ranges * rows_per_range);
+        for _ in 0..num_ranges {
+            let start = rng.gen_range(0..max_start);
+            query.extend(start..(start + rows_per_range as u64).min(max_row as u64));
+        }
+        query.sort_unstable();
+        queries.push(query);
+    }
+
+    queries
+}
+
+/// Check that `batch` holds exactly the rows with ids `expected`, comparing
+/// every generated column it contains.
+///
+/// If `ordered` is false, rows may come back in any order and are matched by
+/// their `id` column, which must then be present.
+pub fn verify_batch(
+    batch: &RecordBatch,
+    expected: &[u64],
+    ordered: bool,
+    seed: u64,
+    dim: usize,
+) -> Result<()> {
+    if batch.num_rows() != expected.len() {
+        anyhow::bail!(
+            "Returned {} rows, expected {}",
+            batch.num_rows(),
+            expected.len()
+        );
+    }
+
+    // Engines may hand back different Arrow types; treat those as failures, not panics
+    let ids = batch
+        .column_by_name("id")
+        .map(|ids| {
+            ids.as_primitive_opt::<UInt64Type>()
+                .ok_or_else(|| anyhow::anyhow!("Unexpected id type {}", ids.data_type()))
+        })
+        .transpose()?;
+    let flags = batch
+        .column_by_name("flag")
+        .map(|flags| {
+            flags
+                .as_boolean_opt()
+                .ok_or_else(|| anyhow::anyhow!("Unexpected flag type {}", flags.data_type()))
+        })
+        .transpose()?;
+    let vectors = batch
+        .column_by_name("vector")
+        .map(|vectors| {
+            vectors
+                .as_fixed_size_list_opt()
+                .ok_or_else(|| anyhow::anyhow!("Unexpected vector type {}", vectors.data_type()))
+        })
+        .transpose()?;
+
+    let mut order: Vec<usize> = (0..batch.num_rows()).collect();
+    let mut expected = expected.to_vec();
+    if !ordered {
+        let ids = ids.ok_or_else(|| anyhow::anyhow!("Unordered results need the id column"))?;
+        order.sort_by_key(|&row| ids.value(row));
+        expected.sort_unstable();
+    }
+
+    for (&row, &id) in order.iter().zip(&expected) {
+        if let Some(ids) = ids {
+            if ids.value(row) != id {
+                anyhow::bail!("Row {} has id {}, expected {}", row, ids.value(row), id);
+            }
+        }
+        if let Some(flags) = flags {
+            if flags.value(row) != flag_for(id) {
+                anyhow::bail!("Row {} has the wrong flag for id {}", row, id);
+            }
+        }
+        if let Some(vectors) = vectors {
+            let vector = vectors.value(row);
+            let values = vector
+                .as_primitive_opt::<Float32Type>()
+                .ok_or_else(|| anyhow::anyhow!("Unexpected vector item type"))?;
+            if values.values()[..] != vector_for(seed, id, dim)[..] {
+                anyhow::bail!("Row {} has the wrong vector for id {}", row, id);
+            }
+        }
+    }
+
+    Ok(())
+}
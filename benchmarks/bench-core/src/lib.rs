@@ -0,0 +1,28 @@
+//! Shared building blocks for the benchmark binaries.
+//!
+//! Anything that isn't specific to one benchmark lives here so a new
+//! benchmark, or a new metric, only needs to be implemented once:
+//!
+//! - [`data`]: deterministic dataset and query generation, and result verification
+//! - [`stats`]: latency statistics
+//! - [`cache`]: page cache dropping and URI handling
+//! - [`affinity`]: CPU and NUMA pinning of worker threads
+//! - [`blockdev`]: block device I/O counters
+//! - [`procio`]: I/O counters of this process
+//! - [`threads`]: thread state sampling
+//! - [`monitor`]: rolling throughput reporting
+//! - [`duration`]: parsing of human-friendly durations for CLI flags
+//! - [`cli`]: command-line flags shared by every benchmark
+//! - [`results`]: the JSON results files written with `--output`
+
+pub mod affinity;
+pub mod blockdev;
+pub mod cache;
+pub mod cli;
+pub mod data;
+pub mod duration;
+pub mod monitor;
+pub mod procio;
+pub mod results;
+pub mod stats;
+pub mod threads;
@@ -0,0 +1,186 @@
+//! Rolling throughput reporting during timed phases.
+//!
+//! Queries update process-wide counters as they start and finish, and a
+//! background thread periodically prints the throughput and mean latency
+//! achieved since its previous report. Live views can take [`counters`]
+//! themselves and diff them for rates and percentiles.
+
+use indicatif::ProgressBar;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+static COMPLETED: AtomicU64 = AtomicU64::new(0);
+static LATENCY_MICROS: AtomicU64 = AtomicU64::new(0);
+
+/// Latency histogram buckets, four per doubling: bucket `i` counts latencies
+/// below 2^((i + 1) / 4) microseconds, so percentiles are within ~19%.
+const BUCKETS: usize = 128;
+static LATENCY_BUCKETS: [AtomicU64; BUCKETS] = [const { AtomicU64::new(0) }; BUCKETS];
+
+fn bucket(latency: f64) -> usize {
+    let micros = (latency * 1_000_000.0).max(1.0);
+    ((micros.log2() * 4.0) as usize).min(BUCKETS - 1)
+}
+
+/// Record that a query has been issued.
+pub fn query_started() {
+    IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Record that a query has finished, with its latency if it succeeded.
+pub fn query_finished(latency: Option<f64>) {
+    IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+    if let Some(latency) = latency {
+        COMPLETED.fetch_add(1, Ordering::Relaxed);
+        LATENCY_MICROS.fetch_add((latency * 1_000_000.0) as u64, Ordering::Relaxed);
+        LATENCY_BUCKETS[bucket(latency)].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// The counters of every query so far; diff two to see the queries between.
+#[derive(Debug, Clone)]
+pub struct Counters {
+    /// Queries issued but not yet finished
+    pub in_flight: usize,
+    /// Queries that succeeded
+    pub completed: u64,
+    latency_micros: u64,
+    buckets: Vec<u64>,
+}
+
+/// Read the counters now.
+pub fn counters() -> Counters {
+    Counters {
+        in_flight: IN_FLIGHT.load(Ordering::Relaxed),
+        completed: COMPLETED.load(Ordering::Relaxed),
+        latency_micros: LATENCY_MICROS.load(Ordering::Relaxed),
+        buckets: LATENCY_BUCKETS
+            .iter()
+            .map(|b| b.load(Ordering::Relaxed))
+            .collect(),
+    }
+}
+
+impl Counters {
+    /// Queries that succeeded since `earlier`.
+    pub fn completed_since(&self, earlier: &Self) -> u64 {
+        self.completed - earlier.completed
+    }
+
+    /// Mean latency of the queries since `earlier` (seconds).
+    pub fn mean_since(&self, earlier: &Self) -> Option<f64> {
+        let queries = self.completed_since(earlier);
+        (queries > 0)
+            .then(|| (self.latency_micros - earlier.latency_micros) as f64 / queries as f64 / 1e6)
+    }
+
+    /// Upper bound of the `percentile` (0 to 100) latency of the queries
+    /// since `earlier` (seconds).
+    pub fn percentile_since(&self, earlier: &Self, percentile: f64) -> Option<f64> {
+        let queries = self.completed_since(earlier);
+        if queries == 0 {
+            return None;
+        }
+        let rank = ((percentile / 100.0 * queries as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, (now, before)) in self.buckets.iter().zip(&earlier.buckets).enumerate() {
+            seen += now - before;
+            if seen >= rank {
+                return Some(2f64.powf((i + 1) as f64 / 4.0) / 1e6);
+            }
+        }
+        None
+    }
+}
+
+/// Prints a throughput line every `interval` until stopped.
+pub struct ThroughputMonitor {
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<()>>,
+}
+
+impl ThroughputMonitor {
+    /// Start reporting through `pb` so lines don't clobber the progress bar.
+    ///
+    /// A zero `interval` disables reporting.
+    pub fn start(interval: Duration, pb: &ProgressBar) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        if interval.is_zero() {
+            return Self { stop, handle: None };
+        }
+
+        let thread_stop = stop.clone();
+        let pb = pb.clone();
+        let handle = thread::spawn(move || {
+            let start = Instant::now();
+            let mut last_report = start;
+            let mut last_completed = COMPLETED.load(Ordering::Relaxed);
+            let mut last_latency = LATENCY_MICROS.load(Ordering::Relaxed);
+
+            while !thread_stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(100));
+                if last_report.elapsed() < interval {
+                    continue;
+                }
+
+                let now = Instant::now();
+                let completed = COMPLETED.load(Ordering::Relaxed);
+                let latency = LATENCY_MICROS.load(Ordering::Relaxed);
+                let queries = completed - last_completed;
+                let mean_ms = if queries > 0 {
+                    (latency - last_latency) as f64 / queries as f64 / 1000.0
+                } else {
+                    0.0
+                };
+
+                pb.println(format!(
+                    "  [{:>6.1}s] {:>10.2} queries/sec, mean {:.3} ms, {} in flight",
+                    (now - start).as_secs_f64(),
+                    queries as f64 / (now - last_report).as_secs_f64(),
+                    mean_ms,
+                    IN_FLIGHT.load(Ordering::Relaxed)
+                ));
+
+                last_report = now;
+                last_completed = completed;
+                last_latency = latency;
+            }
+        });
+
+        Self {
+            stop,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_since() {
+        let earlier = counters();
+        for _ in 0..99 {
+            query_started();
+            query_finished(Some(0.001));
+        }
+        query_started();
+        query_finished(Some(0.5));
+        let now = counters();
+        let p50 = now.percentile_since(&earlier, 50.0).unwrap();
+        assert!((0.001..0.0012).contains(&p50));
+        let max = now.percentile_since(&earlier, 100.0).unwrap();
+        assert!((0.5..0.6).contains(&max));
+    }
+}
@@ -0,0 +1,46 @@
+//! I/O counters of this process from `/proc/self/io`.
+//!
+//! Unlike [`crate::blockdev`], these count only this process's reads, and
+//! count them as issued: `read_bytes` includes reads served from the page
+//! cache, `storage_read_bytes` only those that reached a block device.
+
+use serde::{Deserialize, Serialize};
+
+/// Reads by this process between two [`ProcessIo::now`] snapshots.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProcessIo {
+    /// Bytes returned by read-like syscalls (`rchar`)
+    pub read_bytes: u64,
+    /// Read-like syscalls (`syscr`)
+    pub read_syscalls: u64,
+    /// Bytes fetched from storage (`read_bytes`)
+    pub storage_read_bytes: u64,
+}
+
+impl ProcessIo {
+    /// Counters so far, or None where `/proc/self/io` isn't available.
+    pub fn now() -> Option<Self> {
+        let io = std::fs::read_to_string("/proc/self/io").ok()?;
+        let field = |name: &str| {
+            io.lines()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+                .and_then(|value| value.trim().parse().ok())
+        };
+        Some(Self {
+            read_bytes: field("rchar")?,
+            read_syscalls: field("syscr")?,
+            storage_read_bytes: field("read_bytes")?,
+        })
+    }
+
+    /// Reads between `earlier` and this snapshot.
+    pub fn since(&self, earlier: &Self) -> Self {
+        Self {
+            read_bytes: self.read_bytes.saturating_sub(earlier.read_bytes),
+            read_syscalls: self.read_syscalls.saturating_sub(earlier.read_syscalls),
+            storage_read_bytes: self
+                .storage_read_bytes
+                .saturating_sub(earlier.storage_read_bytes),
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! JSON results files written with `--output`.
+//!
+//! Every benchmark's file has the same envelope: `benchmark_type` and
+//! `timestamp`, the benchmark's own run-wide fields, then `results`.
+
+use anyhow::Result;
+use serde::Serialize;
+use std::path::Path;
+
+/// A results file, with the fields of `metadata` next to the common ones.
+#[derive(Serialize)]
+struct ResultsFile<'a, M, R> {
+    benchmark_type: &'static str,
+    timestamp: u64,
+    #[serde(flatten)]
+    metadata: M,
+    results: &'a [R],
+}
+
+/// `results` of `benchmark_type` as pretty-printed JSON, timestamped now.
+///
+/// `metadata` must serialize as a struct or map; its fields describe the
+/// whole run, e.g. the dataset size.
+pub fn results_json<M: Serialize, R: Serialize>(
+    benchmark_type: &'static str,
+    metadata: M,
+    results: &[R],
+) -> Result<Vec<u8>> {
+    let file = ResultsFile {
+        benchmark_type,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs(),
+        metadata,
+        results,
+    };
+    Ok(serde_json::to_vec_pretty(&file)?)
+}
+
+/// Write `results` of `benchmark_type` to the local file `path`, creating
+/// its directory. See [`results_json`].
+pub fn write_results<M: Serialize, R: Serialize>(
+    path: &Path,
+    benchmark_type: &'static str,
+    metadata: M,
+    results: &[R],
+) -> Result<()> {
+    let json = results_json(benchmark_type, metadata, results)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, json)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metadata_sits_next_to_common_fields() {
+        #[derive(Serialize)]
+        struct Metadata {
+            rows: usize,
+        }
+        let json = results_json("sql", Metadata { rows: 10 }, &[1, 2]).unwrap();
+        let file: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(file["benchmark_type"], "sql");
+        assert!(file["timestamp"].as_u64().unwrap() > 0);
+        assert_eq!(file["rows"], 10);
+        assert_eq!(file["results"], serde_json::json!([1, 2]));
+    }
+}
@@ -0,0 +1,71 @@
+//! Statistics computation for benchmark results.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct Statistics {
+    pub mean: f64,
+    pub std: f64,
+    pub min: f64,
+    pub max: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+/// Summarize latencies. All fields are NaN when `latencies` is empty.
+pub fn compute_statistics(latencies: &[f64]) -> Statistics {
+    if latencies.is_empty() {
+        return Statistics {
+            mean: f64::NAN,
+            std: f64::NAN,
+            min: f64::NAN,
+            max: f64::NAN,
+            p50: f64::NAN,
+            p95: f64::NAN,
+            p99: f64::NAN,
+        };
+    }
+
+    let n = latencies.len() as f64;
+    let mean = latencies.iter().sum::<f64>() / n;
+
+    let variance = latencies.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n;
+    let std = variance.sqrt();
+
+    let mut sorted = latencies.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let min = sorted[0];
+    let max = sorted[sorted.len() - 1];
+    let p50 = sorted[(n * 0.50) as usize];
+    let p95 = sorted[(n * 0.95) as usize];
+    let p99 = sorted[(n * 0.99) as usize];
+
+    Statistics {
+        mean,
+        std,
+        min,
+        max,
+        p50,
+        p95,
+        p99,
+    }
+}
+
+/// Latency at every whole percentile from 0 to 100, the points of a latency
+/// CDF. Empty when `latencies` is.
+pub fn compute_quantiles(latencies: &[f64]) -> Vec<f64> {
+    if latencies.is_empty() {
+        return Vec::new();
+    }
+    let mut sorted = latencies.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    let n = sorted.len() as f64;
+    (0..=100)
+        .map(|percentile| {
+            let index = (n * percentile as f64 / 100.0) as usize;
+            sorted[index.min(sorted.len() - 1)]
+        })
+        .collect()
+}
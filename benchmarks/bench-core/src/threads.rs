@@ -0,0 +1,133 @@
+//! Sampling of this process's thread states during timed phases.
+//!
+//! A background thread reads every thread's scheduler state from
+//! `/proc/self/task/*/stat` at a fixed interval, and for sleeping threads
+//! what they are waiting in from `wchan`. The share of samples in each state
+//! tells a phase limited by CPU work (decoding) apart from one where threads
+//! mostly wait on I/O or on each other.
+//!
+//! Kernels that hide `wchan` (it reads `0` without the right capabilities)
+//! count every sleeping thread as `other_sleep`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Thread samples of a phase, by state.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ThreadStates {
+    /// Thread samples taken (threads x sampling rounds)
+    pub samples: u64,
+    /// Running or runnable
+    pub on_cpu: u64,
+    /// Waiting on I/O: uninterruptible sleep, or sleeping in an io_uring or block I/O wait
+    pub io_wait: u64,
+    /// Waiting on a futex (locks, condition variables, parked runtime threads)
+    pub futex: u64,
+    /// Any other sleep, such as epoll or timers
+    pub other_sleep: u64,
+}
+
+impl ThreadStates {
+    /// Share of samples with `count` threads, as a percentage.
+    pub fn percent(&self, count: u64) -> f64 {
+        if self.samples == 0 {
+            0.0
+        } else {
+            count as f64 * 100.0 / self.samples as f64
+        }
+    }
+}
+
+/// Samples thread states every `interval` until stopped.
+pub struct ThreadSampler {
+    stop: Arc<AtomicBool>,
+    handle: thread::JoinHandle<ThreadStates>,
+}
+
+/// State letter of a thread from its `stat` file.
+fn thread_state(stat: &str) -> Option<char> {
+    // The command name can contain spaces and parentheses, so parse after the last ')'
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.trim_start().chars().next()
+}
+
+fn sample(own_tid: &str, states: &mut ThreadStates) {
+    let Ok(tasks) = std::fs::read_dir("/proc/self/task") else {
+        return;
+    };
+    for task in tasks.flatten() {
+        let tid = task.file_name();
+        if tid.to_str() == Some(own_tid) {
+            continue;
+        }
+        let path = task.path();
+        // Threads can exit between listing and reading
+        let Some(state) = std::fs::read_to_string(path.join("stat"))
+            .ok()
+            .as_deref()
+            .and_then(thread_state)
+        else {
+            continue;
+        };
+        states.samples += 1;
+        match state {
+            'R' => states.on_cpu += 1,
+            'D' => states.io_wait += 1,
+            _ => {
+                let wchan = std::fs::read_to_string(path.join("wchan")).unwrap_or_default();
+                if wchan.starts_with("futex") {
+                    states.futex += 1;
+                } else if wchan.starts_with("io_") {
+                    states.io_wait += 1;
+                } else {
+                    states.other_sleep += 1;
+                }
+            }
+        }
+    }
+}
+
+impl ThreadSampler {
+    pub fn start(interval: Duration) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let handle = thread::spawn(move || {
+            // Leave the sampler itself out of the samples
+            let own_tid = std::fs::read_link("/proc/thread-self")
+                .ok()
+                .and_then(|link| Some(link.file_name()?.to_str()?.to_string()))
+                .unwrap_or_default();
+            let mut states = ThreadStates::default();
+            while !thread_stop.load(Ordering::Relaxed) {
+                sample(&own_tid, &mut states);
+                thread::sleep(interval);
+            }
+            states
+        });
+        Self { stop, handle }
+    }
+
+    /// Stop sampling and return the samples, or None if none were taken.
+    pub fn stop(self) -> Option<ThreadStates> {
+        self.stop.store(true, Ordering::Relaxed);
+        let states = self.handle.join().unwrap();
+        (states.samples > 0).then_some(states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_state_skips_command_name() {
+        assert_eq!(
+            thread_state("1234 (tokio (x) y) S 1 1234 1234 0 -1"),
+            Some('S')
+        );
+        assert_eq!(thread_state("garbage"), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! Blob Benchmark
+//!
+//! Benchmarks random retrieval of large binary values (several MB each, like
+//! images or documents) from:
+//! - Lance, with the column blob-encoded and values read through `take_blobs`
+//! - Parquet, with the values in a binary column
+//! - Plain files, one per value
+//!
+//! Each store is written with the same generated values, then values are
+//! retrieved one at a time by id. Besides latency, the bytes this process
+//! reads per retrieval (from `/proc/self/io`) show how much more than the
+//! value itself each store reads.
+//!
+//! Runs as `lance-bench blob`.
+
+use anyhow::Result;
+use bench_core::cache::drop_directory_cache;
+use bench_core::cli::CommonArgs;
+use bench_core::procio::ProcessIo;
+use bench_core::stats::compute_statistics;
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{RngCore, SeedableRng};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+pub mod results;
+pub mod stores;
+
+use results::StoreResult;
+use stores::Store;
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "blob-benchmark")]
+#[command(about = "Benchmark retrieval of large binary values from Lance, Parquet and files")]
+pub struct Config {
+    /// Directory the stores are written to, rewritten on every run
+    #[arg(long, default_value = "/tmp/blob-bench")]
+    pub data_dir: PathBuf,
+
+    /// Number of values in each store
+    #[arg(long, default_value_t = 256)]
+    pub num_values: usize,
+
+    /// Size of each value in bytes (default 4 MiB)
+    #[arg(long, default_value_t = 4 * 1024 * 1024)]
+    pub value_bytes: usize,
+
+    /// Values retrieved from each store, each one once
+    #[arg(long, default_value_t = 100)]
+    pub num_queries: usize,
+
+    /// Stores to benchmark (comma-separated)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "lance,parquet,files"
+    )]
+    pub stores: Vec<Store>,
+
+    /// Rows per Parquet row group
+    #[arg(long, default_value_t = 64)]
+    pub parquet_rows_per_group: usize,
+
+    /// Drop each store from the page cache before retrieving from it,
+    /// instead of retrieving every value once beforehand
+    #[arg(long)]
+    pub cold: bool,
+
+    #[command(flatten)]
+    pub common: CommonArgs,
+}
+
+impl Config {
+    fn validate(&self) -> Result<()> {
+        if self.common.resume {
+            anyhow::bail!("--resume is not supported by the blob benchmark");
+        }
+        if self.num_values == 0 || self.num_queries == 0 || self.value_bytes == 0 {
+            anyhow::bail!("--num-values, --num-queries and --value-bytes must be positive");
+        }
+        if self.num_queries > self.num_values {
+            anyhow::bail!(
+                "--num-queries ({}) exceeds --num-values ({}); each value is retrieved once",
+                self.num_queries,
+                self.num_values
+            );
+        }
+        if self.parquet_rows_per_group == 0 {
+            anyhow::bail!("--parquet-rows-per-group must be positive");
+        }
+        Ok(())
+    }
+}
+
+/// Random bytes of the value with this id, derived from `seed` and the id alone.
+pub fn value_for(seed: u64, id: u64, len: usize) -> Vec<u8> {
+    let mut rng = StdRng::seed_from_u64(seed ^ id.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+    let mut value = vec![0; len];
+    rng.fill_bytes(&mut value);
+    value
+}
+
+fn print_plan(config: &Config) {
+    println!("{}", "=".repeat(60));
+    println!("Blob Benchmark");
+    println!("{}", "=".repeat(60));
+    println!("Data directory: {}", config.data_dir.display());
+    println!(
+        "Values: {} x {:.2} MB ({:.2} GB per store)",
+        config.num_values,
+        config.value_bytes as f64 / 1_000_000.0,
+        (config.num_values * config.value_bytes) as f64 / 1_000_000_000.0
+    );
+    println!("Retrievals: {} per store", config.num_queries);
+    println!(
+        "Stores: {}",
+        config
+            .stores
+            .iter()
+            .map(|s| s.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!("Parquet rows per group: {}", config.parquet_rows_per_group);
+    println!("Cache: {}", if config.cold { "cold" } else { "hot" });
+    println!("{}", "=".repeat(60));
+}
+
+/// Total size of the files under `path`.
+fn store_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Write `store` and retrieve `ids` from it one at a time.
+fn bench_store(store: Store, config: &Config, ids: &[u64]) -> Result<StoreResult> {
+    println!("\n{}", store.name());
+    let start = Instant::now();
+    let mut blobs = stores::create(store, config)?;
+    let write_time = start.elapsed().as_secs_f64();
+    let store_bytes = store_size(blobs.path());
+    println!(
+        "  Wrote {:.1} MB in {:.2}s",
+        store_bytes as f64 / 1_000_000.0,
+        write_time
+    );
+
+    if config.cold {
+        drop_directory_cache(blobs.path())?;
+    } else {
+        for &id in ids {
+            blobs.get(id)?;
+        }
+    }
+
+    let pb = ProgressBar::new(ids.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("  [{bar:40}] {pos}/{len} values ({eta} remaining)")
+            .unwrap(),
+    );
+    let mut latencies = Vec::with_capacity(ids.len());
+    // Checking values reads no files, so it doesn't skew the I/O counters
+    let io_before = ProcessIo::now();
+    for &id in ids {
+        let start = Instant::now();
+        let value = blobs.get(id)?;
+        latencies.push(start.elapsed().as_secs_f64());
+        if value != value_for(config.common.seed, id, config.value_bytes) {
+            anyhow::bail!("{} returned the wrong value for id {}", store.name(), id);
+        }
+        pb.inc(1);
+    }
+    let io = io_before
+        .zip(ProcessIo::now())
+        .map(|(before, after)| after.since(&before));
+    pb.finish_and_clear();
+
+    Ok(StoreResult {
+        store: store.name().to_string(),
+        store_bytes,
+        write_time,
+        retrievals: ids.len(),
+        throughput: ids.len() as f64 / latencies.iter().sum::<f64>(),
+        stats: compute_statistics(&latencies),
+        io,
+    })
+}
+
+/// Run the benchmark described by `config`, returning the results of every store.
+pub fn run(config: Config) -> Result<Vec<StoreResult>> {
+    config.validate()?;
+    print_plan(&config);
+    if config.common.dry_run {
+        println!("\nDry run: nothing written or retrieved");
+        return Ok(Vec::new());
+    }
+
+    std::fs::create_dir_all(&config.data_dir)?;
+    // Distinct ids, so in a cold run no value was read before
+    let mut ids: Vec<u64> = (0..config.num_values as u64).collect();
+    ids.shuffle(&mut StdRng::seed_from_u64(config.common.seed));
+    ids.truncate(config.num_queries);
+
+    let mut results = Vec::new();
+    for &store in &config.stores {
+        results.push(bench_store(store, &config, &ids)?);
+    }
+    results::print_comparison(&results, config.value_bytes);
+
+    if let Some(output) = &config.common.output {
+        results::write_results(output, config.value_bytes, config.cold, &results)?;
+        println!("\nResults written to {}", output.display());
+    }
+    Ok(results)
+}
@@ -0,0 +1,96 @@
+//! Per-store results of the blob benchmark.
+
+use anyhow::Result;
+use bench_core::procio::ProcessIo;
+use bench_core::results::write_results as write_results_file;
+use bench_core::stats::Statistics;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Retrievals from one store.
+#[derive(Serialize, Deserialize)]
+pub struct StoreResult {
+    pub store: String,
+    /// Size of the written store
+    pub store_bytes: u64,
+    /// Time to write every value (seconds)
+    pub write_time: f64,
+    pub retrievals: usize,
+    /// Latency of a single retrieval (seconds)
+    pub stats: Statistics,
+    /// Retrievals per second, one at a time
+    pub throughput: f64,
+    /// Reads of all retrievals together, where `/proc/self/io` is available
+    pub io: Option<ProcessIo>,
+}
+
+/// Fields of the results file written with `--output`, next to the results.
+#[derive(Serialize)]
+struct Metadata {
+    value_bytes: usize,
+    cold: bool,
+}
+
+/// Write all store results to `path` as JSON.
+pub fn write_results(
+    path: &Path,
+    value_bytes: usize,
+    cold: bool,
+    results: &[StoreResult],
+) -> Result<()> {
+    write_results_file(path, "blob", Metadata { value_bytes, cold }, results)
+}
+
+/// Print one row per store.
+///
+/// Read amplification is bytes read per retrieval over the value size; disk
+/// reads are the part of them that missed the page cache.
+pub fn print_comparison(results: &[StoreResult], value_bytes: usize) {
+    println!("\n{}", "=".repeat(60));
+    println!(
+        "Retrieving {:.2} MB values",
+        value_bytes as f64 / 1_000_000.0
+    );
+    println!("{}", "=".repeat(60));
+    println!(
+        "{:<10} {:>10} {:>10} {:>10} {:>10} {:>12} {:>8} {:>12} {:>9}",
+        "Store",
+        "Size(MB)",
+        "p50(ms)",
+        "p99(ms)",
+        "Values/s",
+        "Read/value",
+        "Amplif.",
+        "Disk/value",
+        "Syscalls"
+    );
+    println!("{}", "-".repeat(99));
+    for result in results {
+        let per_value = |count: u64| count as f64 / result.retrievals as f64;
+        let mb = |bytes: f64| format!("{:.2} MB", bytes / 1_000_000.0);
+        let (read, amplification, disk, syscalls) = match result.io {
+            Some(io) => (
+                mb(per_value(io.read_bytes)),
+                format!("{:.2}x", per_value(io.read_bytes) / value_bytes as f64),
+                mb(per_value(io.storage_read_bytes)),
+                format!("{:.1}", per_value(io.read_syscalls)),
+            ),
+            None => Default::default(),
+        };
+        println!(
+            "{:<10} {:>10.1} {:>10.3} {:>10.3} {:>10.1} {:>12} {:>8} {:>12} {:>9}",
+            result.store,
+            result.store_bytes as f64 / 1_000_000.0,
+            result.stats.p50 * 1000.0,
+            result.stats.p99 * 1000.0,
+            result.throughput,
+            read,
+            amplification,
+            disk,
+            syscalls
+        );
+    }
+    if results.iter().any(|r| r.io.is_none()) {
+        println!("\nWarning: /proc/self/io is unavailable, bytes read are not reported");
+    }
+}
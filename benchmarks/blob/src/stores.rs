@@ -0,0 +1,241 @@
+//! The stores large binary values are retrieved from.
+
+use anyhow::Result;
+use arrow::array::{AsArray, LargeBinaryArray, RecordBatch, RecordBatchIterator, UInt64Array};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
+use clap::ValueEnum;
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use parquet::arrow::arrow_reader::{
+    ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder, RowSelection,
+    RowSelector,
+};
+use parquet::arrow::{ArrowWriter, ProjectionMask};
+use parquet::file::properties::WriterProperties;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::runtime::Runtime;
+
+use crate::{value_for, Config};
+
+/// Column holding the values.
+pub const BLOB_COLUMN: &str = "blob";
+
+/// Field metadata that makes Lance store a column with its blob encoding.
+const BLOB_META_KEY: &str = "lance-encoding:blob";
+
+/// Values per written batch; each value is several MB.
+const BATCH_ROWS: usize = 16;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Store {
+    /// Lance dataset with a blob-encoded column, read with `take_blobs`
+    Lance,
+    /// Parquet file with a binary column, read with a row selection
+    Parquet,
+    /// One file per value
+    Files,
+}
+
+impl Store {
+    pub fn name(self) -> &'static str {
+        match self {
+            Store::Lance => "lance",
+            Store::Parquet => "parquet",
+            Store::Files => "files",
+        }
+    }
+}
+
+/// Written values, retrieved one at a time by id.
+pub trait BlobStore {
+    /// Directory or file holding the values.
+    fn path(&self) -> &Path;
+
+    /// The value with this id.
+    fn get(&mut self, id: u64) -> Result<Vec<u8>>;
+}
+
+fn schema(blob_metadata: bool) -> Arc<Schema> {
+    let mut blob = Field::new(BLOB_COLUMN, DataType::LargeBinary, false);
+    if blob_metadata {
+        blob = blob.with_metadata(HashMap::from([(
+            BLOB_META_KEY.to_string(),
+            "true".to_string(),
+        )]));
+    }
+    Arc::new(Schema::new(vec![
+        Field::new("id", DataType::UInt64, false),
+        blob,
+    ]))
+}
+
+/// Every value in id order, in batches of [`BATCH_ROWS`].
+fn value_batches(
+    config: &Config,
+    schema: Arc<Schema>,
+) -> impl Iterator<Item = Result<RecordBatch, ArrowError>> + Send + 'static {
+    let (num_values, value_bytes, seed) =
+        (config.num_values, config.value_bytes, config.common.seed);
+    (0..num_values).step_by(BATCH_ROWS).map(move |start| {
+        let ids = start as u64..(start + BATCH_ROWS).min(num_values) as u64;
+        let values: Vec<Vec<u8>> = ids
+            .clone()
+            .map(|id| value_for(seed, id, value_bytes))
+            .collect();
+        RecordBatch::try_new(
+            schema.clone(),
+            vec![
+                Arc::new(UInt64Array::from_iter_values(ids)),
+                Arc::new(LargeBinaryArray::from_iter_values(values)),
+            ],
+        )
+    })
+}
+
+/// Write every value to `store` under `config.data_dir` and open it.
+pub fn create(store: Store, config: &Config) -> Result<Box<dyn BlobStore>> {
+    let path = config.data_dir.join(store.name());
+    if path.is_dir() {
+        fs::remove_dir_all(&path)?;
+    } else if path.exists() {
+        fs::remove_file(&path)?;
+    }
+    Ok(match store {
+        Store::Lance => Box::new(LanceStore::create(path, config)?),
+        Store::Parquet => Box::new(ParquetStore::create(path, config)?),
+        Store::Files => Box::new(FileStore::create(path, config)?),
+    })
+}
+
+struct LanceStore {
+    path: PathBuf,
+    runtime: Runtime,
+    dataset: Arc<Dataset>,
+}
+
+impl LanceStore {
+    fn create(path: PathBuf, config: &Config) -> Result<Self> {
+        let runtime = Runtime::new()?;
+        let schema = schema(true);
+        let reader = RecordBatchIterator::new(value_batches(config, schema.clone()), schema);
+        let params = WriteParams {
+            mode: WriteMode::Create,
+            // One fragment, so row ids are the value ids
+            max_rows_per_file: config.num_values,
+            ..Default::default()
+        };
+        let uri = path.to_string_lossy().to_string();
+        let dataset = runtime.block_on(Dataset::write(reader, &uri, Some(params)))?;
+        Ok(Self {
+            path,
+            runtime,
+            dataset: Arc::new(dataset),
+        })
+    }
+}
+
+impl BlobStore for LanceStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn get(&mut self, id: u64) -> Result<Vec<u8>> {
+        self.runtime.block_on(async {
+            let blobs = self.dataset.take_blobs(&[id], BLOB_COLUMN).await?;
+            let blob = blobs
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("No blob for id {}", id))?;
+            Ok(blob.read().await?.to_vec())
+        })
+    }
+}
+
+struct ParquetStore {
+    path: PathBuf,
+    file: File,
+    metadata: ArrowReaderMetadata,
+    rows_per_group: usize,
+}
+
+impl ParquetStore {
+    fn create(path: PathBuf, config: &Config) -> Result<Self> {
+        let schema = schema(false);
+        let props = WriterProperties::builder()
+            .set_max_row_group_size(config.parquet_rows_per_group)
+            .build();
+        let mut writer = ArrowWriter::try_new(File::create(&path)?, schema.clone(), Some(props))?;
+        for batch in value_batches(config, schema) {
+            writer.write(&batch?)?;
+        }
+        writer.close()?;
+
+        let file = File::open(&path)?;
+        // The page index lets a row selection skip the other values' pages
+        let options = ArrowReaderOptions::new().with_page_index(true);
+        let metadata = ArrowReaderMetadata::load(&file, options)?;
+        Ok(Self {
+            path,
+            file,
+            metadata,
+            rows_per_group: config.parquet_rows_per_group,
+        })
+    }
+}
+
+impl BlobStore for ParquetStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn get(&mut self, id: u64) -> Result<Vec<u8>> {
+        let id = id as usize;
+        let builder = ParquetRecordBatchReaderBuilder::new_with_metadata(
+            self.file.try_clone()?,
+            self.metadata.clone(),
+        );
+        let projection = ProjectionMask::columns(builder.parquet_schema(), [BLOB_COLUMN]);
+        let selection = RowSelection::from(vec![
+            RowSelector::skip(id % self.rows_per_group),
+            RowSelector::select(1),
+        ]);
+        let mut reader = builder
+            .with_row_groups(vec![id / self.rows_per_group])
+            .with_row_selection(selection)
+            .with_projection(projection)
+            .with_batch_size(1)
+            .build()?;
+        let batch = reader
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("No row for id {}", id))??;
+        Ok(batch.column(0).as_binary::<i64>().value(0).to_vec())
+    }
+}
+
+struct FileStore {
+    path: PathBuf,
+}
+
+impl FileStore {
+    fn create(path: PathBuf, config: &Config) -> Result<Self> {
+        fs::create_dir_all(&path)?;
+        for id in 0..config.num_values as u64 {
+            let value = value_for(config.common.seed, id, config.value_bytes);
+            fs::write(path.join(format!("{}.bin", id)), value)?;
+        }
+        Ok(Self { path })
+    }
+}
+
+impl BlobStore for FileStore {
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn get(&mut self, id: u64) -> Result<Vec<u8>> {
+        Ok(fs::read(self.path.join(format!("{}.bin", id)))?)
+    }
+}
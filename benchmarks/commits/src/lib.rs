@@ -0,0 +1,333 @@
+//! Commits Benchmark
+//!
+//! Runs several writers appending to the same Lance dataset at once and
+//! measures how commits behave under contention:
+//! - latency of writing each append's data files
+//! - latency of committing it, including every retry after losing a race
+//!   to another writer
+//! - retries per commit
+//! - aggregate commits and rows per second across all writers
+//!
+//! Each writer writes its data files first, then commits the transaction
+//! without Lance's own retries. When another writer committed first it
+//! loads the latest version and tries again, so every retry is counted.
+//!
+//! The datasets can live anywhere Lance can commit to, and the commit
+//! mechanism follows from the URI: rename on local files, conditional put on
+//! object stores, and an external DynamoDB manifest store on `s3+ddb://`.
+//!
+//! Runs as `lance-bench commits`.
+
+use anyhow::Result;
+use arrow::array::RecordBatchIterator;
+use bench_core::cli::CommonArgs;
+use bench_core::data::{create_schema, generate_vector_batch};
+use bench_core::stats::compute_statistics;
+use clap::Parser;
+use indicatif::{ProgressBar, ProgressStyle};
+use lance::dataset::{CommitBuilder, Dataset, InsertBuilder, WriteMode, WriteParams};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Barrier;
+use tokio::task::JoinSet;
+
+pub mod results;
+
+use results::ContentionResult;
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "commits-benchmark")]
+#[command(about = "Benchmark concurrent commits to one Lance dataset")]
+pub struct Config {
+    /// Dataset to commit to (repeatable), a local path or object-store URI,
+    /// recreated for every writer count
+    #[arg(
+        long = "dataset-uri",
+        default_value = "/tmp/commits-bench/dataset.lance"
+    )]
+    pub dataset_uris: Vec<String>,
+
+    /// Numbers of concurrent writers to run (comma-separated)
+    #[arg(long, value_delimiter = ',', default_value = "1,2,4,8,16")]
+    pub writers: Vec<usize>,
+
+    /// Appends committed by each writer
+    #[arg(long, default_value_t = 20)]
+    pub commits_per_writer: usize,
+
+    /// Rows in each append
+    #[arg(long, default_value_t = 1000)]
+    pub rows_per_commit: usize,
+
+    /// Vector dimension
+    #[arg(long, default_value_t = 8)]
+    pub vector_dim: usize,
+
+    /// Attempts at a single commit before the benchmark gives up
+    #[arg(long, default_value_t = 1000)]
+    pub max_attempts: usize,
+
+    #[command(flatten)]
+    pub common: CommonArgs,
+}
+
+impl Config {
+    fn validate(&self) -> Result<()> {
+        if self.common.resume {
+            anyhow::bail!("--resume is not supported by the commits benchmark");
+        }
+        if self.writers.is_empty() || self.writers.contains(&0) {
+            anyhow::bail!("--writers must list positive writer counts");
+        }
+        if self.commits_per_writer == 0
+            || self.rows_per_commit == 0
+            || self.vector_dim == 0
+            || self.max_attempts == 0
+        {
+            anyhow::bail!(
+                "--commits-per-writer, --rows-per-commit, --vector-dim and --max-attempts must be positive"
+            );
+        }
+        Ok(())
+    }
+}
+
+/// How Lance makes commits to `uri` atomic.
+pub fn commit_mechanism(uri: &str) -> &'static str {
+    match uri.split_once("://").map(|(scheme, _)| scheme) {
+        None | Some("file") | Some("file+uring") => "rename",
+        Some("memory") => "in-memory",
+        Some("s3+ddb") => "dynamodb",
+        Some(_) => "conditional put",
+    }
+}
+
+fn print_plan(config: &Config) {
+    println!("{}", "=".repeat(60));
+    println!("Commits Benchmark");
+    println!("{}", "=".repeat(60));
+    for uri in &config.dataset_uris {
+        println!("Dataset: {} (commits by {})", uri, commit_mechanism(uri));
+    }
+    println!(
+        "Writers: {}",
+        config
+            .writers
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!(
+        "Appends: {} per writer of {} rows each (vector dim {})",
+        config.commits_per_writer, config.rows_per_commit, config.vector_dim
+    );
+    println!("{}", "=".repeat(60));
+}
+
+/// Whether `error` means another writer committed first.
+fn is_conflict(error: &lance::Error) -> bool {
+    matches!(
+        error,
+        lance::Error::CommitConflict { .. }
+            | lance::Error::RetryableCommitConflict { .. }
+            | lance::Error::TooMuchWriteContention { .. }
+    )
+}
+
+/// What one writer measured.
+#[derive(Default)]
+struct WriterTimes {
+    /// Time to write each append's data files (seconds)
+    writes: Vec<f64>,
+    /// Time to commit each append, retries included (seconds)
+    commits: Vec<f64>,
+    /// Retries of each commit
+    retries: Vec<usize>,
+}
+
+/// Append `config.commits_per_writer` times to `dataset` as writer `writer`.
+async fn write_appends(
+    config: Arc<Config>,
+    mut dataset: Dataset,
+    writer: usize,
+    start: Arc<Barrier>,
+    pb: ProgressBar,
+) -> Result<WriterTimes> {
+    let schema = create_schema(config.vector_dim);
+    let mut times = WriterTimes::default();
+    start.wait().await;
+    for commit in 0..config.commits_per_writer {
+        // Rows of different writers never share ids
+        let first_row = (writer * config.commits_per_writer + commit) * config.rows_per_commit;
+        let batch = generate_vector_batch(
+            schema.clone(),
+            first_row,
+            config.rows_per_commit,
+            config.vector_dim,
+            config.common.seed,
+        )?;
+        let params = WriteParams {
+            mode: WriteMode::Append,
+            ..Default::default()
+        };
+
+        let write_start = Instant::now();
+        let transaction = InsertBuilder::new(Arc::new(dataset.clone()))
+            .with_params(&params)
+            .execute_uncommitted(vec![batch])
+            .await?;
+        times.writes.push(write_start.elapsed().as_secs_f64());
+
+        let commit_start = Instant::now();
+        let mut retries = 0;
+        dataset = loop {
+            let committed = CommitBuilder::new(Arc::new(dataset.clone()))
+                .with_max_retries(0)
+                .execute(transaction.clone())
+                .await;
+            match committed {
+                Ok(committed) => break committed,
+                Err(e) if is_conflict(&e) && retries + 1 < config.max_attempts => {
+                    retries += 1;
+                    dataset.checkout_latest().await?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+        times.commits.push(commit_start.elapsed().as_secs_f64());
+        times.retries.push(retries);
+        pb.inc(1);
+    }
+    Ok(times)
+}
+
+/// Recreate the dataset at `uri` and run `writers` writers against it.
+async fn bench_writers(
+    config: &Arc<Config>,
+    uri: &str,
+    writers: usize,
+) -> Result<ContentionResult> {
+    // Overwrite, so the dataset starts with one small fragment wherever it lives
+    let schema = create_schema(config.vector_dim);
+    let batch = generate_vector_batch(schema.clone(), 0, 1, config.vector_dim, config.common.seed)?;
+    let params = WriteParams {
+        mode: WriteMode::Overwrite,
+        ..Default::default()
+    };
+    let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+    let initial = Dataset::write(reader, uri, Some(params)).await?;
+    let initial_version = initial.version().version;
+    let initial_rows = initial.count_rows(None).await?;
+
+    let commits = writers * config.commits_per_writer;
+    let pb = ProgressBar::new(commits as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("  [{bar:40}] {pos}/{len} commits ({eta} remaining)")
+            .unwrap(),
+    );
+    // Every writer opens the dataset before any of them starts writing
+    let start = Arc::new(Barrier::new(writers + 1));
+    let mut tasks = JoinSet::new();
+    for writer in 0..writers {
+        let dataset = Dataset::open(uri).await?;
+        tasks.spawn(write_appends(
+            config.clone(),
+            dataset,
+            writer,
+            start.clone(),
+            pb.clone(),
+        ));
+    }
+    start.wait().await;
+    let wall_start = Instant::now();
+    let mut times = WriterTimes::default();
+    while let Some(writer) = tasks.join_next().await {
+        let writer = writer??;
+        times.writes.extend(writer.writes);
+        times.commits.extend(writer.commits);
+        times.retries.extend(writer.retries);
+    }
+    let wall_time = wall_start.elapsed().as_secs_f64();
+    pb.finish_and_clear();
+
+    // Appends never conflict with each other, so every one must have landed
+    let dataset = Dataset::open(uri).await?;
+    let rows = commits * config.rows_per_commit;
+    if dataset.version().version != initial_version + commits as u64
+        || dataset.count_rows(None).await? != initial_rows + rows
+    {
+        anyhow::bail!(
+            "{} has version {} after {} commits on version {}; commits were lost",
+            uri,
+            dataset.version().version,
+            commits,
+            initial_version
+        );
+    }
+
+    let total_retries: usize = times.retries.iter().sum();
+    Ok(ContentionResult {
+        dataset_uri: uri.to_string(),
+        mechanism: commit_mechanism(uri).to_string(),
+        writers,
+        commits,
+        wall_time,
+        write: compute_statistics(&times.writes),
+        commit: compute_statistics(&times.commits),
+        total_retries,
+        max_retries: times.retries.iter().copied().max().unwrap_or(0),
+        commits_per_second: commits as f64 / wall_time,
+        rows_per_second: rows as f64 / wall_time,
+    })
+}
+
+/// Run the benchmark described by `config`, returning the results of every
+/// dataset and writer count.
+pub fn run(config: Config) -> Result<Vec<ContentionResult>> {
+    config.validate()?;
+    print_plan(&config);
+    if config.common.dry_run {
+        println!("\nDry run: nothing written or committed");
+        return Ok(Vec::new());
+    }
+
+    let config = Arc::new(config);
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    let mut results = Vec::new();
+    for uri in &config.dataset_uris {
+        for &writers in &config.writers {
+            println!("\n{}: {} writers", uri, writers);
+            results.push(runtime.block_on(bench_writers(&config, uri, writers))?);
+        }
+    }
+    results::print_comparison(&results);
+
+    if let Some(output) = &config.common.output {
+        results::write_results(output, config.rows_per_commit, &results)?;
+        println!("\nResults written to {}", output.display());
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commit_mechanism() {
+        assert_eq!(commit_mechanism("/tmp/data.lance"), "rename");
+        assert_eq!(commit_mechanism("file:///tmp/data.lance"), "rename");
+        assert_eq!(
+            commit_mechanism("s3://bucket/data.lance"),
+            "conditional put"
+        );
+        assert_eq!(
+            commit_mechanism("s3+ddb://bucket/data.lance?ddbTableName=commits"),
+            "dynamodb"
+        );
+    }
+}
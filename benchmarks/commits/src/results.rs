@@ -0,0 +1,82 @@
+//! Results of the commits benchmark, one per dataset and writer count.
+
+use anyhow::Result;
+use bench_core::results::write_results as write_results_file;
+use bench_core::stats::Statistics;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Concurrent appends by `writers` writers to one dataset.
+#[derive(Serialize, Deserialize)]
+pub struct ContentionResult {
+    pub dataset_uri: String,
+    /// How commits to the dataset are made atomic
+    pub mechanism: String,
+    pub writers: usize,
+    /// Commits made by all writers together
+    pub commits: usize,
+    /// Time from the writers starting to the last commit (seconds)
+    pub wall_time: f64,
+    /// Latency of writing an append's data files (seconds)
+    pub write: Statistics,
+    /// Latency of committing an append, retries included (seconds)
+    pub commit: Statistics,
+    /// Retries after losing a commit to another writer, over all commits
+    pub total_retries: usize,
+    /// Most retries any single commit needed
+    pub max_retries: usize,
+    pub commits_per_second: f64,
+    pub rows_per_second: f64,
+}
+
+/// Fields of the results file written with `--output`, next to the results.
+#[derive(Serialize)]
+struct Metadata {
+    rows_per_commit: usize,
+}
+
+/// Write all results to `path` as JSON.
+pub fn write_results(
+    path: &Path,
+    rows_per_commit: usize,
+    results: &[ContentionResult],
+) -> Result<()> {
+    write_results_file(path, "commits", Metadata { rows_per_commit }, results)
+}
+
+/// Print one table per dataset, a row per writer count.
+pub fn print_comparison(results: &[ContentionResult]) {
+    let mut uris: Vec<&str> = results.iter().map(|r| r.dataset_uri.as_str()).collect();
+    uris.dedup();
+    for uri in uris {
+        let rows: Vec<_> = results.iter().filter(|r| r.dataset_uri == uri).collect();
+        println!("\n{}", "=".repeat(60));
+        println!("{} (commits by {})", uri, rows[0].mechanism);
+        println!("{}", "=".repeat(60));
+        println!(
+            "{:>8} {:>10} {:>12} {:>15} {:>16} {:>16} {:>15} {:>12}",
+            "Writers",
+            "Commits/s",
+            "Rows/s",
+            "Write p50(ms)",
+            "Commit p50(ms)",
+            "Commit p99(ms)",
+            "Retries/commit",
+            "Max retries"
+        );
+        println!("{}", "-".repeat(111));
+        for result in rows {
+            println!(
+                "{:>8} {:>10.1} {:>12.0} {:>15.3} {:>16.3} {:>16.3} {:>15.2} {:>12}",
+                result.writers,
+                result.commits_per_second,
+                result.rows_per_second,
+                result.write.p50 * 1000.0,
+                result.commit.p50 * 1000.0,
+                result.commit.p99 * 1000.0,
+                result.total_retries as f64 / result.commits as f64,
+                result.max_retries
+            );
+        }
+    }
+}
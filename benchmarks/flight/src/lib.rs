@@ -0,0 +1,389 @@
+//! Flight Benchmark
+//!
+//! Serves each engine's dataset over Arrow Flight from within the harness,
+//! as the serving tier in front of Lance does in many deployments, and
+//! measures what clients see end to end:
+//! - takes, one `DoExchange` round trip each, next to the same takes made
+//!   directly against the dataset, so the overhead of Flight shows
+//! - full scans streamed with `DoGet`
+//!
+//! The engines and their datasets are those of the take benchmark (see
+//! `take_benchmark::engines`); the server runs on the engine's runtime and
+//! the clients on their own (see [`server`]).
+//!
+//! Runs as `lance-bench flight`.
+
+use anyhow::Result;
+use arrow::array::{ArrayRef, RecordBatch, UInt64Array};
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::{FlightClient, Ticket};
+use bench_core::cli::CommonArgs;
+use bench_core::data::{generate_queries, query_seed};
+use bench_core::stats::compute_statistics;
+use clap::Parser;
+use futures::future::BoxFuture;
+use futures::{stream, FutureExt, StreamExt, TryStreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+use take_benchmark::engines::{
+    create_registry, with_fallbacks, DatasetHandle, Engine, RuntimeFlavor, RuntimeSpec, TakeOptions,
+};
+use tokio::runtime::Runtime;
+use tonic::transport::Channel;
+
+pub mod results;
+pub mod server;
+
+use results::{EngineResult, ScanResult, TakeResult};
+use server::{FlightServer, INDICES_COLUMN};
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "flight-benchmark")]
+#[command(about = "Benchmark takes and scans served over Arrow Flight across storage engines")]
+pub struct Config {
+    /// Storage engines to serve (comma-separated, e.g. lance,parquet)
+    #[arg(short, long, value_delimiter = ',', default_value = "lance,parquet")]
+    pub engines: Vec<String>,
+
+    /// Directory each engine's dataset is written to, rewritten on every run
+    #[arg(long, default_value = "/tmp/flight-bench")]
+    pub data_dir: PathBuf,
+
+    /// Rows in each engine's dataset
+    #[arg(long, default_value_t = 1_000_000)]
+    pub rows: usize,
+
+    /// Batch size when writing data
+    #[arg(long, default_value_t = 100_000)]
+    pub write_batch_size: usize,
+
+    /// Vector dimension
+    #[arg(long, default_value_t = 768)]
+    pub vector_dim: usize,
+
+    /// Columns served by takes and scans (comma-separated, e.g. id,vector)
+    #[arg(long, value_delimiter = ',', default_value = "vector")]
+    pub columns: Vec<String>,
+
+    /// Takes made per engine, both directly and through Flight
+    #[arg(long, default_value_t = 1_000)]
+    pub num_queries: usize,
+
+    /// Rows requested by each take
+    #[arg(long, default_value_t = 100)]
+    pub rows_per_query: usize,
+
+    /// Takes in flight at once
+    #[arg(long, default_value_t = 8)]
+    pub concurrent_queries: usize,
+
+    /// Full scans per engine through `DoGet` (0 to skip scans)
+    #[arg(long, default_value_t = 3)]
+    pub scan_iterations: usize,
+
+    /// Rows per batch streamed by `DoGet`
+    #[arg(long, default_value_t = 8192)]
+    pub scan_batch_rows: usize,
+
+    #[command(flatten)]
+    pub common: CommonArgs,
+}
+
+impl Config {
+    fn validate(&self) -> Result<()> {
+        if self.common.resume {
+            anyhow::bail!("--resume is not supported by the Flight benchmark");
+        }
+        if self.rows == 0 || self.write_batch_size == 0 || self.vector_dim == 0 {
+            anyhow::bail!("--rows, --write-batch-size and --vector-dim must be positive");
+        }
+        if self.num_queries == 0 || self.rows_per_query == 0 || self.concurrent_queries == 0 {
+            anyhow::bail!(
+                "--num-queries, --rows-per-query and --concurrent-queries must be positive"
+            );
+        }
+        if self.rows_per_query > self.rows {
+            anyhow::bail!(
+                "--rows-per-query ({}) exceeds --rows ({})",
+                self.rows_per_query,
+                self.rows
+            );
+        }
+        if self.scan_batch_rows == 0 {
+            anyhow::bail!("--scan-batch-rows must be positive");
+        }
+        Ok(())
+    }
+
+    /// The take benchmark configuration the datasets are written and opened with.
+    fn take_config(&self) -> Result<take_benchmark::Config> {
+        let argv = [
+            "take-benchmark".to_string(),
+            format!("--rows-per-dataset={}", self.rows),
+            format!("--write-batch-size={}", self.write_batch_size),
+            format!("--vector-dim={}", self.vector_dim),
+            format!("--take-columns={}", self.columns.join(",")),
+            format!("--seed={}", self.common.seed),
+        ];
+        take_benchmark::Config::try_parse_from(argv).map_err(|e| anyhow::anyhow!("{}", e))
+    }
+}
+
+fn print_plan(config: &Config) {
+    println!("{}", "=".repeat(60));
+    println!("Flight Benchmark");
+    println!("{}", "=".repeat(60));
+    println!("Engines: {}", config.engines.join(", "));
+    println!("Data directory: {}", config.data_dir.display());
+    println!(
+        "Rows: {} per engine (vector dim {})",
+        config.rows, config.vector_dim
+    );
+    println!("Columns: {}", config.columns.join(", "));
+    println!(
+        "Takes: {} x {} rows, {} in flight",
+        config.num_queries, config.rows_per_query, config.concurrent_queries
+    );
+    if config.scan_iterations > 0 {
+        println!(
+            "Scans: {} per engine, {} rows per batch",
+            config.scan_iterations, config.scan_batch_rows
+        );
+    }
+    println!("{}", "=".repeat(60));
+}
+
+/// Makes one take, returning the number of rows it returned.
+type TakeFn = Arc<dyn Fn(Vec<u64>) -> BoxFuture<'static, Result<usize>> + Send + Sync>;
+
+/// Make every take in `queries`, `concurrency` at a time, returning the
+/// latency of each and the total time.
+fn run_takes(
+    runtime: &Runtime,
+    queries: &[Vec<u64>],
+    concurrency: usize,
+    take: TakeFn,
+    pb: &ProgressBar,
+) -> Result<(Vec<f64>, f64)> {
+    runtime.block_on(async {
+        let start = Instant::now();
+        let latencies: Vec<f64> = stream::iter(queries.iter().cloned())
+            .map(|indices| {
+                let take = take.clone();
+                async move {
+                    let start = Instant::now();
+                    take(indices).await?;
+                    pb.inc(1);
+                    Ok::<_, anyhow::Error>(start.elapsed().as_secs_f64())
+                }
+            })
+            .buffer_unordered(concurrency)
+            .try_collect()
+            .await?;
+        Ok((latencies, start.elapsed().as_secs_f64()))
+    })
+}
+
+fn progress_bar(len: usize, label: &str) -> ProgressBar {
+    let pb = ProgressBar::new(len as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(&format!("  {} [{{bar:40}}] {{pos}}/{{len}}", label))
+            .unwrap(),
+    );
+    pb
+}
+
+/// Take `indices` with a `DoExchange` call.
+async fn flight_take(channel: Channel, indices: Vec<u64>) -> Result<usize> {
+    let mut client = FlightClient::new(channel);
+    let request = RecordBatch::try_from_iter([(
+        INDICES_COLUMN,
+        Arc::new(UInt64Array::from(indices)) as ArrayRef,
+    )])?;
+    let data = FlightDataEncoderBuilder::new().build(stream::once(async { Ok(request) }));
+    let batches: Vec<RecordBatch> = client.do_exchange(data).await?.try_collect().await?;
+    Ok(batches.iter().map(|batch| batch.num_rows()).sum())
+}
+
+/// Scan the whole dataset with `DoGet`, returning the rows and bytes received.
+async fn flight_scan(channel: Channel) -> Result<(usize, usize)> {
+    let mut client = FlightClient::new(channel);
+    let mut batches = client.do_get(Ticket::new("scan")).await?;
+    let (mut rows, mut bytes) = (0, 0);
+    while let Some(batch) = batches.try_next().await? {
+        rows += batch.num_rows();
+        bytes += batch.get_array_memory_size();
+    }
+    Ok((rows, bytes))
+}
+
+/// Serve `engine`'s dataset and measure takes and scans through it.
+fn bench_engine(
+    engine: &dyn Engine,
+    take_config: &take_benchmark::Config,
+    queries: &[Vec<u64>],
+    client_runtime: &Runtime,
+    config: &Config,
+) -> Result<EngineResult> {
+    println!("\n{}", engine.name());
+    let uri = config
+        .data_dir
+        .join(engine.name())
+        .to_string_lossy()
+        .to_string();
+    if std::path::Path::new(&uri).exists() {
+        std::fs::remove_dir_all(&uri)?;
+    }
+    let start = Instant::now();
+    engine.write(&uri, take_config)?;
+    println!(
+        "  Wrote {} rows in {:.2}s",
+        config.rows,
+        start.elapsed().as_secs_f64()
+    );
+    let dataset: Arc<dyn DatasetHandle> = with_fallbacks(engine, engine.open(&uri)?, take_config);
+    let options = TakeOptions {
+        columns: config.columns.clone(),
+        filter: false,
+    };
+
+    let direct_take: TakeFn = {
+        let dataset = dataset.clone();
+        let options = options.clone();
+        Arc::new(move |indices| {
+            let dataset = dataset.clone();
+            let options = options.clone();
+            async move { Ok(dataset.take(&indices, &options).await?.num_rows()) }.boxed()
+        })
+    };
+    let pb = progress_bar(queries.len(), "Direct takes");
+    let (direct, direct_time) = run_takes(
+        client_runtime,
+        queries,
+        config.concurrent_queries,
+        direct_take,
+        &pb,
+    )?;
+    pb.finish_and_clear();
+
+    let server = FlightServer::start(
+        &engine.runtime(),
+        dataset,
+        config.rows,
+        options,
+        config.scan_batch_rows,
+    )?;
+    let channel = client_runtime
+        .block_on(Channel::from_shared(format!("http://{}", server.addr))?.connect())?;
+    let flight_take_fn: TakeFn = {
+        let channel = channel.clone();
+        Arc::new(move |indices| flight_take(channel.clone(), indices).boxed())
+    };
+    let pb = progress_bar(queries.len(), "Flight takes");
+    let (flight, flight_time) = run_takes(
+        client_runtime,
+        queries,
+        config.concurrent_queries,
+        flight_take_fn,
+        &pb,
+    )?;
+    pb.finish_and_clear();
+
+    let scan = if config.scan_iterations > 0 {
+        let mut times = Vec::with_capacity(config.scan_iterations);
+        let (mut rows, mut bytes) = (0, 0);
+        for _ in 0..config.scan_iterations {
+            let start = Instant::now();
+            (rows, bytes) = client_runtime.block_on(flight_scan(channel.clone()))?;
+            times.push(start.elapsed().as_secs_f64());
+        }
+        if rows != config.rows {
+            anyhow::bail!(
+                "{}: DoGet returned {} rows, expected {}",
+                engine.name(),
+                rows,
+                config.rows
+            );
+        }
+        Some(ScanResult {
+            iterations: config.scan_iterations,
+            rows,
+            bytes,
+            stats: compute_statistics(&times),
+        })
+    } else {
+        None
+    };
+    drop(server);
+
+    Ok(EngineResult {
+        engine: engine.name().to_string(),
+        take: TakeResult {
+            rows_per_query: config.rows_per_query,
+            concurrency: config.concurrent_queries,
+            queries: queries.len(),
+            direct_throughput: queries.len() as f64 / direct_time,
+            flight_throughput: queries.len() as f64 / flight_time,
+            direct: compute_statistics(&direct),
+            flight: compute_statistics(&flight),
+        },
+        scan,
+    })
+}
+
+/// Run the benchmark described by `config`, returning the results of every engine.
+pub fn run(config: Config) -> Result<Vec<EngineResult>> {
+    config.validate()?;
+    let take_config = config.take_config()?;
+    // The server is spawned onto the engine's runtime, which needs worker threads to run it
+    let runtime = RuntimeSpec {
+        flavor: RuntimeFlavor::MultiThread,
+        worker_threads: None,
+    };
+    let registry = create_registry(runtime, &[]);
+    let engines = config
+        .engines
+        .iter()
+        .map(|name| registry.resolve(name))
+        .collect::<Result<Vec<_>>>()?;
+    if let Some(engine) = engines.iter().find(|e| !e.supports_take()) {
+        anyhow::bail!(
+            "{} can't take rows by offset, which serving over Flight relies on",
+            engine.name()
+        );
+    }
+    print_plan(&config);
+    if config.common.dry_run {
+        println!("\nDry run: nothing written or served");
+        return Ok(Vec::new());
+    }
+
+    std::fs::create_dir_all(&config.data_dir)?;
+    let client_runtime = Runtime::new()?;
+    let queries = generate_queries(
+        config.num_queries,
+        config.rows_per_query,
+        config.rows,
+        query_seed(config.common.seed, &[config.rows_per_query as u64]),
+    );
+    let mut results = Vec::with_capacity(engines.len());
+    for engine in &engines {
+        results.push(bench_engine(
+            engine.as_ref(),
+            &take_config,
+            &queries,
+            &client_runtime,
+            &config,
+        )?);
+    }
+    results::print_comparison(&results);
+
+    if let Some(output) = &config.common.output {
+        results::write_results(output, config.rows, &results)?;
+        println!("\nResults written to {}", output.display());
+    }
+    Ok(results)
+}
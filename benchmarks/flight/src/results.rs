@@ -0,0 +1,105 @@
+//! Per-engine results of the Flight benchmark.
+
+use anyhow::Result;
+use bench_core::results::write_results as write_results_file;
+use bench_core::stats::Statistics;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// The same takes made directly against the dataset and through Flight.
+#[derive(Serialize, Deserialize)]
+pub struct TakeResult {
+    pub rows_per_query: usize,
+    /// Takes in flight at once
+    pub concurrency: usize,
+    pub queries: usize,
+    /// Latency of a take made directly against the open dataset (seconds)
+    pub direct: Statistics,
+    /// Latency of a `DoExchange` round trip for the same take (seconds)
+    pub flight: Statistics,
+    /// Takes per second made directly
+    pub direct_throughput: f64,
+    /// Takes per second through Flight
+    pub flight_throughput: f64,
+}
+
+/// Full scans through `DoGet`.
+#[derive(Serialize, Deserialize)]
+pub struct ScanResult {
+    pub iterations: usize,
+    /// Rows received per scan
+    pub rows: usize,
+    /// In-memory size of the batches received per scan
+    pub bytes: usize,
+    /// Time of a whole scan (seconds)
+    pub stats: Statistics,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct EngineResult {
+    pub engine: String,
+    pub take: TakeResult,
+    pub scan: Option<ScanResult>,
+}
+
+/// Fields of the results file written with `--output`, next to the results.
+#[derive(Serialize)]
+struct Metadata {
+    rows: usize,
+}
+
+/// Write all engine results to `path` as JSON.
+pub fn write_results(path: &Path, rows: usize, results: &[EngineResult]) -> Result<()> {
+    write_results_file(path, "flight", Metadata { rows }, results)
+}
+
+/// Print the takes of every engine, then its scans.
+///
+/// "Overhead" is how much Flight added to the p50 take latency.
+pub fn print_comparison(results: &[EngineResult]) {
+    println!("\n{}", "=".repeat(60));
+    println!("TAKES (DoExchange)");
+    println!("{}", "=".repeat(60));
+    println!(
+        "{:<24} {:>15} {:>15} {:>14} {:>11} {:>11}",
+        "Engine", "Direct p50(ms)", "Flight p50(ms)", "Overhead(ms)", "Direct QPS", "Flight QPS"
+    );
+    println!("{}", "-".repeat(95));
+    for result in results {
+        let take = &result.take;
+        println!(
+            "{:<24} {:>15.3} {:>15.3} {:>14.3} {:>11.1} {:>11.1}",
+            result.engine,
+            take.direct.p50 * 1000.0,
+            take.flight.p50 * 1000.0,
+            (take.flight.p50 - take.direct.p50) * 1000.0,
+            take.direct_throughput,
+            take.flight_throughput
+        );
+    }
+
+    if results.iter().all(|r| r.scan.is_none()) {
+        return;
+    }
+    println!("\n{}", "=".repeat(60));
+    println!("SCANS (DoGet)");
+    println!("{}", "=".repeat(60));
+    println!(
+        "{:<24} {:>12} {:>12} {:>14} {:>10}",
+        "Engine", "Rows", "p50(s)", "Rows/s", "MB/s"
+    );
+    println!("{}", "-".repeat(76));
+    for result in results {
+        let Some(scan) = &result.scan else {
+            continue;
+        };
+        println!(
+            "{:<24} {:>12} {:>12.3} {:>14.0} {:>10.1}",
+            result.engine,
+            scan.rows,
+            scan.stats.p50,
+            scan.rows as f64 / scan.stats.p50,
+            scan.bytes as f64 / 1_000_000.0 / scan.stats.p50
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! The Flight server that sits in front of an engine's dataset.
+//!
+//! - `DoGet` with any ticket streams the whole dataset. It is read in
+//!   batches of contiguous rows, through the same take path the take
+//!   benchmark measures, since that is what every engine supports.
+//! - `DoExchange` takes rows: each batch the client sends holds the row
+//!   offsets to take in its [`INDICES_COLUMN`], and is answered with the
+//!   taken rows.
+//!
+//! Every other Flight call is unimplemented.
+
+use anyhow::Result;
+use arrow::array::AsArray;
+use arrow::datatypes::UInt64Type;
+use arrow_flight::decode::FlightRecordBatchStream;
+use arrow_flight::encode::FlightDataEncoderBuilder;
+use arrow_flight::error::FlightError;
+use arrow_flight::flight_service_server::{FlightService, FlightServiceServer};
+use arrow_flight::{
+    Action, ActionType, Criteria, Empty, FlightData, FlightDescriptor, FlightInfo,
+    HandshakeRequest, HandshakeResponse, PollInfo, PutResult, SchemaResult, Ticket,
+};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use take_benchmark::engines::{DatasetHandle, TakeOptions};
+use tokio::net::TcpListener;
+use tokio::runtime::Runtime;
+use tokio::sync::oneshot;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::{Request, Response, Status, Streaming};
+
+/// Column of the batches sent to `DoExchange` holding the row offsets to take.
+pub const INDICES_COLUMN: &str = "indices";
+
+/// Serves one open dataset.
+struct DatasetService {
+    dataset: Arc<dyn DatasetHandle>,
+    rows: usize,
+    options: TakeOptions,
+    /// Rows per batch streamed by `DoGet`
+    scan_batch_rows: usize,
+}
+
+fn external(e: anyhow::Error) -> FlightError {
+    FlightError::ExternalError(e.into())
+}
+
+#[tonic::async_trait]
+impl FlightService for DatasetService {
+    type HandshakeStream = BoxStream<'static, Result<HandshakeResponse, Status>>;
+    type ListFlightsStream = BoxStream<'static, Result<FlightInfo, Status>>;
+    type DoGetStream = BoxStream<'static, Result<FlightData, Status>>;
+    type DoPutStream = BoxStream<'static, Result<PutResult, Status>>;
+    type DoActionStream = BoxStream<'static, Result<arrow_flight::Result, Status>>;
+    type ListActionsStream = BoxStream<'static, Result<ActionType, Status>>;
+    type DoExchangeStream = BoxStream<'static, Result<FlightData, Status>>;
+
+    async fn handshake(
+        &self,
+        _request: Request<Streaming<HandshakeRequest>>,
+    ) -> Result<Response<Self::HandshakeStream>, Status> {
+        Err(Status::unimplemented("handshake"))
+    }
+
+    async fn list_flights(
+        &self,
+        _request: Request<Criteria>,
+    ) -> Result<Response<Self::ListFlightsStream>, Status> {
+        Err(Status::unimplemented("list_flights"))
+    }
+
+    async fn get_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<FlightInfo>, Status> {
+        Err(Status::unimplemented("get_flight_info"))
+    }
+
+    async fn poll_flight_info(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<PollInfo>, Status> {
+        Err(Status::unimplemented("poll_flight_info"))
+    }
+
+    async fn get_schema(
+        &self,
+        _request: Request<FlightDescriptor>,
+    ) -> Result<Response<SchemaResult>, Status> {
+        Err(Status::unimplemented("get_schema"))
+    }
+
+    async fn do_get(
+        &self,
+        _request: Request<Ticket>,
+    ) -> Result<Response<Self::DoGetStream>, Status> {
+        let dataset = self.dataset.clone();
+        let options = self.options.clone();
+        let (rows, batch_rows) = (self.rows, self.scan_batch_rows);
+        let batches = stream::iter((0..rows).step_by(batch_rows)).then(move |start| {
+            let dataset = dataset.clone();
+            let options = options.clone();
+            async move {
+                let indices: Vec<u64> =
+                    (start as u64..(start + batch_rows).min(rows) as u64).collect();
+                dataset.take(&indices, &options).await.map_err(external)
+            }
+        });
+        let data = FlightDataEncoderBuilder::new()
+            .build(batches)
+            .map_err(Status::from);
+        Ok(Response::new(data.boxed()))
+    }
+
+    async fn do_put(
+        &self,
+        _request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoPutStream>, Status> {
+        Err(Status::unimplemented("do_put"))
+    }
+
+    async fn do_action(
+        &self,
+        _request: Request<Action>,
+    ) -> Result<Response<Self::DoActionStream>, Status> {
+        Err(Status::unimplemented("do_action"))
+    }
+
+    async fn list_actions(
+        &self,
+        _request: Request<Empty>,
+    ) -> Result<Response<Self::ListActionsStream>, Status> {
+        Err(Status::unimplemented("list_actions"))
+    }
+
+    async fn do_exchange(
+        &self,
+        request: Request<Streaming<FlightData>>,
+    ) -> Result<Response<Self::DoExchangeStream>, Status> {
+        let dataset = self.dataset.clone();
+        let options = self.options.clone();
+        let requests = FlightRecordBatchStream::new_from_flight_data(
+            request.into_inner().map_err(FlightError::from),
+        );
+        let taken = requests.and_then(move |batch| {
+            let dataset = dataset.clone();
+            let options = options.clone();
+            async move {
+                let indices = batch
+                    .column_by_name(INDICES_COLUMN)
+                    .and_then(|c| c.as_primitive_opt::<UInt64Type>())
+                    .ok_or_else(|| {
+                        FlightError::ProtocolError(format!(
+                            "expected a UInt64 {} column",
+                            INDICES_COLUMN
+                        ))
+                    })?;
+                dataset
+                    .take(indices.values(), &options)
+                    .await
+                    .map_err(external)
+            }
+        });
+        let data = FlightDataEncoderBuilder::new()
+            .build(taken)
+            .map_err(Status::from);
+        Ok(Response::new(data.boxed()))
+    }
+}
+
+/// A Flight server running on an engine's runtime, stopped when dropped.
+pub struct FlightServer {
+    pub addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl FlightServer {
+    /// Serve `dataset` (of `rows` rows) on a free local port, on `runtime`.
+    pub fn start(
+        runtime: &Runtime,
+        dataset: Arc<dyn DatasetHandle>,
+        rows: usize,
+        options: TakeOptions,
+        scan_batch_rows: usize,
+    ) -> Result<Self> {
+        let listener = runtime.block_on(TcpListener::bind("127.0.0.1:0"))?;
+        let addr = listener.local_addr()?;
+        let service = DatasetService {
+            dataset,
+            rows,
+            options,
+            scan_batch_rows,
+        };
+        let (shutdown, stopped) = oneshot::channel();
+        runtime.spawn(async move {
+            let served = tonic::transport::Server::builder()
+                .add_service(FlightServiceServer::new(service))
+                .serve_with_incoming_shutdown(TcpListenerStream::new(listener), async {
+                    stopped.await.ok();
+                })
+                .await;
+            if let Err(e) = served {
+                eprintln!("Flight server stopped: {}", e);
+            }
+        });
+        Ok(Self {
+            addr,
+            shutdown: Some(shutdown),
+        })
+    }
+}
+
+impl Drop for FlightServer {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! Bisection of a Lance regression across revisions of a Lance checkout.
+//!
+//! `lance-bench bisect` builds this repository against each revision it
+//! tests by patching the benchmarks' Lance git dependency with the crates of
+//! a local Lance checkout (cargo `--config patch…`), runs a take benchmark
+//! with the rebuilt binary, and compares one metric of one engine against
+//! the good revision's run:
+//!
+//! ```text
+//! lance-bench bisect --lance-repo ~/lance --good v0.30.0 --bad main \
+//!     --engine lance --metric p50 --threshold 5% -- --engines lance --dataset-uri /data/take
+//! ```
+//!
+//! A revision is bad when any phase of the engine regressed by more than the
+//! threshold (and `--min-effect`), as in `lance-bench gate`. Revisions are
+//! taken from the first-parent history between `--good` and `--bad`, so each
+//! one is a merged change. The checkout must be clean; its original HEAD is
+//! restored afterwards.
+
+use anyhow::Result;
+use bench_core::duration::parse_duration;
+use clap::Args;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+use take_benchmark::gate::{is_regression, GateOptions, Metric, PhaseKey};
+use take_benchmark::results::{read_results, EngineResult};
+
+/// Git source of the benchmarks' Lance dependencies, as written in `take/Cargo.toml`.
+const LANCE_GIT: &str = "https://github.com/lance-format/lance";
+
+#[derive(Args, Debug)]
+pub struct BisectArgs {
+    /// Local Lance git checkout to build against
+    #[arg(long)]
+    pub lance_repo: PathBuf,
+
+    /// Revision without the regression
+    #[arg(long)]
+    pub good: String,
+
+    /// Revision with the regression
+    #[arg(long)]
+    pub bad: String,
+
+    /// Engine whose metric regressed
+    #[arg(long, default_value = "lance")]
+    pub engine: String,
+
+    /// Metric that regressed
+    #[arg(long, value_enum, default_value = "p50")]
+    pub metric: Metric,
+
+    /// Relative change that counts as a regression (e.g. 5% or 0.05)
+    #[arg(long, default_value = "5%", value_parser = take_benchmark::gate::parse_threshold)]
+    pub threshold: f64,
+
+    /// Smallest latency increase that counts as a regression
+    #[arg(long, default_value = "0ms", value_parser = parse_duration)]
+    pub min_effect: Duration,
+
+    /// Directory of the per-revision results files
+    #[arg(long, default_value = "bisect-results")]
+    pub output_dir: PathBuf,
+
+    /// Arguments of the take benchmark to run at each revision (after `--`),
+    /// which should include `--engines` with the engine being bisected
+    #[arg(last = true, required = true)]
+    pub take_args: Vec<String>,
+}
+
+/// Run `git` in `repo`, returning its trimmed stdout.
+fn git(repo: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .output()?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// `--config` arguments patching every crate of the Lance checkout into the build.
+fn patch_args(lance_repo: &Path) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    for entry in std::fs::read_dir(lance_repo.join("rust"))? {
+        let dir = entry?.path();
+        if !dir.join("Cargo.toml").exists() {
+            continue;
+        }
+        // Lance's crates are named after their directories
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        args.push("--config".to_string());
+        args.push(format!(
+            "patch.'{}'.{}.path='{}'",
+            LANCE_GIT,
+            name,
+            dir.display()
+        ));
+    }
+    if args.is_empty() {
+        anyhow::bail!("No crates under {}/rust", lance_repo.display());
+    }
+    Ok(args)
+}
+
+/// Rebuild lance-bench against the checkout and run the benchmark, returning its results.
+fn build_and_run(args: &BisectArgs, revision: &str) -> Result<Vec<EngineResult>> {
+    let bench_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    println!("\nBuilding lance-bench against lance {}", revision);
+    let status = Command::new("cargo")
+        .current_dir(bench_dir)
+        .args(["build", "--release"])
+        .args(patch_args(&args.lance_repo)?)
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("Build against lance {} failed", revision);
+    }
+
+    let output = args.output_dir.join(format!("{}.json", revision));
+    println!("Running the benchmark, results in {}", output.display());
+    let status = Command::new(bench_dir.join("target/release/lance-bench"))
+        .arg("take")
+        .args(&args.take_args)
+        .arg("--output")
+        .arg(&output)
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("Benchmark at lance {} failed", revision);
+    }
+    read_results(&output)
+}
+
+/// Whether `current` regressed against `baseline`, printing each phase's change.
+fn regressed(args: &BisectArgs, baseline: &[EngineResult], current: &[EngineResult]) -> bool {
+    let options = GateOptions {
+        threshold: args.threshold,
+        min_effect: args.min_effect,
+    };
+    let mut regressed = false;
+    for base_result in baseline.iter().filter(|r| r.engine == args.engine) {
+        for base_phase in &base_result.phases {
+            let key = PhaseKey::of(base_result, base_phase);
+            let Some(current_phase) = current
+                .iter()
+                .flat_map(|r| r.phases.iter().map(move |p| (r, p)))
+                .find(|(r, p)| key.matches(r, p))
+                .map(|(_, p)| p)
+            else {
+                continue;
+            };
+            let (base, cur) = (
+                args.metric.value(base_phase),
+                args.metric.value(current_phase),
+            );
+            let bad = is_regression(args.metric, base, cur, &options);
+            println!(
+                "  {}: {} -> {}{}",
+                key,
+                args.metric.format(base),
+                args.metric.format(cur),
+                if bad { " (regressed)" } else { "" }
+            );
+            regressed |= bad;
+        }
+    }
+    regressed
+}
+
+/// Find the first revision between `args.good` and `args.bad` with the regression.
+pub fn bisect(args: &BisectArgs) -> Result<()> {
+    let repo = &args.lance_repo;
+    if !git(repo, &["status", "--porcelain", "--untracked-files=no"])?.is_empty() {
+        anyhow::bail!("{} has uncommitted changes", repo.display());
+    }
+    // Restore the branch if one was checked out, else the detached commit
+    let original = match git(repo, &["rev-parse", "--abbrev-ref", "HEAD"])?.as_str() {
+        "HEAD" => git(repo, &["rev-parse", "HEAD"])?,
+        branch => branch.to_string(),
+    };
+    let good = git(repo, &["rev-parse", &args.good])?;
+    let revisions: Vec<String> = git(
+        repo,
+        &[
+            "rev-list",
+            "--first-parent",
+            "--reverse",
+            &format!("{}..{}", good, args.bad),
+        ],
+    )?
+    .lines()
+    .map(str::to_string)
+    .collect();
+    if revisions.is_empty() {
+        anyhow::bail!("No revisions between {} and {}", args.good, args.bad);
+    }
+    std::fs::create_dir_all(&args.output_dir)?;
+
+    let result = (|| -> Result<String> {
+        git(repo, &["checkout", "--quiet", "--detach", &good])?;
+        let baseline = build_and_run(args, &good)?;
+
+        // revisions[lo..hi] holds the first bad revision; the last one is bad by assumption
+        let (mut lo, mut hi) = (0, revisions.len() - 1);
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            println!(
+                "\n{}\nBisecting: {} revisions left, testing {}\n{}",
+                "=".repeat(60),
+                hi - lo + 1,
+                revisions[mid],
+                "=".repeat(60)
+            );
+            git(repo, &["checkout", "--quiet", "--detach", &revisions[mid]])?;
+            let current = build_and_run(args, &revisions[mid])?;
+            if regressed(args, &baseline, &current) {
+                hi = mid;
+            } else {
+                lo = mid + 1;
+            }
+        }
+        Ok(revisions[lo].clone())
+    })();
+
+    git(repo, &["checkout", "--quiet", &original])?;
+    let first_bad = result?;
+    println!(
+        "\nFirst bad revision: {}",
+        git(
+            repo,
+            &["log", "-1", "--format=%h %s (%an, %ad)", &first_bad]
+        )?
+    );
+    Ok(())
+}
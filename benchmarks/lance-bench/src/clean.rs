@@ -0,0 +1,145 @@
+//! Removal of generated datasets.
+//!
+//! Every dataset the benchmarks generate carries a manifest (see
+//! `take_benchmark::manifest`), which is how datasets are told apart from
+//! anything else under the directory being cleaned. Directories without a
+//! manifest, including datasets generated before manifests existed, are left
+//! alone.
+
+use anyhow::Result;
+use bench_core::duration::parse_duration;
+use clap::Args;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use take_benchmark::manifest::{DatasetManifest, MANIFEST_FILE};
+
+#[derive(Args, Debug)]
+pub struct CleanArgs {
+    /// Directory holding generated datasets (searched recursively)
+    #[arg(long)]
+    pub output_dir: PathBuf,
+
+    /// Only remove datasets generated longer ago than this (e.g. 7d)
+    #[arg(long, value_parser = parse_duration)]
+    pub older_than: Option<Duration>,
+
+    /// Keep datasets with these fingerprints, as printed by the benchmarks (comma-separated)
+    ///
+    /// Pass the fingerprints of the current configuration to remove only
+    /// datasets that it would regenerate anyway.
+    #[arg(long, value_delimiter = ',')]
+    pub keep_fingerprint: Vec<String>,
+
+    /// List what would be removed without removing anything
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+/// A generated dataset found under the output directory.
+struct FoundDataset {
+    path: PathBuf,
+    /// None if the manifest can't be parsed
+    manifest: Option<DatasetManifest>,
+    /// Age of the manifest, written right after the dataset
+    age: Duration,
+    bytes: u64,
+}
+
+fn directory_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn find_datasets(dir: &Path) -> Result<Vec<FoundDataset>> {
+    let mut datasets = Vec::new();
+    for entry in walkdir::WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_name() != MANIFEST_FILE || !entry.file_type().is_file() {
+            continue;
+        }
+        let path = entry.path().parent().unwrap().to_path_buf();
+        let manifest = std::fs::read(entry.path())
+            .ok()
+            .and_then(|contents| serde_json::from_slice(&contents).ok());
+        let age = SystemTime::now()
+            .duration_since(entry.metadata()?.modified()?)
+            .unwrap_or_default();
+        let bytes = directory_size(&path);
+        datasets.push(FoundDataset {
+            path,
+            manifest,
+            age,
+            bytes,
+        });
+    }
+    Ok(datasets)
+}
+
+fn gb(bytes: u64) -> f64 {
+    bytes as f64 / 1024.0 / 1024.0 / 1024.0
+}
+
+/// Remove the generated datasets selected by `args` and report the space reclaimed.
+pub fn clean(args: &CleanArgs) -> Result<()> {
+    if !args.output_dir.is_dir() {
+        anyhow::bail!("{} is not a directory", args.output_dir.display());
+    }
+
+    let datasets = find_datasets(&args.output_dir)?;
+    let mut removed = 0;
+    let mut reclaimed = 0u64;
+    for dataset in &datasets {
+        let fingerprint = dataset.manifest.as_ref().map(|m| m.fingerprint.as_str());
+        let keep_reason = if args.older_than.is_some_and(|min_age| dataset.age < min_age) {
+            Some("too recent")
+        } else if fingerprint.is_some_and(|fp| args.keep_fingerprint.iter().any(|k| k == fp)) {
+            Some("fingerprint kept")
+        } else {
+            None
+        };
+
+        let description = format!(
+            "{} ({}, fingerprint {}, {:.2} GB, {:.1} days old)",
+            dataset.path.display(),
+            dataset
+                .manifest
+                .as_ref()
+                .map_or("unknown engine", |m| m.engine.as_str()),
+            fingerprint.unwrap_or("unreadable"),
+            gb(dataset.bytes),
+            dataset.age.as_secs_f64() / 86400.0
+        );
+        match keep_reason {
+            Some(reason) => println!("Keeping {}: {}", description, reason),
+            None if args.dry_run => println!("Would remove {}", description),
+            None => {
+                std::fs::remove_dir_all(&dataset.path).map_err(|e| {
+                    anyhow::anyhow!("Failed to remove {}: {}", dataset.path.display(), e)
+                })?;
+                println!("Removed {}", description);
+            }
+        }
+        if keep_reason.is_none() {
+            removed += 1;
+            reclaimed += dataset.bytes;
+        }
+    }
+
+    println!(
+        "\n{} {} of {} dataset(s), {:.2} GB",
+        if args.dry_run {
+            "Would remove"
+        } else {
+            "Removed"
+        },
+        removed,
+        datasets.len(),
+        gb(reclaimed)
+    );
+    Ok(())
+}
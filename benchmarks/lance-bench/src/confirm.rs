@@ -0,0 +1,169 @@
+//! Re-running suspected regressions before `lance-bench gate` fails.
+//!
+//! With `--confirm-runs N` and the take arguments that produced the current
+//! results after `--`, every engine with a regressed phase is run N more
+//! times, limited to the regressed query sizes:
+//!
+//! ```text
+//! lance-bench gate --baseline main.json --current pr.json --confirm-runs 4 \
+//!     -- --engines lance,parquet --dataset-uri /data/take --duration 30s
+//! ```
+//!
+//! A regression only stands if [`confirms_regression`] finds it across the
+//! current run and the re-runs together. Engines from other builds
+//! (`<engine>@<label>`) can't be re-run here, so their regressions are kept.
+
+use anyhow::Result;
+use clap::Args;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+use take_benchmark::external::strip_flags;
+use take_benchmark::gate::{confirms_regression, Failure, GateOptions, Violation};
+use take_benchmark::results::{read_results, EngineResult};
+
+/// Flags of the take arguments that each re-run sets itself, and whether each takes a value.
+const RERUN_FLAGS: &[(&str, bool)] = &[
+    ("--engines", true),
+    ("-e", true),
+    ("--rows-per-query", true),
+    ("--output", true),
+    ("--resume", false),
+    ("--lance-build", true),
+];
+
+#[derive(Args, Debug)]
+pub struct ConfirmArgs {
+    /// Re-run each regressed engine this many more times and fail only on
+    /// regressions that are significant across all runs
+    #[arg(long, default_value_t = 0, requires = "take_args")]
+    pub confirm_runs: usize,
+
+    /// Directory of the re-runs' results files
+    #[arg(long, default_value = "confirm-results")]
+    pub confirm_dir: PathBuf,
+
+    /// Arguments of the take benchmark that produced the current results (after `--`)
+    #[arg(last = true)]
+    pub take_args: Vec<String>,
+}
+
+/// Run `engine` once more with only `rows_per_query`, returning its results.
+fn rerun(
+    args: &ConfirmArgs,
+    engine: &str,
+    rows_per_query: &[usize],
+    run: usize,
+) -> Result<Vec<EngineResult>> {
+    let output = args.confirm_dir.join(format!("{}-{}.json", engine, run));
+    let rows: Vec<String> = rows_per_query.iter().map(|r| r.to_string()).collect();
+    println!(
+        "\nRe-running {} ({} of {}), results in {}",
+        engine,
+        run,
+        args.confirm_runs,
+        output.display()
+    );
+    let status = Command::new(std::env::current_exe()?)
+        .arg("take")
+        .args(strip_flags(&args.take_args, RERUN_FLAGS))
+        .arg("--engines")
+        .arg(engine)
+        .arg("--rows-per-query")
+        .arg(rows.join(","))
+        .arg("--output")
+        .arg(&output)
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("Re-run {} of {} failed: {}", run, engine, status);
+    }
+    read_results(&output)
+}
+
+/// The violations that still stand after re-running the regressed engines.
+///
+/// Missing phases and failed engines are kept as they are.
+pub fn confirm(
+    args: &ConfirmArgs,
+    violations: Vec<Violation>,
+    options: &GateOptions,
+) -> Result<Vec<Violation>> {
+    // Query sizes to re-run for each engine
+    let mut regressed: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for violation in &violations {
+        if let Failure::Regressed { .. } = violation.failure {
+            let rows = regressed.entry(violation.phase.engine.clone()).or_default();
+            if !rows.contains(&violation.phase.rows_per_query) {
+                rows.push(violation.phase.rows_per_query);
+            }
+        }
+    }
+    regressed.retain(|engine, _| {
+        let external = engine.contains('@');
+        if external {
+            println!(
+                "Warning: {} comes from another build and can't be re-run, keeping its regressions",
+                engine
+            );
+        }
+        !external
+    });
+    if regressed.is_empty() {
+        return Ok(violations);
+    }
+
+    std::fs::create_dir_all(&args.confirm_dir)?;
+    let mut reruns = Vec::new();
+    for (engine, rows_per_query) in &regressed {
+        for run in 1..=args.confirm_runs {
+            reruns.extend(rerun(args, engine, rows_per_query, run)?);
+        }
+    }
+
+    println!("\n{}", "=".repeat(60));
+    println!(
+        "Confirming regressions over {} more runs",
+        args.confirm_runs
+    );
+    println!("{}", "=".repeat(60));
+    let mut confirmed = Vec::new();
+    for violation in violations {
+        let Failure::Regressed {
+            metric,
+            baseline,
+            current,
+        } = violation.failure
+        else {
+            confirmed.push(violation);
+            continue;
+        };
+        if !regressed.contains_key(&violation.phase.engine) {
+            confirmed.push(violation);
+            continue;
+        }
+        let mut samples = vec![current];
+        samples.extend(
+            reruns
+                .iter()
+                .filter(|r| r.error.is_none())
+                .flat_map(|r| r.phases.iter().map(move |p| (r, p)))
+                .filter(|(r, p)| violation.phase.matches(r, p))
+                .map(|(_, p)| metric.value(p)),
+        );
+        let mean = samples.iter().sum::<f64>() / samples.len() as f64;
+        let significant = confirms_regression(metric, baseline, &samples, options);
+        println!(
+            "  {} {}: {} -> mean {} over {} runs ({})",
+            violation.phase,
+            metric.name(),
+            metric.format(baseline),
+            metric.format(mean),
+            samples.len(),
+            if significant { "confirmed" } else { "noise" }
+        );
+        if significant {
+            confirmed.push(violation);
+        }
+    }
+    Ok(confirmed)
+}
@@ -0,0 +1,431 @@
+//! lance-bench
+//!
+//! A single entry point for the benchmarks in this repository, with one
+//! subcommand per benchmark (`lance-bench take …`, `lance-bench ann …`,
+//! `lance-bench blob …`, `lance-bench sql …`, `lance-bench flight …`,
+//! `lance-bench versions …`, `lance-bench commits …`) plus tools that work
+//! on take results (`lance-bench compare …`, `lance-bench gate …`, `lance-bench pr-comment …`,
+//! `lance-bench trend …`) and `lance-bench bisect …` to find the Lance commit
+//! behind a regression (see [`bisect`]). `lance-bench run --suite` runs a whole suite of benchmarks
+//! described in a YAML file (see [`suite`]), `lance-bench list
+//! engines|benchmarks` shows what this build can run and `lance-bench clean`
+//! removes generated datasets (see [`clean`]).
+//!
+//! Flags shared by every benchmark, such as `--seed` and `--output`, come from
+//! `bench_core::cli::CommonArgs` so they are spelled the same in every
+//! subcommand.
+
+use anyhow::Result;
+use bench_core::duration::parse_duration;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod bisect;
+mod clean;
+mod confirm;
+mod suite;
+
+extern crate jemallocator;
+
+#[cfg(not(feature = "dhat-heap"))]
+#[global_allocator]
+static GLOBAL: jemallocator::Jemalloc = jemallocator::Jemalloc;
+
+// dhat only sees allocations made through it (--profile heap)
+#[cfg(feature = "dhat-heap")]
+#[global_allocator]
+static GLOBAL: dhat::Alloc = dhat::Alloc;
+
+#[derive(Parser, Debug)]
+#[command(name = "lance-bench")]
+#[command(about = "Lance storage benchmarks")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Benchmark take (point lookup) performance across storage engines
+    Take(take_benchmark::Config),
+    /// Benchmark vector search latency and recall across index types
+    Ann(ann_benchmark::Config),
+    /// Benchmark retrieval of large binary values from Lance, Parquet and files
+    Blob(blob_benchmark::Config),
+    /// Benchmark SQL queries through DataFusion across storage engines
+    Sql(sql_benchmark::Config),
+    /// Benchmark takes and scans served over Arrow Flight across storage engines
+    Flight(flight_benchmark::Config),
+    /// Benchmark how Lance dataset open cost grows with the number of versions
+    Versions(versions_benchmark::Config),
+    /// Benchmark concurrent commits to one Lance dataset
+    Commits(commits_benchmark::Config),
+    /// Run every benchmark of a suite file in order
+    Run {
+        /// Suite file (YAML)
+        #[arg(long)]
+        suite: PathBuf,
+
+        /// Resume an interrupted run of the same suite file, keeping its results
+        #[arg(long)]
+        resume: bool,
+
+        /// Validate every run and print its execution plan without running it
+        #[arg(long, conflicts_with = "resume")]
+        dry_run: bool,
+    },
+    /// Remove generated datasets and report the space reclaimed
+    Clean(clean::CleanArgs),
+    /// List what this build can run
+    List {
+        #[command(subcommand)]
+        what: ListCommand,
+    },
+    /// Compare the results of earlier runs side by side
+    Compare {
+        /// Results files written with --output
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Fail if a results file regressed against a baseline
+    Gate {
+        #[command(flatten)]
+        gate: GateArgs,
+
+        #[command(flatten)]
+        confirm: confirm::ConfirmArgs,
+    },
+    /// Write a Markdown comparison of two results files for a PR comment
+    PrComment(PrCommentArgs),
+    /// Show a metric's history across results files, with changepoints marked
+    Trend(TrendArgs),
+    /// Find the Lance commit that introduced a regression
+    Bisect(bisect::BisectArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct GateArgs {
+    /// Results file of the baseline run
+    #[arg(long)]
+    baseline: PathBuf,
+
+    /// Results file of the run to check
+    #[arg(long)]
+    current: PathBuf,
+
+    /// Relative change that counts as a regression (e.g. 5% or 0.05)
+    #[arg(long, default_value = "5%", value_parser = take_benchmark::gate::parse_threshold)]
+    threshold: f64,
+
+    /// Smallest latency increase that counts as a regression
+    #[arg(long, default_value = "0ms", value_parser = parse_duration)]
+    min_effect: Duration,
+}
+
+#[derive(clap::Args, Debug)]
+struct PrCommentArgs {
+    #[command(flatten)]
+    gate: GateArgs,
+
+    /// Write the comment to this file instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ListCommand {
+    /// Storage engines, with their capabilities and whether they are compiled in
+    Engines,
+    /// Benchmark subcommands
+    Benchmarks,
+}
+
+/// Benchmark subcommands and what they measure.
+const BENCHMARKS: &[(&str, &str)] = &[
+    (
+        "take",
+        "Take (point lookup) performance across storage engines",
+    ),
+    ("ann", "Vector search latency and recall across index types"),
+    (
+        "blob",
+        "Retrieval of large binary values from Lance, Parquet and files",
+    ),
+    ("sql", "SQL query latency through DataFusion across engines"),
+    (
+        "flight",
+        "Takes and scans served over Arrow Flight, with the overhead Flight adds",
+    ),
+    (
+        "versions",
+        "Lance dataset open and checkout latency as the version count grows",
+    ),
+    (
+        "commits",
+        "Commit latency, retries and throughput of concurrent Lance writers",
+    ),
+];
+
+fn list_engines() {
+    let registry = take_benchmark::engines::create_registry(Default::default(), &[]);
+    let yes_no = |supported: bool| if supported { "yes" } else { "no" };
+
+    println!(
+        "{:<24} {:>6} {:>8} {:>8} {:>12} {:>14} {:>12} {:>10} {:>8} {:>10} {:>8} {:>6} {:>6}",
+        "Engine",
+        "Take",
+        "Lookup",
+        "Filter",
+        "Projection",
+        "Object store",
+        "Direct I/O",
+        "In memory",
+        "Delete",
+        "Fragments",
+        "Compact",
+        "Scan",
+        "Count"
+    );
+    for engine in registry.engines() {
+        println!(
+            "{:<24} {:>6} {:>8} {:>8} {:>12} {:>14} {:>12} {:>10} {:>8} {:>10} {:>8} {:>6} {:>6}",
+            engine.name(),
+            yes_no(engine.supports_take()),
+            yes_no(engine.supports_lookup()),
+            yes_no(engine.supports_filter_pushdown()),
+            yes_no(engine.supports_projection()),
+            yes_no(engine.supports_object_store()),
+            yes_no(engine.supports_direct_io()),
+            yes_no(engine.supports_in_memory()),
+            yes_no(engine.supports_delete()),
+            yes_no(engine.supports_fragments()),
+            yes_no(engine.supports_compaction()),
+            yes_no(engine.supports_scan()),
+            yes_no(engine.supports_count())
+        );
+    }
+    println!("\nWithout filter pushdown or projection the benchmark filters and projects taken rows itself.");
+    for (name, feature) in registry.missing() {
+        println!(
+            "{:<24} not compiled in (build with `--features {}`)",
+            name, feature
+        );
+    }
+}
+
+fn list_benchmarks() {
+    for (name, description) in BENCHMARKS {
+        println!("{:<16} {}", name, description);
+    }
+}
+
+/// Print one comparison table across the engines of every results file.
+///
+/// With several files, engines are labelled with the file they came from so
+/// runs of the same engine can be told apart.
+fn compare(files: &[PathBuf]) -> Result<()> {
+    let mut results = Vec::new();
+    for file in files {
+        for mut result in take_benchmark::results::read_results(file)? {
+            if files.len() > 1 {
+                let stem = file.file_stem().unwrap_or_default().to_string_lossy();
+                result.engine = format!("{}@{}", result.engine, stem);
+            }
+            results.push(result);
+        }
+    }
+    take_benchmark::results::print_comparison(&results);
+    Ok(())
+}
+
+#[derive(clap::Args, Debug)]
+struct TrendArgs {
+    /// Results files, or directories searched for `*.json` results files
+    #[arg(required = true)]
+    history: Vec<PathBuf>,
+
+    /// Engine whose history to show
+    #[arg(long)]
+    engine: String,
+
+    /// Metric to show
+    #[arg(long, value_enum, default_value = "p50")]
+    metric: take_benchmark::gate::Metric,
+}
+
+/// Results files among `paths`, expanding directories.
+fn results_files(paths: &[PathBuf]) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        if path.is_dir() {
+            for entry in walkdir::WalkDir::new(path).into_iter().flatten() {
+                if entry.path().extension().is_some_and(|ext| ext == "json") {
+                    files.push(entry.into_path());
+                }
+            }
+        } else {
+            files.push(path.clone());
+        }
+    }
+    files
+}
+
+/// Print the history of `args.metric` for `args.engine`.
+fn trend(args: &TrendArgs) -> Result<()> {
+    use take_benchmark::trend::{print_trend, series, HistoryPoint};
+
+    let mut history = Vec::new();
+    for file in results_files(&args.history) {
+        // Directories can hold suite files and other JSON
+        match take_benchmark::results::read_timestamped_results(&file) {
+            Ok((timestamp, results)) => history.push(HistoryPoint {
+                timestamp,
+                label: file
+                    .file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into(),
+                results,
+            }),
+            Err(e) => println!("Warning: skipping {}: {:#}", file.display(), e),
+        }
+    }
+    history.sort_by_key(|point| point.timestamp);
+
+    let series = series(&history, &args.engine, args.metric);
+    if series.is_empty() {
+        anyhow::bail!("No results for engine '{}'", args.engine);
+    }
+    print_trend(&history, &series, args.metric);
+    Ok(())
+}
+
+/// Check `args.current` against `args.baseline`, failing on any regression.
+///
+/// With `--confirm-runs`, regressions are re-run first (see [`confirm`]).
+fn gate(args: &GateArgs, confirm_args: &confirm::ConfirmArgs) -> Result<()> {
+    use take_benchmark::gate::{check, print_violations, GateOptions};
+
+    let baseline = take_benchmark::results::read_results(&args.baseline)?;
+    let current = take_benchmark::results::read_results(&args.current)?;
+    let options = GateOptions {
+        threshold: args.threshold,
+        min_effect: args.min_effect,
+    };
+    let mut violations = check(&baseline, &current, &options);
+    if !violations.is_empty() && confirm_args.confirm_runs > 0 {
+        violations = confirm::confirm(confirm_args, violations, &options)?;
+    }
+    if violations.is_empty() {
+        println!(
+            "No regressions beyond {:.1}% (min effect {:?})",
+            args.threshold * 100.0,
+            args.min_effect
+        );
+        return Ok(());
+    }
+    print_violations(&violations);
+    anyhow::bail!(
+        "{} regression(s) against {}",
+        violations.len(),
+        args.baseline.display()
+    )
+}
+
+/// Render the PR comment for `args.current` against `args.baseline`.
+fn pr_comment(args: &PrCommentArgs) -> Result<()> {
+    use take_benchmark::gate::GateOptions;
+
+    let gate = &args.gate;
+    let baseline = take_benchmark::results::read_results(&gate.baseline)?;
+    let current = take_benchmark::results::read_results(&gate.current)?;
+    let options = GateOptions {
+        threshold: gate.threshold,
+        min_effect: gate.min_effect,
+    };
+    let name = |path: &PathBuf| {
+        path.file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .into_owned()
+    };
+    let comment = take_benchmark::markdown::pr_comment(
+        &baseline,
+        &current,
+        &name(&gate.baseline),
+        &name(&gate.current),
+        &options,
+    );
+    match &args.output {
+        Some(path) => {
+            std::fs::write(path, comment)?;
+            eprintln!("PR comment written to {}", path.display());
+        }
+        None => print!("{}", comment),
+    }
+    Ok(())
+}
+
+fn main() -> Result<()> {
+    env_logger::init();
+
+    match Cli::parse().command {
+        Command::Take(config) => {
+            take_benchmark::run(config)?;
+            Ok(())
+        }
+        Command::Ann(config) => {
+            ann_benchmark::run(config)?;
+            Ok(())
+        }
+        Command::Blob(config) => {
+            blob_benchmark::run(config)?;
+            Ok(())
+        }
+        Command::Sql(config) => {
+            sql_benchmark::run(config)?;
+            Ok(())
+        }
+        Command::Flight(config) => {
+            flight_benchmark::run(config)?;
+            Ok(())
+        }
+        Command::Versions(config) => {
+            versions_benchmark::run(config)?;
+            Ok(())
+        }
+        Command::Commits(config) => {
+            commits_benchmark::run(config)?;
+            Ok(())
+        }
+        Command::Run {
+            suite,
+            resume,
+            dry_run,
+        } => {
+            let suite = suite::Suite::load(&suite)?;
+            if dry_run {
+                suite.dry_run()
+            } else {
+                suite.run(resume)
+            }
+        }
+        Command::Clean(args) => clean::clean(&args),
+        Command::List { what } => {
+            match what {
+                ListCommand::Engines => list_engines(),
+                ListCommand::Benchmarks => list_benchmarks(),
+            }
+            Ok(())
+        }
+        Command::Compare { files } => compare(&files),
+        Command::Gate {
+            gate: args,
+            confirm,
+        } => gate(&args, &confirm),
+        Command::PrComment(args) => pr_comment(&args),
+        Command::Trend(args) => trend(&args),
+        Command::Bisect(args) => bisect::bisect(&args),
+    }
+}
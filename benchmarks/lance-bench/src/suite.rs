@@ -0,0 +1,407 @@
+//! Benchmark suites.
+//!
+//! A suite file lists benchmark runs to execute one after another, so a
+//! nightly machine can run everything with a single command and get a single
+//! results file:
+//!
+//! ```yaml
+//! name: nightly
+//! output: results/nightly.json
+//! benchmarks:
+//!   - name: take-hot
+//!     benchmark: take
+//!     args:
+//!       engines: [lance, parquet]
+//!       dataset-uri: /data/take
+//!       duration: 60s
+//!       take-filter: true
+//!     matrix:
+//!       rows-per-query: [1, 100]
+//!       vector-dim: [128, 768]
+//! ```
+//!
+//! `args` are the benchmark's own command-line flags without the leading
+//! `--`: `true` passes a bare flag, `false` leaves it out and lists are joined
+//! with commas. Each `matrix` entry is swept: the benchmark runs once for
+//! every combination of its values.
+
+use anyhow::Result;
+use clap::Parser;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use take_benchmark::results::EngineResult;
+
+/// A suite file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Suite {
+    pub name: String,
+    /// Combined results file, rewritten after every run
+    pub output: PathBuf,
+    pub benchmarks: Vec<SuiteEntry>,
+}
+
+/// One benchmark of a suite, possibly expanded into several runs by `matrix`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteEntry {
+    pub name: String,
+    /// Benchmark to run (a `lance-bench` subcommand, e.g. `take`)
+    pub benchmark: String,
+    #[serde(default)]
+    pub args: BTreeMap<String, Value>,
+    #[serde(default)]
+    pub matrix: BTreeMap<String, Vec<Value>>,
+}
+
+/// A single, fully expanded benchmark invocation.
+pub struct SuiteRun {
+    pub name: String,
+    pub benchmark: String,
+    /// Command-line arguments for the benchmark
+    pub args: Vec<String>,
+}
+
+/// Format a flag value as it is written on the command line.
+fn value_string(key: &str, value: &Value) -> Result<String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Sequence(values) => Ok(values
+            .iter()
+            .map(|value| value_string(key, value))
+            .collect::<Result<Vec<_>>>()?
+            .join(",")),
+        other => anyhow::bail!("Unsupported value for '{}': {:?}", key, other),
+    }
+}
+
+/// Turn `key: value` into command-line arguments.
+fn flag(key: &str, value: &Value) -> Result<Vec<String>> {
+    if key == "output" {
+        anyhow::bail!("Set output at the suite level, not per benchmark");
+    }
+    Ok(match value {
+        Value::Bool(true) => vec![format!("--{}", key)],
+        Value::Bool(false) => vec![],
+        value => vec![format!("--{}", key), value_string(key, value)?],
+    })
+}
+
+impl SuiteEntry {
+    /// Expand `matrix` into one run per combination of its values.
+    pub fn runs(&self) -> Result<Vec<SuiteRun>> {
+        // Each combination is a list of (key, value) picks, one per matrix key
+        let mut combinations: Vec<Vec<(&String, &Value)>> = vec![vec![]];
+        for (key, values) in &self.matrix {
+            if values.is_empty() {
+                anyhow::bail!("{}: matrix entry '{}' has no values", self.name, key);
+            }
+            combinations = combinations
+                .into_iter()
+                .flat_map(|picks| {
+                    values.iter().map(move |value| {
+                        let mut picks = picks.clone();
+                        picks.push((key, value));
+                        picks
+                    })
+                })
+                .collect();
+        }
+
+        combinations
+            .into_iter()
+            .map(|picks| {
+                let mut args = Vec::new();
+                for (key, value) in self.args.iter().chain(picks.iter().copied()) {
+                    args.extend(flag(key, value)?);
+                }
+                let name = if picks.is_empty() {
+                    self.name.clone()
+                } else {
+                    let picks = picks
+                        .iter()
+                        .map(|(key, value)| Ok(format!("{}={}", key, value_string(key, value)?)))
+                        .collect::<Result<Vec<_>>>()?;
+                    format!("{}[{}]", self.name, picks.join(","))
+                };
+                Ok(SuiteRun {
+                    name,
+                    benchmark: self.benchmark.clone(),
+                    args,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A parsed run, ready to execute.
+enum Runnable {
+    Take(Box<take_benchmark::Config>),
+}
+
+impl SuiteRun {
+    /// Parse the run's arguments with its benchmark's own parser.
+    fn parse(&self) -> Result<Runnable> {
+        match self.benchmark.as_str() {
+            "take" => {
+                let argv: Vec<String> = std::iter::once("take".to_string())
+                    .chain(self.args.iter().cloned())
+                    .collect();
+                let mut config = take_benchmark::Config::try_parse_from(&argv)
+                    .map_err(|e| anyhow::anyhow!("{}: {}", self.name, e))?;
+                // Worker processes re-run this configuration through `lance-bench take`
+                config.worker_args = Some(argv);
+                Ok(Runnable::Take(Box::new(config)))
+            }
+            other => anyhow::bail!("{}: unknown benchmark '{}'", self.name, other),
+        }
+    }
+}
+
+/// Results of one run, as written to the combined results file.
+#[derive(Serialize, Deserialize)]
+struct SuiteRunResult {
+    name: String,
+    benchmark: String,
+    args: Vec<String>,
+    results: Vec<EngineResult>,
+}
+
+/// Combined results file of a suite.
+#[derive(Serialize)]
+struct SuiteOutput<'a> {
+    benchmark_type: &'static str,
+    suite: &'a str,
+    timestamp: u64,
+    runs: &'a [SuiteRunResult],
+}
+
+/// The parts of an existing combined results file needed to resume a suite.
+#[derive(Deserialize)]
+struct SuiteInput {
+    timestamp: u64,
+    runs: Vec<SuiteRunResult>,
+}
+
+impl Suite {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+        serde_yaml::from_str(&contents)
+            .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))
+    }
+
+    /// Every run of the suite, in order.
+    pub fn runs(&self) -> Result<Vec<SuiteRun>> {
+        let mut runs = Vec::new();
+        for entry in &self.benchmarks {
+            runs.extend(entry.runs()?);
+        }
+        Ok(runs)
+    }
+
+    /// Per-engine results of run `index` while it is in progress.
+    ///
+    /// The benchmark writes it after every engine, so resuming an interrupted
+    /// run skips the engines it already finished.
+    fn partial_output(&self, index: usize) -> PathBuf {
+        let mut dir = self.output.clone().into_os_string();
+        dir.push(".partial");
+        PathBuf::from(dir).join(format!("run-{}.json", index))
+    }
+
+    fn write_output(&self, timestamp: u64, runs: &[SuiteRunResult]) -> Result<()> {
+        let output = SuiteOutput {
+            benchmark_type: "suite",
+            suite: &self.name,
+            timestamp,
+            runs,
+        };
+        if let Some(parent) = self.output.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.output, serde_json::to_string_pretty(&output)?)?;
+        Ok(())
+    }
+
+    fn print_run_header(&self, index: usize, total: usize, run: &SuiteRun) {
+        println!("\n{}", "#".repeat(60));
+        println!(
+            "Suite {}: run {}/{}: {}",
+            self.name,
+            index + 1,
+            total,
+            run.name
+        );
+        println!("{}", "#".repeat(60));
+    }
+
+    /// Dry-run every benchmark of the suite, writing nothing.
+    pub fn dry_run(&self) -> Result<()> {
+        let runs = self.runs()?;
+        let parsed = runs
+            .iter()
+            .map(|run| run.parse())
+            .collect::<Result<Vec<_>>>()?;
+
+        for (i, (run, runnable)) in runs.iter().zip(parsed).enumerate() {
+            self.print_run_header(i, runs.len(), run);
+
+            match runnable {
+                Runnable::Take(mut config) => {
+                    config.common.dry_run = true;
+                    take_benchmark::run(*config)?;
+                }
+            }
+        }
+
+        println!(
+            "\nSuite {}: dry run of {} runs OK, results would be written to {}",
+            self.name,
+            runs.len(),
+            self.output.display()
+        );
+        Ok(())
+    }
+
+    /// Run every benchmark of the suite in order.
+    ///
+    /// All runs are parsed before the first one starts, so a typo fails fast.
+    /// The combined results file is rewritten after each run, so an aborted
+    /// suite keeps the results of the runs that completed. With `resume`,
+    /// runs already in that file are skipped, as are the engines an
+    /// interrupted run had finished; the suite file must not have changed.
+    pub fn run(&self, resume: bool) -> Result<()> {
+        let runs = self.runs()?;
+        let parsed = runs
+            .iter()
+            .map(|run| run.parse())
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)?
+            .as_secs();
+        let mut completed = Vec::new();
+        if resume && self.output.exists() {
+            let contents = std::fs::read_to_string(&self.output)?;
+            let input: SuiteInput = serde_json::from_str(&contents)
+                .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", self.output.display(), e))?;
+            timestamp = input.timestamp;
+            completed = input.runs;
+        }
+
+        let mut results = Vec::with_capacity(runs.len());
+        for (i, (run, runnable)) in runs.iter().zip(parsed).enumerate() {
+            if let Some(pos) = completed.iter().position(|done| done.name == run.name) {
+                println!("\nSkipping run {}: already complete", run.name);
+                results.push(completed.remove(pos));
+                continue;
+            }
+
+            self.print_run_header(i, runs.len(), run);
+
+            let partial = self.partial_output(i);
+            let engine_results = match runnable {
+                Runnable::Take(mut config) => {
+                    config.common.output = Some(partial.clone());
+                    config.common.resume = resume;
+                    take_benchmark::run(*config)?
+                }
+            };
+            results.push(SuiteRunResult {
+                name: run.name.clone(),
+                benchmark: run.benchmark.clone(),
+                args: run.args.clone(),
+                results: engine_results,
+            });
+            self.write_output(timestamp, &results)?;
+            std::fs::remove_file(&partial)?;
+        }
+        if let Some(dir) = self.partial_output(0).parent() {
+            let _ = std::fs::remove_dir(dir);
+        }
+
+        println!(
+            "\nSuite {} complete: {} runs, results written to {}",
+            self.name,
+            runs.len(),
+            self.output.display()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_expansion() {
+        let entry: SuiteEntry = serde_yaml::from_str(
+            "
+name: take
+benchmark: take
+args:
+  engines: [lance, parquet]
+  take-filter: true
+  unique-indices: false
+matrix:
+  rows-per-query: [1, 100]
+  vector-dim: [128]
+",
+        )
+        .unwrap();
+        let runs = entry.runs().unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].name, "take[rows-per-query=1,vector-dim=128]");
+        assert_eq!(
+            runs[1].args,
+            [
+                "--engines",
+                "lance,parquet",
+                "--take-filter",
+                "--rows-per-query",
+                "100",
+                "--vector-dim",
+                "128"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_runs_start_from_default_settings() {
+        let dir = std::env::temp_dir().join("lance-bench-suite-settings");
+        let suite: Suite = serde_yaml::from_str(&format!(
+            "
+name: settings
+output: {dir}/results.json
+benchmarks:
+  - name: direct
+    benchmark: take
+    args:
+      engines: parquet
+      dataset-uri: {dir}
+      direct-io: true
+      lance-stable-row-ids: true
+  - name: buffered
+    benchmark: take
+    args:
+      engines: parquet
+      dataset-uri: {dir}
+",
+            dir = dir.display()
+        ))
+        .unwrap();
+        suite.dry_run().unwrap();
+        assert!(!take_benchmark::engines::direct_io());
+        assert!(!take_benchmark::engines::lance_stable_row_ids());
+        assert_eq!(
+            bench_core::cache::cache_drop_mode(),
+            bench_core::cache::CacheDropMode::Fadvise
+        );
+    }
+}
@@ -0,0 +1,344 @@
+//! SQL Benchmark
+//!
+//! Runs a user-supplied list of SQL queries through DataFusion against the
+//! same generated rows stored by each engine:
+//! - Lance, registered through Lance's `LanceTableProvider`
+//! - Parquet, registered through DataFusion's own Parquet reader
+//!
+//! Every engine's dataset is registered as the table `data`, with the
+//! columns of the take benchmark's datasets (`id`, `flag` and `vector`), so
+//! new workloads are new queries rather than new Rust code. Serialized
+//! Substrait plans can be given as well, to replay workloads captured from
+//! other systems exactly (see [`substrait`]). Each query is planned, run and
+//! collected in full several times per engine, and its latency reported per
+//! engine.
+//!
+//! Runs as `lance-bench sql`.
+
+use anyhow::Result;
+use bench_core::cache::drop_directory_cache;
+use bench_core::cli::CommonArgs;
+use bench_core::stats::compute_statistics;
+use clap::Parser;
+use datafusion::prelude::SessionContext;
+use datafusion_substrait::substrait::proto::Plan;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::runtime::Runtime;
+
+pub mod results;
+pub mod substrait;
+pub mod tables;
+
+use results::QueryResult;
+use tables::Engine;
+
+/// Name every engine's dataset is registered under.
+pub const TABLE: &str = "data";
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "sql-benchmark")]
+#[command(about = "Benchmark SQL queries through DataFusion across storage engines")]
+pub struct Config {
+    /// Directory the datasets are written to, rewritten on every run
+    #[arg(long, default_value = "/tmp/sql-bench")]
+    pub data_dir: PathBuf,
+
+    /// Rows in each engine's dataset
+    #[arg(long, default_value_t = 1_000_000)]
+    pub rows: usize,
+
+    /// Batch size when writing data
+    #[arg(long, default_value_t = 100_000)]
+    pub write_batch_size: usize,
+
+    /// Vector dimension
+    #[arg(long, default_value_t = 768)]
+    pub vector_dim: usize,
+
+    /// Engines to benchmark (comma-separated)
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "lance,parquet"
+    )]
+    pub engines: Vec<Engine>,
+
+    /// SQL query to run against the table `data` (repeatable)
+    #[arg(long = "query")]
+    pub queries: Vec<String>,
+
+    /// File of SQL queries separated by `;`, run after any given with --query
+    ///
+    /// Lines starting with `--` are comments.
+    #[arg(long)]
+    pub query_file: Option<PathBuf>,
+
+    /// Serialized (binary protobuf) Substrait plan to replay (repeatable),
+    /// run after the SQL queries
+    #[arg(long = "substrait-plan")]
+    pub substrait_plans: Vec<PathBuf>,
+
+    /// Also register every dataset under this name (repeatable), for plans
+    /// captured against a table named differently
+    #[arg(long = "table-alias")]
+    pub table_aliases: Vec<String>,
+
+    /// Timed runs of each query per engine
+    #[arg(long, default_value_t = 10)]
+    pub iterations: usize,
+
+    /// Untimed runs of each query per engine before the timed ones
+    #[arg(long, default_value_t = 1)]
+    pub warmup: usize,
+
+    /// Rows per Parquet row group
+    #[arg(long, default_value_t = 100_000)]
+    pub parquet_rows_per_group: usize,
+
+    /// Drop the dataset from the page cache before every timed run
+    #[arg(long)]
+    pub cold: bool,
+
+    #[command(flatten)]
+    pub common: CommonArgs,
+}
+
+impl Config {
+    fn validate(&self) -> Result<()> {
+        if self.common.resume {
+            anyhow::bail!("--resume is not supported by the SQL benchmark");
+        }
+        if self.rows == 0 || self.write_batch_size == 0 || self.vector_dim == 0 {
+            anyhow::bail!("--rows, --write-batch-size and --vector-dim must be positive");
+        }
+        if self.iterations == 0 {
+            anyhow::bail!("--iterations must be positive");
+        }
+        if self.parquet_rows_per_group == 0 {
+            anyhow::bail!("--parquet-rows-per-group must be positive");
+        }
+        if self.engines.is_empty() {
+            anyhow::bail!("--engines must name at least one engine");
+        }
+        Ok(())
+    }
+
+    /// Every query to run: those given with --query, then those in
+    /// --query-file, then the --substrait-plan plans.
+    pub async fn load_queries(&self) -> Result<Vec<Query>> {
+        let mut sql = self.queries.clone();
+        if let Some(path) = &self.query_file {
+            let text = std::fs::read_to_string(path)?;
+            sql.extend(parse_queries(&text));
+        }
+        let mut queries: Vec<Query> = sql.into_iter().map(Query::Sql).collect();
+        for path in &self.substrait_plans {
+            queries.push(Query::Substrait {
+                path: path.clone(),
+                plan: substrait::load_plan(path).await?,
+            });
+        }
+        if queries.is_empty() {
+            anyhow::bail!("Give at least one query with --query, --query-file or --substrait-plan");
+        }
+        Ok(queries)
+    }
+}
+
+/// One query of the workload.
+#[derive(Debug, Clone)]
+pub enum Query {
+    Sql(String),
+    /// A Substrait plan, with the file it was read from
+    Substrait {
+        path: PathBuf,
+        plan: Box<Plan>,
+    },
+}
+
+impl Query {
+    /// How the query is shown in the report and results.
+    pub fn description(&self) -> String {
+        match self {
+            Query::Sql(sql) => sql.clone(),
+            Query::Substrait { path, .. } => format!("substrait:{}", path.display()),
+        }
+    }
+}
+
+/// Split a query file into its queries, dropping `--` comment lines.
+fn parse_queries(text: &str) -> Vec<String> {
+    let uncommented: Vec<&str> = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("--"))
+        .collect();
+    uncommented
+        .join("\n")
+        .split(';')
+        .map(|query| query.trim().to_string())
+        .filter(|query| !query.is_empty())
+        .collect()
+}
+
+fn print_plan(config: &Config, queries: &[Query]) {
+    println!("{}", "=".repeat(60));
+    println!("SQL Benchmark");
+    println!("{}", "=".repeat(60));
+    println!("Data directory: {}", config.data_dir.display());
+    println!(
+        "Rows: {} per engine (vector dim {})",
+        config.rows, config.vector_dim
+    );
+    println!(
+        "Engines: {}",
+        config
+            .engines
+            .iter()
+            .map(|e| e.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!(
+        "Runs: {} warmup + {} timed per query",
+        config.warmup, config.iterations
+    );
+    println!("Cache: {}", if config.cold { "cold" } else { "hot" });
+    println!("Queries (table '{}'):", TABLE);
+    for (index, query) in queries.iter().enumerate() {
+        println!("  Q{}: {}", index + 1, query.description());
+    }
+    if !config.table_aliases.is_empty() {
+        println!("Table aliases: {}", config.table_aliases.join(", "));
+    }
+    println!("{}", "=".repeat(60));
+}
+
+/// Plan, run and collect `query`, returning the number of rows it returned.
+async fn run_query(ctx: &SessionContext, query: &Query) -> Result<usize> {
+    let batches = match query {
+        Query::Sql(sql) => ctx.sql(sql).await?.collect().await?,
+        Query::Substrait { plan, .. } => substrait::execute(ctx, plan).await?,
+    };
+    Ok(batches.iter().map(|batch| batch.num_rows()).sum())
+}
+
+/// Run every query against `engine`'s dataset at `path`.
+async fn bench_engine(
+    engine: Engine,
+    path: &Path,
+    queries: &[Query],
+    config: &Config,
+) -> Result<Vec<QueryResult>> {
+    let ctx = SessionContext::new();
+    tables::register(&ctx, engine, path).await?;
+    if !config.table_aliases.is_empty() {
+        let provider = ctx.table_provider(TABLE).await?;
+        for alias in &config.table_aliases {
+            ctx.register_table(alias.as_str(), provider.clone())?;
+        }
+    }
+
+    let pb = ProgressBar::new((queries.len() * config.iterations) as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("  [{bar:40}] {pos}/{len} runs ({eta} remaining)")
+            .unwrap(),
+    );
+    let mut results = Vec::with_capacity(queries.len());
+    for (query_index, query) in queries.iter().enumerate() {
+        for _ in 0..config.warmup {
+            run_query(&ctx, query).await?;
+        }
+        let mut latencies = Vec::with_capacity(config.iterations);
+        let mut rows = 0;
+        for _ in 0..config.iterations {
+            if config.cold {
+                drop_directory_cache(path)?;
+            }
+            let start = Instant::now();
+            rows = run_query(&ctx, query).await?;
+            latencies.push(start.elapsed().as_secs_f64());
+            pb.inc(1);
+        }
+        results.push(QueryResult {
+            engine: engine.name().to_string(),
+            query_index,
+            query: query.description(),
+            rows,
+            stats: compute_statistics(&latencies),
+        });
+    }
+    pb.finish_and_clear();
+    Ok(results)
+}
+
+/// Run the benchmark described by `config`, returning the results of every
+/// query on every engine.
+pub fn run(config: Config) -> Result<Vec<QueryResult>> {
+    config.validate()?;
+    let runtime = Runtime::new()?;
+    let queries = runtime.block_on(config.load_queries())?;
+    print_plan(&config, &queries);
+    if config.common.dry_run {
+        println!("\nDry run: nothing written or queried");
+        return Ok(Vec::new());
+    }
+
+    std::fs::create_dir_all(&config.data_dir)?;
+    let mut results = Vec::new();
+    for &engine in &config.engines {
+        println!("\n{}", engine.name());
+        let start = Instant::now();
+        let path = runtime.block_on(tables::create(engine, &config))?;
+        println!(
+            "  Wrote {} rows in {:.2}s",
+            config.rows,
+            start.elapsed().as_secs_f64()
+        );
+        results.extend(runtime.block_on(bench_engine(engine, &path, &queries, &config))?);
+    }
+
+    // Every engine holds the same rows, so a query should return as many from each
+    let descriptions: Vec<String> = queries.iter().map(Query::description).collect();
+    for (index, query) in descriptions.iter().enumerate() {
+        let mut counts = results
+            .iter()
+            .filter(|r| r.query_index == index)
+            .map(|r| (r.engine.as_str(), r.rows));
+        if let Some((first_engine, first_rows)) = counts.next() {
+            for (engine, rows) in counts.filter(|&(_, rows)| rows != first_rows) {
+                println!(
+                    "\nWarning: '{}' returned {} rows from {} but {} from {}",
+                    query, first_rows, first_engine, rows, engine
+                );
+            }
+        }
+    }
+    results::print_comparison(&results, &descriptions);
+
+    if let Some(output) = &config.common.output {
+        results::write_results(output, config.rows, &results)?;
+        println!("\nResults written to {}", output.display());
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_queries() {
+        let text = "-- point lookup\nSELECT id FROM data\n  WHERE id = 7;\n\n-- count\nSELECT count(*) FROM data WHERE flag;\n";
+        assert_eq!(
+            parse_queries(text),
+            vec![
+                "SELECT id FROM data\n  WHERE id = 7",
+                "SELECT count(*) FROM data WHERE flag",
+            ]
+        );
+    }
+}
@@ -0,0 +1,56 @@
+//! Per-query results of the SQL benchmark.
+
+use anyhow::Result;
+use bench_core::results::write_results as write_results_file;
+use bench_core::stats::Statistics;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Runs of one query against one engine's table.
+#[derive(Serialize, Deserialize)]
+pub struct QueryResult {
+    pub engine: String,
+    /// Position of the query in the list given
+    pub query_index: usize,
+    pub query: String,
+    /// Rows the query returned
+    pub rows: usize,
+    /// Time to plan and run the query and collect every batch (seconds)
+    pub stats: Statistics,
+}
+
+/// Fields of the results file written with `--output`, next to the results.
+#[derive(Serialize)]
+struct Metadata {
+    rows: usize,
+}
+
+/// Write all query results to `path` as JSON.
+pub fn write_results(path: &Path, rows: usize, results: &[QueryResult]) -> Result<()> {
+    write_results_file(path, "sql", Metadata { rows }, results)
+}
+
+/// Print one table per query, with a row per engine.
+pub fn print_comparison(results: &[QueryResult], queries: &[String]) {
+    for (index, query) in queries.iter().enumerate() {
+        println!("\n{}", "=".repeat(60));
+        println!("Q{}: {}", index + 1, query);
+        println!("{}", "=".repeat(60));
+        println!(
+            "{:<10} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            "Engine", "Rows", "p50(ms)", "p99(ms)", "Mean(ms)", "Min(ms)"
+        );
+        println!("{}", "-".repeat(65));
+        for result in results.iter().filter(|r| r.query_index == index) {
+            println!(
+                "{:<10} {:>10} {:>10.3} {:>10.3} {:>10.3} {:>10.3}",
+                result.engine,
+                result.rows,
+                result.stats.p50 * 1000.0,
+                result.stats.p99 * 1000.0,
+                result.stats.mean * 1000.0,
+                result.stats.min * 1000.0
+            );
+        }
+    }
+}
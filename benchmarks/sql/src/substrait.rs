@@ -0,0 +1,32 @@
+//! Substrait plans replayed with `--substrait-plan`.
+//!
+//! A plan captured from another system is read from its serialized protobuf
+//! form and turned into a DataFusion logical plan against the tables
+//! registered for an engine, so the workload runs exactly as captured.
+
+use anyhow::Result;
+use arrow::record_batch::RecordBatch;
+use datafusion::prelude::SessionContext;
+use datafusion_substrait::logical_plan::consumer::from_substrait_plan;
+use datafusion_substrait::serializer::deserialize_bytes;
+use datafusion_substrait::substrait::proto::Plan;
+use std::path::Path;
+
+/// Read a serialized (binary protobuf) Substrait plan.
+pub async fn load_plan(path: &Path) -> Result<Box<Plan>> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    deserialize_bytes(bytes)
+        .await
+        .map_err(|e| anyhow::anyhow!("{} is not a Substrait plan: {}", path.display(), e))
+}
+
+/// Plan, run and collect `plan` against the tables registered in `ctx`.
+pub async fn execute(ctx: &SessionContext, plan: &Plan) -> Result<Vec<RecordBatch>> {
+    let logical_plan = from_substrait_plan(&ctx.state(), plan).await?;
+    Ok(ctx
+        .execute_logical_plan(logical_plan)
+        .await?
+        .collect()
+        .await?)
+}
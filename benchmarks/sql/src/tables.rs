@@ -0,0 +1,104 @@
+//! The engines whose datasets are queried, each exposed to DataFusion as a table.
+
+use anyhow::Result;
+use arrow::array::RecordBatchIterator;
+use bench_core::data::{create_schema, generate_vector_batch};
+use clap::ValueEnum;
+use datafusion::prelude::{ParquetReadOptions, SessionContext};
+use lance::datafusion::LanceTableProvider;
+use lance::dataset::{Dataset, WriteMode, WriteParams};
+use parquet::arrow::ArrowWriter;
+use parquet::file::properties::WriterProperties;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::{Config, TABLE};
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    /// Lance dataset, through Lance's `LanceTableProvider`
+    Lance,
+    /// Parquet file, through DataFusion's own Parquet reader
+    Parquet,
+}
+
+impl Engine {
+    pub fn name(self) -> &'static str {
+        match self {
+            Engine::Lance => "lance",
+            Engine::Parquet => "parquet",
+        }
+    }
+}
+
+/// Write every row for `engine` under `config.data_dir`, returning its path.
+pub async fn create(engine: Engine, config: &Config) -> Result<PathBuf> {
+    let path = config.data_dir.join(engine.name());
+    if path.is_dir() {
+        fs::remove_dir_all(&path)?;
+    } else if path.exists() {
+        fs::remove_file(&path)?;
+    }
+
+    let schema = create_schema(config.vector_dim);
+    let (rows, batch_rows, dim, seed) = (
+        config.rows,
+        config.write_batch_size,
+        config.vector_dim,
+        config.common.seed,
+    );
+    let batches = (0..rows).step_by(batch_rows).map({
+        let schema = schema.clone();
+        move |start| {
+            generate_vector_batch(
+                schema.clone(),
+                start,
+                batch_rows.min(rows - start),
+                dim,
+                seed,
+            )
+        }
+    });
+
+    match engine {
+        Engine::Lance => {
+            let reader = RecordBatchIterator::new(batches, schema);
+            let params = WriteParams {
+                mode: WriteMode::Create,
+                ..Default::default()
+            };
+            Dataset::write(reader, &path.to_string_lossy(), Some(params)).await?;
+        }
+        Engine::Parquet => {
+            fs::create_dir_all(&path)?;
+            let file = File::create(path.join("data.parquet"))?;
+            let props = WriterProperties::builder()
+                .set_max_row_group_size(config.parquet_rows_per_group)
+                .build();
+            let mut writer = ArrowWriter::try_new(file, schema, Some(props))?;
+            for batch in batches {
+                writer.write(&batch?)?;
+            }
+            writer.close()?;
+        }
+    }
+    Ok(path)
+}
+
+/// Register the dataset at `path` as [`TABLE`] in `ctx`.
+pub async fn register(ctx: &SessionContext, engine: Engine, path: &Path) -> Result<()> {
+    let uri = path.to_string_lossy();
+    match engine {
+        Engine::Lance => {
+            let dataset = Dataset::open(&uri).await?;
+            let provider = LanceTableProvider::new(Arc::new(dataset), false, false);
+            ctx.register_table(TABLE, Arc::new(provider))?;
+        }
+        Engine::Parquet => {
+            ctx.register_parquet(TABLE, uri.as_ref(), ParquetReadOptions::default())
+                .await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! Records the resolved versions of the storage libraries being benchmarked.
+//!
+//! Versions come from `cargo metadata` for the workspace being built (this
+//! package, or a binary such as `lance-bench` that depends on it) and are
+//! exposed to the crate as `DEP_VERSION_*` environment variables. Each is the
+//! package this crate's own resolve node links, so a lock holding several
+//! versions of a crate still records the right one. The build fails if a
+//! version can't be resolved.
+
+use std::path::PathBuf;
+
+use cargo_metadata::{CargoOpt, Metadata, MetadataCommand, Package};
+
+/// Crates whose versions are recorded, with the variable each is exposed as
+/// and the feature of this crate that enables it, if it is optional.
+const CRATES: &[(&str, &str, Option<&str>)] = &[
+    ("lance", "DEP_VERSION_LANCE", None),
+    ("parquet", "DEP_VERSION_PARQUET", None),
+    ("arrow", "DEP_VERSION_ARROW", None),
+    ("vortex", "DEP_VERSION_VORTEX", Some("VORTEX")),
+];
+
+/// Manifest of the workspace being built: the one whose target directory
+/// holds `OUT_DIR`, or else this package's own.
+fn workspace_manifest() -> PathBuf {
+    let manifest_dir = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    out_dir
+        .ancestors()
+        .find(|dir| dir.join("Cargo.toml").exists() && dir.join("Cargo.lock").exists())
+        .unwrap_or(&manifest_dir)
+        .join("Cargo.toml")
+}
+
+/// Version of `package`, with the commit for git dependencies.
+fn describe(package: &Package) -> String {
+    match &package.source {
+        Some(source) if source.repr.starts_with("git+") => {
+            let commit = source.repr.rsplit('#').next().unwrap_or_default();
+            format!(
+                "{} (git {})",
+                package.version,
+                &commit[..commit.len().min(12)]
+            )
+        }
+        _ => package.version.to_string(),
+    }
+}
+
+/// The package `name` that this crate links, as resolved by Cargo.
+fn linked_package<'a>(metadata: &'a Metadata, own: &Package, name: &str) -> &'a Package {
+    let resolve = metadata
+        .resolve
+        .as_ref()
+        .expect("cargo metadata returned no dependency resolution");
+    let node = resolve
+        .nodes
+        .iter()
+        .find(|node| node.id == own.id)
+        .unwrap_or_else(|| panic!("{} is missing from the dependency resolution", own.name));
+    let dependency = node
+        .deps
+        .iter()
+        .find(|dep| metadata[&dep.pkg].name == name)
+        .unwrap_or_else(|| panic!("{} does not depend on {}", own.name, name));
+    &metadata[&dependency.pkg]
+}
+
+fn main() {
+    let manifest = workspace_manifest();
+    // Lock resolution doesn't depend on features, so resolving with all of
+    // them only makes sure optional dependencies are in the graph
+    let metadata = MetadataCommand::new()
+        .manifest_path(&manifest)
+        .features(CargoOpt::AllFeatures)
+        .other_options(vec!["--locked".to_string()])
+        .exec()
+        .unwrap_or_else(|err| panic!("cargo metadata failed for {}: {}", manifest.display(), err));
+
+    let own_name = std::env::var("CARGO_PKG_NAME").unwrap();
+    let manifest_dir = PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let own = metadata
+        .packages
+        .iter()
+        .find(|p| {
+            p.name == own_name
+                && p.manifest_path.parent().map(|dir| dir.as_std_path())
+                    == Some(manifest_dir.as_path())
+        })
+        .unwrap_or_else(|| panic!("{} is missing from cargo metadata", own_name));
+
+    for (name, var, feature) in CRATES {
+        let enabled = match feature {
+            Some(feature) => std::env::var_os(format!("CARGO_FEATURE_{}", feature)).is_some(),
+            None => true,
+        };
+        if enabled {
+            let version = describe(linked_package(&metadata, own, name));
+            println!("cargo:rustc-env={}={}", var, version);
+        }
+    }
+    println!(
+        "cargo:rerun-if-changed={}",
+        manifest.with_file_name("Cargo.lock").display()
+    );
+    println!("cargo:rerun-if-changed=build.rs");
+}
@@ -0,0 +1,93 @@
+//! Calibration of the harness's own per-query overhead.
+//!
+//! Every query's latency includes work done by the harness rather than the
+//! engine: reading the clock, receiving the query from the channel, cloning
+//! the dataset and settings handles, spawning the query's task and
+//! advancing the progress bar. Each of these is timed in a tight loop
+//! before the run, and phases report the sum next to their latencies.
+//! Latencies aren't adjusted; the overhead is only reported.
+
+use crossbeam_channel::bounded;
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use std::hint::black_box;
+use std::sync::Arc;
+use std::time::Instant;
+
+/// Iterations of each operation.
+const ITERATIONS: u32 = 100_000;
+
+/// Tasks spawned to time task spawning, which is much slower than the rest.
+const SPAWN_ITERATIONS: u32 = 10_000;
+
+/// Mean cost of each harness operation a query goes through (nanoseconds).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct HarnessOverhead {
+    /// `Instant::now()` plus `elapsed()`
+    pub timer: f64,
+    /// Sending and receiving a query on the channel
+    pub channel: f64,
+    /// Cloning and dropping an `Arc`, done three times per query
+    pub arc_clone: f64,
+    /// Spawning a task on the runtime and awaiting it
+    pub spawn: f64,
+    /// `ProgressBar::inc`
+    pub progress: f64,
+}
+
+impl HarnessOverhead {
+    /// Estimated harness time in each query's latency (seconds).
+    pub fn per_query(&self) -> f64 {
+        (self.timer + self.channel + 3.0 * self.arc_clone + self.spawn + self.progress) / 1e9
+    }
+}
+
+/// Mean time of `op` over `iterations` calls, in nanoseconds.
+fn time(iterations: u32, mut op: impl FnMut()) -> f64 {
+    let start = Instant::now();
+    for _ in 0..iterations {
+        op();
+    }
+    start.elapsed().as_nanos() as f64 / iterations as f64
+}
+
+/// Time each harness operation.
+pub fn measure() -> HarnessOverhead {
+    let timer = time(ITERATIONS, || {
+        black_box(Instant::now().elapsed());
+    });
+
+    let (tx, rx) = bounded::<(usize, Vec<u64>)>(1);
+    let channel = time(ITERATIONS, || {
+        tx.send((0, Vec::new())).unwrap();
+        black_box(rx.recv().unwrap());
+    });
+
+    let shared = Arc::new(0u64);
+    let arc_clone = time(ITERATIONS, || {
+        black_box(shared.clone());
+    });
+
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .build()
+        .unwrap();
+    let spawn = runtime.block_on(async {
+        let start = Instant::now();
+        for _ in 0..SPAWN_ITERATIONS {
+            tokio::task::spawn(async {}).await.unwrap();
+        }
+        start.elapsed().as_nanos() as f64 / SPAWN_ITERATIONS as f64
+    });
+
+    let pb = ProgressBar::hidden();
+    let progress = time(ITERATIONS, || pb.inc(1));
+
+    HarnessOverhead {
+        timer,
+        channel,
+        arc_clone,
+        spawn,
+        progress,
+    }
+}
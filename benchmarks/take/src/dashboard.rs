@@ -0,0 +1,364 @@
+//! Live dashboard of timed phases (`--tui`).
+//!
+//! Takes over the terminal for each timed phase in place of the progress bar
+//! and rolling throughput lines, redrawing once a second from the query
+//! counters of [`monitor`], this process's I/O counters and its memory use.
+//! Phases that finish are listed with their throughput and p50, so a run of
+//! many engines and workloads can be followed at a glance. Ctrl-C restores
+//! the terminal and exits.
+
+use anyhow::Result;
+use bench_core::monitor::{self, Counters};
+use bench_core::procio::ProcessIo;
+use indicatif::ProgressBar;
+use ratatui::backend::CrosstermBackend;
+use ratatui::crossterm::cursor::Show;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyModifiers};
+use ratatui::crossterm::execute;
+use ratatui::crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, Paragraph, Sparkline};
+use ratatui::{Frame, Terminal};
+use std::collections::VecDeque;
+use std::io::{self, Stdout};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the dashboard redraws.
+const TICK: Duration = Duration::from_secs(1);
+
+/// Ticks the latency percentiles are computed over.
+const LATENCY_TICKS: usize = 5;
+
+/// (engine, summary) of every phase finished so far in this run.
+static FINISHED: Mutex<Vec<(String, String)>> = Mutex::new(Vec::new());
+
+/// The phase a dashboard follows.
+pub struct PhaseInfo {
+    /// Every engine of the run, in order
+    pub engines: Vec<String>,
+    /// Engine running the phase
+    pub engine: String,
+    /// Workload and cache state of the phase
+    pub title: String,
+    /// Length of a `--duration` phase, whose progress bar has no length
+    pub duration: Option<Duration>,
+}
+
+/// Counters read at one tick.
+#[derive(Clone)]
+struct Sample {
+    at: Instant,
+    queries: Counters,
+    io: Option<ProcessIo>,
+}
+
+impl Sample {
+    fn now() -> Self {
+        Self {
+            at: Instant::now(),
+            queries: monitor::counters(),
+            io: ProcessIo::now(),
+        }
+    }
+}
+
+/// Draws the dashboard on a background thread until stopped.
+pub struct Dashboard {
+    stop: Arc<AtomicBool>,
+    handle: Option<thread::JoinHandle<Result<()>>>,
+}
+
+impl Dashboard {
+    /// Take over the terminal and follow the phase whose queries `pb` counts.
+    pub fn start(info: PhaseInfo, pb: &ProgressBar) -> Result<Self> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        let pb = pb.clone();
+        let handle = thread::spawn(move || {
+            let drawn = draw_until(terminal, &info, &pb, &thread_stop);
+            restore();
+            drawn
+        });
+        Ok(Self {
+            stop,
+            handle: Some(handle),
+        })
+    }
+
+    /// Close the dashboard and give the terminal back.
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            if let Err(e) = handle.join().unwrap() {
+                println!("Warning: the dashboard failed: {:#}", e);
+            }
+        }
+    }
+}
+
+fn restore() {
+    let _ = disable_raw_mode();
+    let _ = execute!(io::stdout(), LeaveAlternateScreen, Show);
+}
+
+fn draw_until(
+    mut terminal: Terminal<CrosstermBackend<Stdout>>,
+    info: &PhaseInfo,
+    pb: &ProgressBar,
+    stop: &AtomicBool,
+) -> Result<()> {
+    let first = Sample::now();
+    let mut samples = VecDeque::from([first.clone()]);
+    let mut throughput = Vec::new();
+    terminal.draw(|frame| render(frame, info, pb, &first, &samples, &throughput))?;
+    let mut next_tick = first.at + TICK;
+    while !stop.load(Ordering::Relaxed) {
+        if Instant::now() >= next_tick {
+            let sample = Sample::now();
+            let last = samples.back().unwrap();
+            let seconds = (sample.at - last.at).as_secs_f64().max(f64::EPSILON);
+            throughput
+                .push((sample.queries.completed_since(&last.queries) as f64 / seconds) as u64);
+            samples.push_back(sample);
+            if samples.len() > LATENCY_TICKS + 1 {
+                samples.pop_front();
+            }
+            terminal.draw(|frame| render(frame, info, pb, &first, &samples, &throughput))?;
+            next_tick += TICK;
+        }
+
+        // Raw mode swallows Ctrl-C, so interrupt the run here
+        if event::poll(Duration::from_millis(100))? {
+            if let Event::Key(key) = event::read()? {
+                if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+                    restore();
+                    std::process::exit(130);
+                }
+            }
+        }
+    }
+
+    let last = Sample::now();
+    let seconds = (last.at - first.at).as_secs_f64();
+    let queries = last.queries.completed_since(&first.queries);
+    let p50 = last.queries.percentile_since(&first.queries, 50.0);
+    FINISHED.lock().unwrap().push((
+        info.engine.clone(),
+        format!(
+            "{} {}: {} queries, {:.1} queries/sec, p50 {}",
+            info.engine,
+            info.title,
+            queries,
+            queries as f64 / seconds,
+            format_ms(p50)
+        ),
+    ));
+    Ok(())
+}
+
+fn format_ms(latency: Option<f64>) -> String {
+    latency.map_or("-".to_string(), |l| format!("{:.3} ms", l * 1000.0))
+}
+
+fn render(
+    frame: &mut Frame,
+    info: &PhaseInfo,
+    pb: &ProgressBar,
+    first: &Sample,
+    samples: &VecDeque<Sample>,
+    throughput: &[u64],
+) {
+    let [header, progress, rates, gauges, history] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(3),
+        Constraint::Length(8),
+        Constraint::Length(4),
+        Constraint::Min(4),
+    ])
+    .areas(frame.area());
+    let last = samples.back().unwrap();
+    let elapsed = last.at - first.at;
+
+    frame.render_widget(
+        Paragraph::new(format!(
+            " {} | {} | {:.0}s elapsed (Ctrl-C to stop)",
+            info.engine,
+            info.title,
+            elapsed.as_secs_f64()
+        )),
+        header,
+    );
+
+    let (ratio, label) = match (info.duration, pb.length()) {
+        (Some(duration), _) => (
+            elapsed.as_secs_f64() / duration.as_secs_f64(),
+            format!(
+                "{:.0}s of {:.0}s",
+                elapsed.as_secs_f64(),
+                duration.as_secs_f64()
+            ),
+        ),
+        (None, Some(len)) if len > 0 => (
+            pb.position() as f64 / len as f64,
+            format!("{} of {} queries", pb.position(), len),
+        ),
+        _ => (0.0, format!("{} queries", pb.position())),
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title("Progress"))
+            .ratio(ratio.clamp(0.0, 1.0))
+            .label(label),
+        progress,
+    );
+
+    let [throughput_area, latency_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(rates);
+    render_throughput(frame, throughput_area, throughput);
+    let window = &samples.front().unwrap().queries;
+    let latency = Paragraph::new(vec![
+        Line::from(format!(
+            "p50   {}",
+            format_ms(last.queries.percentile_since(window, 50.0))
+        )),
+        Line::from(format!(
+            "p95   {}",
+            format_ms(last.queries.percentile_since(window, 95.0))
+        )),
+        Line::from(format!(
+            "p99   {}",
+            format_ms(last.queries.percentile_since(window, 99.0))
+        )),
+        Line::from(format!(
+            "mean  {}",
+            format_ms(last.queries.mean_since(window))
+        )),
+        Line::from(format!("{} in flight", last.queries.in_flight)),
+    ])
+    .block(Block::bordered().title(format!("Latency, last {}s", LATENCY_TICKS)));
+    frame.render_widget(latency, latency_area);
+
+    let [memory_area, io_area] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(gauges);
+    render_memory(frame, memory_area);
+    render_io(frame, io_area, samples);
+
+    let [engines_area, finished_area] =
+        Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)]).areas(history);
+    render_history(frame, engines_area, finished_area, info);
+}
+
+fn render_throughput(frame: &mut Frame, area: Rect, throughput: &[u64]) {
+    // One bar per tick, as many of the latest as fit
+    let width = area.width.saturating_sub(2) as usize;
+    let recent = &throughput[throughput.len().saturating_sub(width)..];
+    let title = format!(
+        "Throughput, {} queries/sec",
+        throughput.last().copied().unwrap_or(0)
+    );
+    frame.render_widget(
+        Sparkline::default()
+            .block(Block::bordered().title(title))
+            .data(recent),
+        area,
+    );
+}
+
+/// A `/proc` field in kB, e.g. `VmRSS` of `/proc/self/status`.
+fn proc_kb(path: &str, name: &str) -> Option<u64> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    contents.lines().find_map(|line| {
+        line.strip_prefix(name)?
+            .strip_prefix(':')?
+            .trim()
+            .strip_suffix("kB")?
+            .trim()
+            .parse()
+            .ok()
+    })
+}
+
+fn render_memory(frame: &mut Frame, area: Rect) {
+    let gib = |kb: u64| kb as f64 / (1024.0 * 1024.0);
+    let rss = proc_kb("/proc/self/status", "VmRSS");
+    let total = proc_kb("/proc/meminfo", "MemTotal");
+    let (ratio, label) = match (rss, total) {
+        (Some(rss), Some(total)) if total > 0 => (
+            rss as f64 / total as f64,
+            format!("{:.2} GiB of {:.1} GiB", gib(rss), gib(total)),
+        ),
+        _ => (0.0, "-".to_string()),
+    };
+    frame.render_widget(
+        Gauge::default()
+            .block(Block::bordered().title("Resident memory"))
+            .ratio(ratio.clamp(0.0, 1.0))
+            .label(label),
+        area,
+    );
+}
+
+fn render_io(frame: &mut Frame, area: Rect, samples: &VecDeque<Sample>) {
+    let last = samples.back().unwrap();
+    let previous = &samples[samples.len().saturating_sub(2)];
+    let lines = match (&last.io, &previous.io) {
+        (Some(now), Some(before)) => {
+            let io = now.since(before);
+            let seconds = (last.at - previous.at).as_secs_f64().max(f64::EPSILON);
+            vec![
+                Line::from(format!(
+                    "Read {:.1} MB/s in {:.0} syscalls/s",
+                    io.read_bytes as f64 / seconds / 1e6,
+                    io.read_syscalls as f64 / seconds
+                )),
+                Line::from(format!(
+                    "From storage {:.1} MB/s",
+                    io.storage_read_bytes as f64 / seconds / 1e6
+                )),
+            ]
+        }
+        _ => vec![Line::from("No /proc/self/io")],
+    };
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("Process I/O")),
+        area,
+    );
+}
+
+fn render_history(frame: &mut Frame, engines_area: Rect, finished_area: Rect, info: &PhaseInfo) {
+    let finished = FINISHED.lock().unwrap();
+    let engines: Vec<Line> = info
+        .engines
+        .iter()
+        .map(|engine| {
+            let phases = finished.iter().filter(|(e, _)| e == engine).count();
+            let marker = if *engine == info.engine { ">" } else { " " };
+            Line::from(format!("{} {} ({} phases done)", marker, engine, phases))
+        })
+        .collect();
+    frame.render_widget(
+        Paragraph::new(engines).block(Block::bordered().title("Engines")),
+        engines_area,
+    );
+
+    // The latest phases that fit
+    let rows = finished_area.height.saturating_sub(2) as usize;
+    let lines: Vec<Line> = finished[finished.len().saturating_sub(rows)..]
+        .iter()
+        .map(|(_, summary)| Line::from(summary.as_str()))
+        .collect();
+    frame.render_widget(
+        Paragraph::new(lines).block(Block::bordered().title("Finished phases")),
+        finished_area,
+    );
+}
@@ -0,0 +1,66 @@
+//! Rows deleted from every dataset with `--delete-fraction`.
+//!
+//! The first rows of every block of [`BLOCK_ROWS`] ids are deleted, so
+//! deletions are spread evenly over the dataset and which ids survive can be
+//! computed without reading the data. Queries keep addressing rows by
+//! offset, now counting live rows only, and [`Deletions::id_at`] maps an
+//! offset back to the id of the row it lands on.
+
+/// Ids per block; each block loses the same number of rows.
+pub const BLOCK_ROWS: u64 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deletions {
+    /// Rows deleted at the start of each block
+    per_block: u64,
+}
+
+impl Deletions {
+    /// Delete `fraction` of the rows, rounded to whole rows per block.
+    pub fn new(fraction: f64) -> Self {
+        Self {
+            per_block: ((fraction * BLOCK_ROWS as f64).round() as u64).min(BLOCK_ROWS - 1),
+        }
+    }
+
+    /// SQL predicate matching the deleted rows.
+    pub fn predicate(&self) -> String {
+        format!("id % {} < {}", BLOCK_ROWS, self.per_block)
+    }
+
+    /// Rows left of a dataset of `rows` rows.
+    pub fn live_rows(&self, rows: usize) -> usize {
+        let rows = rows as u64;
+        let full_blocks = rows / BLOCK_ROWS;
+        let tail = (rows % BLOCK_ROWS).saturating_sub(self.per_block);
+        (full_blocks * (BLOCK_ROWS - self.per_block) + tail) as usize
+    }
+
+    /// Id of the live row at `offset`.
+    pub fn id_at(&self, offset: u64) -> u64 {
+        let live_per_block = BLOCK_ROWS - self.per_block;
+        (offset / live_per_block) * BLOCK_ROWS + self.per_block + offset % live_per_block
+    }
+
+    pub fn fraction(&self) -> f64 {
+        self.per_block as f64 / BLOCK_ROWS as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_id_at_skips_deleted_rows() {
+        let deletions = Deletions::new(0.1);
+        let rows = 2500;
+        let live: Vec<u64> = (0..rows as u64)
+            .filter(|id| id % BLOCK_ROWS >= 100)
+            .collect();
+        assert_eq!(deletions.live_rows(rows), live.len());
+        for (offset, &id) in live.iter().enumerate() {
+            assert_eq!(deletions.id_at(offset as u64), id);
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Lance encoding configurations swept with `--lance-encoding`.
+//!
+//! Lance picks how to encode each column from `lance-encoding:*` field
+//! metadata, so a configuration is a set of those keys applied to every
+//! column of the written schema. Each one is benchmarked on its own copy of
+//! the datasets and reported as `<engine>@<label>`, next to the default
+//! encoding.
+
+use arrow::datatypes::{Field, Schema};
+use std::fmt;
+use std::sync::Arc;
+
+/// Prefix of the field metadata keys Lance reads encoding settings from.
+const META_PREFIX: &str = "lance-encoding:";
+
+/// Encoding settings that can be given, without the prefix.
+pub const KEYS: &[&str] = &[
+    "compression",
+    "compression-level",
+    "structural-encoding",
+    "packed",
+    "bss",
+    "rle-threshold",
+    "dict-divisor",
+];
+
+/// A named set of encoding settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanceEncoding {
+    pub label: String,
+    /// (key without prefix, value), in the order given
+    pub settings: Vec<(String, String)>,
+}
+
+impl LanceEncoding {
+    /// `schema` with these settings in the metadata of every field.
+    pub fn apply(&self, schema: &Schema) -> Arc<Schema> {
+        let fields: Vec<Field> = schema
+            .fields()
+            .iter()
+            .map(|field| {
+                let mut metadata = field.metadata().clone();
+                for (key, value) in &self.settings {
+                    metadata.insert(format!("{}{}", META_PREFIX, key), value.clone());
+                }
+                field.as_ref().clone().with_metadata(metadata)
+            })
+            .collect();
+        Arc::new(Schema::new_with_metadata(fields, schema.metadata().clone()))
+    }
+}
+
+impl fmt::Display for LanceEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let settings: Vec<String> = self
+            .settings
+            .iter()
+            .map(|(key, value)| format!("{}:{}", key, value))
+            .collect();
+        write!(f, "{}={}", self.label, settings.join(","))
+    }
+}
+
+/// Check that `label` can name a sweep configuration in result names and dataset URIs.
+pub fn check_label(label: &str) -> Result<(), String> {
+    if label.is_empty()
+        || !label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "label '{}' must be letters, digits, '-' and '_'",
+            label
+        ));
+    }
+    // The other result suffixes
+    if label == "tmpfs"
+        || label == "compacted"
+        || label == "post-filter"
+        || label.ends_with("frags")
+    {
+        return Err(format!("label '{}' is reserved", label));
+    }
+    Ok(())
+}
+
+/// Parse `label=key:value[,key:value...]`, as given to `--lance-encoding`.
+pub fn parse_lance_encoding(s: &str) -> Result<LanceEncoding, String> {
+    let (label, settings) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <label>=<key>:<value>[,...], got '{}'", s))?;
+    check_label(label)?;
+    let settings = settings
+        .split(',')
+        .map(|setting| {
+            let (key, value) = setting
+                .strip_prefix(META_PREFIX)
+                .unwrap_or(setting)
+                .split_once(':')
+                .ok_or_else(|| format!("expected <key>:<value>, got '{}'", setting))?;
+            if !KEYS.contains(&key) {
+                return Err(format!(
+                    "unknown encoding setting '{}', expected one of {}",
+                    key,
+                    KEYS.join(", ")
+                ));
+            }
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(LanceEncoding {
+        label: label.to_string(),
+        settings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_lance_encoding() {
+        let encoding =
+            parse_lance_encoding("zstd3=compression:zstd,lance-encoding:compression-level:3")
+                .unwrap();
+        assert_eq!(encoding.label, "zstd3");
+        assert_eq!(
+            encoding.settings,
+            vec![
+                ("compression".to_string(), "zstd".to_string()),
+                ("compression-level".to_string(), "3".to_string()),
+            ]
+        );
+        assert_eq!(
+            encoding.to_string(),
+            "zstd3=compression:zstd,compression-level:3"
+        );
+        assert!(parse_lance_encoding("zstd").is_err());
+        assert!(parse_lance_encoding("x=compresion:zstd").is_err());
+        assert!(parse_lance_encoding("tmpfs=compression:zstd").is_err());
+    }
+}
@@ -0,0 +1,104 @@
+//! Baseline fallbacks for take options an engine can't apply itself.
+
+use anyhow::Result;
+use arrow::array::AsArray;
+use arrow::record_batch::RecordBatch;
+use async_trait::async_trait;
+use std::sync::Arc;
+
+use crate::data::create_schema;
+use crate::results::Skipping;
+use crate::Config;
+
+use super::traits::{DatasetHandle, Engine, TakeOptions, FILTER_COLUMN};
+
+/// Wraps a dataset whose engine lacks filter pushdown or projection.
+///
+/// Takes then read the columns the engine can return and the benchmark filters
+/// and projects the rows itself, which is what a caller of that engine would
+/// have to do.
+///
+/// `--compare-filter` also wraps engines that have pushdown, to measure what
+/// filtering this way costs them.
+struct FallbackHandle {
+    inner: Arc<dyn DatasetHandle>,
+    /// Apply `TakeOptions::filter` to the taken rows
+    post_filter: bool,
+    /// Every column, read in full when the engine can't project
+    all_columns: Option<Vec<String>>,
+}
+
+#[async_trait]
+impl DatasetHandle for FallbackHandle {
+    async fn take(&self, indices: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
+        let post_filter = self.post_filter && options.filter;
+        let mut columns = self
+            .all_columns
+            .clone()
+            .unwrap_or_else(|| options.columns.clone());
+        if post_filter && !columns.iter().any(|c| c == FILTER_COLUMN) {
+            columns.push(FILTER_COLUMN.to_string());
+        }
+        let inner_options = TakeOptions {
+            columns,
+            filter: options.filter && !post_filter,
+        };
+        let mut batch = self.inner.take(indices, &inner_options).await?;
+
+        if post_filter {
+            let flags = batch
+                .column_by_name(FILTER_COLUMN)
+                .ok_or_else(|| anyhow::anyhow!("Missing {} column", FILTER_COLUMN))?
+                .as_boolean()
+                .clone();
+            batch = arrow::compute::filter_record_batch(&batch, &flags)?;
+        }
+        let projection = options
+            .columns
+            .iter()
+            .map(|c| batch.schema().index_of(c))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(batch.project(&projection)?)
+    }
+
+    async fn lookup(&self, keys: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
+        self.inner.lookup(keys, options).await
+    }
+
+    async fn scan(&self, columns: &[String]) -> Result<usize> {
+        self.inner.scan(columns).await
+    }
+
+    async fn count_rows(&self, key_below: Option<u64>) -> Result<usize> {
+        self.inner.count_rows(key_below).await
+    }
+
+    fn skipping(&self) -> Option<Skipping> {
+        self.inner.skipping()
+    }
+}
+
+/// Wrap `dataset` in the fallbacks its engine needs, if any.
+pub fn with_fallbacks(
+    engine: &dyn Engine,
+    dataset: Arc<dyn DatasetHandle>,
+    config: &Config,
+) -> Arc<dyn DatasetHandle> {
+    let post_filter =
+        config.take_filter && (config.post_filter || !engine.supports_filter_pushdown());
+    let all_columns = (!engine.supports_projection()).then(|| {
+        create_schema(config.vector_dim)
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect()
+    });
+    if !post_filter && all_columns.is_none() {
+        return dataset;
+    }
+    Arc::new(FallbackHandle {
+        inner: dataset,
+        post_filter,
+        all_columns,
+    })
+}
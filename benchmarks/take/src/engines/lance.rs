@@ -4,56 +4,287 @@ use anyhow::Result;
 use arrow::array::RecordBatchIterator;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
+use futures::TryStreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
-use lance::dataset::{Dataset, WriteMode, WriteParams};
+use lance::dataset::builder::DatasetBuilder;
+use lance::dataset::optimize::{compact_files, CompactionOptions};
+use lance::dataset::{Dataset, ReadParams, WriteMode, WriteParams};
+use lance_file::version::LanceFileVersion;
+use lance_index::scalar::ScalarIndexParams;
+use lance_index::{DatasetIndexExt, IndexType};
+use lance_io::object_store::{ObjectStoreParams, WrappingObjectStore};
+use object_store::memory::InMemory;
+use object_store::ObjectStore;
 use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 
-use crate::cache::drop_directory_cache;
+use crate::cache::{drop_directory_cache, is_local_uri};
 use crate::data::{create_schema, generate_vector_batch};
+use crate::inject::{self, Injection};
+use crate::results::{Compaction, Skipping};
+use crate::storage::load_into_memory;
 use crate::Config;
 
-use super::traits::{DatasetHandle, Engine};
+use super::runtime::{EngineRuntime, RuntimeSpec};
+use super::traits::{DatasetHandle, Engine, SkipCounter, TakeOptions, FILTER_COLUMN, KEY_COLUMN};
+use super::versions;
+
+/// How a Lance engine addresses the rows it takes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanceTakeMode {
+    /// `Dataset::take` by row offset
+    Offsets,
+    /// `Dataset::take_rows` by row address (fragment id << 32 | offset in fragment)
+    RowAddresses,
+    /// `Dataset::take_rows` by stable row id (dataset written with stable row ids)
+    RowIds,
+}
+
+/// Wraps the object store of datasets opened with latency injection or
+/// `--in-memory` on.
+#[derive(Debug)]
+struct ReadWrapper {
+    /// In-memory copy of the dataset read instead of its store (`--in-memory`)
+    memory: Option<Arc<InMemory>>,
+    injection: Option<Injection>,
+}
+
+impl WrappingObjectStore for ReadWrapper {
+    fn wrap(&self, _store_prefix: &str, original: Arc<dyn ObjectStore>) -> Arc<dyn ObjectStore> {
+        let store: Arc<dyn ObjectStore> = match &self.memory {
+            Some(memory) => memory.clone(),
+            None => original,
+        };
+        match self.injection {
+            Some(injection) => inject::wrap_with(store, injection),
+            None => store,
+        }
+    }
+}
 
 /// Handle to an open Lance dataset.
 pub struct LanceHandle {
     dataset: Dataset,
+    take_mode: LanceTakeMode,
+    /// (first row offset, fragment id) for each fragment, sorted by offset.
+    /// Offsets count live rows, as takes by offset do.
+    fragment_starts: Vec<(u64, u64)>,
+    /// Time taken to load the manifest, if the dataset was opened rather than written
+    metadata_load: Option<Duration>,
+    /// Fragments takes could have read, and did. Lance leaves filtering to
+    /// the caller, so these are fragments skipped by position, not pruned by
+    /// the filter. Lookups and counts go through the scanner, which doesn't
+    /// say which fragments it pruned.
+    skipping: SkipCounter,
+}
+
+impl LanceHandle {
+    fn new(
+        dataset: Dataset,
+        take_mode: LanceTakeMode,
+        metadata_load: Option<Duration>,
+    ) -> Result<Self> {
+        let live_rows = dataset
+            .fragments()
+            .iter()
+            .map(|fragment| {
+                let deleted = match &fragment.deletion_file {
+                    Some(file) => file.num_deleted_rows.ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Fragment {} has a deletion file without a row count",
+                            fragment.id
+                        )
+                    })?,
+                    None => 0,
+                };
+                Ok((
+                    fragment.id,
+                    fragment.physical_rows.unwrap_or(0).saturating_sub(deleted),
+                ))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            dataset,
+            take_mode,
+            fragment_starts: fragment_starts(live_rows.into_iter()),
+            metadata_load,
+            skipping: SkipCounter::new("fragments (by position)"),
+        })
+    }
+
+    /// Convert sorted dataset offsets to row addresses.
+    fn offsets_to_addresses(&self, indices: &[u64]) -> Vec<u64> {
+        indices
+            .iter()
+            .map(|&idx| {
+                let pos = self
+                    .fragment_starts
+                    .partition_point(|(start, _)| *start <= idx)
+                    - 1;
+                let (start, fragment_id) = self.fragment_starts[pos];
+                (fragment_id << 32) | (idx - start)
+            })
+            .collect()
+    }
+}
+
+/// (first row offset, fragment id) of fragments given as (id, live rows).
+fn fragment_starts(fragments: impl Iterator<Item = (u64, usize)>) -> Vec<(u64, u64)> {
+    let mut offset = 0u64;
+    fragments
+        .map(|(id, rows)| {
+            let start = offset;
+            offset += rows as u64;
+            (start, id)
+        })
+        .collect()
+}
+
+/// Fragments holding at least one of the sorted dataset offsets `indices`.
+fn fragments_touched(fragment_starts: &[(u64, u64)], indices: &[u64]) -> usize {
+    let mut touched = 0;
+    let mut rest = indices;
+    for &(start, _) in fragment_starts.iter().skip(1) {
+        let inside = rest.partition_point(|&idx| idx < start);
+        if inside > 0 {
+            touched += 1;
+            rest = &rest[inside..];
+        }
+    }
+    touched + usize::from(!rest.is_empty())
 }
 
 #[async_trait]
 impl DatasetHandle for LanceHandle {
-    async fn take(&self, indices: &[u64]) -> Result<RecordBatch> {
-        Ok(self
-            .dataset
-            .take(
-                indices,
-                lance::dataset::ProjectionRequest::Sql(vec![(
-                    "vector".to_string(),
-                    "vector".to_string(),
-                )]),
-            )
-            .await?)
+    async fn take(&self, indices: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
+        // Lance has no filtered take, so the benchmark filters the taken rows
+        if options.filter {
+            anyhow::bail!("Lance takes are filtered by the caller");
+        }
+        let projection = lance::dataset::ProjectionRequest::Sql(
+            options
+                .columns
+                .iter()
+                .map(|c| (c.clone(), c.clone()))
+                .collect(),
+        );
+
+        let batch = match self.take_mode {
+            LanceTakeMode::Offsets => self.dataset.take(indices, projection).await?,
+            LanceTakeMode::RowAddresses => {
+                let addresses = self.offsets_to_addresses(indices);
+                self.dataset.take_rows(&addresses, projection).await?
+            }
+            // Stable row ids are assigned sequentially on write, so they match offsets
+            LanceTakeMode::RowIds => self.dataset.take_rows(indices, projection).await?,
+        };
+        self.skipping.record(
+            self.fragment_starts.len(),
+            fragments_touched(&self.fragment_starts, indices),
+        );
+        Ok(batch)
+    }
+
+    async fn lookup(&self, keys: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
+        let keys = keys
+            .iter()
+            .map(|k| k.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let mut filter = format!("{} IN ({})", KEY_COLUMN, keys);
+        if options.filter {
+            filter.push_str(&format!(" AND {} = true", FILTER_COLUMN));
+        }
+
+        // The scanner answers the IN list from the BTree index on the key column
+        let mut scanner = self.dataset.scan();
+        scanner.project(&options.columns)?;
+        scanner.filter(&filter)?;
+        Ok(scanner.try_into_batch().await?)
+    }
+
+    async fn scan(&self, columns: &[String]) -> Result<usize> {
+        let mut scanner = self.dataset.scan();
+        scanner.project(columns)?;
+        let mut stream = scanner.try_into_stream().await?;
+        let mut rows = 0;
+        while let Some(batch) = stream.try_next().await? {
+            rows += batch.num_rows();
+        }
+        Ok(rows)
+    }
+
+    async fn count_rows(&self, key_below: Option<u64>) -> Result<usize> {
+        let filter = key_below.map(|bound| format!("{} < {}", KEY_COLUMN, bound));
+        Ok(self.dataset.count_rows(filter).await?)
+    }
+
+    fn metadata_load_time(&self) -> Option<Duration> {
+        self.metadata_load
+    }
+
+    fn skipping(&self) -> Option<Skipping> {
+        Some(self.skipping.totals())
     }
 }
 
 /// Lance storage engine.
 pub struct LanceEngine {
-    runtime: Arc<Runtime>,
+    name: &'static str,
+    /// File format version to write with (None uses Lance's default)
+    file_version: Option<LanceFileVersion>,
+    take_mode: LanceTakeMode,
+    runtime: EngineRuntime,
 }
 
 impl LanceEngine {
-    pub fn new() -> Self {
+    pub fn new(runtime: RuntimeSpec) -> Self {
         Self {
-            runtime: Arc::new(
-                tokio::runtime::Builder::new_current_thread()
-                    .build()
-                    .unwrap(),
-            ),
+            name: "lance",
+            file_version: None,
+            take_mode: LanceTakeMode::Offsets,
+            runtime: EngineRuntime::new(runtime),
+        }
+    }
+
+    /// Create a Lance engine that writes datasets with a specific file format version.
+    pub fn with_file_version(
+        name: &'static str,
+        file_version: LanceFileVersion,
+        runtime: RuntimeSpec,
+    ) -> Self {
+        Self {
+            name,
+            file_version: Some(file_version),
+            ..Self::new(runtime)
+        }
+    }
+
+    /// Create a Lance engine that addresses rows with a specific take mode.
+    ///
+    /// `RowIds` datasets are written with stable row ids enabled.
+    pub fn with_take_mode(
+        name: &'static str,
+        take_mode: LanceTakeMode,
+        runtime: RuntimeSpec,
+    ) -> Self {
+        Self {
+            name,
+            take_mode,
+            ..Self::new(runtime)
         }
     }
 
+    /// Whether datasets are written with stable row ids: always for `RowIds`,
+    /// otherwise with `--lance-stable-row-ids`.
+    fn stable_row_ids(&self) -> bool {
+        self.take_mode == LanceTakeMode::RowIds || super::lance_stable_row_ids()
+    }
+
     /// Convert a URI to a Lance URI with uring support.
     /// If already has a scheme, use as-is; otherwise prepend file+uring://
     fn to_lance_uri(&self, uri: &str) -> String {
@@ -64,6 +295,34 @@ impl LanceEngine {
         }
     }
 
+    /// Open `uri` for benchmarking.
+    ///
+    /// With latency injection on, the dataset's object store is wrapped in the
+    /// injecting store. Local datasets are then opened by path rather than
+    /// through io_uring, whose reads would bypass the object store.
+    async fn open_dataset(&self, uri: &str) -> Result<Dataset> {
+        let injection = inject::current();
+        let memory = if super::in_memory() {
+            Some(load_into_memory(uri).await?.0)
+        } else {
+            None
+        };
+        if injection.is_none() && memory.is_none() {
+            return Ok(Dataset::open(&self.to_lance_uri(uri)).await?);
+        }
+        let params = ReadParams {
+            store_options: Some(ObjectStoreParams {
+                object_store_wrapper: Some(Arc::new(ReadWrapper { memory, injection })),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        Ok(DatasetBuilder::from_uri(uri)
+            .with_read_params(params)
+            .load()
+            .await?)
+    }
+
     /// Extract the file path from a URI for cache operations.
     fn uri_to_path<'a>(&self, uri: &'a str) -> &'a str {
         if let Some(path) = uri.strip_prefix("file+uring://") {
@@ -82,18 +341,22 @@ impl LanceEngine {
 
 impl Default for LanceEngine {
     fn default() -> Self {
-        Self::new()
+        Self::new(RuntimeSpec::default())
     }
 }
 
 #[async_trait]
 impl Engine for LanceEngine {
     fn name(&self) -> &'static str {
-        "lance"
+        self.name
     }
 
     fn runtime(&self) -> Arc<Runtime> {
-        self.runtime.clone()
+        self.runtime.get()
+    }
+
+    fn runtime_spec(&self) -> RuntimeSpec {
+        self.runtime.spec()
     }
 
     fn exists(&self, uri: &str, expected_rows: usize) -> bool {
@@ -110,9 +373,15 @@ impl Engine for LanceEngine {
 
     fn open(&self, uri: &str) -> Result<Arc<dyn DatasetHandle>> {
         self.runtime.block_on(async {
-            let lance_uri = self.to_lance_uri(uri);
-            let dataset = Dataset::open(&lance_uri).await?;
-            Ok(Arc::new(LanceHandle { dataset }) as Arc<dyn DatasetHandle>)
+            // Opening a Lance dataset reads its manifest and nothing else
+            let start = Instant::now();
+            let dataset = self.open_dataset(uri).await?;
+            let metadata_load = start.elapsed();
+            Ok(Arc::new(LanceHandle::new(
+                dataset,
+                self.take_mode,
+                Some(metadata_load),
+            )?) as Arc<dyn DatasetHandle>)
         })
     }
 
@@ -129,37 +398,164 @@ impl Engine for LanceEngine {
                     .unwrap(),
             );
 
-            let schema = create_schema(config.vector_dim);
+            let mut schema = create_schema(config.vector_dim);
+            if let Some(encoding) = &config.lance_encoding {
+                println!("  Encoding: {}", encoding);
+                schema = encoding.apply(&schema);
+            }
+            let reader_schema = schema.clone();
             let batch_size = config.write_batch_size;
             let dim = config.vector_dim;
+            let seed = config.common.seed;
 
             // Use atomic counter for progress tracking
             let counter = Arc::new(AtomicU64::new(0));
             let counter_clone = counter.clone();
 
-            let batches = (0..num_batches).map(move |_| {
-                let batch = generate_vector_batch(schema.clone(), batch_size, dim);
+            let batches = (0..num_batches).map(move |i| {
+                let batch =
+                    generate_vector_batch(schema.clone(), i * batch_size, batch_size, dim, seed);
                 let count = counter_clone.fetch_add(1, Ordering::Relaxed);
                 pb.set_position(count + 1);
                 batch
             });
 
-            let reader = RecordBatchIterator::new(batches, create_schema(config.vector_dim));
+            let reader = RecordBatchIterator::new(batches, reader_schema);
 
             let params = WriteParams {
                 mode: WriteMode::Create,
-                max_rows_per_file: config.rows_per_dataset,
+                max_rows_per_file: config.rows_per_dataset / config.fragments_per_dataset(),
+                data_storage_version: self.file_version,
+                enable_stable_row_ids: self.stable_row_ids(),
                 ..Default::default()
             };
 
-            let dataset = Dataset::write(reader, &lance_uri, Some(params)).await?;
+            let mut dataset = Dataset::write(reader, &lance_uri, Some(params)).await?;
+
+            println!("  Creating BTree index on '{}'", KEY_COLUMN);
+            dataset
+                .create_index(
+                    &[KEY_COLUMN],
+                    IndexType::BTree,
+                    None,
+                    &ScalarIndexParams::default(),
+                    true,
+                )
+                .await?;
+            if inject::current().is_some() {
+                dataset = self.open_dataset(uri).await?;
+            }
+
+            Ok(Arc::new(LanceHandle::new(dataset, self.take_mode, None)?)
+                as Arc<dyn DatasetHandle>)
+        })
+    }
 
-            Ok(Arc::new(LanceHandle { dataset }) as Arc<dyn DatasetHandle>)
+    fn compact(&self, uri: &str) -> Result<Compaction> {
+        self.runtime.block_on(async {
+            let mut dataset = Dataset::open(&self.to_lance_uri(uri)).await?;
+            let start = Instant::now();
+            let metrics = compact_files(&mut dataset, CompactionOptions::default(), None).await?;
+            Ok(Compaction {
+                time: start.elapsed().as_secs_f64(),
+                fragments_removed: metrics.fragments_removed,
+                fragments_added: metrics.fragments_added,
+            })
         })
     }
 
     fn drop_cache(&self, uri: &str) -> Result<()> {
+        if !is_local_uri(uri) {
+            return Ok(());
+        }
         let path = self.uri_to_path(uri);
         drop_directory_cache(Path::new(path))
     }
+
+    fn delete(&self, uri: &str, predicate: &str) -> Result<()> {
+        self.runtime.block_on(async {
+            let mut dataset = Dataset::open(&self.to_lance_uri(uri)).await?;
+            dataset.delete(predicate).await?;
+            Ok(())
+        })
+    }
+
+    fn write_options(&self) -> String {
+        format!(
+            "file_version={:?} stable_row_ids={}",
+            self.file_version,
+            self.stable_row_ids()
+        )
+    }
+
+    fn library_versions(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("lance", versions::LANCE), ("arrow", versions::ARROW)]
+    }
+
+    fn supports_lookup(&self) -> bool {
+        true
+    }
+
+    fn supports_projection(&self) -> bool {
+        true
+    }
+
+    fn supports_object_store(&self) -> bool {
+        true
+    }
+
+    fn supports_in_memory(&self) -> bool {
+        true
+    }
+
+    fn supports_count(&self) -> bool {
+        true
+    }
+
+    // Row addresses and stable row ids are computed from offsets assuming
+    // no row was deleted
+    fn supports_delete(&self) -> bool {
+        self.take_mode == LanceTakeMode::Offsets
+    }
+
+    fn supports_fragments(&self) -> bool {
+        true
+    }
+
+    fn supports_compaction(&self) -> bool {
+        true
+    }
+
+    fn supports_scan(&self) -> bool {
+        true
+    }
+
+    fn supports_lance_encoding(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::deletes::{Deletions, BLOCK_ROWS};
+
+    #[test]
+    fn test_fragments_touched_with_deletions() {
+        // Three fragments of one block each, 10% of every block deleted
+        let deletions = Deletions::new(0.1);
+        let live = deletions.live_rows(BLOCK_ROWS as usize);
+        let starts = fragment_starts((0..3).map(|id| (id, live)));
+        assert_eq!(starts, vec![(0, 0), (900, 1), (1800, 2)]);
+
+        // Every live offset lands in the fragment holding its row's id
+        for offset in 0..3 * live as u64 {
+            let pos = starts.partition_point(|(start, _)| *start <= offset) - 1;
+            assert_eq!(starts[pos].1, deletions.id_at(offset) / BLOCK_ROWS);
+        }
+
+        assert_eq!(fragments_touched(&starts, &[850, 899, 900, 1799, 1800]), 3);
+        assert_eq!(fragments_touched(&starts, &[900, 1799]), 1);
+        assert_eq!(fragments_touched(&starts, &[]), 0);
+    }
 }
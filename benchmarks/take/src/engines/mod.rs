@@ -1,23 +1,125 @@
 //! Storage engine implementations.
 
+mod fallback;
 mod lance;
 mod parquet;
 mod parquet_async;
+mod runtime;
 mod traits;
+pub mod versions;
+#[cfg(feature = "vortex")]
 mod vortex;
 
-pub use lance::LanceEngine;
-pub use parquet::ParquetEngine;
+use lance_file::version::LanceFileVersion;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub use fallback::with_fallbacks;
+pub use lance::{LanceEngine, LanceTakeMode};
+pub use parquet::{ParquetEngine, ParquetReadMode};
 pub use parquet_async::ParquetAsyncEngine;
-pub use traits::{DatasetHandle, EngineRegistry};
+pub use runtime::{parse_engine_runtime, RuntimeFlavor, RuntimeSpec};
+pub use traits::{DatasetHandle, Engine, EngineRegistry, TakeOptions};
+#[cfg(feature = "vortex")]
 pub use vortex::VortexEngine;
 
-/// Create a registry with all available engines.
-pub fn create_registry() -> EngineRegistry {
+/// Whether engines that support it read local files with `O_DIRECT` (`--direct-io`).
+static DIRECT_IO: AtomicBool = AtomicBool::new(false);
+
+/// Make datasets opened from now on read with (or without) `O_DIRECT`.
+pub fn set_direct_io(enabled: bool) {
+    DIRECT_IO.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether datasets are opened for `O_DIRECT` reads.
+pub fn direct_io() -> bool {
+    DIRECT_IO.load(Ordering::Relaxed)
+}
+
+/// Whether engines read datasets fully loaded into memory (`--in-memory`).
+static IN_MEMORY: AtomicBool = AtomicBool::new(false);
+
+/// Make datasets opened from now on be read into memory when opened (or not).
+pub fn set_in_memory(enabled: bool) {
+    IN_MEMORY.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether datasets are loaded into memory when opened.
+pub fn in_memory() -> bool {
+    IN_MEMORY.load(Ordering::Relaxed)
+}
+
+/// Whether every Lance engine writes datasets with stable row ids (`--lance-stable-row-ids`).
+static LANCE_STABLE_ROW_IDS: AtomicBool = AtomicBool::new(false);
+
+/// Make Lance datasets written from now on use stable row ids (or not).
+pub fn set_lance_stable_row_ids(enabled: bool) {
+    LANCE_STABLE_ROW_IDS.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether Lance datasets are written with stable row ids regardless of take mode.
+pub fn lance_stable_row_ids() -> bool {
+    LANCE_STABLE_ROW_IDS.load(Ordering::Relaxed)
+}
+
+/// Create a registry with all engines compiled into this build.
+///
+/// Each engine gets the runtime named for it in `overrides`, or `runtime`.
+/// Heavyweight engines sit behind Cargo features; when one is disabled it is
+/// still registered as missing so selecting it explains how to enable it.
+pub fn create_registry(
+    runtime: RuntimeSpec,
+    overrides: &[(String, RuntimeSpec)],
+) -> EngineRegistry {
+    let runtime_for = |name: &str| {
+        overrides
+            .iter()
+            .find(|(engine, _)| engine == name)
+            .map_or(runtime, |(_, spec)| *spec)
+    };
+
     let mut registry = EngineRegistry::new();
-    registry.register(std::sync::Arc::new(LanceEngine::new()));
-    registry.register(std::sync::Arc::new(ParquetEngine::new()));
-    registry.register(std::sync::Arc::new(ParquetAsyncEngine::new()));
-    registry.register(std::sync::Arc::new(VortexEngine::new()));
+    registry.register(std::sync::Arc::new(LanceEngine::new(runtime_for("lance"))));
+    registry.register(std::sync::Arc::new(LanceEngine::with_file_version(
+        "lance-2.0",
+        LanceFileVersion::V2_0,
+        runtime_for("lance-2.0"),
+    )));
+    registry.register(std::sync::Arc::new(LanceEngine::with_file_version(
+        "lance-2.1",
+        LanceFileVersion::V2_1,
+        runtime_for("lance-2.1"),
+    )));
+    registry.register(std::sync::Arc::new(LanceEngine::with_file_version(
+        "lance-2.2",
+        LanceFileVersion::V2_2,
+        runtime_for("lance-2.2"),
+    )));
+    registry.register(std::sync::Arc::new(LanceEngine::with_take_mode(
+        "lance-take-rows",
+        LanceTakeMode::RowAddresses,
+        runtime_for("lance-take-rows"),
+    )));
+    registry.register(std::sync::Arc::new(LanceEngine::with_take_mode(
+        "lance-stable-row-ids",
+        LanceTakeMode::RowIds,
+        runtime_for("lance-stable-row-ids"),
+    )));
+    registry.register(std::sync::Arc::new(ParquetEngine::new(runtime_for(
+        "parquet",
+    ))));
+    registry.register(std::sync::Arc::new(ParquetEngine::with_read_mode(
+        "parquet-mmap",
+        ParquetReadMode::Mmap,
+        runtime_for("parquet-mmap"),
+    )));
+    registry.register(std::sync::Arc::new(ParquetAsyncEngine::new(runtime_for(
+        "parquet-async",
+    ))));
+    #[cfg(feature = "vortex")]
+    registry.register(std::sync::Arc::new(VortexEngine::new(runtime_for(
+        "vortex",
+    ))));
+    #[cfg(not(feature = "vortex"))]
+    registry.register_missing("vortex", "vortex");
     registry
 }
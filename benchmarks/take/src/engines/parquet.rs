@@ -1,32 +1,56 @@
 //! Parquet storage engine implementation.
 
 use anyhow::Result;
-use arrow::array::RecordBatch;
-use arrow::datatypes::SchemaRef;
+use arrow::array::{AsArray, RecordBatch, RecordBatchReader};
+use arrow::datatypes::UInt64Type;
 use async_trait::async_trait;
 use indicatif::{ProgressBar, ProgressStyle};
 use parquet::arrow::arrow_reader::{
-    ArrowReaderMetadata, ArrowReaderOptions, ParquetRecordBatchReaderBuilder, RowSelection,
-    RowSelector,
+    ArrowPredicate, ArrowPredicateFn, ArrowReaderMetadata, ArrowReaderOptions,
+    ParquetRecordBatchReaderBuilder, RowFilter, RowSelection, RowSelector,
 };
-use parquet::arrow::ArrowWriter;
+use parquet::arrow::{ArrowWriter, ProjectionMask};
+use parquet::file::metadata::ParquetMetaData;
 use parquet::file::properties::{EnabledStatistics, WriterProperties};
 use parquet::file::reader::{ChunkReader, FileReader, Length, SerializedFileReader};
+use parquet::file::statistics::Statistics;
+use parquet::schema::types::{ColumnPath, SchemaDescriptor};
+use std::collections::HashSet;
 use std::fs::{self, File};
 use std::io::BufReader;
 use std::os::unix::fs::FileExt;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 
-use crate::cache::drop_directory_cache;
+use crate::cache::{drop_directory_cache, open_direct, read_direct_at};
 use crate::data::{create_schema, generate_vector_batch};
+use crate::parquet_layouts::{DEFAULT_PAGE_SIZE, DEFAULT_WRITE_BATCH_SIZE};
+use crate::results::Skipping;
 use crate::Config;
 
-use super::traits::{DatasetHandle, Engine};
+use super::runtime::{EngineRuntime, RuntimeSpec};
+use super::traits::{DatasetHandle, Engine, SkipCounter, TakeOptions, FILTER_COLUMN, KEY_COLUMN};
+use super::versions;
+
+/// How the bytes of an open Parquet file are read.
+#[derive(Clone)]
+enum FileSource {
+    /// pread on a file handle
+    File {
+        file: Arc<File>,
+        /// `file` was opened with `O_DIRECT`, so reads must be aligned
+        direct: bool,
+    },
+    /// Slices of a memory map of the whole file, faulted in on access
+    Mmap(bytes::Bytes),
+    /// Slices of the whole file, read into memory when opened (`--in-memory`)
+    Memory(bytes::Bytes),
+}
 
 struct FileRef {
-    file: Arc<File>,
+    source: FileSource,
     size: u64,
 }
 
@@ -45,39 +69,82 @@ impl ChunkReader for FileRef {
     }
 
     fn get_bytes(&self, start: u64, length: usize) -> parquet::errors::Result<bytes::Bytes> {
-        let mut buf = vec![0; length];
-        self.file
-            .read_exact_at(&mut buf, start)
-            .map(|_| bytes::Bytes::from(buf))
+        let buf = match &self.source {
+            FileSource::Mmap(data) | FileSource::Memory(data) => {
+                let start = start as usize;
+                return Ok(data.slice(start..start + length));
+            }
+            FileSource::File { file, direct: true } => read_direct_at(file, start, length),
+            FileSource::File { file, .. } => {
+                let mut buf = vec![0; length];
+                file.read_exact_at(&mut buf, start).map(|_| buf)
+            }
+        };
+        buf.map(bytes::Bytes::from)
             .map_err(|e| parquet::errors::ParquetError::External(e.into()))
     }
 }
 
-/// Handle to an open Parquet dataset with cached file handle and metadata.
-pub struct ParquetHandle {
-    /// Cached file handle (we clone it for each read)
-    file: Arc<File>,
+/// How a Parquet engine reads the data file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParquetReadMode {
+    /// pread, with `O_DIRECT` under `--direct-io`
+    Pread,
+    /// Memory-map the file and read through the mapping
+    Mmap,
+}
+
+/// One data file of an open Parquet dataset, with cached file handle and metadata.
+struct ParquetFile {
+    /// Cached file handle or mapping (we clone it for each read)
+    source: FileSource,
     /// Size of the file, in bytes
     size: u64,
     /// Cached Arrow reader metadata
     arrow_metadata: ArrowReaderMetadata,
-    /// Cached schema
-    schema: SchemaRef,
-    /// Total row count
+    /// Dataset offset of the file's first row
+    first_row: u64,
+    /// Rows in this file
     row_count: usize,
 }
 
-impl ParquetHandle {
-    fn new(path: &str) -> Result<Self> {
-        let file = Arc::new(File::open(path)?);
+impl ParquetFile {
+    /// Open `path`, returning the file and the time taken to load its metadata.
+    fn open(
+        path: &str,
+        first_row: u64,
+        read_mode: ParquetReadMode,
+        direct: bool,
+    ) -> Result<(Self, Duration)> {
+        let file = File::open(path)?;
 
         let size = file.metadata()?.len();
 
         let options = ArrowReaderOptions::new().with_page_index(true);
 
         // Load and cache Arrow reader metadata
-        let arrow_metadata = ArrowReaderMetadata::load(file.as_ref(), options)?;
-        let schema = arrow_metadata.schema().clone();
+        let start = Instant::now();
+        let arrow_metadata = ArrowReaderMetadata::load(&file, options)?;
+        let metadata_load = start.elapsed();
+
+        // Metadata is read once through the page cache; takes bypass it
+        // with O_DIRECT, or read from the copy loaded under --in-memory
+        let source = match read_mode {
+            _ if super::in_memory() => FileSource::Memory(bytes::Bytes::from(fs::read(path)?)),
+            ParquetReadMode::Mmap => {
+                // SAFETY: benchmark datasets are not modified while they are open
+                let mmap = unsafe { memmap2::Mmap::map(&file)? };
+                FileSource::Mmap(bytes::Bytes::from_owner(mmap))
+            }
+            ParquetReadMode::Pread if direct => FileSource::File {
+                file: Arc::new(open_direct(Path::new(path))?),
+                direct,
+            },
+            ParquetReadMode::Pread => FileSource::File {
+                file: Arc::new(file),
+                direct,
+            },
+        };
 
         // Get total row count from metadata
         let row_count: usize = arrow_metadata
@@ -87,18 +154,65 @@ impl ParquetHandle {
             .map(|rg| rg.num_rows() as usize)
             .sum();
 
-        Ok(Self {
-            file,
+        let file = Self {
+            source,
             size,
             arrow_metadata,
-            schema,
+            first_row,
             row_count,
+        };
+        Ok((file, metadata_load))
+    }
+
+    /// Reader builder over this file with the cached handle and metadata.
+    fn reader_builder(&self) -> ParquetRecordBatchReaderBuilder<FileRef> {
+        let file = FileRef {
+            source: self.source.clone(),
+            size: self.size,
+        };
+        ParquetRecordBatchReaderBuilder::new_with_metadata(file, self.arrow_metadata.clone())
+    }
+}
+
+/// Handle to an open Parquet dataset of one or more data files.
+pub struct ParquetHandle {
+    /// Data files in row order
+    files: Vec<ParquetFile>,
+    /// Time taken to load the metadata of every file
+    metadata_load: Duration,
+    /// Row groups in all files
+    row_groups: usize,
+    /// Row groups queries could have read, and did
+    skipping: SkipCounter,
+}
+
+impl ParquetHandle {
+    fn new(paths: &[String], read_mode: ParquetReadMode, direct: bool) -> Result<Self> {
+        let mut files = Vec::with_capacity(paths.len());
+        let mut metadata_load = Duration::ZERO;
+        let mut first_row = 0;
+        for path in paths {
+            let (file, load) = ParquetFile::open(path, first_row, read_mode, direct)?;
+            first_row += file.row_count as u64;
+            metadata_load += load;
+            files.push(file);
+        }
+        let row_groups = files
+            .iter()
+            .map(|file| file.arrow_metadata.metadata().num_row_groups())
+            .sum();
+        Ok(Self {
+            files,
+            metadata_load,
+            row_groups,
+            skipping: SkipCounter::new("row groups"),
         })
     }
 }
 
 /// Convert sorted indices to a RowSelection.
-/// Indices must be sorted in ascending order.
+/// Indices must be sorted in ascending order. A row can only be selected once,
+/// so duplicate indices are collapsed.
 fn indices_to_row_selection(indices: &[u64], total_rows: usize) -> RowSelection {
     if indices.is_empty() {
         return RowSelection::from(vec![RowSelector::skip(total_rows)]);
@@ -110,6 +224,11 @@ fn indices_to_row_selection(indices: &[u64], total_rows: usize) -> RowSelection
     for &idx in indices {
         let idx = idx as usize;
 
+        // Duplicate of the previous index
+        if idx < current_pos {
+            continue;
+        }
+
         // Skip rows before this index
         if idx > current_pos {
             selectors.push(RowSelector::skip(idx - current_pos));
@@ -128,49 +247,341 @@ fn indices_to_row_selection(indices: &[u64], total_rows: usize) -> RowSelection
     RowSelection::from(selectors)
 }
 
+/// Predicate keeping rows where `FILTER_COLUMN = true`.
+pub(super) fn flag_predicate(schema_descr: &SchemaDescriptor) -> Box<dyn ArrowPredicate> {
+    Box::new(ArrowPredicateFn::new(
+        ProjectionMask::columns(schema_descr, [FILTER_COLUMN]),
+        |batch: RecordBatch| Ok(batch.column(0).as_boolean().clone()),
+    ))
+}
+
+/// Predicate keeping rows whose `KEY_COLUMN` value is in `keys`.
+pub(super) fn key_predicate(
+    schema_descr: &SchemaDescriptor,
+    keys: &[u64],
+) -> Box<dyn ArrowPredicate> {
+    let keys: HashSet<u64> = keys.iter().copied().collect();
+    Box::new(ArrowPredicateFn::new(
+        ProjectionMask::columns(schema_descr, [KEY_COLUMN]),
+        move |batch: RecordBatch| {
+            Ok(batch
+                .column(0)
+                .as_primitive::<UInt64Type>()
+                .iter()
+                .map(|key| key.map(|key| keys.contains(&key)))
+                .collect())
+        },
+    ))
+}
+
+/// Predicate keeping rows whose `KEY_COLUMN` value is below `bound`.
+pub(super) fn key_below_predicate(
+    schema_descr: &SchemaDescriptor,
+    bound: u64,
+) -> Box<dyn ArrowPredicate> {
+    Box::new(ArrowPredicateFn::new(
+        ProjectionMask::columns(schema_descr, [KEY_COLUMN]),
+        move |batch: RecordBatch| {
+            Ok(batch
+                .column(0)
+                .as_primitive::<UInt64Type>()
+                .iter()
+                .map(|key| key.map(|key| key < bound))
+                .collect())
+        },
+    ))
+}
+
+/// (min, max) `KEY_COLUMN` value of each row group, where its statistics
+/// have them.
+fn key_ranges(metadata: &ParquetMetaData) -> Vec<Option<(u64, u64)>> {
+    let key_idx = metadata
+        .file_metadata()
+        .schema_descr()
+        .columns()
+        .iter()
+        .position(|c| c.name() == KEY_COLUMN);
+
+    metadata
+        .row_groups()
+        .iter()
+        .map(|rg| {
+            // UInt64 is stored as INT64; ids never reach the sign bit
+            let Some(Statistics::Int64(stats)) = key_idx.and_then(|i| rg.column(i).statistics())
+            else {
+                return None;
+            };
+            match (stats.min_opt(), stats.max_opt()) {
+                (Some(&min), Some(&max)) => Some((min as u64, max as u64)),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Row groups whose `KEY_COLUMN` min/max statistics may contain one of `keys`.
+///
+/// Row groups without statistics are always kept.
+pub(super) fn matching_row_groups(metadata: &ParquetMetaData, keys: &[u64]) -> Vec<usize> {
+    key_ranges(metadata)
+        .into_iter()
+        .enumerate()
+        .filter(|(_, range)| match range {
+            Some((min, max)) => keys.iter().any(|&key| min <= key && key <= max),
+            None => true,
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Row groups holding at least one of the sorted row `indices`.
+pub(super) fn row_groups_touched(metadata: &ParquetMetaData, indices: &[u64]) -> usize {
+    let mut touched = 0;
+    let mut rest = indices;
+    let mut end_row = 0;
+    for rg in metadata.row_groups() {
+        end_row += rg.num_rows() as u64;
+        let inside = rest.partition_point(|&idx| idx < end_row);
+        if inside > 0 {
+            touched += 1;
+            rest = &rest[inside..];
+        }
+    }
+    touched
+}
+
+/// Count the rows whose `KEY_COLUMN` value is below `bound` from row group
+/// statistics alone, where they allow it.
+///
+/// Returns the rows of the row groups entirely below `bound`, and the row
+/// groups that may straddle it, which must be read to count. Row groups
+/// without statistics may straddle it.
+pub(super) fn count_below_by_statistics(
+    metadata: &ParquetMetaData,
+    bound: u64,
+) -> (usize, Vec<usize>) {
+    let mut below = 0;
+    let mut straddling = Vec::new();
+    for (i, range) in key_ranges(metadata).into_iter().enumerate() {
+        match range {
+            Some((_, max)) if max < bound => below += metadata.row_group(i).num_rows() as usize,
+            Some((min, _)) if min >= bound => {}
+            _ => straddling.push(i),
+        }
+    }
+    (below, straddling)
+}
+
+/// Writer properties shared by the Parquet engines, with `--parquet-layout`
+/// overrides from `config`.
+///
+/// Statistics are only kept for the key column, so key lookups can prune
+/// row groups without affecting the layout the take path reads.
+///
+/// Keep [`WRITE_OPTIONS`] in sync.
+pub(super) fn writer_properties(config: &Config) -> WriterProperties {
+    let layout = config.parquet_layout.clone().unwrap_or_default();
+    let mut builder = WriterProperties::builder()
+        .set_dictionary_enabled(false)
+        .set_data_page_size_limit(layout.page_size.unwrap_or(DEFAULT_PAGE_SIZE))
+        .set_statistics_enabled(EnabledStatistics::None)
+        .set_column_statistics_enabled(ColumnPath::from(KEY_COLUMN), EnabledStatistics::Chunk)
+        .set_write_batch_size(layout.write_batch_size.unwrap_or(DEFAULT_WRITE_BATCH_SIZE))
+        .set_offset_index_disabled(layout.page_index == Some(false));
+    if let Some(row_group_size) = layout.row_group_size {
+        builder = builder.set_max_row_group_size(row_group_size);
+    }
+    builder.build()
+}
+
+/// Summary of the default `writer_properties`, recorded in dataset manifests.
+pub(super) const WRITE_OPTIONS: &str =
+    "dictionary=false data_page_size_limit=8192 statistics=id:chunk write_batch_size=1";
+
 #[async_trait]
 impl DatasetHandle for ParquetHandle {
-    async fn take(&self, indices: &[u64]) -> Result<RecordBatch> {
-        // Build row selection from indices
-        let selection = indices_to_row_selection(indices, self.row_count);
-
-        let file = FileRef {
-            file: self.file.clone(),
-            size: self.size,
-        };
+    async fn take(&self, indices: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
+        let mut schema = None;
+        let mut batches = Vec::new();
+        let mut row_groups_read = 0;
+        for file in &self.files {
+            // Indices are sorted, so those in this file are contiguous
+            let end_row = file.first_row + file.row_count as u64;
+            let start = indices.partition_point(|&idx| idx < file.first_row);
+            let end = indices.partition_point(|&idx| idx < end_row);
+            if start == end {
+                continue;
+            }
+            let file_indices: Vec<u64> = indices[start..end]
+                .iter()
+                .map(|&idx| idx - file.first_row)
+                .collect();
+
+            // Build row selection from indices
+            let selection = indices_to_row_selection(&file_indices, file.row_count);
+            row_groups_read += row_groups_touched(file.arrow_metadata.metadata(), &file_indices);
+
+            let schema_descr = file
+                .arrow_metadata
+                .metadata()
+                .file_metadata()
+                .schema_descr();
+            let projection =
+                ProjectionMask::columns(schema_descr, options.columns.iter().map(|c| c.as_str()));
+
+            // Build reader with cloned file handle and cached metadata, applying row selection
+            let mut builder = file
+                .reader_builder()
+                .with_row_selection(selection)
+                .with_projection(projection);
+
+            // Evaluate the predicate in the reader so projected columns are only
+            // decoded for matching rows
+            if options.filter {
+                builder =
+                    builder.with_row_filter(RowFilter::new(vec![flag_predicate(schema_descr)]));
+            }
 
-        // Build reader with cloned file handle and cached metadata, applying row selection
-        let builder =
-            ParquetRecordBatchReaderBuilder::new_with_metadata(file, self.arrow_metadata.clone())
-                .with_row_selection(selection);
-        let reader = builder.build()?;
+            let reader = builder.build()?;
+            schema = Some(reader.schema());
 
-        // Read selected batches
-        let batches: Vec<RecordBatch> = reader.collect::<Result<Vec<_>, _>>()?;
+            // Read selected batches
+            for batch in reader {
+                batches.push(batch?);
+            }
+        }
 
-        if batches.is_empty() {
+        // A filtered take can legitimately match no rows
+        let Some(schema) = schema.filter(|_| !batches.is_empty() || options.filter) else {
             anyhow::bail!("No data in parquet file");
-        }
+        };
+        self.skipping.record(self.row_groups, row_groups_read);
 
         // Concatenate batches (should already have only selected rows)
-        let result = arrow::compute::concat_batches(&self.schema, &batches)?;
+        let result = arrow::compute::concat_batches(&schema, &batches)?;
         Ok(result)
     }
+
+    async fn lookup(&self, keys: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
+        let mut schema = None;
+        let mut batches = Vec::new();
+        let mut row_groups_read = 0;
+        for file in &self.files {
+            let metadata = file.arrow_metadata.metadata();
+            let schema_descr = metadata.file_metadata().schema_descr();
+            let projection =
+                ProjectionMask::columns(schema_descr, options.columns.iter().map(|c| c.as_str()));
+
+            let mut predicates = vec![key_predicate(schema_descr, keys)];
+            if options.filter {
+                predicates.push(flag_predicate(schema_descr));
+            }
+
+            // Prune row groups by the key column's statistics, then evaluate the
+            // predicates in the reader
+            let row_groups = matching_row_groups(metadata, keys);
+            row_groups_read += row_groups.len();
+            let reader = file
+                .reader_builder()
+                .with_row_groups(row_groups)
+                .with_projection(projection)
+                .with_row_filter(RowFilter::new(predicates))
+                .build()?;
+            schema = Some(reader.schema());
+
+            for batch in reader {
+                batches.push(batch?);
+            }
+        }
+
+        let schema = schema.ok_or_else(|| anyhow::anyhow!("Dataset has no parquet files"))?;
+        self.skipping.record(self.row_groups, row_groups_read);
+        Ok(arrow::compute::concat_batches(&schema, &batches)?)
+    }
+
+    async fn scan(&self, columns: &[String]) -> Result<usize> {
+        let mut rows = 0;
+        for file in &self.files {
+            let builder = file.reader_builder();
+            let projection = ProjectionMask::columns(
+                builder.parquet_schema(),
+                columns.iter().map(|c| c.as_str()),
+            );
+            for batch in builder.with_projection(projection).build()? {
+                rows += batch?.num_rows();
+            }
+        }
+        Ok(rows)
+    }
+
+    async fn count_rows(&self, key_below: Option<u64>) -> Result<usize> {
+        let Some(bound) = key_below else {
+            self.skipping.record(self.row_groups, 0);
+            return Ok(self.files.iter().map(|file| file.row_count).sum());
+        };
+        let mut rows = 0;
+        let mut row_groups_read = 0;
+        for file in &self.files {
+            let metadata = file.arrow_metadata.metadata();
+            let (below, straddling) = count_below_by_statistics(metadata, bound);
+            rows += below;
+            row_groups_read += straddling.len();
+            if straddling.is_empty() {
+                continue;
+            }
+            let schema_descr = metadata.file_metadata().schema_descr();
+            let reader = file
+                .reader_builder()
+                .with_row_groups(straddling)
+                .with_projection(ProjectionMask::columns(schema_descr, [KEY_COLUMN]))
+                .with_row_filter(RowFilter::new(vec![key_below_predicate(
+                    schema_descr,
+                    bound,
+                )]))
+                .build()?;
+            for batch in reader {
+                rows += batch?.num_rows();
+            }
+        }
+        self.skipping.record(self.row_groups, row_groups_read);
+        Ok(rows)
+    }
+
+    fn metadata_load_time(&self) -> Option<Duration> {
+        Some(self.metadata_load)
+    }
+
+    fn skipping(&self) -> Option<Skipping> {
+        Some(self.skipping.totals())
+    }
 }
 
 /// Parquet storage engine.
 pub struct ParquetEngine {
-    runtime: Arc<Runtime>,
+    name: &'static str,
+    read_mode: ParquetReadMode,
+    runtime: EngineRuntime,
 }
 
 impl ParquetEngine {
-    pub fn new() -> Self {
+    pub fn new(runtime: RuntimeSpec) -> Self {
+        Self {
+            name: "parquet",
+            read_mode: ParquetReadMode::Pread,
+            runtime: EngineRuntime::new(runtime),
+        }
+    }
+
+    /// Create a Parquet engine that reads the data file with a specific read mode.
+    pub fn with_read_mode(
+        name: &'static str,
+        read_mode: ParquetReadMode,
+        runtime: RuntimeSpec,
+    ) -> Self {
         Self {
-            runtime: Arc::new(
-                tokio::runtime::Builder::new_current_thread()
-                    .build()
-                    .unwrap(),
-            ),
+            name,
+            read_mode,
+            ..Self::new(runtime)
         }
     }
 
@@ -187,68 +598,103 @@ impl ParquetEngine {
         }
     }
 
-    /// Get the parquet file path within the dataset directory.
-    fn get_parquet_file(&self, uri: &str) -> String {
+    /// Path of data file `index` of `count` within the dataset directory.
+    ///
+    /// A single file is `data.parquet`; more are numbered so they sort in row order.
+    fn get_parquet_file(&self, uri: &str, index: usize, count: usize) -> String {
         let base_path = self.uri_to_path(uri);
-        format!("{}/data.parquet", base_path)
+        if count == 1 {
+            format!("{}/data.parquet", base_path)
+        } else {
+            format!("{}/data-{:05}.parquet", base_path, index)
+        }
+    }
+
+    /// Paths of the data files within the dataset directory, in row order.
+    fn list_parquet_files(&self, uri: &str) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+        for entry in fs::read_dir(self.uri_to_path(uri))? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == "parquet") {
+                files.push(path.to_string_lossy().to_string());
+            }
+        }
+        files.sort();
+        Ok(files)
     }
 }
 
 impl Default for ParquetEngine {
     fn default() -> Self {
-        Self::new()
+        Self::new(RuntimeSpec::default())
     }
 }
 
 #[async_trait]
 impl Engine for ParquetEngine {
     fn name(&self) -> &'static str {
-        "parquet"
+        self.name
     }
 
     fn runtime(&self) -> Arc<Runtime> {
-        self.runtime.clone()
+        self.runtime.get()
     }
 
-    fn exists(&self, uri: &str, expected_rows: usize) -> bool {
-        let parquet_file = self.get_parquet_file(uri);
-        let path = Path::new(&parquet_file);
+    fn runtime_spec(&self) -> RuntimeSpec {
+        self.runtime.spec()
+    }
 
-        if !path.exists() {
+    fn exists(&self, uri: &str, expected_rows: usize) -> bool {
+        let Ok(parquet_files) = self.list_parquet_files(uri) else {
+            return false;
+        };
+        if parquet_files.is_empty() {
             return false;
         }
 
         // Check row count
-        if let Ok(file) = File::open(path) {
-            if let Ok(reader) = SerializedFileReader::new(file) {
-                let metadata = reader.metadata();
-                let row_count: usize = metadata
-                    .row_groups()
-                    .iter()
-                    .map(|rg| rg.num_rows() as usize)
-                    .sum();
-                return row_count == expected_rows;
-            }
+        let mut row_count = 0;
+        for parquet_file in &parquet_files {
+            let Ok(file) = File::open(parquet_file) else {
+                return false;
+            };
+            let Ok(reader) = SerializedFileReader::new(file) else {
+                return false;
+            };
+            row_count += reader
+                .metadata()
+                .row_groups()
+                .iter()
+                .map(|rg| rg.num_rows() as usize)
+                .sum::<usize>();
         }
-        false
+        row_count == expected_rows
     }
 
     fn open(&self, uri: &str) -> Result<Arc<dyn DatasetHandle>> {
-        let parquet_file = self.get_parquet_file(uri);
-        let handle = ParquetHandle::new(&parquet_file)?;
+        let parquet_files = self.list_parquet_files(uri)?;
+        let handle = ParquetHandle::new(&parquet_files, self.read_mode, super::direct_io())?;
         Ok(Arc::new(handle))
     }
 
     fn write(&self, uri: &str, config: &Config) -> Result<Arc<dyn DatasetHandle>> {
         let base_path = self.uri_to_path(uri);
-        let parquet_file = self.get_parquet_file(uri);
+        let num_files = config.fragments_per_dataset();
+        let rows_per_file = config.rows_per_dataset / num_files;
+        let batch_size = config.write_batch_size.min(rows_per_file);
 
-        println!("\nGenerating dataset: {}", parquet_file);
+        println!(
+            "\nGenerating dataset: {}",
+            self.get_parquet_file(uri, 0, num_files)
+        );
+        if num_files > 1 {
+            println!("  Split into {} files of {} rows", num_files, rows_per_file);
+        }
 
         // Create the directory
         fs::create_dir_all(base_path)?;
 
-        let num_batches = config.rows_per_dataset / config.write_batch_size;
+        let num_batches = num_files * rows_per_file.div_ceil(batch_size);
         let pb = ProgressBar::new(num_batches as u64);
         pb.set_style(
             ProgressStyle::default_bar()
@@ -258,29 +704,35 @@ impl Engine for ParquetEngine {
 
         let schema = create_schema(config.vector_dim);
 
-        // Create the parquet writer
-        let file = File::create(&parquet_file)?;
-        let props = WriterProperties::builder()
-            .set_dictionary_enabled(false)
-            .set_data_page_size_limit(8 * 1024)
-            .set_statistics_enabled(EnabledStatistics::None)
-            .set_write_batch_size(1)
-            .build();
-        let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
-
-        // Write batches
-        for _ in 0..num_batches {
-            let batch =
-                generate_vector_batch(schema.clone(), config.write_batch_size, config.vector_dim)?;
-            writer.write(&batch)?;
-            pb.inc(1);
-        }
+        let mut parquet_files = Vec::with_capacity(num_files);
+        for index in 0..num_files {
+            // Create the parquet writer
+            let parquet_file = self.get_parquet_file(uri, index, num_files);
+            let file = File::create(&parquet_file)?;
+            let mut writer =
+                ArrowWriter::try_new(file, schema.clone(), Some(writer_properties(config)))?;
+
+            // Write batches
+            let first_row = index * rows_per_file;
+            for start in (first_row..first_row + rows_per_file).step_by(batch_size) {
+                let batch = generate_vector_batch(
+                    schema.clone(),
+                    start,
+                    batch_size.min(first_row + rows_per_file - start),
+                    config.vector_dim,
+                    config.common.seed,
+                )?;
+                writer.write(&batch)?;
+                pb.inc(1);
+            }
 
-        writer.close()?;
+            writer.close()?;
+            parquet_files.push(parquet_file);
+        }
         pb.finish();
 
-        // Open the written file with cached handle and metadata
-        let handle = ParquetHandle::new(&parquet_file)?;
+        // Open the written files with cached handles and metadata
+        let handle = ParquetHandle::new(&parquet_files, self.read_mode, super::direct_io())?;
         Ok(Arc::new(handle))
     }
 
@@ -288,4 +740,48 @@ impl Engine for ParquetEngine {
         let path = self.uri_to_path(uri);
         drop_directory_cache(Path::new(path))
     }
+
+    fn write_options(&self) -> String {
+        WRITE_OPTIONS.to_string()
+    }
+
+    fn library_versions(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("parquet", versions::PARQUET), ("arrow", versions::ARROW)]
+    }
+
+    fn supports_lookup(&self) -> bool {
+        true
+    }
+
+    fn supports_direct_io(&self) -> bool {
+        self.read_mode == ParquetReadMode::Pread
+    }
+
+    fn supports_in_memory(&self) -> bool {
+        true
+    }
+
+    fn supports_count(&self) -> bool {
+        true
+    }
+
+    fn supports_filter_pushdown(&self) -> bool {
+        true
+    }
+
+    fn supports_projection(&self) -> bool {
+        true
+    }
+
+    fn supports_fragments(&self) -> bool {
+        true
+    }
+
+    fn supports_scan(&self) -> bool {
+        true
+    }
+
+    fn supports_parquet_layout(&self) -> bool {
+        true
+    }
 }
@@ -1,51 +1,95 @@
 //! Async Parquet storage engine implementation using tokio I/O.
+//!
+//! Local files are read with tokio file I/O; object-store URIs (e.g. `s3://`)
+//! are read and written through `object_store`.
 
 use anyhow::Result;
 use arrow::array::RecordBatch;
-use arrow::datatypes::SchemaRef;
 use async_trait::async_trait;
 use futures::TryStreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
+use object_store::local::LocalFileSystem;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
 use parquet::arrow::arrow_reader::{
-    ArrowReaderMetadata, ArrowReaderOptions, RowSelection, RowSelector,
+    ArrowReaderMetadata, ArrowReaderOptions, RowFilter, RowSelection, RowSelector,
 };
-use parquet::arrow::async_reader::ParquetRecordBatchStreamBuilder;
-use parquet::arrow::ArrowWriter;
-use parquet::file::properties::{EnabledStatistics, WriterProperties};
-use parquet::file::reader::{FileReader, SerializedFileReader};
+use parquet::arrow::async_reader::{
+    AsyncFileReader, ParquetObjectReader, ParquetRecordBatchStreamBuilder,
+};
+use parquet::arrow::async_writer::ParquetObjectWriter;
+use parquet::arrow::{ArrowWriter, AsyncArrowWriter, ProjectionMask};
 use std::fs::{self, File};
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::fs::File as TokioFile;
 use tokio::runtime::Runtime;
+use url::Url;
 
-use crate::cache::drop_directory_cache;
+use crate::cache::{drop_directory_cache, is_local_uri};
 use crate::data::{create_schema, generate_vector_batch};
+use crate::inject;
+use crate::results::Skipping;
+use crate::storage::{load_into_memory, object_store_for};
 use crate::Config;
 
-use super::traits::{DatasetHandle, Engine};
+use super::parquet::{
+    count_below_by_statistics, flag_predicate, key_below_predicate, key_predicate,
+    matching_row_groups, row_groups_touched, writer_properties, WRITE_OPTIONS,
+};
+use super::runtime::{EngineRuntime, RuntimeSpec};
+use super::traits::{DatasetHandle, Engine, SkipCounter, TakeOptions, KEY_COLUMN};
+use super::versions;
+
+/// Where a Parquet file lives.
+#[derive(Clone)]
+enum ParquetLocation {
+    /// Local file path, reopened with tokio for every read
+    Local(String),
+    /// Object in an object store
+    Remote {
+        store: Arc<dyn ObjectStore>,
+        path: ObjectPath,
+    },
+}
+
+impl ParquetLocation {
+    /// Open a new reader for the file.
+    async fn reader(&self) -> Result<Box<dyn AsyncFileReader>> {
+        Ok(match self {
+            ParquetLocation::Local(path) => Box::new(TokioFile::open(path).await?),
+            ParquetLocation::Remote { store, path } => {
+                Box::new(ParquetObjectReader::new(store.clone(), path.clone()))
+            }
+        })
+    }
+}
 
 /// Handle to an open Parquet dataset for async reading.
-/// Stores the path and metadata, opens a new file handle per read.
+/// Stores the location and metadata, opens a new reader per read.
 pub struct ParquetAsyncHandle {
-    /// Path to the parquet file (for reopening)
-    path: String,
+    /// Location of the parquet file (for reopening)
+    location: ParquetLocation,
     /// Cached Arrow reader metadata
     arrow_metadata: ArrowReaderMetadata,
-    /// Cached schema
-    schema: SchemaRef,
+    /// Time taken to load `arrow_metadata`
+    metadata_load: Duration,
     /// Total row count
     row_count: usize,
+    /// Row groups queries could have read, and did
+    skipping: SkipCounter,
 }
 
 impl ParquetAsyncHandle {
-    async fn new(path: &str) -> Result<Self> {
-        let mut file = TokioFile::open(path).await?;
+    async fn new(location: ParquetLocation) -> Result<Self> {
+        let mut reader = location.reader().await?;
         let options = ArrowReaderOptions::new().with_page_index(true);
 
         // Load and cache Arrow reader metadata
-        let arrow_metadata = ArrowReaderMetadata::load_async(&mut file, options).await?;
-        let schema = arrow_metadata.schema().clone();
+        let start = Instant::now();
+        let arrow_metadata = ArrowReaderMetadata::load_async(&mut reader, options).await?;
+        let metadata_load = start.elapsed();
 
         // Get total row count from metadata
         let row_count: usize = arrow_metadata
@@ -56,16 +100,18 @@ impl ParquetAsyncHandle {
             .sum();
 
         Ok(Self {
-            path: path.to_string(),
+            location,
             arrow_metadata,
-            schema,
+            metadata_load,
             row_count,
+            skipping: SkipCounter::new("row groups"),
         })
     }
 }
 
 /// Convert sorted indices to a RowSelection.
-/// Indices must be sorted in ascending order.
+/// Indices must be sorted in ascending order. A row can only be selected once,
+/// so duplicate indices are collapsed.
 fn indices_to_row_selection(indices: &[u64], total_rows: usize) -> RowSelection {
     if indices.is_empty() {
         return RowSelection::from(vec![RowSelector::skip(total_rows)]);
@@ -77,6 +123,11 @@ fn indices_to_row_selection(indices: &[u64], total_rows: usize) -> RowSelection
     for &idx in indices {
         let idx = idx as usize;
 
+        // Duplicate of the previous index
+        if idx < current_pos {
+            continue;
+        }
+
         // Skip rows before this index
         if idx > current_pos {
             selectors.push(RowSelector::skip(idx - current_pos));
@@ -97,47 +148,136 @@ fn indices_to_row_selection(indices: &[u64], total_rows: usize) -> RowSelection
 
 #[async_trait]
 impl DatasetHandle for ParquetAsyncHandle {
-    async fn take(&self, indices: &[u64]) -> Result<RecordBatch> {
+    async fn take(&self, indices: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
         // Build row selection from indices
         let selection = indices_to_row_selection(indices, self.row_count);
 
-        // Open a new file handle for this read
-        let file = TokioFile::open(&self.path).await?;
+        // Open a new reader for this read
+        let file = self.location.reader().await?;
+
+        let schema_descr = self
+            .arrow_metadata
+            .metadata()
+            .file_metadata()
+            .schema_descr();
+        let projection =
+            ProjectionMask::columns(schema_descr, options.columns.iter().map(|c| c.as_str()));
 
         // Build async reader with cached metadata, applying row selection
-        let builder = ParquetRecordBatchStreamBuilder::new_with_metadata(
+        let mut builder = ParquetRecordBatchStreamBuilder::new_with_metadata(
             file,
             self.arrow_metadata.clone(),
         )
-        .with_row_selection(selection);
+        .with_row_selection(selection)
+        .with_projection(projection);
+
+        // Evaluate the predicate in the reader so projected columns are only
+        // decoded for matching rows
+        if options.filter {
+            builder = builder.with_row_filter(RowFilter::new(vec![flag_predicate(schema_descr)]));
+        }
+
         let stream = builder.build()?;
+        let schema = stream.schema().clone();
 
         // Read selected batches asynchronously
         let batches: Vec<RecordBatch> = stream.try_collect().await?;
 
-        if batches.is_empty() {
+        // A filtered take can legitimately match no rows
+        if batches.is_empty() && !options.filter {
             anyhow::bail!("No data in parquet file");
         }
+        let metadata = self.arrow_metadata.metadata();
+        self.skipping.record(
+            metadata.num_row_groups(),
+            row_groups_touched(metadata, indices),
+        );
 
         // Concatenate batches
-        let result = arrow::compute::concat_batches(&self.schema, &batches)?;
+        let result = arrow::compute::concat_batches(&schema, &batches)?;
         Ok(result)
     }
+
+    async fn lookup(&self, keys: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
+        let file = self.location.reader().await?;
+
+        let metadata = self.arrow_metadata.metadata();
+        let schema_descr = metadata.file_metadata().schema_descr();
+        let projection =
+            ProjectionMask::columns(schema_descr, options.columns.iter().map(|c| c.as_str()));
+
+        let mut predicates = vec![key_predicate(schema_descr, keys)];
+        if options.filter {
+            predicates.push(flag_predicate(schema_descr));
+        }
+
+        // Prune row groups by the key column's statistics, then evaluate the
+        // predicates in the reader
+        let row_groups = matching_row_groups(metadata, keys);
+        self.skipping.record(metadata.num_row_groups(), row_groups.len());
+        let stream =
+            ParquetRecordBatchStreamBuilder::new_with_metadata(file, self.arrow_metadata.clone())
+                .with_row_groups(row_groups)
+                .with_projection(projection)
+                .with_row_filter(RowFilter::new(predicates))
+                .build()?;
+        let schema = stream.schema().clone();
+
+        let batches: Vec<RecordBatch> = stream.try_collect().await?;
+        Ok(arrow::compute::concat_batches(&schema, &batches)?)
+    }
+
+    async fn count_rows(&self, key_below: Option<u64>) -> Result<usize> {
+        let metadata = self.arrow_metadata.metadata();
+        let Some(bound) = key_below else {
+            self.skipping.record(metadata.num_row_groups(), 0);
+            return Ok(self.row_count);
+        };
+        let (below, straddling) = count_below_by_statistics(metadata, bound);
+        self.skipping.record(metadata.num_row_groups(), straddling.len());
+        if straddling.is_empty() {
+            return Ok(below);
+        }
+
+        // Only the row groups statistics can't settle are read
+        let file = self.location.reader().await?;
+        let schema_descr = metadata.file_metadata().schema_descr();
+        let stream =
+            ParquetRecordBatchStreamBuilder::new_with_metadata(file, self.arrow_metadata.clone())
+                .with_row_groups(straddling)
+                .with_projection(ProjectionMask::columns(schema_descr, [KEY_COLUMN]))
+                .with_row_filter(RowFilter::new(vec![key_below_predicate(
+                    schema_descr,
+                    bound,
+                )]))
+                .build()?;
+        stream
+            .try_fold(
+                below,
+                |rows, batch| async move { Ok(rows + batch.num_rows()) },
+            )
+            .await
+            .map_err(Into::into)
+    }
+
+    fn metadata_load_time(&self) -> Option<Duration> {
+        Some(self.metadata_load)
+    }
+
+    fn skipping(&self) -> Option<Skipping> {
+        Some(self.skipping.totals())
+    }
 }
 
 /// Async Parquet storage engine using tokio I/O.
 pub struct ParquetAsyncEngine {
-    runtime: Arc<Runtime>,
+    runtime: EngineRuntime,
 }
 
 impl ParquetAsyncEngine {
-    pub fn new() -> Self {
+    pub fn new(runtime: RuntimeSpec) -> Self {
         Self {
-            runtime: Arc::new(
-                tokio::runtime::Builder::new_current_thread()
-                    .build()
-                    .unwrap(),
-            ),
+            runtime: EngineRuntime::new(runtime),
         }
     }
 
@@ -159,11 +299,52 @@ impl ParquetAsyncEngine {
         let base_path = self.uri_to_path(uri);
         format!("{}/data.parquet", base_path)
     }
+
+    /// Resolve the parquet file within the dataset at `uri`.
+    fn get_location(&self, uri: &str) -> Result<ParquetLocation> {
+        if is_local_uri(uri) {
+            return Ok(ParquetLocation::Local(self.get_parquet_file(uri)));
+        }
+
+        let url = Url::parse(&format!("{}/data.parquet", uri.trim_end_matches('/')))?;
+        let (store, path) = object_store_for(&url)?;
+        Ok(ParquetLocation::Remote { store, path })
+    }
+
+    /// Location to benchmark reads of `uri` from.
+    ///
+    /// Under `--in-memory` the file is read from an in-memory copy. With
+    /// latency injection on, local files are read through an object store as
+    /// well so every read goes through the injecting wrapper.
+    fn read_location(&self, uri: &str) -> Result<ParquetLocation> {
+        let location = if super::in_memory() {
+            let (store, prefix) = self.runtime.block_on(load_into_memory(uri))?;
+            ParquetLocation::Remote {
+                store,
+                path: prefix.child("data.parquet"),
+            }
+        } else {
+            self.get_location(uri)?
+        };
+        if inject::current().is_none() {
+            return Ok(location);
+        }
+        Ok(match location {
+            ParquetLocation::Local(file) => ParquetLocation::Remote {
+                store: inject::wrap(Arc::new(LocalFileSystem::new())),
+                path: ObjectPath::from_filesystem_path(&file)?,
+            },
+            ParquetLocation::Remote { store, path } => ParquetLocation::Remote {
+                store: inject::wrap(store),
+                path,
+            },
+        })
+    }
 }
 
 impl Default for ParquetAsyncEngine {
     fn default() -> Self {
-        Self::new()
+        Self::new(RuntimeSpec::default())
     }
 }
 
@@ -174,40 +355,42 @@ impl Engine for ParquetAsyncEngine {
     }
 
     fn runtime(&self) -> Arc<Runtime> {
-        self.runtime.clone()
+        self.runtime.get()
     }
 
-    fn exists(&self, uri: &str, expected_rows: usize) -> bool {
-        let parquet_file = self.get_parquet_file(uri);
-        let path = Path::new(&parquet_file);
+    fn runtime_spec(&self) -> RuntimeSpec {
+        self.runtime.spec()
+    }
 
-        if !path.exists() {
+    fn exists(&self, uri: &str, expected_rows: usize) -> bool {
+        // Check row count (a missing file fails to open)
+        let Ok(location) = self.get_location(uri) else {
             return false;
-        }
-
-        // Check row count
-        if let Ok(file) = File::open(path) {
-            if let Ok(reader) = SerializedFileReader::new(file) {
-                let metadata = reader.metadata();
-                let row_count: usize = metadata
-                    .row_groups()
-                    .iter()
-                    .map(|rg| rg.num_rows() as usize)
-                    .sum();
-                return row_count == expected_rows;
-            }
-        }
-        false
+        };
+        self.runtime
+            .block_on(ParquetAsyncHandle::new(location))
+            .map(|handle| handle.row_count == expected_rows)
+            .unwrap_or(false)
     }
 
     fn open(&self, uri: &str) -> Result<Arc<dyn DatasetHandle>> {
-        let parquet_file = self.get_parquet_file(uri);
+        let location = self.read_location(uri)?;
         // Use block_on to create the async handle
-        let handle = self.runtime.block_on(ParquetAsyncHandle::new(&parquet_file))?;
+        let handle = self.runtime.block_on(ParquetAsyncHandle::new(location))?;
         Ok(Arc::new(handle))
     }
 
     fn write(&self, uri: &str, config: &Config) -> Result<Arc<dyn DatasetHandle>> {
+        let location = self.get_location(uri)?;
+        if let ParquetLocation::Remote { store, path } = &location {
+            println!("\nGenerating dataset: {}", uri);
+            self.runtime
+                .block_on(write_remote(store.clone(), path.clone(), config))?;
+            let location = self.read_location(uri)?;
+            let handle = self.runtime.block_on(ParquetAsyncHandle::new(location))?;
+            return Ok(Arc::new(handle));
+        }
+
         let base_path = self.uri_to_path(uri);
         let parquet_file = self.get_parquet_file(uri);
 
@@ -228,18 +411,17 @@ impl Engine for ParquetAsyncEngine {
 
         // Create the parquet writer (sync write is fine for benchmarks)
         let file = File::create(&parquet_file)?;
-        let props = WriterProperties::builder()
-            .set_dictionary_enabled(false)
-            .set_data_page_size_limit(8 * 1024)
-            .set_statistics_enabled(EnabledStatistics::None)
-            .set_write_batch_size(1)
-            .build();
-        let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))?;
+        let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(writer_properties(config)))?;
 
         // Write batches
-        for _ in 0..num_batches {
-            let batch =
-                generate_vector_batch(schema.clone(), config.write_batch_size, config.vector_dim)?;
+        for i in 0..num_batches {
+            let batch = generate_vector_batch(
+                schema.clone(),
+                i * config.write_batch_size,
+                config.write_batch_size,
+                config.vector_dim,
+                config.common.seed,
+            )?;
             writer.write(&batch)?;
             pb.inc(1);
         }
@@ -248,12 +430,90 @@ impl Engine for ParquetAsyncEngine {
         pb.finish();
 
         // Open the written file with async handle
-        let handle = self.runtime.block_on(ParquetAsyncHandle::new(&parquet_file))?;
+        let location = self.read_location(uri)?;
+        let handle = self.runtime.block_on(ParquetAsyncHandle::new(location))?;
         Ok(Arc::new(handle))
     }
 
     fn drop_cache(&self, uri: &str) -> Result<()> {
+        if !is_local_uri(uri) {
+            return Ok(());
+        }
         let path = self.uri_to_path(uri);
         drop_directory_cache(Path::new(path))
     }
+
+    fn write_options(&self) -> String {
+        WRITE_OPTIONS.to_string()
+    }
+
+    fn library_versions(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("parquet", versions::PARQUET), ("arrow", versions::ARROW)]
+    }
+
+    fn supports_lookup(&self) -> bool {
+        true
+    }
+
+    fn supports_filter_pushdown(&self) -> bool {
+        true
+    }
+
+    fn supports_projection(&self) -> bool {
+        true
+    }
+
+    fn supports_object_store(&self) -> bool {
+        true
+    }
+
+    fn supports_in_memory(&self) -> bool {
+        true
+    }
+
+    fn supports_count(&self) -> bool {
+        true
+    }
+
+    fn supports_parquet_layout(&self) -> bool {
+        true
+    }
+}
+
+/// Write the dataset to an object store with a multipart upload.
+async fn write_remote(
+    store: Arc<dyn ObjectStore>,
+    path: ObjectPath,
+    config: &Config,
+) -> Result<()> {
+    let num_batches = config.rows_per_dataset / config.write_batch_size;
+    let pb = ProgressBar::new(num_batches as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("  Writing batches [{bar:40}] {pos}/{len}")
+            .unwrap(),
+    );
+
+    let schema = create_schema(config.vector_dim);
+    let mut writer = AsyncArrowWriter::try_new(
+        ParquetObjectWriter::new(store, path),
+        schema.clone(),
+        Some(writer_properties(config)),
+    )?;
+
+    for i in 0..num_batches {
+        let batch = generate_vector_batch(
+            schema.clone(),
+            i * config.write_batch_size,
+            config.write_batch_size,
+            config.vector_dim,
+            config.common.seed,
+        )?;
+        writer.write(&batch).await?;
+        pb.inc(1);
+    }
+
+    writer.close().await?;
+    pb.finish();
+    Ok(())
 }
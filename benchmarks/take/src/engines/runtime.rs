@@ -0,0 +1,154 @@
+//! Tokio runtime configuration for engines.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use tokio::runtime::Runtime;
+
+/// Kind of tokio runtime an engine runs its I/O on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum RuntimeFlavor {
+    CurrentThread,
+    MultiThread,
+}
+
+/// Runtime settings, written `current-thread`, `multi-thread` or `multi-thread:<threads>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuntimeSpec {
+    pub flavor: RuntimeFlavor,
+    /// Worker threads of a multi-thread runtime (None uses tokio's default, one per CPU)
+    pub worker_threads: Option<usize>,
+}
+
+impl Default for RuntimeSpec {
+    fn default() -> Self {
+        Self {
+            flavor: RuntimeFlavor::CurrentThread,
+            worker_threads: None,
+        }
+    }
+}
+
+impl FromStr for RuntimeSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (flavor, threads) = match s.split_once(':') {
+            Some((flavor, threads)) => (flavor, Some(threads)),
+            None => (s, None),
+        };
+        let flavor = match flavor {
+            "current-thread" => RuntimeFlavor::CurrentThread,
+            "multi-thread" => RuntimeFlavor::MultiThread,
+            other => {
+                return Err(format!(
+                    "unknown runtime '{}' (expected current-thread or multi-thread[:threads])",
+                    other
+                ))
+            }
+        };
+        let worker_threads = match threads {
+            None => None,
+            Some(_) if flavor == RuntimeFlavor::CurrentThread => {
+                return Err("a current-thread runtime has no worker count".to_string())
+            }
+            Some(threads) => match threads.parse::<usize>() {
+                Ok(threads) if threads > 0 => Some(threads),
+                _ => return Err(format!("invalid worker thread count '{}'", threads)),
+            },
+        };
+        Ok(Self {
+            flavor,
+            worker_threads,
+        })
+    }
+}
+
+impl fmt::Display for RuntimeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.flavor, self.worker_threads) {
+            (RuntimeFlavor::CurrentThread, _) => write!(f, "current-thread"),
+            (RuntimeFlavor::MultiThread, None) => write!(f, "multi-thread"),
+            (RuntimeFlavor::MultiThread, Some(threads)) => write!(f, "multi-thread:{}", threads),
+        }
+    }
+}
+
+/// Parse `engine=runtime`, as given to `--engine-runtime`.
+pub fn parse_engine_runtime(s: &str) -> Result<(String, RuntimeSpec), String> {
+    let (engine, spec) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <engine>=<runtime>, got '{}'", s))?;
+    Ok((engine.to_string(), spec.parse()?))
+}
+
+/// An engine's runtime, built the first time it is used.
+///
+/// Every engine is created up front for the registry, so building lazily keeps
+/// engines that aren't benchmarked from starting worker threads.
+pub struct EngineRuntime {
+    spec: RuntimeSpec,
+    runtime: OnceLock<Arc<Runtime>>,
+}
+
+impl EngineRuntime {
+    pub fn new(spec: RuntimeSpec) -> Self {
+        Self {
+            spec,
+            runtime: OnceLock::new(),
+        }
+    }
+
+    pub fn spec(&self) -> RuntimeSpec {
+        self.spec
+    }
+
+    pub fn get(&self) -> Arc<Runtime> {
+        self.runtime().clone()
+    }
+
+    fn runtime(&self) -> &Arc<Runtime> {
+        self.runtime.get_or_init(|| {
+            let mut builder = match self.spec.flavor {
+                RuntimeFlavor::CurrentThread => tokio::runtime::Builder::new_current_thread(),
+                RuntimeFlavor::MultiThread => tokio::runtime::Builder::new_multi_thread(),
+            };
+            if let Some(threads) = self.spec.worker_threads {
+                builder.worker_threads(threads);
+            }
+            // Object-store clients need the I/O and time drivers
+            Arc::new(builder.enable_all().build().unwrap())
+        })
+    }
+}
+
+impl Deref for EngineRuntime {
+    type Target = Runtime;
+
+    fn deref(&self) -> &Runtime {
+        self.runtime()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_runtime_spec() {
+        assert_eq!(
+            "current-thread".parse::<RuntimeSpec>().unwrap(),
+            RuntimeSpec::default()
+        );
+        let spec: RuntimeSpec = "multi-thread:8".parse().unwrap();
+        assert_eq!(spec.flavor, RuntimeFlavor::MultiThread);
+        assert_eq!(spec.worker_threads, Some(8));
+        assert_eq!(spec.to_string(), "multi-thread:8");
+        assert!("current-thread:2".parse::<RuntimeSpec>().is_err());
+        assert!("multi-thread:0".parse::<RuntimeSpec>().is_err());
+        assert!(parse_engine_runtime("lance").is_err());
+    }
+}
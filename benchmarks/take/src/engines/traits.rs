@@ -3,16 +3,97 @@
 use anyhow::Result;
 use arrow::record_batch::RecordBatch;
 use async_trait::async_trait;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 
+use super::runtime::RuntimeSpec;
+use crate::results::{Compaction, Skipping};
 use crate::Config;
 
+/// Options shared by every take query in a run.
+#[derive(Debug, Clone)]
+pub struct TakeOptions {
+    /// Columns to return
+    pub columns: Vec<String>,
+    /// Only return rows where `FILTER_COLUMN = true`
+    pub filter: bool,
+}
+
+/// Boolean column used by filtered takes.
+pub const FILTER_COLUMN: &str = "flag";
+
+/// Column used by key lookups.
+pub const KEY_COLUMN: &str = "id";
+
+/// Running totals of the data skipping of a handle's queries.
+pub struct SkipCounter {
+    unit: &'static str,
+    considered: AtomicU64,
+    read: AtomicU64,
+}
+
+impl SkipCounter {
+    /// Count units of data called `unit`, e.g. "row groups".
+    pub fn new(unit: &'static str) -> Self {
+        Self {
+            unit,
+            considered: AtomicU64::new(0),
+            read: AtomicU64::new(0),
+        }
+    }
+
+    /// Record a query that could have read `considered` units and read `read`.
+    pub fn record(&self, considered: usize, read: usize) {
+        self.considered
+            .fetch_add(considered as u64, Ordering::Relaxed);
+        self.read.fetch_add(read as u64, Ordering::Relaxed);
+    }
+
+    /// Totals of every query recorded so far.
+    pub fn totals(&self) -> Skipping {
+        Skipping {
+            unit: self.unit.to_string(),
+            considered: self.considered.load(Ordering::Relaxed),
+            read: self.read.load(Ordering::Relaxed),
+        }
+    }
+}
+
 /// A handle to an open dataset that can execute queries.
 #[async_trait]
 pub trait DatasetHandle: Send + Sync {
-    /// Execute a take query, returning the specified row indices.
-    async fn take(&self, indices: &[u64]) -> Result<RecordBatch>;
+    /// Execute a take query for the specified row indices.
+    async fn take(&self, indices: &[u64], options: &TakeOptions) -> Result<RecordBatch>;
+
+    /// Execute a lookup query for the rows whose `KEY_COLUMN` value is in `keys`.
+    async fn lookup(&self, _keys: &[u64], _options: &TakeOptions) -> Result<RecordBatch> {
+        anyhow::bail!("Key lookups are not supported by this engine")
+    }
+
+    /// Read `columns` of every row, returning the number of rows read.
+    async fn scan(&self, _columns: &[String]) -> Result<usize> {
+        anyhow::bail!("Scans are not supported by this engine")
+    }
+
+    /// Count the rows whose `KEY_COLUMN` value is below `key_below`, or every
+    /// row if it is None.
+    async fn count_rows(&self, _key_below: Option<u64>) -> Result<usize> {
+        anyhow::bail!("Counting rows is not supported by this engine")
+    }
+
+    /// Part of opening the dataset spent loading metadata (footers, manifests),
+    /// if the engine tracks it.
+    fn metadata_load_time(&self) -> Option<Duration> {
+        None
+    }
+
+    /// Data skipping of every query since the dataset was opened, if the
+    /// engine counts it.
+    fn skipping(&self) -> Option<Skipping> {
+        None
+    }
 }
 
 /// Engine trait for different storage backends.
@@ -24,6 +105,9 @@ pub trait Engine: Send + Sync {
     /// Get the runtime for the engine.
     fn runtime(&self) -> Arc<Runtime>;
 
+    /// How the engine's runtime is configured.
+    fn runtime_spec(&self) -> RuntimeSpec;
+
     /// Check if a dataset exists at the given URI with the expected row count.
     fn exists(&self, uri: &str, expected_rows: usize) -> bool;
 
@@ -35,17 +119,126 @@ pub trait Engine: Send + Sync {
 
     /// Drop the dataset from the kernel page cache.
     fn drop_cache(&self, uri: &str) -> Result<()>;
+
+    /// Delete the rows matching the SQL `predicate` from the dataset at `uri`.
+    fn delete(&self, _uri: &str, _predicate: &str) -> Result<()> {
+        anyhow::bail!("Deletes are not supported by this engine")
+    }
+
+    /// Rewrite the dataset at `uri` into fewer, larger fragments, dropping deleted rows.
+    fn compact(&self, _uri: &str) -> Result<Compaction> {
+        anyhow::bail!("Compaction is not supported by this engine")
+    }
+
+    /// Whether datasets from this engine implement `DatasetHandle::take` by row offset.
+    fn supports_take(&self) -> bool {
+        true
+    }
+
+    /// Whether datasets from this engine implement `DatasetHandle::lookup`.
+    fn supports_lookup(&self) -> bool {
+        false
+    }
+
+    /// Whether `DatasetHandle::take` applies `TakeOptions::filter` itself.
+    ///
+    /// Otherwise the benchmark filters the taken rows (see `fallback`).
+    fn supports_filter_pushdown(&self) -> bool {
+        false
+    }
+
+    /// Whether `DatasetHandle::take` returns only `TakeOptions::columns`.
+    ///
+    /// Otherwise the benchmark reads every column and projects them itself.
+    fn supports_projection(&self) -> bool {
+        false
+    }
+
+    /// Engine-specific write settings that shape the files on disk.
+    ///
+    /// Recorded in the dataset manifest, so changing them regenerates datasets.
+    fn write_options(&self) -> String {
+        String::new()
+    }
+
+    /// Versions of the libraries this engine exercises, as (crate, version).
+    ///
+    /// Recorded with the results so a change can be traced to a dependency bump.
+    fn library_versions(&self) -> Vec<(&'static str, &'static str)> {
+        Vec::new()
+    }
+
+    /// Whether this engine can read and write object-store URIs such as `s3://`.
+    fn supports_object_store(&self) -> bool {
+        false
+    }
+
+    /// Whether this engine reads local files with `O_DIRECT` under `--direct-io`.
+    fn supports_direct_io(&self) -> bool {
+        false
+    }
+
+    /// Whether this engine implements `DatasetHandle::count_rows`.
+    fn supports_count(&self) -> bool {
+        false
+    }
+
+    /// Whether this engine reads datasets from memory under `--in-memory`,
+    /// loading every byte of them when they are opened.
+    fn supports_in_memory(&self) -> bool {
+        false
+    }
+
+    /// Whether this engine implements `Engine::delete` and takes by offset
+    /// around the deleted rows.
+    fn supports_delete(&self) -> bool {
+        false
+    }
+
+    /// Whether `Engine::write` splits datasets into `Config::fragments_per_dataset`
+    /// fragments (or files).
+    fn supports_fragments(&self) -> bool {
+        false
+    }
+
+    /// Whether `Engine::write` applies `Config::lance_encoding` to the written columns.
+    fn supports_lance_encoding(&self) -> bool {
+        false
+    }
+
+    /// Whether `Engine::write` applies `Config::parquet_layout` to the written files.
+    fn supports_parquet_layout(&self) -> bool {
+        false
+    }
+
+    /// Whether `Engine::write` applies `Config::vortex_layout` to the written files.
+    fn supports_vortex_layout(&self) -> bool {
+        false
+    }
+
+    /// Whether datasets from this engine implement `DatasetHandle::scan`.
+    fn supports_scan(&self) -> bool {
+        false
+    }
+
+    /// Whether this engine implements `Engine::compact`.
+    fn supports_compaction(&self) -> bool {
+        false
+    }
 }
 
 /// Registry of available engines.
 pub struct EngineRegistry {
     engines: Vec<Arc<dyn Engine>>,
+    /// Engines left out of this build: (name, Cargo feature that enables it)
+    missing: Vec<(&'static str, &'static str)>,
 }
 
 impl EngineRegistry {
     pub fn new() -> Self {
         Self {
             engines: Vec::new(),
+            missing: Vec::new(),
         }
     }
 
@@ -53,6 +246,30 @@ impl EngineRegistry {
         self.engines.push(engine);
     }
 
+    /// Record an engine whose Cargo feature is disabled in this build.
+    pub fn register_missing(&mut self, name: &'static str, feature: &'static str) {
+        self.missing.push((name, feature));
+    }
+
+    /// Look up an engine, explaining why it can't be used if it isn't available.
+    pub fn resolve(&self, name: &str) -> Result<Arc<dyn Engine>> {
+        if let Some(engine) = self.get(name) {
+            return Ok(engine);
+        }
+        match self.missing.iter().find(|(missing, _)| *missing == name) {
+            Some((_, feature)) => anyhow::bail!(
+                "Engine '{}' was not compiled in. Rebuild with `--features {}`",
+                name,
+                feature
+            ),
+            None => anyhow::bail!(
+                "Unknown engine '{}'. Available engines: {:?} (see `lance-bench list engines`)",
+                name,
+                self.available()
+            ),
+        }
+    }
+
     pub fn get(&self, name: &str) -> Option<Arc<dyn Engine>> {
         self.engines.iter().find(|e| e.name() == name).cloned()
     }
@@ -60,6 +277,16 @@ impl EngineRegistry {
     pub fn available(&self) -> Vec<&'static str> {
         self.engines.iter().map(|e| e.name()).collect()
     }
+
+    /// Every engine compiled into this build.
+    pub fn engines(&self) -> &[Arc<dyn Engine>] {
+        &self.engines
+    }
+
+    /// Engines left out of this build, with the Cargo feature that enables each.
+    pub fn missing(&self) -> &[(&'static str, &'static str)] {
+        &self.missing
+    }
 }
 
 impl Default for EngineRegistry {
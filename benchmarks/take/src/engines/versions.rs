@@ -0,0 +1,8 @@
+//! Versions of the storage libraries in this build, resolved through
+//! `cargo metadata` by `build.rs`.
+
+pub const LANCE: &str = env!("DEP_VERSION_LANCE");
+pub const PARQUET: &str = env!("DEP_VERSION_PARQUET");
+pub const ARROW: &str = env!("DEP_VERSION_ARROW");
+#[cfg(feature = "vortex")]
+pub const VORTEX: &str = env!("DEP_VERSION_VORTEX");
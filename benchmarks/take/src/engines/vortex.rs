@@ -7,52 +7,102 @@ use indicatif::{ProgressBar, ProgressStyle};
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::runtime::Runtime;
 use vortex::array::arrays::ChunkedArray;
 use vortex::array::arrow::{FromArrowArray, IntoArrowArray};
 use vortex::array::stream::ArrayStreamExt;
 use vortex::array::{Array, ArrayRef};
-use vortex::buffer::Buffer;
-use vortex::dtype::DType;
-use vortex::file::{OpenOptionsSessionExt, VortexFile, VortexWriteOptions};
+use vortex::buffer::{Buffer, ByteBuffer};
+use vortex::dtype::{DType, FieldName, FieldNames};
+use vortex::expr::{get_item, root, select};
+use vortex::file::{OpenOptionsSessionExt, VortexFile, VortexWriteOptions, WriteStrategyBuilder};
 use vortex::io::session::RuntimeSessionExt;
+use vortex::layout::layouts::compact::CompactCompressor;
+use vortex::layout::LayoutStrategy;
 use vortex::scan::Selection;
 use vortex::session::VortexSession;
 use vortex::VortexSessionDefault;
 
 use crate::cache::drop_directory_cache;
 use crate::data::{create_schema, generate_vector_batch};
+use crate::vortex_layouts::{VortexCompressor, VortexLayout};
 use crate::Config;
 
-use super::traits::{DatasetHandle, Engine};
+use super::runtime::{EngineRuntime, RuntimeSpec};
+use super::traits::{DatasetHandle, Engine, TakeOptions, FILTER_COLUMN};
+use super::versions;
+
+/// Write strategy for `layout`: Vortex's default with its overrides applied.
+fn write_strategy(layout: &VortexLayout) -> Arc<dyn LayoutStrategy> {
+    let mut builder = WriteStrategyBuilder::new();
+    if let Some(row_block_size) = layout.row_block_size {
+        builder = builder.with_row_block_size(row_block_size);
+    }
+    if layout.compressor == Some(VortexCompressor::Compact) {
+        builder = builder.with_compressor(CompactCompressor::default());
+    }
+    builder.build()
+}
 
 /// Handle to an open Vortex dataset.
 pub struct VortexHandle {
     /// The opened Vortex file
     file: VortexFile,
+    /// Time taken to read the file's footer and layout
+    metadata_load: Duration,
 }
 
 impl VortexHandle {
     async fn new(path: &str, session: &VortexSession) -> Result<Self> {
-        let file = session
-            .open_options()
-            .open(path)
-            .await
-            .map_err(|e| anyhow::anyhow!("Failed to open Vortex file: {}", e))?;
+        // Under --in-memory the whole file is loaded first, outside the metadata load
+        let buffer = if super::in_memory() {
+            Some(ByteBuffer::from(tokio::fs::read(path).await?))
+        } else {
+            None
+        };
+        let start = Instant::now();
+        let file = if let Some(buffer) = buffer {
+            session.open_options().open_buffer(buffer)
+        } else {
+            session.open_options().open(path).await
+        }
+        .map_err(|e| anyhow::anyhow!("Failed to open Vortex file: {}", e))?;
+        let metadata_load = start.elapsed();
 
-        Ok(Self { file })
+        Ok(Self {
+            file,
+            metadata_load,
+        })
     }
 }
 
 #[async_trait]
 impl DatasetHandle for VortexHandle {
-    async fn take(&self, indices: &[u64]) -> Result<RecordBatch> {
-        // Read all data from the file first
-        let array = self
+    async fn take(&self, indices: &[u64], options: &TakeOptions) -> Result<RecordBatch> {
+        let projection = select(
+            options
+                .columns
+                .iter()
+                .map(|c| FieldName::from(c.as_str()))
+                .collect::<FieldNames>(),
+            root(),
+        );
+
+        let mut scan = self
             .file
             .scan()
             .map_err(|e| anyhow::anyhow!("Failed to create scan: {}", e))?
-            .with_selection(Selection::IncludeByIndex(Buffer::copy_from(indices)))
+            .with_projection(projection)
+            .with_selection(Selection::IncludeByIndex(Buffer::copy_from(indices)));
+
+        // The flag column is boolean, so it can be used as the filter directly
+        if options.filter {
+            scan = scan.with_filter(get_item(FILTER_COLUMN, root()));
+        }
+
+        // Read all data from the file first
+        let array = scan
             .into_array_stream()
             .map_err(|e| anyhow::anyhow!("Failed to create array stream: {}", e))?
             .read_all()
@@ -73,23 +123,44 @@ impl DatasetHandle for VortexHandle {
         let batch = RecordBatch::from(struct_array);
         Ok(batch)
     }
+
+    async fn scan(&self, columns: &[String]) -> Result<usize> {
+        let projection = select(
+            columns
+                .iter()
+                .map(|c| FieldName::from(c.as_str()))
+                .collect::<FieldNames>(),
+            root(),
+        );
+        let array = self
+            .file
+            .scan()
+            .map_err(|e| anyhow::anyhow!("Failed to create scan: {}", e))?
+            .with_projection(projection)
+            .into_array_stream()
+            .map_err(|e| anyhow::anyhow!("Failed to create array stream: {}", e))?
+            .read_all()
+            .await
+            .map_err(|e| anyhow::anyhow!("Failed to read array: {}", e))?;
+        Ok(array.len())
+    }
+
+    fn metadata_load_time(&self) -> Option<Duration> {
+        Some(self.metadata_load)
+    }
 }
 
 /// Vortex storage engine.
 pub struct VortexEngine {
     session: VortexSession,
-    runtime: Arc<Runtime>,
+    runtime: EngineRuntime,
 }
 
 impl VortexEngine {
-    pub fn new() -> Self {
+    pub fn new(runtime: RuntimeSpec) -> Self {
         Self {
             session: VortexSession::default().with_tokio(),
-            runtime: Arc::new(
-                tokio::runtime::Builder::new_current_thread()
-                    .build()
-                    .unwrap(),
-            ),
+            runtime: EngineRuntime::new(runtime),
         }
     }
 }
@@ -120,7 +191,11 @@ impl Engine for VortexEngine {
     }
 
     fn runtime(&self) -> Arc<Runtime> {
-        self.runtime.clone()
+        self.runtime.get()
+    }
+
+    fn runtime_spec(&self) -> RuntimeSpec {
+        self.runtime.spec()
     }
 
     fn exists(&self, uri: &str, expected_rows: usize) -> bool {
@@ -180,11 +255,13 @@ impl Engine for VortexEngine {
             let mut vortex_chunks: Vec<ArrayRef> = Vec::with_capacity(num_batches);
             let mut vortex_dtype: Option<DType> = None;
 
-            for _ in 0..num_batches {
+            for i in 0..num_batches {
                 let batch = generate_vector_batch(
                     schema.clone(),
+                    i * config.write_batch_size,
                     config.write_batch_size,
                     config.vector_dim,
+                    config.common.seed,
                 )?;
 
                 // Convert Arrow RecordBatch to StructArray first, then to Vortex array
@@ -208,7 +285,11 @@ impl Engine for VortexEngine {
 
             // Write to file
             let file = tokio::fs::File::create(&vortex_file).await?;
-            VortexWriteOptions::new(self.session.clone())
+            let mut options = VortexWriteOptions::new(self.session.clone());
+            if let Some(layout) = &config.vortex_layout {
+                options = options.with_strategy(write_strategy(layout));
+            }
+            options
                 .write(file, chunked.to_array_stream())
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to write Vortex file: {}", e))?;
@@ -223,4 +304,28 @@ impl Engine for VortexEngine {
         let path = self.uri_to_path(uri);
         drop_directory_cache(Path::new(path))
     }
+
+    fn supports_filter_pushdown(&self) -> bool {
+        true
+    }
+
+    fn supports_projection(&self) -> bool {
+        true
+    }
+
+    fn supports_vortex_layout(&self) -> bool {
+        true
+    }
+
+    fn supports_scan(&self) -> bool {
+        true
+    }
+
+    fn supports_in_memory(&self) -> bool {
+        true
+    }
+
+    fn library_versions(&self) -> Vec<(&'static str, &'static str)> {
+        vec![("vortex", versions::VORTEX), ("arrow", versions::ARROW)]
+    }
 }
@@ -0,0 +1,117 @@
+//! Lance engines from other builds of the benchmark (`--lance-build`).
+//!
+//! Only one version of the Lance crates can be linked into a binary, so to
+//! compare Lance versions in one run, each `--lance-build label=path` names
+//! another build of the same benchmark binary compiled against a different
+//! Lance (a release, git main, a local branch). After the in-process engines
+//! finish, every Lance engine of the run is benchmarked again by each build
+//! in a subprocess with the same arguments, on the same datasets, and its
+//! results are added to this run's as `<engine>@<label>`.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::results::{read_results, EngineResult};
+
+/// Parse `label=path`, as given to `--lance-build`.
+pub fn parse_lance_build(s: &str) -> Result<(String, PathBuf), String> {
+    let (label, path) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <label>=<binary>, got '{}'", s))?;
+    Ok((label.to_string(), PathBuf::from(path)))
+}
+
+/// Flags of this run that the subprocess must not inherit, and whether each takes a value.
+const STRIPPED_FLAGS: &[(&str, bool)] = &[
+    ("--lance-build", true),
+    ("--engines", true),
+    ("-e", true),
+    ("--output", true),
+    ("--resume", false),
+];
+
+/// `args` without `flags` (each with whether it takes a value), in either
+/// `--flag value` or `--flag=value` form.
+pub fn strip_flags(args: &[String], flags: &[(&str, bool)]) -> Vec<String> {
+    let mut kept = Vec::with_capacity(args.len());
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let stripped = flags.iter().find(|(flag, _)| {
+            arg == flag
+                || arg
+                    .strip_prefix(flag)
+                    .is_some_and(|rest| rest.starts_with('='))
+        });
+        match stripped {
+            Some((flag, true)) if arg == flag => {
+                iter.next();
+            }
+            Some(_) => {}
+            None => kept.push(arg.clone()),
+        }
+    }
+    kept
+}
+
+/// Run `engines` with the build at `binary`, returning its results renamed to `<engine>@<label>`.
+///
+/// `args` re-create this run's configuration, as for worker processes.
+pub fn run_build(
+    label: &str,
+    binary: &Path,
+    engines: &[&str],
+    args: &[String],
+) -> Result<Vec<EngineResult>> {
+    let output =
+        std::env::temp_dir().join(format!("lance-build-{}-{}.json", label, std::process::id()));
+    println!("\n{}", "=".repeat(60));
+    println!("Lance build {}: {}", label, binary.display());
+    println!("{}", "=".repeat(60));
+
+    let status = Command::new(binary)
+        .args(strip_flags(args, STRIPPED_FLAGS))
+        .arg("--engines")
+        .arg(engines.join(","))
+        .arg("--output")
+        .arg(&output)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to start {}: {}", binary.display(), e))?;
+    if !status.success() {
+        anyhow::bail!("Lance build {} failed: {}", label, status);
+    }
+
+    let mut results = read_results(&output)?;
+    std::fs::remove_file(&output)?;
+    for result in &mut results {
+        result.engine = format!("{}@{}", result.engine, label);
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_flags() {
+        let args: Vec<String> = [
+            "take",
+            "--engines",
+            "lance,parquet",
+            "--lance-build=main=/tmp/lb",
+            "--output",
+            "out.json",
+            "--resume",
+            "--duration",
+            "10s",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(
+            strip_flags(&args, STRIPPED_FLAGS),
+            vec!["take", "--duration", "10s"]
+        );
+    }
+}
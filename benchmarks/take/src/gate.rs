@@ -0,0 +1,353 @@
+//! Regression gate between two results files (`lance-bench gate`).
+//!
+//! Every phase of the baseline is matched with the current phase of the same
+//! engine, query size, concurrency and cache state. A latency metric
+//! regresses when it grows by more than the threshold *and* by at least the
+//! minimum effect, so tiny latencies can't fail the gate on noise alone;
+//! throughput regresses when it drops by more than the threshold. Phases and
+//! engines missing from the current results, or that failed there, also fail
+//! the gate.
+//!
+//! A regression seen in a single run can be noise. With several runs of the
+//! current code, [`confirms_regression`] only keeps it if a one-sided t-test
+//! finds the runs' mean beyond the regression limit.
+
+use clap::ValueEnum;
+use std::time::Duration;
+
+use crate::results::{EngineResult, PhaseResult};
+
+/// When a change counts as a regression.
+#[derive(Debug, Clone, Copy)]
+pub struct GateOptions {
+    /// Relative change that counts as a regression (0.05 = 5%)
+    pub threshold: f64,
+    /// Smallest latency increase that counts as a regression
+    pub min_effect: Duration,
+}
+
+/// Metrics compared by the gate.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    Mean,
+    P50,
+    P95,
+    P99,
+    Throughput,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 5] = [
+        Metric::Mean,
+        Metric::P50,
+        Metric::P95,
+        Metric::P99,
+        Metric::Throughput,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Metric::Mean => "mean",
+            Metric::P50 => "p50",
+            Metric::P95 => "p95",
+            Metric::P99 => "p99",
+            Metric::Throughput => "QPS",
+        }
+    }
+
+    /// The metric's value in a phase: seconds for latencies, queries/sec for throughput.
+    pub fn value(self, phase: &PhaseResult) -> f64 {
+        match self {
+            Metric::Mean => phase.stats.mean,
+            Metric::P50 => phase.stats.p50,
+            Metric::P95 => phase.stats.p95,
+            Metric::P99 => phase.stats.p99,
+            Metric::Throughput => phase.throughput,
+        }
+    }
+
+    /// Whether a lower value is better.
+    pub fn is_latency(self) -> bool {
+        self != Metric::Throughput
+    }
+
+    /// Format a value of this metric with its unit.
+    pub fn format(self, value: f64) -> String {
+        if self.is_latency() {
+            format!("{:.3} ms", value * 1000.0)
+        } else {
+            format!("{:.2} q/s", value)
+        }
+    }
+}
+
+/// A phase of the baseline, identified across results files.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseKey {
+    pub engine: String,
+    pub rows_per_query: usize,
+    pub concurrency: usize,
+    pub cache: String,
+}
+
+impl PhaseKey {
+    pub fn of(result: &EngineResult, phase: &PhaseResult) -> Self {
+        Self {
+            engine: result.engine.clone(),
+            rows_per_query: phase.rows_per_query,
+            concurrency: phase.concurrency,
+            cache: phase.cache.clone(),
+        }
+    }
+
+    pub fn matches(&self, result: &EngineResult, phase: &PhaseResult) -> bool {
+        *self == Self::of(result, phase)
+    }
+}
+
+impl std::fmt::Display for PhaseKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}r x{} {}",
+            self.engine, self.rows_per_query, self.concurrency, self.cache
+        )
+    }
+}
+
+/// Why a baseline phase failed the gate.
+#[derive(Debug, Clone)]
+pub enum Failure {
+    /// A metric got worse by more than allowed
+    Regressed {
+        metric: Metric,
+        baseline: f64,
+        current: f64,
+    },
+    /// The current results have no such phase
+    Missing,
+    /// The engine failed in the current results
+    EngineFailed(String),
+}
+
+/// A baseline phase that failed the gate.
+#[derive(Debug, Clone)]
+pub struct Violation {
+    pub phase: PhaseKey,
+    pub failure: Failure,
+}
+
+/// Relative change from `baseline` to `current`, signed so that positive is worse.
+pub fn relative_change(metric: Metric, baseline: f64, current: f64) -> f64 {
+    if baseline == 0.0 {
+        return 0.0;
+    }
+    let change = (current - baseline) / baseline;
+    if metric.is_latency() {
+        change
+    } else {
+        -change
+    }
+}
+
+/// Whether moving from `baseline` to `current` is a regression of `metric`.
+pub fn is_regression(metric: Metric, baseline: f64, current: f64, options: &GateOptions) -> bool {
+    if relative_change(metric, baseline, current) <= options.threshold {
+        return false;
+    }
+    !metric.is_latency() || current - baseline >= options.min_effect.as_secs_f64()
+}
+
+/// One-sided 95% critical values of Student's t for 1 to 30 degrees of freedom.
+const T_CRITICAL: [f64; 30] = [
+    6.314, 2.920, 2.353, 2.132, 2.015, 1.943, 1.895, 1.860, 1.833, 1.812, 1.796, 1.782, 1.771,
+    1.761, 1.753, 1.746, 1.740, 1.734, 1.729, 1.725, 1.721, 1.717, 1.714, 1.711, 1.708, 1.706,
+    1.703, 1.701, 1.699, 1.697,
+];
+
+/// Critical value beyond 30 degrees of freedom (the normal distribution's).
+const Z_CRITICAL: f64 = 1.645;
+
+/// Whether repeated runs' values of `metric` confirm a regression from `baseline`.
+///
+/// The runs' mean must be beyond the smallest regressed value (the threshold
+/// and, for latencies, the minimum effect) by more than the 95% one-sided
+/// confidence margin of the runs. A single run falls back to [`is_regression`].
+pub fn confirms_regression(
+    metric: Metric,
+    baseline: f64,
+    samples: &[f64],
+    options: &GateOptions,
+) -> bool {
+    match samples {
+        [] => return false,
+        [current] => return is_regression(metric, baseline, *current, options),
+        _ => {}
+    }
+    let n = samples.len() as f64;
+    let mean = samples.iter().sum::<f64>() / n;
+    let variance = samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let t_critical = T_CRITICAL
+        .get(samples.len() - 2)
+        .copied()
+        .unwrap_or(Z_CRITICAL);
+    let margin = t_critical * (variance / n).sqrt();
+    if metric.is_latency() {
+        let limit =
+            (baseline * (1.0 + options.threshold)).max(baseline + options.min_effect.as_secs_f64());
+        mean - margin > limit
+    } else {
+        mean + margin < baseline * (1.0 - options.threshold)
+    }
+}
+
+/// Compare `current` against `baseline`, returning every phase that fails the gate.
+pub fn check(
+    baseline: &[EngineResult],
+    current: &[EngineResult],
+    options: &GateOptions,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for base_result in baseline.iter().filter(|r| r.error.is_none()) {
+        let current_result = current.iter().find(|r| r.engine == base_result.engine);
+        for base_phase in &base_result.phases {
+            let phase = PhaseKey::of(base_result, base_phase);
+            let failure = match current_result {
+                None => Some(Failure::Missing),
+                Some(result) if result.error.is_some() => Some(Failure::EngineFailed(
+                    result.error.clone().unwrap_or_default(),
+                )),
+                Some(result) => match result.phases.iter().find(|p| phase.matches(result, p)) {
+                    None => Some(Failure::Missing),
+                    Some(current_phase) => {
+                        for metric in Metric::ALL {
+                            let (base, cur) =
+                                (metric.value(base_phase), metric.value(current_phase));
+                            if is_regression(metric, base, cur, options) {
+                                violations.push(Violation {
+                                    phase: phase.clone(),
+                                    failure: Failure::Regressed {
+                                        metric,
+                                        baseline: base,
+                                        current: cur,
+                                    },
+                                });
+                            }
+                        }
+                        None
+                    }
+                },
+            };
+            if let Some(failure) = failure {
+                violations.push(Violation { phase, failure });
+            }
+        }
+    }
+    violations
+}
+
+/// Print the violations as a table.
+pub fn print_violations(violations: &[Violation]) {
+    println!(
+        "\n{:<40} {:>6} {:>14} {:>14} {:>9}",
+        "Phase", "Metric", "Baseline", "Current", "Change"
+    );
+    println!("{}", "-".repeat(87));
+    for violation in violations {
+        match &violation.failure {
+            Failure::Regressed {
+                metric,
+                baseline,
+                current,
+            } => println!(
+                "{:<40} {:>6} {:>14} {:>14} {:>+8.1}%",
+                violation.phase.to_string(),
+                metric.name(),
+                metric.format(*baseline),
+                metric.format(*current),
+                (current - baseline) / baseline * 100.0
+            ),
+            Failure::Missing => println!(
+                "{:<40} missing from the current results",
+                violation.phase.to_string()
+            ),
+            Failure::EngineFailed(error) => println!(
+                "{:<40} engine failed: {}",
+                violation.phase.to_string(),
+                error
+            ),
+        }
+    }
+}
+
+/// Parse a threshold such as `5%` or `0.05`.
+pub fn parse_threshold(s: &str) -> Result<f64, String> {
+    let s = s.trim();
+    let value = match s.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f64>().map(|p| p / 100.0),
+        None => s.parse::<f64>(),
+    }
+    .map_err(|_| format!("invalid threshold '{}' (use e.g. 5% or 0.05)", s))?;
+    if value < 0.0 {
+        return Err(format!("threshold '{}' must not be negative", s));
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latency_regression_needs_threshold_and_min_effect() {
+        let options = GateOptions {
+            threshold: 0.05,
+            min_effect: Duration::from_millis(2),
+        };
+        // +10% but only 1ms
+        assert!(!is_regression(Metric::P50, 0.010, 0.011, &options));
+        // +10% and 10ms
+        assert!(is_regression(Metric::P50, 0.100, 0.110, &options));
+        // Faster is never a regression
+        assert!(!is_regression(Metric::P50, 0.100, 0.050, &options));
+        // Throughput only needs the threshold
+        assert!(is_regression(Metric::Throughput, 100.0, 90.0, &options));
+        assert!(!is_regression(Metric::Throughput, 100.0, 110.0, &options));
+    }
+
+    #[test]
+    fn test_confirms_regression() {
+        let options = GateOptions {
+            threshold: 0.05,
+            min_effect: Duration::ZERO,
+        };
+        // One noisy run over the limit, the reruns back at the baseline
+        assert!(!confirms_regression(
+            Metric::P50,
+            0.100,
+            &[0.120, 0.099, 0.101, 0.100],
+            &options
+        ));
+        // Every run well over the limit
+        assert!(confirms_regression(
+            Metric::P50,
+            0.100,
+            &[0.120, 0.119, 0.121, 0.120],
+            &options
+        ));
+        assert!(confirms_regression(
+            Metric::Throughput,
+            100.0,
+            &[80.0, 81.0, 79.0],
+            &options
+        ));
+    }
+
+    #[test]
+    fn test_parse_threshold() {
+        assert_eq!(parse_threshold("5%").unwrap(), 0.05);
+        assert_eq!(parse_threshold("0.1").unwrap(), 0.1);
+        assert!(parse_threshold("-5%").is_err());
+        assert!(parse_threshold("five").is_err());
+    }
+}
@@ -0,0 +1,271 @@
+//! Network latency, jitter, failure and bandwidth injection for object-store reads.
+//!
+//! With any of `--inject-latency`, `--inject-jitter`, `--inject-failure-rate`
+//! or `--throttle-read-bw` set, datasets are opened through an
+//! [`InjectingStore`] that delays (and possibly fails) every read request
+//! before passing it on. Local datasets are read through an object store too,
+//! so "what does a take look like at 30ms RTT" can be answered on a laptop.
+//! Dataset writes are never affected.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::path::Path as ObjectPath;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult,
+};
+use rand::Rng;
+use std::fmt;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// What to inject into every read request.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Injection {
+    /// Fixed delay added to every request
+    pub latency: Duration,
+    /// Extra delay drawn uniformly from `0..=jitter`
+    pub jitter: Duration,
+    /// Fraction of requests that fail after their delay
+    pub failure_rate: f64,
+    /// Read bandwidth in bytes/sec, shared by every read of the process
+    pub read_bandwidth: Option<u64>,
+}
+
+impl fmt::Display for Injection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} latency + up to {:?} jitter, {:.2}% failures",
+            self.latency,
+            self.jitter,
+            self.failure_rate * 100.0
+        )?;
+        if let Some(bandwidth) = self.read_bandwidth {
+            write!(f, ", {:.1} MB/s", bandwidth as f64 / 1_000_000.0)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parse a bandwidth such as `200MB/s` or `1GiB/s` into bytes/sec.
+pub fn parse_bandwidth(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let unit_start = s.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(s.len());
+    let (value, unit) = s.split_at(unit_start);
+    let value: f64 = value
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid bandwidth '{}'", s))?;
+    let multiplier = match unit.strip_suffix("/s").unwrap_or(unit) {
+        "" | "B" => 1.0,
+        "KB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        other => {
+            return Err(format!(
+            "unknown bandwidth unit '{}' (use B, KB, MB, GB, KiB, MiB or GiB, optionally with /s)",
+            other
+        ))
+        }
+    };
+    let bytes = (value * multiplier) as u64;
+    if bytes == 0 {
+        return Err(format!("bandwidth '{}' must be positive", s));
+    }
+    Ok(bytes)
+}
+
+/// Injection for the current run, like the run's CPU pinning.
+static INJECTION: Mutex<Option<Injection>> = Mutex::new(None);
+
+/// When the throttled read bandwidth is next free.
+///
+/// Reads queue up behind each other, so concurrent reads share the bandwidth
+/// of the whole process rather than getting it each.
+static THROTTLE_FREE_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Set the injection for datasets opened from now on.
+pub fn set(injection: Option<Injection>) {
+    *INJECTION.lock().unwrap() = injection;
+}
+
+/// The injection for the current run, if any.
+pub fn current() -> Option<Injection> {
+    *INJECTION.lock().unwrap()
+}
+
+/// Wrap `store` so its reads get the current run's injection, if any.
+pub fn wrap(store: Arc<dyn ObjectStore>) -> Arc<dyn ObjectStore> {
+    match current() {
+        Some(injection) => wrap_with(store, injection),
+        None => store,
+    }
+}
+
+/// Wrap `store` so its reads get `injection`.
+pub fn wrap_with(store: Arc<dyn ObjectStore>, injection: Injection) -> Arc<dyn ObjectStore> {
+    Arc::new(InjectingStore {
+        inner: store,
+        injection,
+    })
+}
+
+/// Object store that delays and fails read requests before passing them on.
+#[derive(Debug)]
+pub struct InjectingStore {
+    inner: Arc<dyn ObjectStore>,
+    injection: Injection,
+}
+
+impl InjectingStore {
+    /// Wait out the injected delay, then fail the request or let it through.
+    async fn inject(&self) -> object_store::Result<()> {
+        let (delay, fail) = {
+            let mut rng = rand::thread_rng();
+            let jitter = self.injection.jitter.mul_f64(rng.gen::<f64>());
+            (
+                self.injection.latency + jitter,
+                rng.gen::<f64>() < self.injection.failure_rate,
+            )
+        };
+        tokio::time::sleep(delay).await;
+        if fail {
+            return Err(object_store::Error::Generic {
+                store: "InjectingStore",
+                source: "injected request failure".into(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Hold a read of `bytes` back until the throttled bandwidth has carried it.
+    async fn throttle(&self, bytes: u64) {
+        let Some(bandwidth) = self.injection.read_bandwidth else {
+            return;
+        };
+        let transfer = Duration::from_secs_f64(bytes as f64 / bandwidth as f64);
+        let done_at = {
+            let mut free_at = THROTTLE_FREE_AT.lock().unwrap();
+            let start = free_at.map_or(Instant::now(), |t| t.max(Instant::now()));
+            *free_at = Some(start + transfer);
+            start + transfer
+        };
+        tokio::time::sleep_until(done_at.into()).await;
+    }
+}
+
+impl fmt::Display for InjectingStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "InjectingStore({}, {})", self.inner, self.injection)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for InjectingStore {
+    async fn put_opts(
+        &self,
+        location: &ObjectPath,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> object_store::Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &ObjectPath,
+        opts: PutMultipartOpts,
+    ) -> object_store::Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(
+        &self,
+        location: &ObjectPath,
+        options: GetOptions,
+    ) -> object_store::Result<GetResult> {
+        self.inject().await?;
+        let result = self.inner.get_opts(location, options).await?;
+        self.throttle(result.range.end - result.range.start).await;
+        Ok(result)
+    }
+
+    async fn get_range(
+        &self,
+        location: &ObjectPath,
+        range: Range<u64>,
+    ) -> object_store::Result<Bytes> {
+        self.inject().await?;
+        let bytes = self.inner.get_range(location, range).await?;
+        self.throttle(bytes.len() as u64).await;
+        Ok(bytes)
+    }
+
+    async fn get_ranges(
+        &self,
+        location: &ObjectPath,
+        ranges: &[Range<u64>],
+    ) -> object_store::Result<Vec<Bytes>> {
+        self.inject().await?;
+        let data = self.inner.get_ranges(location, ranges).await?;
+        let bytes = data.iter().map(|b| b.len() as u64).sum();
+        self.throttle(bytes).await;
+        Ok(data)
+    }
+
+    async fn head(&self, location: &ObjectPath) -> object_store::Result<ObjectMeta> {
+        self.inject().await?;
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &ObjectPath) -> object_store::Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(
+        &self,
+        prefix: Option<&ObjectPath>,
+    ) -> BoxStream<'static, object_store::Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    async fn list_with_delimiter(
+        &self,
+        prefix: Option<&ObjectPath>,
+    ) -> object_store::Result<ListResult> {
+        self.inject().await?;
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &ObjectPath, to: &ObjectPath) -> object_store::Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(
+        &self,
+        from: &ObjectPath,
+        to: &ObjectPath,
+    ) -> object_store::Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_bandwidth() {
+        assert_eq!(parse_bandwidth("200MB/s").unwrap(), 200_000_000);
+        assert_eq!(parse_bandwidth("1GiB/s").unwrap(), 1 << 30);
+        assert_eq!(parse_bandwidth("512KB").unwrap(), 512_000);
+        assert!(parse_bandwidth("0MB/s").is_err());
+        assert!(parse_bandwidth("10Mbit/s").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+//! Counts of Lance's own tracing spans and events during timed phases (`--trace-lance`).
+//!
+//! Lance instruments its I/O scheduler, file readers and decoders with
+//! `tracing`. The layer installed by [`layer`] counts the spans and events
+//! of every `lance*` target while a phase is recorded, and sums the time
+//! spent inside each span, so a phase's results show which parts of Lance
+//! the queries went through and where the time went. Time in nested spans
+//! is counted in each of them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_subscriber::filter::{filter_fn, FilterFn, Filtered};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Whether a phase is being recorded.
+static RECORDING: AtomicBool = AtomicBool::new(false);
+
+/// Counts of the phase being recorded, by callsite.
+static COUNTS: Mutex<Option<HashMap<(&'static str, &'static str), Count>>> = Mutex::new(None);
+
+#[derive(Default)]
+struct Count {
+    spans: u64,
+    events: u64,
+    busy: Duration,
+}
+
+/// How often one Lance span or event fired during a phase.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TraceCount {
+    pub target: String,
+    /// Span name, or `event file:line` for events
+    pub name: String,
+    /// Spans closed or events emitted
+    pub count: u64,
+    /// Time spent inside the span, summed over its instances (seconds); None for events
+    pub busy: Option<f64>,
+}
+
+/// Time spent inside one span, kept in the span's extensions.
+#[derive(Default)]
+struct SpanTiming {
+    busy: Duration,
+    entered: Option<Instant>,
+}
+
+/// Layer counting Lance's spans and events while a phase is recorded.
+pub struct LanceTraceLayer;
+
+/// The `--trace-lance` layer, which only sees Lance's targets.
+pub fn layer<S>() -> Filtered<LanceTraceLayer, FilterFn, S>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    LanceTraceLayer.with_filter(filter_fn(is_lance as fn(&Metadata<'_>) -> bool))
+}
+
+fn is_lance(metadata: &Metadata<'_>) -> bool {
+    metadata.target().starts_with("lance")
+}
+
+fn add(metadata: &'static Metadata<'static>, update: impl FnOnce(&mut Count)) {
+    if !RECORDING.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(counts) = COUNTS.lock().unwrap().as_mut() {
+        update(
+            counts
+                .entry((metadata.target(), metadata.name()))
+                .or_default(),
+        );
+    }
+}
+
+impl<S> Layer<S> for LanceTraceLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, _attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanTiming::default());
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timing) = span.extensions_mut().get_mut::<SpanTiming>() {
+                timing.entered = Some(Instant::now());
+            }
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            if let Some(timing) = span.extensions_mut().get_mut::<SpanTiming>() {
+                if let Some(entered) = timing.entered.take() {
+                    timing.busy += entered.elapsed();
+                }
+            }
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        if let Some(span) = ctx.span(&id) {
+            let busy = span
+                .extensions()
+                .get::<SpanTiming>()
+                .map_or(Duration::ZERO, |timing| timing.busy);
+            add(span.metadata(), |count| {
+                count.spans += 1;
+                count.busy += busy;
+            });
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        add(event.metadata(), |count| count.events += 1);
+    }
+}
+
+/// Start counting for a phase.
+pub fn start() {
+    *COUNTS.lock().unwrap() = Some(HashMap::new());
+    RECORDING.store(true, Ordering::Relaxed);
+}
+
+/// Stop counting and return the phase's counts, busiest spans first then
+/// the most frequent events.
+///
+/// Spans still open at the end of the phase aren't counted.
+pub fn finish() -> Vec<TraceCount> {
+    RECORDING.store(false, Ordering::Relaxed);
+    let Some(counts) = COUNTS.lock().unwrap().take() else {
+        return Vec::new();
+    };
+    let mut counts: Vec<TraceCount> = counts
+        .into_iter()
+        .map(|((target, name), count)| TraceCount {
+            target: target.to_string(),
+            name: name.to_string(),
+            count: count.spans + count.events,
+            busy: (count.spans > 0).then(|| count.busy.as_secs_f64()),
+        })
+        .collect();
+    counts.sort_by(|a, b| {
+        b.busy
+            .unwrap_or(-1.0)
+            .total_cmp(&a.busy.unwrap_or(-1.0))
+            .then(b.count.cmp(&a.count))
+    });
+    counts
+}
@@ -0,0 +1,2323 @@
+//! Take Benchmark
+//!
+//! Benchmarks take (point lookup) performance across different storage engines.
+//!
+//! Supports:
+//! - Lance (default, plus `lance-2.0`/`lance-2.1`/`lance-2.2` file version variants)
+//! - Parquet (sync, mmap and async readers)
+//! - Vortex (behind the default `vortex` Cargo feature)
+//!
+//! Multiple engines can be given to `--engines`, in which case each one is
+//! benchmarked in turn and a comparison table is printed at the end.
+//!
+//! The benchmark is a library so it can run both as the standalone
+//! `take-benchmark` binary and as `lance-bench take`.
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use crossbeam_channel::{bounded, Receiver, Sender};
+use futures::stream::{self, StreamExt};
+use indicatif::{ProgressBar, ProgressStyle};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+use tracing::Instrument;
+
+mod calibrate;
+#[cfg(feature = "tui")]
+mod dashboard;
+mod deletes;
+pub mod encodings;
+pub mod engines;
+pub mod external;
+pub mod gate;
+mod inject;
+mod lance_trace;
+pub mod manifest;
+pub mod markdown;
+mod matrix;
+pub mod parquet_layouts;
+mod plan;
+#[cfg(feature = "plots")]
+pub mod plots;
+mod profile;
+mod report;
+pub mod results;
+mod storage;
+pub mod trend;
+mod validate;
+pub mod vortex_layouts;
+mod workers;
+
+use bench_core::affinity::{parse_cpu_list, CpuList, Pinning};
+use bench_core::cache::{reset_cache_drop_mode, set_cache_drop_mode, CacheDropMode};
+use bench_core::cli::CommonArgs;
+use bench_core::duration::parse_duration;
+use bench_core::{blockdev, cache, data, monitor, stats, threads};
+use blockdev::BlockSnapshot;
+use calibrate::HarnessOverhead;
+use deletes::Deletions;
+use encodings::{parse_lance_encoding, LanceEncoding};
+use engines::{
+    create_registry, parse_engine_runtime, set_direct_io, set_in_memory, set_lance_stable_row_ids,
+    with_fallbacks, DatasetHandle, Engine, RuntimeSpec, TakeOptions,
+};
+use external::parse_lance_build;
+use inject::{parse_bandwidth, Injection};
+use manifest::DatasetManifest;
+use monitor::ThroughputMonitor;
+use parquet_layouts::{parse_parquet_layout, ParquetLayout};
+use profile::ProfileMode;
+use results::{
+    print_concurrency_curve, print_engine_result, try_read_results, write_results, Compaction,
+    CountTiming, EngineResult, OpenTimings, PhaseResult, Skipping,
+};
+use stats::{compute_quantiles, compute_statistics, Statistics};
+use threads::ThreadSampler;
+use vortex_layouts::{parse_vortex_layout, VortexLayout};
+
+/// Take benchmark configuration.
+#[derive(Parser, Debug, Clone)]
+#[command(name = "take-benchmark")]
+#[command(about = "Benchmark take (point lookup) performance across storage engines")]
+pub struct Config {
+    /// Storage engines to benchmark (comma-separated, e.g. lance-2.1,parquet,vortex)
+    #[arg(short, long, value_delimiter = ',', default_value = "lance")]
+    pub engines: Vec<String>,
+
+    /// Number of rows per dataset
+    #[arg(long, default_value_t = 1_000_000)]
+    pub rows_per_dataset: usize,
+
+    /// Batch size when writing data
+    #[arg(long, default_value_t = 100_000)]
+    pub write_batch_size: usize,
+
+    /// Vector dimension
+    #[arg(long, default_value_t = 768)]
+    pub vector_dim: usize,
+
+    /// Number of queries to execute
+    #[arg(long, default_value_t = 2_000)]
+    pub num_queries: usize,
+
+    /// Run each timed phase for this long (e.g. 60s, 5m) instead of --num-queries
+    ///
+    /// Queries are generated on the fly until the time budget is used up.
+    /// Warmup still runs --num-queries queries.
+    #[arg(long, value_parser = parse_duration)]
+    pub duration: Option<Duration>,
+
+    /// Number of rows per query (comma-separated values run as a sweep, e.g. 1,10,100)
+    #[arg(long, value_delimiter = ',', default_value = "500")]
+    pub rows_per_query: Vec<usize>,
+
+    /// How the row indices of each query are laid out
+    #[arg(long, value_enum, default_value_t = QueryPattern::Random)]
+    pub query_pattern: QueryPattern,
+
+    /// How each query addresses its rows
+    #[arg(long, value_enum, default_value_t = QueryBy::Offset)]
+    pub query_by: QueryBy,
+
+    /// Check that every returned row holds the requested row's generated values
+    ///
+    /// Catches engines returning the wrong rows. Verification runs after latency is
+    /// measured but still costs CPU on the worker threads, lowering throughput.
+    /// Datasets must have been written with the same --seed.
+    #[arg(long)]
+    pub verify: bool,
+
+    /// Never repeat a row index within a query
+    ///
+    /// By default indices are drawn independently, so a query can request the same
+    /// row more than once and engines differ on whether they return the duplicates.
+    #[arg(long)]
+    pub unique_indices: bool,
+
+    /// Number of contiguous ranges per query (ranges pattern only)
+    ///
+    /// Must divide every --rows-per-query value, so each range has the same
+    /// number of rows.
+    #[arg(long, default_value_t = 1)]
+    pub ranges_per_query: usize,
+
+    /// Columns to fetch in each take (comma-separated, e.g. id,vector)
+    #[arg(long, value_delimiter = ',', default_value = "vector")]
+    pub take_columns: Vec<String>,
+
+    /// Combine each take with the predicate `flag = true` (matches every even row)
+    #[arg(long)]
+    pub take_filter: bool,
+
+    /// Compare pushing the take filter into the reader with applying it after the read
+    ///
+    /// Implies --take-filter. Every engine that supports filter pushdown
+    /// runs a second time with the predicate applied in memory to every
+    /// requested row, as an engine without pushdown would; those results
+    /// are reported as `<engine>@post-filter`, with the speedup pushdown gave.
+    #[arg(long)]
+    pub compare_filter: bool,
+
+    /// Time counting the rows matched by a filter selecting this fraction of
+    /// them (comma-separated)
+    ///
+    /// The filter is `id < bound`, which engines can settle from statistics
+    /// and pruning rather than decoding; a fraction of 1 counts every row
+    /// with no filter. Counts run --count-iterations times each after every
+    /// dataset is opened. Only engines that can count rows run.
+    #[arg(long = "count-selectivity", value_delimiter = ',')]
+    pub count_selectivities: Vec<f64>,
+
+    /// Timed counts per --count-selectivity
+    #[arg(long, default_value_t = 10)]
+    pub count_iterations: usize,
+
+    /// Apply the take filter after reading even if the engine could push it
+    /// down, set for the second run of `--compare-filter`
+    #[arg(skip)]
+    pub post_filter: bool,
+
+    /// Delete this fraction of every dataset's rows after writing it (0 to 1)
+    ///
+    /// Measures what deletion vectors cost reads. The first rows of every
+    /// block of 1000 ids are deleted, and queries address the remaining
+    /// rows. Only engines that support deletes run.
+    #[arg(long)]
+    pub delete_fraction: Option<f64>,
+
+    /// Number of worker runtimes
+    #[arg(long, default_value_t = 16)]
+    pub num_runtimes: usize,
+
+    /// Tokio runtime each engine runs its I/O on: current-thread, or
+    /// multi-thread with an optional worker count (e.g. multi-thread:8)
+    ///
+    /// All worker threads drive the engine's one runtime; with multi-thread,
+    /// spawned queries also run on the runtime's own worker pool.
+    #[arg(long, default_value = "current-thread")]
+    pub runtime: RuntimeSpec,
+
+    /// Runtime for one engine, overriding --runtime (comma-separated, e.g. lance=multi-thread:8)
+    #[arg(long = "engine-runtime", value_delimiter = ',', value_parser = parse_engine_runtime)]
+    pub engine_runtimes: Vec<(String, RuntimeSpec)>,
+
+    /// Also run the Lance engines with another build of this benchmark (label=binary, repeatable)
+    ///
+    /// The build runs in a subprocess with the same arguments after the other
+    /// engines finish; its results are named <engine>@<label>. Use a build
+    /// compiled against a different Lance version to compare versions in one
+    /// comparison table.
+    #[arg(long = "lance-build", value_parser = parse_lance_build)]
+    pub lance_builds: Vec<(String, PathBuf)>,
+
+    /// Run each worker runtime in its own process instead of a thread
+    ///
+    /// Removes allocator and runtime contention between workers at high
+    /// concurrency. Each process opens its own dataset handles and warms them up.
+    /// Only hot closed-loop phases are supported, and there is no rolling
+    /// throughput report.
+    #[arg(long)]
+    pub worker_processes: bool,
+
+    /// Internal: run as a worker process with this JSON spec
+    #[arg(long, hide = true)]
+    pub worker_process: Option<String>,
+
+    /// Arguments that re-create this configuration in a worker process
+    ///
+    /// Defaults to this process's own arguments; callers that build a `Config`
+    /// without parsing the command line must set it.
+    #[arg(skip)]
+    pub worker_args: Option<Vec<String>>,
+
+    /// Per-query harness overhead, measured at the start of the run
+    #[arg(skip)]
+    pub harness_overhead: Option<HarnessOverhead>,
+
+    /// Concurrent queries per runtime (comma-separated values run as a sweep, e.g. 1,2,4,8)
+    #[arg(long, value_delimiter = ',', default_value = "4")]
+    pub concurrent_queries: Vec<usize>,
+
+    /// Pin worker runtime i to core i of this list (cpulist format, e.g. 0-15,32-47)
+    #[arg(long, value_parser = parse_cpu_list, conflicts_with = "pin_numa_nodes")]
+    pub pin_cores: Option<CpuList>,
+
+    /// Pin worker runtimes round-robin to all cores of these NUMA nodes (e.g. 0,1)
+    #[arg(long, value_parser = parse_cpu_list)]
+    pub pin_numa_nodes: Option<CpuList>,
+
+    /// Dataset URIs (can be specified multiple times)
+    ///
+    /// Local paths or, for engines that support them, object-store URIs such as
+    /// `s3://bucket/prefix`, `gs://bucket/prefix` or `az://container/prefix`.
+    /// Credentials come from each cloud's environment variables; set
+    /// AWS_ENDPOINT for MinIO and other S3-compatible stores.
+    #[arg(short, long, default_value = "file:///tmp/dataset")]
+    pub dataset_uri: Vec<String>,
+
+    /// Delay every dataset read request by this much (e.g. 30ms) to simulate a remote store
+    ///
+    /// Datasets are read through an object store wrapper that adds the delay,
+    /// local ones included, so only engines that support object stores run.
+    /// Generating datasets is not delayed.
+    #[arg(long, value_parser = parse_duration)]
+    pub inject_latency: Option<Duration>,
+
+    /// Add a random delay of up to this much to every dataset read request
+    #[arg(long, value_parser = parse_duration)]
+    pub inject_jitter: Option<Duration>,
+
+    /// Fail this fraction of dataset read requests (0 to 1)
+    ///
+    /// Failed requests fail their query, so raise --max-error-rate to match.
+    #[arg(long)]
+    pub inject_failure_rate: Option<f64>,
+
+    /// Throttle dataset reads to this bandwidth (e.g. 200MB/s)
+    ///
+    /// The bandwidth is shared by all concurrent reads of a process, so with
+    /// --worker-processes each worker gets this much. Like the --inject-*
+    /// flags, datasets are read through an object store wrapper.
+    #[arg(long, value_parser = parse_bandwidth)]
+    pub throttle_read_bw: Option<u64>,
+
+    /// Discard queries issued during this initial part of each hot timed phase (e.g. 10s)
+    ///
+    /// Unlike warmup, this runs the timed workload itself while caches, connection
+    /// pools and readahead settle. With --duration the phase is extended by the ramp-up.
+    #[arg(long, value_parser = parse_duration)]
+    pub ramp_up: Option<Duration>,
+
+    /// Skip warmup phase
+    #[arg(long, default_value_t = false)]
+    pub skip_warmup: bool,
+
+    /// Round-robin timed phases across engines instead of running one engine at a time
+    ///
+    /// Each workload runs on every engine before moving to the next, so slow
+    /// drift such as thermal throttling affects all engines alike. Engines are
+    /// warmed up before each of their phases. Results are only written once
+    /// every engine has finished.
+    #[arg(long)]
+    pub interleave: bool,
+
+    /// Shuffle the engine order and the order of timed queries
+    ///
+    /// Rules out ordering effects. With --interleave the engine order is
+    /// reshuffled for every workload. The seed is printed and recorded in the
+    /// results so the order can be reproduced with --shuffle-seed.
+    #[arg(long)]
+    pub shuffle: bool,
+
+    /// Seed for --shuffle (picked at random if not given)
+    #[arg(long, requires = "shuffle")]
+    pub shuffle_seed: Option<u64>,
+
+    /// Issue timed queries open-loop at this rate instead of as fast as possible
+    ///
+    /// Latencies then include queueing delay. Not supported with cold cache modes.
+    #[arg(long)]
+    pub target_qps: Option<f64>,
+
+    /// Page cache state for the timed phase
+    #[arg(long, value_enum, default_value_t = CacheMode::DropOnce)]
+    pub cache_mode: CacheMode,
+
+    /// Deprecated: use `--cache-mode hot`
+    #[arg(long, hide = true, conflicts_with = "cache_mode")]
+    pub skip_cache_drop: bool,
+
+    /// How cold phases evict datasets from the page cache
+    ///
+    /// fadvise is best effort and leaves mapped pages cached; cgroup also
+    /// runs each cold phase in a child cgroup that can cache little, without
+    /// root when run in a delegated cgroup of its own.
+    #[arg(long, value_enum, default_value_t = CacheDropMode::Fadvise)]
+    pub cache_drop_mode: CacheDropMode,
+
+    /// memory.high of the cgroup each cold phase runs in with
+    /// --cache-drop-mode cgroup (MiB), which bounds what stays cached between
+    /// waves
+    ///
+    /// Memory allocated during the phase counts against it too. Above it the
+    /// phase is throttled rather than killed, so leave room for the queries'
+    /// own buffers.
+    #[arg(long, default_value_t = 256)]
+    pub cgroup_memory_mb: u64,
+
+    /// Also benchmark a copy of every dataset under this tmpfs directory (e.g. /dev/shm/take)
+    ///
+    /// The copy's results are reported as `<engine>@tmpfs` next to the disk
+    /// results. Reads from tmpfs never wait on a device, so their latency is
+    /// the engine's CPU (decode) cost and the rest of the disk latency is I/O.
+    /// The copy is generated and reused like any other dataset.
+    #[arg(long)]
+    pub tmpfs_dir: Option<PathBuf>,
+
+    /// Write every dataset split into each of these numbers of fragments
+    /// (Lance) or files (Parquet) and benchmark them all (e.g. 1,10,100,1000)
+    ///
+    /// Replaces the single run on the datasets as given: each count gets its
+    /// own copy next to them and is reported as `<engine>@<n>frags`, with a
+    /// full scan timed after every open. Shows how open, scan and take
+    /// latency degrade as the same rows spread over more, smaller files.
+    #[arg(long, value_delimiter = ',', conflicts_with = "tmpfs_dir")]
+    pub fragments: Vec<usize>,
+
+    /// Fragments (or files) every dataset is written as, set for each count
+    /// of a `--fragments` sweep
+    #[arg(skip)]
+    pub fragment_count: Option<usize>,
+
+    /// Write every Lance dataset with stable row ids
+    ///
+    /// Stable row ids survive compaction and updates, at the cost of a row id
+    /// sequence kept with every fragment. Compare runs with and without to
+    /// see what they cost takes and scans. lance-stable-row-ids always
+    /// writes them; it also takes by row id.
+    #[arg(long)]
+    pub lance_stable_row_ids: bool,
+
+    /// Time a full scan of the take columns after every dataset is opened
+    ///
+    /// Always on in --fragments and --lance-encoding sweeps and with
+    /// --in-memory. Only engines that support scans run.
+    #[arg(long)]
+    pub time_scan: bool,
+
+    /// Also benchmark Lance datasets written with these encoding settings
+    /// (label=key:value[,key:value...], repeatable)
+    ///
+    /// Each setting becomes `lance-encoding:<key>` metadata on every column,
+    /// e.g. `zstd=compression:zstd,compression-level:3` or
+    /// `fullzip=structural-encoding:fullzip`. Settings are: compression,
+    /// compression-level, structural-encoding, packed, bss, rle-threshold
+    /// and dict-divisor. Each configuration gets its own copy of the
+    /// datasets and is reported as `<engine>@<label>` next to the default
+    /// encoding, with its size and scan time. Only Lance engines run.
+    #[arg(long = "lance-encoding", value_parser = parse_lance_encoding)]
+    pub lance_encodings: Vec<LanceEncoding>,
+
+    /// Encoding settings every dataset is written with, set for each
+    /// configuration of a `--lance-encoding` sweep
+    #[arg(skip)]
+    pub lance_encoding: Option<LanceEncoding>,
+
+    /// Also benchmark Parquet datasets written with these page layouts
+    /// (label=key:value[,key:value...], repeatable)
+    ///
+    /// By default pages are limited to 8 KiB, checked after every value
+    /// (write batch size 1), and an offset index lets takes read single
+    /// pages. Settings are: page-size (bytes), write-batch-size,
+    /// page-index (true or false) and row-group-size, e.g.
+    /// `1mb=page-size:1048576,write-batch-size:1024`. Each layout gets its
+    /// own copy of the datasets and is reported as `<engine>@<label>` next
+    /// to the default, with its size and scan time. Only Parquet engines run.
+    #[arg(long = "parquet-layout", value_parser = parse_parquet_layout)]
+    pub parquet_layouts: Vec<ParquetLayout>,
+
+    /// Page layout every dataset is written with, set for each layout of a
+    /// `--parquet-layout` sweep
+    #[arg(skip)]
+    pub parquet_layout: Option<ParquetLayout>,
+
+    /// Also benchmark Vortex datasets written with these layouts
+    /// (label=key:value[,key:value...], repeatable)
+    ///
+    /// By default Vortex's own write strategy is used. Settings are:
+    /// row-block-size (rows per layout chunk) and compressor (btrblocks, the
+    /// default, or compact, which adds general-purpose compression), e.g.
+    /// `compact64k=compressor:compact,row-block-size:65536`. Each layout gets
+    /// its own copy of the datasets and is reported as `<engine>@<label>`
+    /// next to the default, with its size and scan time. Only the Vortex
+    /// engine runs.
+    #[arg(long = "vortex-layout", value_parser = parse_vortex_layout)]
+    pub vortex_layouts: Vec<VortexLayout>,
+
+    /// Layout every dataset is written with, set for each layout of a
+    /// `--vortex-layout` sweep
+    #[arg(skip)]
+    pub vortex_layout: Option<VortexLayout>,
+
+    /// Also benchmark a compacted copy of every dataset
+    ///
+    /// The copy is written like the datasets as given, then compacted into
+    /// fewer, larger fragments, which also drops rows deleted with
+    /// --delete-fraction. Its results are reported as `<engine>@compacted`
+    /// next to the uncompacted ones, with what compacting cost. Only engines
+    /// that support compaction run.
+    #[arg(long)]
+    pub compact: bool,
+
+    /// Compact every dataset after writing it, set for the copies of `--compact`
+    #[arg(skip)]
+    pub compacted: bool,
+
+    /// Read local dataset files with O_DIRECT, bypassing the page cache entirely
+    ///
+    /// Every read then goes to the device, which makes cold reads more
+    /// reliable than dropping the cache with fadvise. Linux only; engines
+    /// that can't read with O_DIRECT are skipped.
+    #[arg(long)]
+    pub direct_io: bool,
+
+    /// Load every dataset fully into memory when it is opened, and read it
+    /// from there
+    ///
+    /// Takes and scans then measure decoding alone, with no storage or page
+    /// cache involved; open times include loading the whole dataset. Implies
+    /// --time-scan. Engines that can't read from memory are skipped.
+    #[arg(long, conflicts_with = "direct_io")]
+    pub in_memory: bool,
+
+    /// Record benchmark phases and every query as spans in Chrome trace format
+    ///
+    /// Open the file in Perfetto or chrome://tracing to inspect single slow
+    /// queries on a timeline. Spans emitted by the storage libraries are
+    /// included; queries run in --worker-processes are not. Traces of long
+    /// runs get large. Only one run per process can record a trace.
+    #[arg(long)]
+    pub trace_out: Option<PathBuf>,
+
+    /// Count Lance's own tracing spans and events during timed phases
+    ///
+    /// Each phase's results list the I/O scheduler, reader and decoder spans
+    /// and events Lance emitted, with the time spent in each span. Not
+    /// supported with --worker-processes.
+    #[arg(long)]
+    pub trace_lance: bool,
+
+    /// Sample thread states during timed phases to report on-CPU vs blocked time
+    ///
+    /// Every thread of the process is sampled every 10ms and counted as on
+    /// CPU, waiting on I/O, waiting on a futex or otherwise asleep. Not
+    /// supported with --worker-processes.
+    #[arg(long)]
+    pub sample_thread_states: bool,
+
+    /// Profile the timed phases
+    ///
+    /// `heap` needs the run to have a single timed phase; `perf` writes a
+    /// profile per engine and workload.
+    #[arg(long, value_enum)]
+    pub profile: Option<ProfileMode>,
+
+    /// Where to write the --profile output: a file for heap, a directory for perf
+    /// (default depends on the profile)
+    #[arg(long, requires = "profile")]
+    pub profile_out: Option<PathBuf>,
+
+    /// Serve runtime task data to tokio-console while the benchmark runs
+    ///
+    /// Needs RUSTFLAGS="--cfg tokio_unstable" to see tasks. tokio-console
+    /// connects to 127.0.0.1:6669 unless TOKIO_CONSOLE_BIND says otherwise.
+    #[cfg(feature = "console")]
+    #[arg(long)]
+    pub tokio_console: bool,
+
+    /// Show a live dashboard in place of the progress bar during timed phases
+    ///
+    /// Takes over the terminal for each timed phase to show its progress,
+    /// rolling throughput, latency percentiles, memory use and I/O rates, and
+    /// the phases finished so far. Falls back to the progress bar when stdout
+    /// isn't a terminal.
+    #[cfg(feature = "tui")]
+    #[arg(long, conflicts_with = "worker_processes")]
+    pub tui: bool,
+
+    /// Render plots of the results into this directory: latency CDFs,
+    /// throughput against queries in flight, dataset size against latency,
+    /// and a heatmap of p50 by engine and workload when the run sweeps them
+    #[cfg(feature = "plots")]
+    #[arg(long)]
+    pub plots: Option<PathBuf>,
+
+    /// Image format of --plots
+    #[cfg(feature = "plots")]
+    #[arg(long, value_enum, default_value = "svg", requires = "plots")]
+    pub plot_format: plots::PlotFormat,
+
+    /// How often to print rolling throughput during timed phases (0 disables)
+    #[arg(long, default_value = "5s", value_parser = parse_duration)]
+    pub report_interval: Duration,
+
+    /// Fail the run if any timed phase has a higher fraction of failed queries
+    ///
+    /// Failed queries are excluded from latency and throughput. The default of
+    /// 0 fails the run on any error.
+    #[arg(long, default_value_t = 0.0)]
+    pub max_error_rate: f64,
+
+    #[command(flatten)]
+    pub common: CommonArgs,
+}
+
+impl Config {
+    /// Rows deleted from every dataset, with `--delete-fraction`.
+    pub fn deletions(&self) -> Option<Deletions> {
+        self.delete_fraction.map(Deletions::new)
+    }
+
+    /// Fragments (or files) every dataset is written as.
+    pub fn fragments_per_dataset(&self) -> usize {
+        self.fragment_count.unwrap_or(1)
+    }
+
+    /// Rows every dataset holds once deletions are applied.
+    pub fn live_rows(&self) -> usize {
+        match self.deletions() {
+            Some(deletions) => deletions.live_rows(self.rows_per_dataset),
+            None => self.rows_per_dataset,
+        }
+    }
+}
+
+/// Layout of the row indices within a query.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryPattern {
+    /// Independent random rows
+    Random,
+    /// `ranges_per_query` contiguous slices splitting `rows_per_query` rows
+    Ranges,
+}
+
+/// How a query addresses the rows it fetches.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryBy {
+    /// Take by physical row offset
+    Offset,
+    /// Look up by `id` key value through the engine's index or statistics
+    ///
+    /// Ids are dense, so keys are drawn exactly like offsets.
+    Key,
+}
+
+/// Page cache handling during the timed phase.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CacheMode {
+    /// Drop the page cache once before each measurement, then run it without
+    /// further cache drops
+    DropOnce,
+    /// Keep the page cache warm (no cache drops)
+    Hot,
+    /// Drop the page cache before every wave of concurrent queries
+    Cold,
+    /// Run both a hot and a cold timed phase and report them side by side
+    Both,
+}
+
+impl CacheMode {
+    /// Cache label of the phase run without dropping the cache between waves.
+    pub fn warm_label(self) -> &'static str {
+        match self {
+            CacheMode::DropOnce => "drop-once",
+            _ => "hot",
+        }
+    }
+
+    /// Whether a phase drops the cache before every wave.
+    pub fn runs_cold(self) -> bool {
+        matches!(self, CacheMode::Cold | CacheMode::Both)
+    }
+
+    /// Whether the page cache is dropped before each measurement.
+    pub fn drops_cache(self) -> bool {
+        self != CacheMode::Hot
+    }
+}
+
+/// Query shape and closed-loop concurrency for one timed phase.
+#[derive(Debug, Clone, Copy)]
+pub struct Workload {
+    pub rows_per_query: usize,
+    /// Concurrent queries per runtime
+    pub concurrent_queries: usize,
+}
+
+static ROW_COUNTER: AtomicUsize = AtomicUsize::new(0);
+static ERROR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+// Queries with duplicate indices, by how the engine handled them
+static DUPLICATES_KEPT: AtomicUsize = AtomicUsize::new(0);
+static DUPLICATES_COLLAPSED: AtomicUsize = AtomicUsize::new(0);
+
+/// Worker CPU pinning of the current run, when requested.
+static PINNING: Mutex<Option<Pinning>> = Mutex::new(None);
+
+/// How often `--sample-thread-states` samples every thread.
+const THREAD_SAMPLE_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Build the worker pinning requested by `--pin-cores` or `--pin-numa-nodes`.
+fn build_pinning(config: &Config) -> Result<Option<Pinning>> {
+    Ok(match (&config.pin_cores, &config.pin_numa_nodes) {
+        (Some(cores), _) => Some(Pinning::cores(cores, config.num_runtimes)),
+        (None, Some(nodes)) => Some(Pinning::numa_nodes(nodes, config.num_runtimes)?),
+        (None, None) => None,
+    })
+}
+
+/// Build the read request injection requested by the `--inject-*` and `--throttle-read-bw` flags.
+fn build_injection(config: &Config) -> Option<Injection> {
+    if config.inject_latency.is_none()
+        && config.inject_jitter.is_none()
+        && config.inject_failure_rate.is_none()
+        && config.throttle_read_bw.is_none()
+    {
+        return None;
+    }
+    Some(Injection {
+        latency: config.inject_latency.unwrap_or_default(),
+        jitter: config.inject_jitter.unwrap_or_default(),
+        failure_rate: config.inject_failure_rate.unwrap_or_default(),
+        read_bandwidth: config.throttle_read_bw,
+    })
+}
+
+/// Install the tracing subscriber for `--trace-out`, `--trace-lance` and
+/// `--tokio-console`, if given.
+///
+/// The trace is written out when the returned guard is dropped.
+fn start_tracing(config: &Config) -> Result<Option<tracing_chrome::FlushGuard>> {
+    use tracing_subscriber::layer::SubscriberExt;
+
+    let (chrome, guard) = match &config.trace_out {
+        Some(path) => {
+            let (layer, guard) = tracing_chrome::ChromeLayerBuilder::new()
+                .file(path)
+                .include_args(true)
+                .build();
+            (Some(layer), Some(guard))
+        }
+        None => (None, None),
+    };
+    #[cfg(feature = "console")]
+    let console = config.tokio_console.then(|| {
+        console_subscriber::ConsoleLayer::builder()
+            .with_default_env()
+            .spawn()
+    });
+    #[cfg(not(feature = "console"))]
+    let console: Option<tracing_subscriber::layer::Identity> = None;
+
+    let lance = config.trace_lance.then(lance_trace::layer);
+
+    if chrome.is_none() && lance.is_none() && console.is_none() {
+        return Ok(None);
+    }
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::registry()
+            .with(chrome)
+            .with(lance)
+            .with(console),
+    )
+    .map_err(|_| anyhow::anyhow!("Tracing is already set up in this process"))?;
+    Ok(guard)
+}
+
+/// Put every process-wide setting back to its default.
+///
+/// A suite calls `run` several times in one process, and each run only sets
+/// what its own flags ask for.
+fn reset_process_settings() {
+    *PINNING.lock().unwrap() = None;
+    inject::set(None);
+    set_direct_io(false);
+    set_in_memory(false);
+    set_lance_stable_row_ids(false);
+    reset_cache_drop_mode();
+}
+
+/// Pin the calling worker thread if `--pin-cores` or `--pin-numa-nodes` was given.
+fn pin_worker(worker: usize) -> Result<()> {
+    match PINNING.lock().unwrap().as_ref() {
+        Some(pinning) => pinning.apply(worker),
+        None => Ok(()),
+    }
+}
+
+// Query task: (dataset_idx, query_indices)
+type QueryTask = (usize, Vec<u64>);
+
+// Completed query: (dataset_idx, latency in seconds)
+type QueryLatency = (usize, f64);
+
+/// Which completed queries a run records.
+#[derive(Debug, Clone, Copy)]
+enum Record {
+    /// Record nothing (warmup)
+    Nothing,
+    /// Record every query
+    All,
+    /// Record queries issued at or after this instant (ramp-up)
+    IssuedFrom(Instant),
+}
+
+impl Record {
+    fn includes(self, issued: Instant) -> bool {
+        match self {
+            Record::Nothing => false,
+            Record::All => true,
+            Record::IssuedFrom(from) => issued >= from,
+        }
+    }
+}
+
+/// Where the queries of a closed-loop run come from.
+enum QuerySource {
+    /// A fixed list of pre-generated queries
+    Fixed(Vec<Vec<u64>>),
+    /// Freshly generated queries of `rows_per_query` rows until `deadline`,
+    /// the nth drawn from `query_seed(seed, &[n])`
+    Until {
+        deadline: Instant,
+        rows_per_query: usize,
+        seed: u64,
+    },
+}
+
+/// Generate `count` queries of `rows_per_query` rows using the configured
+/// pattern, drawn from `seed`.
+fn make_queries(config: &Config, rows_per_query: usize, count: usize, seed: u64) -> Vec<Vec<u64>> {
+    match config.query_pattern {
+        QueryPattern::Random if config.unique_indices => {
+            data::generate_unique_queries(count, rows_per_query, config.live_rows(), seed)
+        }
+        QueryPattern::Random => {
+            data::generate_queries(count, rows_per_query, config.live_rows(), seed)
+        }
+        QueryPattern::Ranges => {
+            let mut queries = data::generate_range_queries(
+                count,
+                config.ranges_per_query,
+                rows_per_query / config.ranges_per_query,
+                config.live_rows(),
+                seed,
+            );
+            // Overlapping ranges repeat rows; queries are sorted so dedup removes them
+            if config.unique_indices {
+                queries.iter_mut().for_each(|query| query.dedup());
+            }
+            queries
+        }
+    }
+}
+
+/// How an engine answered a query that requested some rows more than once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DuplicateHandling {
+    /// One row per requested index, duplicates included
+    Kept,
+    /// One row per distinct index
+    Collapsed,
+}
+
+/// Check a query's returned row count against the (sorted) indices it requested.
+///
+/// Engines may either return every duplicate or collapse them, so both counts
+/// are accepted; anything else is an error. Returns how duplicates were handled
+/// when the query had any.
+fn check_row_count(
+    indices: &[u64],
+    filter: bool,
+    returned: usize,
+) -> Result<Option<DuplicateHandling>> {
+    let mut matching: Vec<u64> = indices
+        .iter()
+        .copied()
+        .filter(|&idx| !filter || data::flag_for(idx))
+        .collect();
+    let requested = matching.len();
+    matching.dedup();
+    let distinct = matching.len();
+
+    if requested == distinct && returned == requested {
+        Ok(None)
+    } else if returned == requested {
+        Ok(Some(DuplicateHandling::Kept))
+    } else if returned == distinct {
+        Ok(Some(DuplicateHandling::Collapsed))
+    } else {
+        anyhow::bail!(
+            "Query returned {} rows, expected {} ({} without duplicates)",
+            returned,
+            requested,
+            distinct
+        )
+    }
+}
+
+/// A successfully completed query.
+struct QueryOutcome {
+    /// Latency in seconds
+    latency: f64,
+    rows: usize,
+    /// How duplicate indices were handled, if the query had any
+    duplicates: Option<DuplicateHandling>,
+}
+
+/// Settings shared by every query of a run.
+struct QuerySettings {
+    options: TakeOptions,
+    query_by: QueryBy,
+    /// Check returned values against the generated data
+    verify: bool,
+    seed: u64,
+    vector_dim: usize,
+    /// Maps the live-row offsets of queries to ids, with `--delete-fraction`
+    deletions: Option<Deletions>,
+}
+
+impl QuerySettings {
+    fn new(config: &Config) -> Self {
+        Self {
+            options: TakeOptions {
+                columns: config.take_columns.clone(),
+                filter: config.take_filter,
+            },
+            query_by: config.query_by,
+            verify: config.verify,
+            seed: config.common.seed,
+            vector_dim: config.vector_dim,
+            deletions: config.deletions(),
+        }
+    }
+}
+
+/// Execute a single take, measuring its latency from `start` and checking the
+/// returned row count (and values, with `--verify`).
+async fn execute_query(
+    dataset: Arc<dyn DatasetHandle>,
+    query_indices: Vec<u64>,
+    settings: Arc<QuerySettings>,
+    start: Instant,
+) -> Result<QueryOutcome> {
+    monitor::query_started();
+    let options = &settings.options;
+    // Offsets count live rows only, ids skip the deleted ones
+    let ids: std::borrow::Cow<[u64]> = match settings.deletions {
+        Some(deletions) => query_indices.iter().map(|&i| deletions.id_at(i)).collect(),
+        None => query_indices.as_slice().into(),
+    };
+    let span = tracing::info_span!("query", rows = query_indices.len());
+    let result = async {
+        match settings.query_by {
+            QueryBy::Offset => dataset.take(&query_indices, options).await,
+            QueryBy::Key => dataset.lookup(&ids, options).await,
+        }
+    }
+    .instrument(span)
+    .await;
+    let result = result.and_then(|batch| {
+        let latency = start.elapsed().as_secs_f64();
+        let rows = batch.num_rows();
+        let duplicates = check_row_count(&ids, options.filter, rows)?;
+
+        if settings.verify {
+            let mut expected: Vec<u64> = ids
+                .iter()
+                .copied()
+                .filter(|&idx| !options.filter || data::flag_for(idx))
+                .collect();
+            if duplicates == Some(DuplicateHandling::Collapsed) {
+                expected.dedup();
+            }
+            // Lookups come back in whatever order the engine scans them
+            data::verify_batch(
+                &batch,
+                &expected,
+                settings.query_by == QueryBy::Offset,
+                settings.seed,
+                settings.vector_dim,
+            )?;
+        }
+
+        Ok(QueryOutcome {
+            latency,
+            rows,
+            duplicates,
+        })
+    });
+    monitor::query_finished(result.as_ref().ok().map(|outcome| outcome.latency));
+
+    result
+}
+
+/// Count a finished query towards the current phase if `record` includes it.
+fn record_query(
+    result: Result<QueryOutcome>,
+    record: Record,
+    issued: Instant,
+    dataset_idx: usize,
+    latencies: &std::sync::Mutex<Vec<QueryLatency>>,
+) {
+    match result {
+        Ok(outcome) if record.includes(issued) => {
+            ROW_COUNTER.fetch_add(outcome.rows, std::sync::atomic::Ordering::Relaxed);
+            match outcome.duplicates {
+                Some(DuplicateHandling::Kept) => {
+                    DUPLICATES_KEPT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+                Some(DuplicateHandling::Collapsed) => {
+                    DUPLICATES_COLLAPSED.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                }
+                None => {}
+            }
+            latencies
+                .lock()
+                .unwrap()
+                .push((dataset_idx, outcome.latency));
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Query failed: {:?}", e);
+            if record.includes(issued) {
+                ERROR_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+fn query_progress_bar(desc: &str, len: usize) -> ProgressBar {
+    let pb = ProgressBar::new(len as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template(&format!("  {} [{{bar:40}}] {{pos}}/{{len}}", desc))
+            .unwrap(),
+    );
+    pb
+}
+
+/// Progress display for duration-based runs, where the query count isn't known up front.
+fn duration_progress_bar(desc: &str) -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(
+        ProgressStyle::default_spinner()
+            .template(&format!(
+                "  {} {{spinner}} {{pos}} queries [{{elapsed}}]",
+                desc
+            ))
+            .unwrap(),
+    );
+    pb.enable_steady_tick(Duration::from_millis(200));
+    pb
+}
+
+fn run_queries(
+    datasets: Vec<Arc<dyn DatasetHandle>>,
+    source: QuerySource,
+    record: Record,
+    concurrent_queries: usize,
+    config: &Config,
+    runtime: Arc<Runtime>,
+    pb: &ProgressBar,
+) -> Result<Vec<QueryLatency>> {
+    let num_datasets = datasets.len();
+    let num_runtimes = config.num_runtimes;
+    let settings = Arc::new(QuerySettings::new(config));
+
+    // Create MPMC channel for query tasks
+    let (tx, rx): (Sender<QueryTask>, Receiver<QueryTask>) = match &source {
+        QuerySource::Fixed(queries) => bounded(queries.len()),
+        QuerySource::Until { .. } => bounded(0),
+    };
+
+    // Send all fixed queries to the channel
+    let (deadline, rows_per_query, seed) = match source {
+        QuerySource::Fixed(queries) => {
+            for (i, query) in queries.into_iter().enumerate() {
+                let dataset_idx = i % num_datasets;
+                tx.send((dataset_idx, query))?;
+            }
+            (None, 0, 0)
+        }
+        QuerySource::Until {
+            deadline,
+            rows_per_query,
+            seed,
+        } => (Some(deadline), rows_per_query, seed),
+    };
+    drop(tx); // Close the sender so threads know when to stop
+    let issued = Arc::new(AtomicUsize::new(0));
+
+    // Spawn worker threads
+    let mut handles = Vec::new();
+    let latencies = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+    for thread_idx in 0..num_runtimes {
+        let rx = rx.clone();
+        let datasets = datasets.clone();
+        let settings = settings.clone();
+        let pb = pb.clone();
+        let latencies = latencies.clone();
+        let issued = issued.clone();
+        let config = config.clone();
+
+        let runtime = runtime.clone();
+
+        // Pull fixed queries from the queue, or generate them until the deadline
+        let next_query = move || match deadline {
+            Some(deadline) => (Instant::now() < deadline).then(|| {
+                let n = issued.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                let query_seed = data::query_seed(seed, &[n as u64]);
+                let query = make_queries(&config, rows_per_query, 1, query_seed).remove(0);
+                (n % num_datasets, query)
+            }),
+            None => rx.recv().ok(),
+        };
+
+        let handle = std::thread::spawn(move || -> Result<()> {
+            pin_worker(thread_idx)?;
+            runtime.block_on(async move {
+                // Process queries from the queue with concurrency control
+                let query_stream = stream::iter(std::iter::from_fn(next_query))
+                    .map(|(dataset_idx, query)| {
+                        let dataset = datasets[dataset_idx].clone();
+                        let pb = pb.clone();
+                        let latencies = latencies.clone();
+                        let settings = settings.clone();
+
+                        tokio::task::spawn(async move {
+                            let issued = Instant::now();
+                            let result = execute_query(dataset, query, settings, issued).await;
+                            pb.inc(1);
+                            record_query(result, record, issued, dataset_idx, &latencies);
+                        })
+                    })
+                    .buffer_unordered(concurrent_queries);
+
+                // Collect all results
+                query_stream
+                    .for_each(|result| async {
+                        if let Err(e) = result {
+                            eprintln!("Query failed in thread {}: {:?}", thread_idx, e);
+                            ERROR_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                        }
+                    })
+                    .await;
+            });
+            Ok(())
+        });
+
+        handles.push(handle);
+    }
+
+    // Wait for all threads to complete
+    for handle in handles {
+        handle
+            .join()
+            .map_err(|_| anyhow::anyhow!("Thread panicked"))??;
+    }
+
+    let latencies = Arc::try_unwrap(latencies).unwrap().into_inner().unwrap();
+
+    Ok(latencies)
+}
+
+/// Issue queries on a fixed schedule of `target_qps`, regardless of completions.
+///
+/// Latency is measured from each query's scheduled start, so it includes any
+/// queueing delay when the engine can't keep up with the offered load.
+fn run_queries_open_loop(
+    datasets: Vec<Arc<dyn DatasetHandle>>,
+    queries: Vec<Vec<u64>>,
+    config: &Config,
+    runtime: Arc<Runtime>,
+    pb: &ProgressBar,
+    target_qps: f64,
+    record: Record,
+) -> Result<Vec<QueryLatency>> {
+    let num_datasets = datasets.len();
+    let settings = Arc::new(QuerySettings::new(config));
+    let latencies = Arc::new(std::sync::Mutex::new(Vec::with_capacity(queries.len())));
+
+    // Worker threads only drive the runtime; queries are spawned onto it below
+    let (done_tx, done_rx) = tokio::sync::watch::channel(false);
+    let mut workers = Vec::new();
+    for worker in 0..config.num_runtimes {
+        let runtime = runtime.clone();
+        let mut done_rx = done_rx.clone();
+        workers.push(std::thread::spawn(move || -> Result<()> {
+            pin_worker(worker)?;
+            runtime.block_on(async move {
+                let _ = done_rx.wait_for(|done| *done).await;
+            });
+            Ok(())
+        }));
+    }
+
+    let interval = Duration::from_secs_f64(1.0 / target_qps);
+    let start = Instant::now();
+    let mut tasks = Vec::with_capacity(queries.len());
+    for (i, query) in queries.into_iter().enumerate() {
+        let scheduled = start + interval.mul_f64(i as f64);
+        if let Some(wait) = scheduled.checked_duration_since(Instant::now()) {
+            std::thread::sleep(wait);
+        }
+
+        let dataset_idx = i % num_datasets;
+        let dataset = datasets[dataset_idx].clone();
+        let settings = settings.clone();
+        let pb = pb.clone();
+        let latencies = latencies.clone();
+        tasks.push(runtime.spawn(async move {
+            let result = execute_query(dataset, query, settings, scheduled).await;
+            pb.inc(1);
+            record_query(result, record, scheduled, dataset_idx, &latencies);
+        }));
+    }
+
+    for result in runtime.block_on(futures::future::join_all(tasks)) {
+        if let Err(e) = result {
+            eprintln!("Query task failed: {:?}", e);
+            ERROR_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    let _ = done_tx.send(true);
+    for worker in workers {
+        worker
+            .join()
+            .map_err(|_| anyhow::anyhow!("Thread panicked"))??;
+    }
+
+    let latencies = Arc::try_unwrap(latencies).unwrap().into_inner().unwrap();
+
+    Ok(latencies)
+}
+
+/// Strip dataset indices from completed query latencies.
+fn all_latencies(latencies: &[QueryLatency]) -> Vec<f64> {
+    latencies.iter().map(|(_, latency)| *latency).collect()
+}
+
+/// Compute latency statistics separately for each dataset.
+///
+/// Datasets that received no queries are omitted.
+fn per_dataset_statistics(
+    latencies: &[QueryLatency],
+    num_datasets: usize,
+) -> Vec<(usize, Statistics)> {
+    (0..num_datasets)
+        .filter_map(|dataset_idx| {
+            let dataset_latencies: Vec<f64> = latencies
+                .iter()
+                .filter(|(idx, _)| *idx == dataset_idx)
+                .map(|(_, latency)| *latency)
+                .collect();
+            (!dataset_latencies.is_empty())
+                .then(|| (dataset_idx, compute_statistics(&dataset_latencies)))
+        })
+        .collect()
+}
+
+/// Dataset URIs for `engine`, with the engine as child folder
+/// (e.g., /tmp/dataset -> /tmp/dataset/lance).
+fn dataset_uris(engine: &dyn Engine, config: &Config) -> Vec<String> {
+    config
+        .dataset_uri
+        .iter()
+        .map(|uri| {
+            let uri = uri.trim_end_matches('/');
+            format!("{}/{}", uri, engine.name())
+        })
+        .collect()
+}
+
+/// Why the dataset at `uri` must be (re)generated, or `None` if it can be reused.
+fn stale_dataset_reason(
+    engine: &dyn Engine,
+    manifest: &DatasetManifest,
+    uri: &str,
+    config: &Config,
+) -> Result<Option<String>> {
+    Ok(
+        match engine.runtime().block_on(DatasetManifest::read(uri))? {
+            None => Some("not found or has no manifest".to_string()),
+            Some(existing) => match manifest.mismatch(&existing) {
+                Some(reason) => Some(reason),
+                None if !engine.exists(uri, config.live_rows()) => {
+                    Some("has the wrong row count".to_string())
+                }
+                None => None,
+            },
+        },
+    )
+}
+
+/// Open `uri`, timing the open and a single-row query on the new handle.
+///
+/// Unless the cache mode is hot, the dataset's cache is dropped first so the
+/// open reads from storage.
+fn open_timed(
+    engine: &dyn Engine,
+    uri: &str,
+    config: &Config,
+) -> Result<(Arc<dyn DatasetHandle>, OpenTimings)> {
+    if config.cache_mode.drops_cache() {
+        engine.drop_cache(uri)?;
+    }
+
+    let start = Instant::now();
+    let dataset = engine.open(uri)?;
+    let open = start.elapsed();
+    let metadata_load = dataset.metadata_load_time();
+    let dataset = with_fallbacks(engine, dataset, config);
+
+    let settings = QuerySettings::new(config);
+    let row = vec![config.live_rows() as u64 / 2];
+    let start = Instant::now();
+    engine.runtime().block_on(async {
+        match settings.query_by {
+            QueryBy::Offset => dataset.take(&row, &settings.options).await,
+            QueryBy::Key => dataset.lookup(&row, &settings.options).await,
+        }
+    })?;
+    let first_query = start.elapsed();
+
+    let timings = OpenTimings {
+        open: open.as_secs_f64(),
+        metadata_load: metadata_load.map(|d| d.as_secs_f64()),
+        first_query: first_query.as_secs_f64(),
+        scan: None,
+        counts: Vec::new(),
+    };
+    println!(
+        "  Opened in {:.2} ms, first query took {:.2} ms",
+        timings.open * 1000.0,
+        timings.first_query * 1000.0
+    );
+    Ok((dataset, timings))
+}
+
+/// Time a full scan of `dataset`'s take columns, after dropping its cache
+/// unless the cache mode is hot.
+fn scan_timed(
+    engine: &dyn Engine,
+    dataset: &Arc<dyn DatasetHandle>,
+    uri: &str,
+    config: &Config,
+) -> Result<f64> {
+    if config.cache_mode.drops_cache() {
+        engine.drop_cache(uri)?;
+    }
+    let start = Instant::now();
+    let rows = engine
+        .runtime()
+        .block_on(dataset.scan(&config.take_columns))?;
+    let scan = start.elapsed().as_secs_f64();
+    if rows != config.live_rows() {
+        anyhow::bail!(
+            "Scan returned {} rows, expected {}",
+            rows,
+            config.live_rows()
+        );
+    }
+    println!("  Scanned {} rows in {:.2} ms", rows, scan * 1000.0);
+    Ok(scan)
+}
+
+/// Time counting the rows of `dataset` at every `--count-selectivity`.
+///
+/// Unless the cache mode is hot, the dataset's cache is dropped before each
+/// selectivity's counts, and with a cold cache mode before every count.
+fn count_timed(
+    engine: &dyn Engine,
+    dataset: &Arc<dyn DatasetHandle>,
+    uri: &str,
+    config: &Config,
+) -> Result<Vec<CountTiming>> {
+    let rows = config.live_rows();
+    let mut counts = Vec::with_capacity(config.count_selectivities.len());
+    for &selectivity in &config.count_selectivities {
+        // Ids run from 0, so every id below the bound is a row
+        let key_below = (selectivity < 1.0).then(|| (rows as f64 * selectivity).round() as u64);
+        let expected = key_below.map_or(rows, |bound| bound as usize);
+        let skipping = dataset.skipping();
+        let mut latencies = Vec::with_capacity(config.count_iterations);
+        for iteration in 0..config.count_iterations {
+            let evict = match iteration {
+                0 => config.cache_mode.drops_cache(),
+                _ => config.cache_mode.runs_cold(),
+            };
+            if evict {
+                engine.drop_cache(uri)?;
+            }
+            let start = Instant::now();
+            let counted = engine.runtime().block_on(dataset.count_rows(key_below))?;
+            latencies.push(start.elapsed().as_secs_f64());
+            if counted != expected {
+                anyhow::bail!(
+                    "Counting {:.1}% of the rows returned {}, expected {}",
+                    selectivity * 100.0,
+                    counted,
+                    expected
+                );
+            }
+        }
+        let stats = compute_statistics(&latencies);
+        let skipping = skipping_since(dataset.skipping(), skipping);
+        println!(
+            "  Counted {} rows ({:.1}%) in {:.2} ms (p50){}",
+            expected,
+            selectivity * 100.0,
+            stats.p50 * 1000.0,
+            skipping.as_ref().map_or(String::new(), |s| format!(
+                ", {:.1}% of {} pruned",
+                s.pruned_fraction() * 100.0,
+                s.unit
+            ))
+        );
+        counts.push(CountTiming {
+            selectivity,
+            rows: expected,
+            p50: stats.p50,
+            p99: stats.p99,
+            skipping,
+        });
+    }
+    Ok(counts)
+}
+
+/// Data skipping counted between the totals `before` and `after`, if the
+/// queries in between counted any.
+fn skipping_since(after: Option<Skipping>, before: Option<Skipping>) -> Option<Skipping> {
+    let skipping = after?.since(&before?);
+    (skipping.considered > 0).then_some(skipping)
+}
+
+/// Live report of a timed phase: rolling throughput lines under its progress
+/// bar, or the `--tui` dashboard in place of both.
+enum PhaseMonitor {
+    Lines(ThroughputMonitor),
+    #[cfg(feature = "tui")]
+    Dashboard(dashboard::Dashboard),
+}
+
+impl PhaseMonitor {
+    /// Start reporting on the phase whose queries `pb` counts.
+    #[cfg_attr(not(feature = "tui"), allow(unused_variables))]
+    fn start(
+        pb: &ProgressBar,
+        engine: &dyn Engine,
+        cache: &str,
+        workload: Workload,
+        config: &Config,
+    ) -> Result<Self> {
+        #[cfg(feature = "tui")]
+        if config.tui {
+            // The bar still counts queries for the dashboard, without drawing
+            pb.set_draw_target(indicatif::ProgressDrawTarget::hidden());
+            let info = dashboard::PhaseInfo {
+                engines: config.engines.clone(),
+                engine: engine.name().to_string(),
+                title: format!(
+                    "{} rows/query, {} in flight, {} cache",
+                    workload.rows_per_query,
+                    config.num_runtimes * workload.concurrent_queries,
+                    cache
+                ),
+                duration: config.duration,
+            };
+            return Ok(Self::Dashboard(dashboard::Dashboard::start(info, pb)?));
+        }
+        Ok(Self::Lines(ThroughputMonitor::start(
+            config.report_interval,
+            pb,
+        )))
+    }
+
+    fn stop(self) {
+        match self {
+            Self::Lines(monitor) => monitor.stop(),
+            #[cfg(feature = "tui")]
+            Self::Dashboard(dashboard) => dashboard.stop(),
+        }
+    }
+}
+
+/// Start sampling thread states if `--sample-thread-states` was given.
+fn thread_sampler(config: &Config) -> Option<ThreadSampler> {
+    config
+        .sample_thread_states
+        .then(|| ThreadSampler::start(THREAD_SAMPLE_INTERVAL))
+}
+
+/// An engine's open datasets and the timed phases it has completed so far.
+struct EngineRun {
+    engine: Arc<dyn Engine>,
+    dataset_uris: Vec<String>,
+    dataset_bytes: u64,
+    datasets: Vec<Arc<dyn DatasetHandle>>,
+    open_timings: Vec<OpenTimings>,
+    compaction: Option<Compaction>,
+    phases: Vec<PhaseResult>,
+}
+
+impl EngineRun {
+    /// Print the engine header and load or create its datasets (step 1).
+    fn prepare(engine: Arc<dyn Engine>, config: &Config) -> Result<Self> {
+        let dataset_uris = dataset_uris(engine.as_ref(), config);
+        let _span = tracing::info_span!("prepare", engine = engine.name()).entered();
+
+        println!("\n{}", "=".repeat(60));
+        println!(
+            "Engine: {} ({} runtime)",
+            engine.name(),
+            engine.runtime_spec()
+        );
+        for (name, version) in engine.library_versions() {
+            println!("  {} {}", name, version);
+        }
+        println!("{}", "=".repeat(60));
+
+        // Step 1: Create datasets
+        println!("\n{}", "=".repeat(60));
+        println!("Step 1: Loading/Creating Datasets");
+        println!("{}", "=".repeat(60));
+
+        let manifest = DatasetManifest::new(engine.as_ref(), config);
+        println!("\nDataset fingerprint: {}", manifest.fingerprint);
+        let mut datasets: Vec<Arc<dyn DatasetHandle>> = Vec::new();
+        let mut open_timings = Vec::new();
+        // Compaction of each compacted dataset, if it's known
+        let mut compactions = Vec::new();
+        for (i, uri) in dataset_uris.iter().enumerate() {
+            println!("\nDataset {}/{}: {}", i + 1, dataset_uris.len(), uri);
+
+            println!("Checking for existence of dataset...");
+            let runtime = engine.runtime();
+            match stale_dataset_reason(engine.as_ref(), &manifest, uri, config)? {
+                None => {
+                    println!(
+                        "  Dataset exists with {} rows and a matching manifest - loading",
+                        config.live_rows()
+                    );
+                    if config.compacted {
+                        // Compacted when it was generated; the cost was recorded then
+                        let existing = runtime.block_on(DatasetManifest::read(uri))?;
+                        compactions.push(existing.and_then(|m| m.compaction));
+                    }
+                }
+                Some(reason) => {
+                    println!("  Dataset {} - creating", reason);
+                    runtime.block_on(storage::remove_dataset(uri))?;
+                    // Reopened below so that every dataset's open is timed
+                    engine.write(uri, config)?;
+                    if let Some(deletions) = config.deletions() {
+                        println!("  Deleting rows where {}", deletions.predicate());
+                        engine.delete(uri, &deletions.predicate())?;
+                    }
+                    let mut written = DatasetManifest::new(engine.as_ref(), config);
+                    if config.compacted {
+                        let compacted = engine.compact(uri)?;
+                        println!(
+                            "  Compacted {} fragments into {} in {:.2}s",
+                            compacted.fragments_removed, compacted.fragments_added, compacted.time
+                        );
+                        written.compaction = Some(compacted);
+                        compactions.push(Some(compacted));
+                    }
+                    runtime.block_on(written.write(uri))?;
+                }
+            }
+
+            let (dataset, mut timings) = open_timed(engine.as_ref(), uri, config)?;
+            if config.time_scan {
+                timings.scan = Some(scan_timed(engine.as_ref(), &dataset, uri, config)?);
+            }
+            if !config.count_selectivities.is_empty() {
+                timings.counts = count_timed(engine.as_ref(), &dataset, uri, config)?;
+            }
+            datasets.push(dataset);
+            open_timings.push(timings);
+        }
+
+        let runtime = engine.runtime();
+        let mut dataset_bytes = 0;
+        for uri in &dataset_uris {
+            dataset_bytes += runtime.block_on(storage::dataset_size(uri))?;
+        }
+        println!(
+            "\nDatasets take {:.2} GB on storage",
+            dataset_bytes as f64 / 1024.0 / 1024.0 / 1024.0
+        );
+
+        Ok(Self {
+            engine,
+            dataset_uris,
+            dataset_bytes,
+            datasets,
+            open_timings,
+            // Only known in total if every dataset's compaction is
+            compaction: compactions
+                .into_iter()
+                .collect::<Option<Vec<_>>>()
+                .filter(|all| !all.is_empty())
+                .map(|all| all.into_iter().fold(Compaction::default(), Compaction::add)),
+            phases: Vec::new(),
+        })
+    }
+
+    /// Run `queries` untimed to warm caches (step 3).
+    fn warmup(&self, queries: &[Vec<u64>], config: &Config) -> Result<()> {
+        let _span = tracing::info_span!("warmup", engine = self.engine.name()).entered();
+        println!("\n{}", "=".repeat(60));
+        println!("Step 3: Warmup Phase ({})", self.engine.name());
+        println!("{}", "=".repeat(60));
+        println!("\nExecuting {} queries...", queries.len());
+        let max_concurrency = config.concurrent_queries.iter().copied().max().unwrap_or(1);
+        let pb = query_progress_bar("Warmup queries", queries.len());
+        run_queries(
+            self.datasets.clone(),
+            QuerySource::Fixed(queries.to_vec()),
+            Record::Nothing,
+            max_concurrency,
+            config,
+            self.engine.runtime(),
+            &pb,
+        )?;
+        pb.finish();
+        Ok(())
+    }
+
+    /// Run the timed phase(s) of one workload (step 4).
+    fn run_timed(
+        &mut self,
+        workload: Workload,
+        queries: &[Vec<u64>],
+        config: &Config,
+    ) -> Result<()> {
+        let run_length = match config.duration {
+            Some(duration) => format!("queries for {:?}", duration),
+            None => format!("{} queries", config.num_queries),
+        };
+        let shuffled;
+        let queries = match config.shuffle_seed {
+            Some(seed) => {
+                shuffled = shuffle_queries(queries, seed, workload);
+                &shuffled
+            }
+            None => queries,
+        };
+
+        let _span = tracing::info_span!(
+            "timed_phase",
+            engine = self.engine.name(),
+            rows_per_query = workload.rows_per_query,
+            concurrency = workload.concurrent_queries
+        )
+        .entered();
+        let _profiler = match config.profile {
+            Some(mode) => {
+                let default = PathBuf::from(mode.default_output());
+                let label = format!(
+                    "{}-{}rows-{}concurrent",
+                    self.engine.name(),
+                    workload.rows_per_query,
+                    workload.concurrent_queries
+                );
+                Some(profile::start(
+                    mode,
+                    config.profile_out.as_ref().unwrap_or(&default),
+                    &label,
+                )?)
+            }
+            None => None,
+        };
+
+        println!("\n{}", "=".repeat(60));
+        println!(
+            "Step 4: Timed Phase ({}, {:?} cache, {} concurrent queries per runtime)",
+            self.engine.name(),
+            config.cache_mode,
+            workload.concurrent_queries
+        );
+        println!("{}", "=".repeat(60));
+
+        if config.cache_mode == CacheMode::DropOnce {
+            println!("\nDropping dataset files from kernel page cache...");
+            cache::eviction_point(|| {
+                for uri in &self.dataset_uris {
+                    self.engine.drop_cache(uri)?;
+                }
+                Ok(())
+            })?;
+        }
+        if config.worker_processes {
+            println!(
+                "\nExecuting {} with a {} cache in {} worker processes...",
+                run_length,
+                config.cache_mode.warm_label(),
+                config.num_runtimes
+            );
+            let snapshot = self.block_snapshot();
+            let mut phase =
+                workers::run_hot_phase(&self.engine, self.datasets.len(), workload, config)?;
+            phase.block_io = snapshot.delta();
+            self.phases.push(phase);
+        } else if config.cache_mode != CacheMode::Cold {
+            println!(
+                "\nExecuting {} with a {} cache...",
+                run_length,
+                config.cache_mode.warm_label()
+            );
+            let snapshot = self.block_snapshot();
+            let skipping = self.skipping_totals();
+            let sampler = thread_sampler(config);
+            lance_trace::start();
+            let mut phase = run_hot_phase(&self.engine, &self.datasets, queries, workload, config)?;
+            phase.lance_trace = lance_trace::finish();
+            phase.thread_states = sampler.and_then(ThreadSampler::stop);
+            phase.block_io = snapshot.delta();
+            phase.skipping = skipping_since(self.skipping_totals(), skipping);
+            self.phases.push(phase);
+        }
+        if config.cache_mode.runs_cold() {
+            println!("\nExecuting {} with a cold cache...", run_length);
+            let snapshot = self.block_snapshot();
+            let skipping = self.skipping_totals();
+            let sampler = thread_sampler(config);
+            lance_trace::start();
+            let mut phase = run_cold_phase(
+                &self.engine,
+                &self.datasets,
+                &self.dataset_uris,
+                queries,
+                workload,
+                config,
+            )?;
+            phase.lance_trace = lance_trace::finish();
+            phase.thread_states = sampler.and_then(ThreadSampler::stop);
+            phase.block_io = snapshot.delta();
+            phase.skipping = skipping_since(self.skipping_totals(), skipping);
+            self.phases.push(phase);
+        }
+        Ok(())
+    }
+
+    /// Data skipping of every query to the engine's datasets so far, if the
+    /// engine counts it.
+    fn skipping_totals(&self) -> Option<Skipping> {
+        self.datasets
+            .iter()
+            .filter_map(|dataset| dataset.skipping())
+            .reduce(Skipping::add)
+    }
+
+    /// Snapshot the block devices holding the engine's local datasets.
+    fn block_snapshot(&self) -> BlockSnapshot {
+        let paths: Vec<&str> = self
+            .dataset_uris
+            .iter()
+            .filter(|uri| cache::is_local_uri(uri))
+            .map(|uri| storage::local_path(uri))
+            .collect();
+        BlockSnapshot::take(&paths)
+    }
+
+    /// Print and return the engine's results.
+    fn finish(self, config: &Config) -> EngineResult {
+        let result = EngineResult {
+            engine: self.engine.name().to_string(),
+            versions: self
+                .engine
+                .library_versions()
+                .into_iter()
+                .map(|(name, version)| (name.to_string(), version.to_string()))
+                .collect(),
+            runtime: Some(self.engine.runtime_spec()),
+            dataset_uris: self.dataset_uris,
+            write_options: self.engine.write_options(),
+            dataset_bytes: self.dataset_bytes,
+            open_timings: self.open_timings,
+            phases: self.phases,
+            harness_overhead: config.harness_overhead.clone(),
+            compaction: self.compaction,
+            error: None,
+        };
+
+        println!("\n{}", "=".repeat(60));
+        println!("RESULTS: {}", result.engine);
+        println!("{}", "=".repeat(60));
+        print_engine_result(&result);
+        if config.concurrent_queries.len() > 1 {
+            print_concurrency_curve(&result);
+        }
+        result
+    }
+}
+
+/// The timed phase's queries in a seeded order that differs from the warmup's.
+///
+/// Every engine gets the same order for the same workload.
+fn shuffle_queries(queries: &[Vec<u64>], seed: u64, workload: Workload) -> Vec<Vec<u64>> {
+    let mut queries = queries.to_vec();
+    let phase = ((workload.rows_per_query as u64) << 32) ^ workload.concurrent_queries as u64;
+    queries.shuffle(&mut StdRng::seed_from_u64(seed ^ phase));
+    queries
+}
+
+/// Generate the queries of one query size (step 2).
+fn generate_queries(config: &Config, rows_per_query: usize) -> Vec<Vec<u64>> {
+    if config.rows_per_query.len() > 1 {
+        println!("\n{}", "=".repeat(60));
+        println!("Query size: {} rows", rows_per_query);
+        println!("{}", "=".repeat(60));
+    }
+
+    println!("\n{}", "=".repeat(60));
+    println!("Step 2: Generating Queries");
+    println!("{}", "=".repeat(60));
+    println!("\nGenerating {} query indices...", config.num_queries);
+    let start = Instant::now();
+    // Every engine gets the same queries for the same query size
+    let seed = data::query_seed(config.common.seed, &[rows_per_query as u64]);
+    let queries = make_queries(config, rows_per_query, config.num_queries, seed);
+    println!("  Done in {:.2}s", start.elapsed().as_secs_f64());
+    queries
+}
+
+/// Result recorded for an engine that failed, after warning about it.
+fn failed_result(engine: &dyn Engine, config: &Config, error: anyhow::Error) -> EngineResult {
+    println!(
+        "\nWarning: {} failed, continuing with the remaining engines: {:#}",
+        engine.name(),
+        error
+    );
+    EngineResult {
+        engine: engine.name().to_string(),
+        versions: Default::default(),
+        runtime: Some(engine.runtime_spec()),
+        dataset_uris: dataset_uris(engine, config),
+        write_options: engine.write_options(),
+        dataset_bytes: 0,
+        open_timings: Vec::new(),
+        phases: Vec::new(),
+        harness_overhead: None,
+        compaction: None,
+        error: Some(format!("{:#}", error)),
+    }
+}
+
+/// Run the full benchmark (load/create, warmup, timed phases) for one engine.
+fn run_engine(engine: Arc<dyn Engine>, config: &Config) -> Result<EngineResult> {
+    let mut run = EngineRun::prepare(engine, config)?;
+    for &rows_per_query in &config.rows_per_query {
+        let queries = generate_queries(config, rows_per_query);
+        if !config.skip_warmup {
+            run.warmup(&queries, config)?;
+        }
+        for &concurrent_queries in &config.concurrent_queries {
+            let workload = Workload {
+                rows_per_query,
+                concurrent_queries,
+            };
+            run.run_timed(workload, &queries, config)?;
+        }
+    }
+    Ok(run.finish(config))
+}
+
+/// Run every engine's timed phases in turn, one workload at a time.
+///
+/// Slow drift (thermal throttling, background jobs) then affects every engine
+/// alike instead of biasing the engines that run last. Each engine is warmed
+/// up right before each of its phases, since the other engines' phases in
+/// between evict its data from the page cache. An engine that fails drops out
+/// of the remaining rounds.
+fn run_interleaved(engines: Vec<Arc<dyn Engine>>, config: &Config) -> Vec<EngineResult> {
+    let mut runs: Vec<Result<EngineRun, EngineResult>> = engines
+        .into_iter()
+        .map(|engine| {
+            EngineRun::prepare(engine.clone(), config)
+                .map_err(|e| failed_result(engine.as_ref(), config, e))
+        })
+        .collect();
+
+    let mut order: Vec<usize> = (0..runs.len()).collect();
+    let mut rng = config.shuffle_seed.map(StdRng::seed_from_u64);
+    for &rows_per_query in &config.rows_per_query {
+        let queries = generate_queries(config, rows_per_query);
+        for &concurrent_queries in &config.concurrent_queries {
+            let workload = Workload {
+                rows_per_query,
+                concurrent_queries,
+            };
+            if let Some(rng) = &mut rng {
+                order.shuffle(rng);
+            }
+            for &i in &order {
+                let slot = &mut runs[i];
+                let Ok(run) = slot else { continue };
+                let warmed = if config.skip_warmup {
+                    Ok(())
+                } else {
+                    run.warmup(&queries, config)
+                };
+                if let Err(e) = warmed.and_then(|_| run.run_timed(workload, &queries, config)) {
+                    *slot = Err(failed_result(run.engine.as_ref(), config, e));
+                }
+            }
+        }
+    }
+
+    runs.into_iter()
+        .map(|run| match run {
+            Ok(run) => run.finish(config),
+            Err(failed) => failed,
+        })
+        .collect()
+}
+
+/// Run the timed queries without touching the page cache.
+fn run_hot_phase(
+    engine: &Arc<dyn Engine>,
+    datasets: &[Arc<dyn DatasetHandle>],
+    queries: &[Vec<u64>],
+    workload: Workload,
+    config: &Config,
+) -> Result<PhaseResult> {
+    ROW_COUNTER.store(0, std::sync::atomic::Ordering::Relaxed);
+    ERROR_COUNTER.store(0, std::sync::atomic::Ordering::Relaxed);
+    DUPLICATES_KEPT.store(0, std::sync::atomic::Ordering::Relaxed);
+    DUPLICATES_COLLAPSED.store(0, std::sync::atomic::Ordering::Relaxed);
+
+    let cache = config.cache_mode.warm_label();
+    let desc = format!("Timed queries ({})", cache);
+    let pb = match config.duration {
+        Some(_) => duration_progress_bar(&desc),
+        None => query_progress_bar(&desc, queries.len()),
+    };
+    let monitor = PhaseMonitor::start(&pb, engine.as_ref(), cache, workload, config)?;
+    let start = Instant::now();
+    let ramp_up = config.ramp_up.unwrap_or(Duration::ZERO);
+    let record = match config.ramp_up {
+        Some(ramp_up) => Record::IssuedFrom(start + ramp_up),
+        None => Record::All,
+    };
+    let seed = data::query_seed(
+        config.common.seed,
+        &[
+            workload.rows_per_query as u64,
+            workload.concurrent_queries as u64,
+        ],
+    );
+    let latencies = match config.target_qps {
+        Some(target_qps) => {
+            // Open-loop runs know their query count up front: duration * rate
+            let queries = match config.duration {
+                Some(duration) => make_queries(
+                    config,
+                    workload.rows_per_query,
+                    ((ramp_up + duration).as_secs_f64() * target_qps).ceil() as usize,
+                    seed,
+                ),
+                None => queries.to_vec(),
+            };
+            run_queries_open_loop(
+                datasets.to_vec(),
+                queries,
+                config,
+                engine.runtime(),
+                &pb,
+                target_qps,
+                record,
+            )?
+        }
+        None => run_queries(
+            datasets.to_vec(),
+            match config.duration {
+                Some(duration) => QuerySource::Until {
+                    deadline: start + ramp_up + duration,
+                    rows_per_query: workload.rows_per_query,
+                    seed,
+                },
+                None => QuerySource::Fixed(queries.to_vec()),
+            },
+            record,
+            workload.concurrent_queries,
+            config,
+            engine.runtime(),
+            &pb,
+        )?,
+    };
+    // Throughput only covers the measured part of the phase
+    let elapsed = start.elapsed().saturating_sub(ramp_up);
+    monitor.stop();
+    pb.finish();
+
+    if latencies.is_empty() && config.ramp_up.is_some() {
+        println!("Warning: the phase finished during --ramp-up, so no queries were recorded");
+    }
+
+    let all = all_latencies(&latencies);
+    Ok(PhaseResult {
+        cache: cache.to_string(),
+        rows_per_query: workload.rows_per_query,
+        concurrency: config.num_runtimes * workload.concurrent_queries,
+        stats: compute_statistics(&all),
+        quantiles: compute_quantiles(&all),
+        per_dataset: per_dataset_statistics(&latencies, datasets.len()),
+        throughput: latencies.len() as f64 / elapsed.as_secs_f64(),
+        rows_taken: ROW_COUNTER.load(std::sync::atomic::Ordering::Relaxed),
+        queries: latencies.len(),
+        errors: ERROR_COUNTER.load(std::sync::atomic::Ordering::Relaxed),
+        duplicates_kept: DUPLICATES_KEPT.load(std::sync::atomic::Ordering::Relaxed),
+        duplicates_collapsed: DUPLICATES_COLLAPSED.load(std::sync::atomic::Ordering::Relaxed),
+        block_io: None,
+        lance_trace: Vec::new(),
+        thread_states: None,
+        skipping: None,
+    })
+}
+
+/// Run the timed queries against a cold page cache.
+///
+/// Queries are issued in waves of `num_runtimes * concurrent_queries` and the
+/// dataset files are dropped from the page cache before each wave. Time spent
+/// dropping the cache is excluded from both latency and throughput.
+fn run_cold_phase(
+    engine: &Arc<dyn Engine>,
+    datasets: &[Arc<dyn DatasetHandle>],
+    dataset_uris: &[String],
+    queries: &[Vec<u64>],
+    workload: Workload,
+    config: &Config,
+) -> Result<PhaseResult> {
+    ROW_COUNTER.store(0, std::sync::atomic::Ordering::Relaxed);
+    ERROR_COUNTER.store(0, std::sync::atomic::Ordering::Relaxed);
+    DUPLICATES_KEPT.store(0, std::sync::atomic::Ordering::Relaxed);
+    DUPLICATES_COLLAPSED.store(0, std::sync::atomic::Ordering::Relaxed);
+
+    let wave_size = (config.num_runtimes * workload.concurrent_queries).max(1);
+    let pb = match config.duration {
+        Some(_) => duration_progress_bar("Timed queries (cold)"),
+        None => query_progress_bar("Timed queries (cold)", queries.len()),
+    };
+    // Reported rates include the time spent dropping the cache between waves
+    let monitor = PhaseMonitor::start(&pb, engine.as_ref(), "cold", workload, config)?;
+    let mut latencies = Vec::with_capacity(queries.len());
+    let cgroup = cache::enter_timed_cgroup()?;
+    let mut elapsed = Duration::ZERO;
+    let mut fixed_waves = queries.chunks(wave_size);
+
+    for wave_index in 0u64.. {
+        // With --duration, only time spent running queries counts against the budget
+        let wave = match config.duration {
+            Some(duration) if elapsed >= duration => break,
+            Some(_) => {
+                let seed = data::query_seed(
+                    config.common.seed,
+                    &[
+                        workload.rows_per_query as u64,
+                        workload.concurrent_queries as u64,
+                        wave_index,
+                    ],
+                );
+                make_queries(config, workload.rows_per_query, wave_size, seed)
+            }
+            None => match fixed_waves.next() {
+                Some(wave) => wave.to_vec(),
+                None => break,
+            },
+        };
+
+        pb.suspend(|| {
+            let _span = tracing::info_span!("drop_cache").entered();
+            cache::eviction_point(|| {
+                for uri in dataset_uris {
+                    engine.drop_cache(uri)?;
+                }
+                Ok(())
+            })
+        })?;
+
+        let start = Instant::now();
+        latencies.extend(run_queries(
+            datasets.to_vec(),
+            QuerySource::Fixed(wave),
+            Record::All,
+            workload.concurrent_queries,
+            config,
+            engine.runtime(),
+            &pb,
+        )?);
+        elapsed += start.elapsed();
+    }
+    drop(cgroup);
+    monitor.stop();
+    pb.finish();
+
+    let all = all_latencies(&latencies);
+    Ok(PhaseResult {
+        cache: "cold".to_string(),
+        rows_per_query: workload.rows_per_query,
+        concurrency: config.num_runtimes * workload.concurrent_queries,
+        stats: compute_statistics(&all),
+        quantiles: compute_quantiles(&all),
+        per_dataset: per_dataset_statistics(&latencies, datasets.len()),
+        throughput: latencies.len() as f64 / elapsed.as_secs_f64(),
+        rows_taken: ROW_COUNTER.load(std::sync::atomic::Ordering::Relaxed),
+        queries: latencies.len(),
+        errors: ERROR_COUNTER.load(std::sync::atomic::Ordering::Relaxed),
+        duplicates_kept: DUPLICATES_KEPT.load(std::sync::atomic::Ordering::Relaxed),
+        duplicates_collapsed: DUPLICATES_COLLAPSED.load(std::sync::atomic::Ordering::Relaxed),
+        block_io: None,
+        lance_trace: Vec::new(),
+        thread_states: None,
+        skipping: None,
+    })
+}
+
+/// Run the take benchmark as configured, returning the results of every engine.
+///
+/// An engine that fails is recorded with its error and the remaining engines
+/// still run; the run then fails once every engine has been tried.
+pub fn run(mut config: Config) -> Result<Vec<EngineResult>> {
+    reset_process_settings();
+
+    if config.skip_cache_drop {
+        if config.worker_process.is_none() {
+            println!("Warning: --skip-cache-drop is deprecated, use --cache-mode hot");
+        }
+        config.cache_mode = CacheMode::Hot;
+    }
+
+    if let Some(spec) = &config.worker_process {
+        // Workers report to their parent process instead
+        workers::run_worker(spec, &config)?;
+        return Ok(Vec::new());
+    }
+
+    validate::check_config(&mut config)?;
+
+    // Resolve all engines up front so a typo fails before any work is done
+    let registry = create_registry(config.runtime, &config.engine_runtimes);
+    let engines = config
+        .engines
+        .iter()
+        .map(|name| registry.resolve(name))
+        .collect::<Result<Vec<_>>>()?;
+    let remote = config
+        .dataset_uri
+        .iter()
+        .any(|uri| !cache::is_local_uri(uri));
+    let injection = build_injection(&config);
+    let engines = validate::supported_engines(engines, &config, remote, injection.is_some())?;
+    validate::check_environment(&mut config, remote, injection.is_some())?;
+
+    if config.shuffle {
+        config.shuffle_seed = Some(config.shuffle_seed.unwrap_or_else(rand::random));
+    }
+
+    let pinning = build_pinning(&config)?;
+    *PINNING.lock().unwrap() = pinning.clone();
+    // Written out when the guard drops, however the run ends
+    let _trace = start_tracing(&config)?;
+    inject::set(injection);
+    set_direct_io(config.direct_io);
+    set_in_memory(config.in_memory);
+    set_lance_stable_row_ids(config.lance_stable_row_ids);
+    if config.cache_mode.drops_cache() {
+        set_cache_drop_mode(
+            config.cache_drop_mode,
+            config.cgroup_memory_mb * 1024 * 1024,
+        )?;
+    }
+
+    report::print_configuration(&engines, &config, injection);
+
+    if config.common.dry_run {
+        plan::print_plan(&engines, &config)?;
+        return Ok(Vec::new());
+    }
+
+    let overhead = calibrate::measure();
+    println!(
+        "\nHarness overhead: {:.2} µs per query (timer {:.0} ns, channel {:.0} ns, Arc clone {:.0} ns, task spawn {:.0} ns, progress {:.0} ns)",
+        overhead.per_query() * 1e6,
+        overhead.timer,
+        overhead.channel,
+        overhead.arc_clone,
+        overhead.spawn,
+        overhead.progress
+    );
+    config.harness_overhead = Some(overhead);
+
+    let mut results = Vec::with_capacity(engines.len());
+    if let Some(output) = config
+        .common
+        .output
+        .as_ref()
+        .filter(|_| config.common.resume)
+    {
+        if let Some(existing) = try_read_results(output)? {
+            results = existing;
+            println!(
+                "\nResuming: {} engine(s) already complete in {}",
+                results.len(),
+                output.display()
+            );
+        }
+    }
+
+    let locations = matrix::locations(&config);
+    let result_name = |engine: &dyn Engine, suffix: &str| format!("{}{}", engine.name(), suffix);
+    let runs_at = |engine: &dyn Engine, location_config: &Config| {
+        !location_config.post_filter || engine.supports_filter_pushdown()
+    };
+
+    // Engines that failed in an interrupted run are retried
+    results.retain(|r| r.error.is_none());
+    let is_done = |results: &[EngineResult], name: &str| results.iter().any(|r| r.engine == name);
+    let mut engines: Vec<_> = engines
+        .into_iter()
+        .filter(|engine| {
+            let done = locations.iter().all(|(location_config, suffix)| {
+                !runs_at(engine.as_ref(), location_config)
+                    || is_done(&results, &result_name(engine.as_ref(), suffix))
+            });
+            if done {
+                println!("\nSkipping {}: already complete", engine.name());
+            }
+            !done
+        })
+        .collect();
+    let worker_cpus = pinning.as_ref().map(|p| p.worker_cpus());
+    if let Some(seed) = config.shuffle_seed {
+        engines.shuffle(&mut StdRng::seed_from_u64(seed));
+    }
+
+    if config.interleave {
+        for (location_config, suffix) in &locations {
+            let pending = engines
+                .iter()
+                .filter(|engine| {
+                    runs_at(engine.as_ref(), location_config)
+                        && !is_done(&results, &result_name(engine.as_ref(), suffix))
+                })
+                .cloned()
+                .collect();
+            for mut result in run_interleaved(pending, location_config) {
+                result.engine.push_str(suffix);
+                results.push(result);
+            }
+        }
+        if let Some(output) = &config.common.output {
+            write_results(
+                output,
+                &results,
+                worker_cpus,
+                config.common.seed,
+                config.shuffle_seed,
+            )?;
+        }
+    } else {
+        for engine in engines {
+            for (location_config, suffix) in &locations {
+                let name = result_name(engine.as_ref(), suffix);
+                if !runs_at(engine.as_ref(), location_config) || is_done(&results, &name) {
+                    continue;
+                }
+                let mut result = run_engine(engine.clone(), location_config)
+                    .unwrap_or_else(|e| failed_result(engine.as_ref(), location_config, e));
+                result.engine = name;
+                results.push(result);
+
+                // Written after every engine so an interrupted run can be resumed
+                if let Some(output) = &config.common.output {
+                    write_results(
+                        output,
+                        &results,
+                        worker_cpus,
+                        config.common.seed,
+                        config.shuffle_seed,
+                    )?;
+                }
+            }
+        }
+    }
+
+    // Other Lance builds run after every in-process engine, on the same datasets
+    let lance_engines: Vec<String> = config
+        .engines
+        .iter()
+        .filter(|name| name.starts_with("lance"))
+        .cloned()
+        .collect();
+    let args = config
+        .worker_args
+        .clone()
+        .unwrap_or_else(|| std::env::args().skip(1).collect());
+    for (label, binary) in &config.lance_builds {
+        let pending: Vec<&str> = lance_engines
+            .iter()
+            .map(String::as_str)
+            .filter(|engine| !is_done(&results, &format!("{}@{}", engine, label)))
+            .collect();
+        if pending.is_empty() {
+            println!("\nSkipping Lance build {}: already complete", label);
+            continue;
+        }
+        match external::run_build(label, binary, &pending, &args) {
+            Ok(build_results) => results.extend(build_results),
+            Err(e) => println!("\nWarning: {:#}, continuing with the remaining builds", e),
+        }
+        if let Some(output) = &config.common.output {
+            write_results(
+                output,
+                &results,
+                worker_cpus,
+                config.common.seed,
+                config.shuffle_seed,
+            )?;
+        }
+    }
+
+    report::print_summary(&results, &config);
+    report::check_failures(&results, &config)?;
+
+    println!("\n{}", "=".repeat(60));
+    println!("Benchmark Complete!");
+    println!("{}", "=".repeat(60));
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_row_count_duplicates() {
+        let indices = [1, 2, 2, 4];
+        assert_eq!(check_row_count(&[1, 2, 4], false, 3).unwrap(), None);
+        assert_eq!(
+            check_row_count(&indices, false, 4).unwrap(),
+            Some(DuplicateHandling::Kept)
+        );
+        assert_eq!(
+            check_row_count(&indices, false, 3).unwrap(),
+            Some(DuplicateHandling::Collapsed)
+        );
+        assert!(check_row_count(&indices, false, 2).is_err());
+        // Only even ids pass the filter
+        assert_eq!(
+            check_row_count(&indices, true, 2).unwrap(),
+            Some(DuplicateHandling::Kept)
+        );
+    }
+
+    #[test]
+    fn test_generate_queries_repeat_with_seed() {
+        let config = |seed: &str| Config::parse_from(["take-benchmark", "--seed", seed]);
+        let first = generate_queries(&config("7"), 100);
+        assert_eq!(first, generate_queries(&config("7"), 100));
+        assert_ne!(first, generate_queries(&config("8"), 100));
+    }
+}
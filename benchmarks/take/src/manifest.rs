@@ -0,0 +1,170 @@
+//! Dataset manifests.
+//!
+//! A manifest is written into every generated dataset recording what it was
+//! generated from. An existing dataset is only reused when its manifest
+//! matches the current configuration, so changing e.g. `--vector-dim`,
+//! `--seed` or an engine's write options regenerates the dataset instead of
+//! silently reusing stale data that happens to have the right row count.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::cache::is_local_uri;
+use crate::data::create_schema;
+use crate::engines::Engine;
+use crate::results::Compaction;
+use crate::storage::{local_path, object_store_for};
+use crate::Config;
+
+/// File name of the manifest within the dataset directory.
+pub const MANIFEST_FILE: &str = "bench_manifest.json";
+
+/// Fields that describe how generating the dataset went rather than what it
+/// was generated from, so they don't decide whether it's reused.
+const UNHASHED_FIELDS: &[&str] = &["fingerprint", "compaction"];
+
+/// What a dataset was generated from.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DatasetManifest {
+    pub engine: String,
+    pub rows: usize,
+    pub seed: u64,
+    pub vector_dim: usize,
+    pub write_batch_size: usize,
+    /// `name: type` of every column
+    pub schema: Vec<String>,
+    /// Engine-specific write settings
+    pub write_options: String,
+    /// SQL predicate of the rows deleted after writing, with `--delete-fraction`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted: Option<String>,
+    /// Fragments (or files) the dataset was split into, in `--fragments` sweeps
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fragments: Option<usize>,
+    /// Lance encoding settings of a `--lance-encoding` sweep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+    /// Parquet page layout of a `--parquet-layout` sweep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parquet_layout: Option<String>,
+    /// Vortex layout of a `--vortex-layout` sweep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vortex_layout: Option<String>,
+    /// Whether the dataset was compacted after writing, for `--compact`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub compacted: bool,
+    /// How compacting the dataset went
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<Compaction>,
+    /// Hash of every other field but `compaction`
+    pub fingerprint: String,
+}
+
+impl DatasetManifest {
+    /// The manifest of a dataset generated by `engine` with `config`.
+    pub fn new(engine: &dyn Engine, config: &Config) -> Self {
+        let mut manifest = Self {
+            engine: engine.name().to_string(),
+            rows: config.rows_per_dataset,
+            seed: config.common.seed,
+            vector_dim: config.vector_dim,
+            write_batch_size: config.write_batch_size,
+            schema: create_schema(config.vector_dim)
+                .fields()
+                .iter()
+                .map(|f| format!("{}: {}", f.name(), f.data_type()))
+                .collect(),
+            write_options: engine.write_options(),
+            deleted: config.deletions().map(|d| d.predicate()),
+            fragments: config.fragment_count,
+            encoding: config.lance_encoding.as_ref().map(|e| e.to_string()),
+            parquet_layout: config.parquet_layout.as_ref().map(|l| l.to_string()),
+            vortex_layout: config.vortex_layout.as_ref().map(|l| l.to_string()),
+            compacted: config.compacted,
+            compaction: None,
+            fingerprint: String::new(),
+        };
+        manifest.fingerprint = manifest.compute_fingerprint();
+        manifest
+    }
+
+    /// FNV-1a hash of the other hashed fields' JSON, which is stable across builds.
+    fn compute_fingerprint(&self) -> String {
+        let mut fields = serde_json::to_value(self).unwrap();
+        for field in UNHASHED_FIELDS {
+            fields.as_object_mut().unwrap().remove(*field);
+        }
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in fields.to_string().bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{:016x}", hash)
+    }
+
+    /// Why a dataset with the `existing` manifest can't be reused, if it can't.
+    pub fn mismatch(&self, existing: &Self) -> Option<String> {
+        if existing.fingerprint == self.fingerprint {
+            return None;
+        }
+        let current = serde_json::to_value(self).unwrap();
+        let existing = serde_json::to_value(existing).unwrap();
+        let fields: Vec<&str> = current
+            .as_object()
+            .unwrap()
+            .iter()
+            .filter(|(field, value)| {
+                !UNHASHED_FIELDS.contains(&field.as_str())
+                    && existing.get(field.as_str()) != Some(*value)
+            })
+            .map(|(field, _)| field.as_str())
+            .collect();
+        Some(if fields.is_empty() {
+            "manifest fingerprint differs".to_string()
+        } else {
+            format!("manifest differs in {}", fields.join(", "))
+        })
+    }
+
+    /// Read the manifest of the dataset at `uri`, if it has one.
+    pub async fn read(uri: &str) -> Result<Option<Self>> {
+        let contents = if is_local_uri(uri) {
+            let path = format!("{}/{}", local_path(uri), MANIFEST_FILE);
+            match std::fs::read(&path) {
+                Ok(contents) => contents,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        } else {
+            let (store, path) = object_store_for(&manifest_url(uri)?)?;
+            match store.get(&path).await {
+                Ok(result) => result.bytes().await?.to_vec(),
+                Err(object_store::Error::NotFound { .. }) => return Ok(None),
+                Err(e) => return Err(e.into()),
+            }
+        };
+        // An unreadable manifest just means the dataset gets regenerated
+        Ok(serde_json::from_slice(&contents).ok())
+    }
+
+    /// Write this manifest into the dataset at `uri`.
+    pub async fn write(&self, uri: &str) -> Result<()> {
+        let contents = serde_json::to_vec_pretty(self)?;
+        if is_local_uri(uri) {
+            std::fs::write(format!("{}/{}", local_path(uri), MANIFEST_FILE), contents)?;
+        } else {
+            let (store, path) = object_store_for(&manifest_url(uri)?)?;
+            store.put(&path, contents.into()).await?;
+        }
+        Ok(())
+    }
+}
+
+fn manifest_url(uri: &str) -> Result<Url> {
+    Ok(Url::parse(&format!(
+        "{}/{}",
+        uri.trim_end_matches('/'),
+        MANIFEST_FILE
+    ))?)
+}
@@ -0,0 +1,265 @@
+//! Markdown comparison of two results files for PR comments (`lance-bench pr-comment`).
+//!
+//! The layout follows the bench-bot comment of `scripts/compare_pr_benchmarks.py`:
+//! a summary, the flagged metrics, and every phase in a collapsed details
+//! section. Regressions use the same threshold and minimum effect as
+//! `lance-bench gate`; improvements are the same test in the other direction.
+
+use std::fmt::Write;
+
+use crate::gate::{is_regression, relative_change, GateOptions, Metric, PhaseKey};
+use crate::results::EngineResult;
+
+/// How one metric of a phase changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Improved,
+    Regressed,
+    Stable,
+}
+
+impl Status {
+    fn of(metric: Metric, baseline: f64, current: f64, options: &GateOptions) -> Self {
+        if is_regression(metric, baseline, current, options) {
+            Status::Regressed
+        } else if is_regression(metric, current, baseline, options) {
+            Status::Improved
+        } else {
+            Status::Stable
+        }
+    }
+
+    fn emoji(self) -> &'static str {
+        match self {
+            Status::Improved => "🚀",
+            Status::Regressed => "⚠️",
+            Status::Stable => "✅",
+        }
+    }
+
+    fn text(self) -> &'static str {
+        match self {
+            Status::Improved => "Improved",
+            Status::Regressed => "Regressed",
+            Status::Stable => "Within threshold",
+        }
+    }
+}
+
+/// Metrics shown for every phase in the details section.
+const DETAIL_METRICS: [Metric; 3] = [Metric::P50, Metric::P99, Metric::Throughput];
+
+/// A baseline phase's metrics next to the current run's.
+struct PhaseComparison {
+    phase: PhaseKey,
+    /// (metric, baseline, current, status), or None if the phase is missing from the current run
+    metrics: Option<Vec<(Metric, f64, f64, Status)>>,
+}
+
+fn compare(
+    baseline: &[EngineResult],
+    current: &[EngineResult],
+    options: &GateOptions,
+) -> Vec<PhaseComparison> {
+    let mut comparisons = Vec::new();
+    for base_result in baseline.iter().filter(|r| r.error.is_none()) {
+        for base_phase in &base_result.phases {
+            let phase = PhaseKey::of(base_result, base_phase);
+            let current_phase = current
+                .iter()
+                .filter(|r| r.error.is_none())
+                .flat_map(|r| r.phases.iter().map(move |p| (r, p)))
+                .find(|(r, p)| phase.matches(r, p))
+                .map(|(_, p)| p);
+            let metrics = current_phase.map(|current_phase| {
+                Metric::ALL
+                    .iter()
+                    .map(|&metric| {
+                        let (base, cur) = (metric.value(base_phase), metric.value(current_phase));
+                        (metric, base, cur, Status::of(metric, base, cur, options))
+                    })
+                    .collect()
+            });
+            comparisons.push(PhaseComparison { phase, metrics });
+        }
+    }
+    comparisons
+}
+
+/// Signed change from `baseline` to `current`, as shown in the tables.
+fn change(baseline: f64, current: f64) -> String {
+    if baseline == 0.0 {
+        "N/A".to_string()
+    } else {
+        format!("{:+.1}%", (current - baseline) / baseline * 100.0)
+    }
+}
+
+/// Render the PR comment comparing `current` against `baseline`.
+///
+/// `baseline_name` and `current_name` label the two runs in the header.
+pub fn pr_comment(
+    baseline: &[EngineResult],
+    current: &[EngineResult],
+    baseline_name: &str,
+    current_name: &str,
+    options: &GateOptions,
+) -> String {
+    let comparisons = compare(baseline, current, options);
+    let mut flagged: Vec<(&PhaseKey, Metric, f64, f64, Status)> = Vec::new();
+    let mut missing = 0;
+    let mut stable = 0;
+    for comparison in &comparisons {
+        match &comparison.metrics {
+            None => missing += 1,
+            Some(metrics) => {
+                let before = flagged.len();
+                for &(metric, base, cur, status) in metrics {
+                    if status != Status::Stable {
+                        flagged.push((&comparison.phase, metric, base, cur, status));
+                    }
+                }
+                if flagged.len() == before {
+                    stable += 1;
+                }
+            }
+        }
+    }
+    // Worst regressions first, then the biggest improvements
+    flagged.sort_by(|a, b| {
+        let key = |f: &(&PhaseKey, Metric, f64, f64, Status)| relative_change(f.1, f.2, f.3);
+        key(b).total_cmp(&key(a))
+    });
+    let count = |status: Status| flagged.iter().filter(|f| f.4 == status).count();
+
+    let mut md = String::new();
+    writeln!(md, "## Take Benchmark Results").unwrap();
+    writeln!(md).unwrap();
+    writeln!(md, "**Baseline:** `{}`", baseline_name).unwrap();
+    writeln!(md, "**Current:** `{}`", current_name).unwrap();
+    writeln!(md).unwrap();
+
+    writeln!(md, "### Summary").unwrap();
+    writeln!(md, "- **Phases compared:** {}", comparisons.len()).unwrap();
+    writeln!(md, "- 🚀 **Improved metrics:** {}", count(Status::Improved)).unwrap();
+    writeln!(
+        md,
+        "- ⚠️ **Regressed metrics:** {}",
+        count(Status::Regressed)
+    )
+    .unwrap();
+    writeln!(md, "- ✅ **Stable phases:** {}", stable).unwrap();
+    writeln!(md, "- ❓ **Missing from current:** {}", missing).unwrap();
+    writeln!(md).unwrap();
+
+    let threshold = format!(
+        "{:.1}%, min effect {:?}",
+        options.threshold * 100.0,
+        options.min_effect
+    );
+    if flagged.is_empty() {
+        writeln!(md, "### ✅ All Benchmarks Within Threshold").unwrap();
+        writeln!(md).unwrap();
+        writeln!(md, "No metric changed by more than {}", threshold).unwrap();
+    } else {
+        writeln!(md, "### Flagged Metrics ({})", threshold).unwrap();
+        writeln!(md).unwrap();
+        writeln!(
+            md,
+            "| Phase | Metric | Baseline | Current | Change | Status |"
+        )
+        .unwrap();
+        writeln!(
+            md,
+            "|-------|--------|----------|---------|--------|--------|"
+        )
+        .unwrap();
+        for (phase, metric, base, cur, status) in &flagged {
+            writeln!(
+                md,
+                "| {} | {} | {} | {} | {} | {} {} |",
+                phase,
+                metric.name(),
+                metric.format(*base),
+                metric.format(*cur),
+                change(*base, *cur),
+                status.emoji(),
+                status.text()
+            )
+            .unwrap();
+        }
+    }
+    writeln!(md).unwrap();
+
+    writeln!(md, "### All Results").unwrap();
+    writeln!(md, "<details>").unwrap();
+    writeln!(
+        md,
+        "<summary>View all {} phases</summary>",
+        comparisons.len()
+    )
+    .unwrap();
+    writeln!(md).unwrap();
+    writeln!(md, "| Phase | p50 | p99 | QPS |").unwrap();
+    writeln!(md, "|-------|-----|-----|-----|").unwrap();
+    for comparison in &comparisons {
+        let cells: Vec<String> = match &comparison.metrics {
+            None => DETAIL_METRICS
+                .iter()
+                .map(|_| "❓ missing".to_string())
+                .collect(),
+            Some(metrics) => DETAIL_METRICS
+                .iter()
+                .map(|metric| {
+                    let (_, base, cur, status) =
+                        metrics.iter().find(|(m, ..)| m == metric).unwrap();
+                    format!(
+                        "{} → {} ({}) {}",
+                        metric.format(*base),
+                        metric.format(*cur),
+                        change(*base, *cur),
+                        status.emoji()
+                    )
+                })
+                .collect(),
+        };
+        writeln!(md, "| {} | {} |", comparison.phase, cells.join(" | ")).unwrap();
+    }
+    writeln!(md).unwrap();
+    writeln!(md, "</details>").unwrap();
+    writeln!(md).unwrap();
+    writeln!(md, "---").unwrap();
+    writeln!(md, "*Generated by lance-bench* 🤖").unwrap();
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_status() {
+        let options = GateOptions {
+            threshold: 0.05,
+            min_effect: Duration::from_millis(2),
+        };
+        assert_eq!(
+            Status::of(Metric::P50, 0.100, 0.120, &options),
+            Status::Regressed
+        );
+        assert_eq!(
+            Status::of(Metric::P50, 0.100, 0.080, &options),
+            Status::Improved
+        );
+        // Too small an effect either way
+        assert_eq!(
+            Status::of(Metric::P50, 0.010, 0.009, &options),
+            Status::Stable
+        );
+        assert_eq!(
+            Status::of(Metric::Throughput, 100.0, 120.0, &options),
+            Status::Improved
+        );
+    }
+}
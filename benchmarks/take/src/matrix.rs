@@ -0,0 +1,153 @@
+//! The locations every engine is benchmarked at.
+//!
+//! Each location is a copy of the configuration pointing at a different copy
+//! of the datasets, together with the suffix its results are reported under.
+
+use std::path::Path;
+
+use crate::results::{fragments_suffix, COMPACTED_SUFFIX, POST_FILTER_SUFFIX, TMPFS_SUFFIX};
+use crate::Config;
+
+/// Every location to benchmark for `config`, with its result suffix.
+pub fn locations(config: &Config) -> Vec<(Config, String)> {
+    // Each engine runs once per location: the datasets as given, then their
+    // tmpfs copy, whose results get the tmpfs suffix. A fragment sweep runs
+    // each split copy of the datasets instead, and with --compact every
+    // location is followed by its compacted copy.
+    let mut locations = if config.fragments.is_empty() {
+        vec![(config.clone(), String::new())]
+    } else {
+        config
+            .fragments
+            .iter()
+            .map(|&n| (fragments_config(config, n), fragments_suffix(n)))
+            .collect()
+    };
+    if let Some(dir) = config.tmpfs_dir.as_deref() {
+        locations.push((tmpfs_config(config, dir), TMPFS_SUFFIX.to_string()));
+    }
+    // Encoding and layout sweeps repeat every location with each configuration
+    let encoded = config.lance_encodings.iter().flat_map(|encoding| {
+        locations.iter().map(move |(location_config, suffix)| {
+            (
+                suffixed_config(location_config, &encoding.label, |c| {
+                    c.lance_encoding = Some(encoding.clone())
+                }),
+                format!("{}@{}", suffix, encoding.label),
+            )
+        })
+    });
+    let laid_out = config.parquet_layouts.iter().flat_map(|layout| {
+        locations.iter().map(move |(location_config, suffix)| {
+            (
+                suffixed_config(location_config, &layout.label, |c| {
+                    c.parquet_layout = Some(layout.clone())
+                }),
+                format!("{}@{}", suffix, layout.label),
+            )
+        })
+    });
+    let vortex_laid_out = config.vortex_layouts.iter().flat_map(|layout| {
+        locations.iter().map(move |(location_config, suffix)| {
+            (
+                suffixed_config(location_config, &layout.label, |c| {
+                    c.vortex_layout = Some(layout.clone())
+                }),
+                format!("{}@{}", suffix, layout.label),
+            )
+        })
+    });
+    let swept: Vec<(Config, String)> = encoded.chain(laid_out).chain(vortex_laid_out).collect();
+    locations.extend(swept);
+    if config.compact {
+        locations = locations
+            .into_iter()
+            .flat_map(|(location_config, suffix)| {
+                let compacted = (
+                    compacted_config(&location_config),
+                    format!("{}{}", suffix, COMPACTED_SUFFIX),
+                );
+                [(location_config, suffix), compacted]
+            })
+            .collect();
+    }
+    // Only engines with pushdown have a different way to filter to compare
+    if config.compare_filter {
+        locations = locations
+            .into_iter()
+            .flat_map(|(location_config, suffix)| {
+                let post_filter = (
+                    post_filter_config(&location_config),
+                    format!("{}{}", suffix, POST_FILTER_SUFFIX),
+                );
+                [(location_config, suffix), post_filter]
+            })
+            .collect();
+    }
+    locations
+}
+
+/// The configuration for benchmarking the tmpfs copy of the datasets under `dir`.
+fn tmpfs_config(config: &Config, dir: &Path) -> Config {
+    let mut tmpfs = config.clone();
+    tmpfs.dataset_uri = (0..config.dataset_uri.len())
+        .map(|i| format!("{}/dataset-{}", dir.display(), i))
+        .collect();
+    tmpfs
+}
+
+/// The configuration for benchmarking copies of the datasets split into `fragments`.
+fn fragments_config(config: &Config, fragments: usize) -> Config {
+    let mut split = config.clone();
+    split.dataset_uri = config
+        .dataset_uri
+        .iter()
+        .map(|uri| format!("{}-{}frags", uri.trim_end_matches('/'), fragments))
+        .collect();
+    split.fragment_count = Some(fragments);
+    split
+}
+
+/// The configuration for benchmarking copies of the datasets suffixed with
+/// `label`, written with the sweep setting that `set` applies.
+fn suffixed_config(config: &Config, label: &str, set: impl FnOnce(&mut Config)) -> Config {
+    let mut suffixed = config.clone();
+    suffixed.dataset_uri = config
+        .dataset_uri
+        .iter()
+        .map(|uri| format!("{}-{}", uri.trim_end_matches('/'), label))
+        .collect();
+    set(&mut suffixed);
+    suffixed
+}
+
+/// The configuration for re-running `config` with the take filter applied after reading.
+fn post_filter_config(config: &Config) -> Config {
+    let mut post_filter = config.clone();
+    post_filter.post_filter = true;
+    post_filter
+}
+
+/// The configuration for benchmarking compacted copies of `config`'s datasets.
+fn compacted_config(config: &Config) -> Config {
+    let mut compacted = config.clone();
+    compacted.dataset_uri = config
+        .dataset_uri
+        .iter()
+        .map(|uri| format!("{}-compacted", uri.trim_end_matches('/')))
+        .collect();
+    compacted.compacted = true;
+    compacted
+}
+
+/// Labels of every `--lance-encoding`, `--parquet-layout` and `--vortex-layout`
+/// configuration.
+pub fn sweep_labels(config: &Config) -> Vec<&str> {
+    config
+        .lance_encodings
+        .iter()
+        .map(|e| e.label.as_str())
+        .chain(config.parquet_layouts.iter().map(|l| l.label.as_str()))
+        .chain(config.vortex_layouts.iter().map(|l| l.label.as_str()))
+        .collect()
+}
@@ -0,0 +1,117 @@
+//! Parquet page layouts swept with `--parquet-layout`.
+//!
+//! The Parquet engines write with small pages and a page index by default,
+//! which suits point lookups; a layout overrides those writer settings. Each
+//! one is benchmarked on its own copy of the datasets and reported as
+//! `<engine>@<label>`, next to the default layout.
+
+use std::fmt;
+
+use crate::encodings::check_label;
+
+/// Data page size limit of the default layout, in bytes.
+pub const DEFAULT_PAGE_SIZE: usize = 8 * 1024;
+
+/// Values written per page-size check in the default layout.
+pub const DEFAULT_WRITE_BATCH_SIZE: usize = 1;
+
+/// Settings that can be given.
+pub const KEYS: &[&str] = &[
+    "page-size",
+    "write-batch-size",
+    "page-index",
+    "row-group-size",
+];
+
+/// A named set of Parquet writer settings; unset ones keep the default layout's.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParquetLayout {
+    pub label: String,
+    /// Data page size limit, in bytes
+    pub page_size: Option<usize>,
+    /// Values the writer buffers between checks of the page size limit
+    pub write_batch_size: Option<usize>,
+    /// Whether the offset index, which lets readers skip to single pages, is written
+    pub page_index: Option<bool>,
+    /// Maximum rows per row group
+    pub row_group_size: Option<usize>,
+}
+
+impl fmt::Display for ParquetLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut settings = Vec::new();
+        if let Some(page_size) = self.page_size {
+            settings.push(format!("page-size:{}", page_size));
+        }
+        if let Some(write_batch_size) = self.write_batch_size {
+            settings.push(format!("write-batch-size:{}", write_batch_size));
+        }
+        if let Some(page_index) = self.page_index {
+            settings.push(format!("page-index:{}", page_index));
+        }
+        if let Some(row_group_size) = self.row_group_size {
+            settings.push(format!("row-group-size:{}", row_group_size));
+        }
+        write!(f, "{}={}", self.label, settings.join(","))
+    }
+}
+
+/// Parse `label=key:value[,key:value...]`, as given to `--parquet-layout`.
+pub fn parse_parquet_layout(s: &str) -> Result<ParquetLayout, String> {
+    let (label, settings) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <label>=<key>:<value>[,...], got '{}'", s))?;
+    check_label(label)?;
+    let mut layout = ParquetLayout {
+        label: label.to_string(),
+        ..Default::default()
+    };
+    for setting in settings.split(',') {
+        let (key, value) = setting
+            .split_once(':')
+            .ok_or_else(|| format!("expected <key>:<value>, got '{}'", setting))?;
+        let size = || match value.parse::<usize>() {
+            Ok(size) if size > 0 => Ok(size),
+            _ => Err(format!(
+                "{} must be a positive integer, got '{}'",
+                key, value
+            )),
+        };
+        match key {
+            "page-size" => layout.page_size = Some(size()?),
+            "write-batch-size" => layout.write_batch_size = Some(size()?),
+            "row-group-size" => layout.row_group_size = Some(size()?),
+            "page-index" => {
+                layout.page_index =
+                    Some(value.parse().map_err(|_| {
+                        format!("page-index must be true or false, got '{}'", value)
+                    })?)
+            }
+            _ => {
+                return Err(format!(
+                    "unknown layout setting '{}', expected one of {}",
+                    key,
+                    KEYS.join(", ")
+                ))
+            }
+        }
+    }
+    Ok(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_parquet_layout() {
+        let layout = parse_parquet_layout("1mb=page-size:1048576,page-index:false").unwrap();
+        assert_eq!(layout.page_size, Some(1048576));
+        assert_eq!(layout.page_index, Some(false));
+        assert_eq!(layout.write_batch_size, None);
+        assert_eq!(layout.to_string(), "1mb=page-size:1048576,page-index:false");
+        assert!(parse_parquet_layout("x=page-size:0").is_err());
+        assert!(parse_parquet_layout("x=page-index:maybe").is_err());
+        assert!(parse_parquet_layout("x=dictionary:true").is_err());
+    }
+}
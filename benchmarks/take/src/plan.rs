@@ -0,0 +1,146 @@
+//! Dry-run execution plans.
+//!
+//! `--dry-run` checks everything that can be checked without writing data or
+//! running queries, then prints what a real run would do.
+
+use anyhow::Result;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::cache::is_local_uri;
+use crate::engines::Engine;
+use crate::manifest::DatasetManifest;
+use crate::storage::local_path;
+use crate::{dataset_uris, stale_dataset_reason, CacheMode, Config};
+
+/// Check that files can be created under `path`, or under its nearest existing
+/// ancestor if it doesn't exist yet.
+fn check_writable(path: &Path) -> Result<()> {
+    let mut dir = path;
+    while !dir.exists() {
+        dir = dir
+            .parent()
+            .filter(|p| !p.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+    }
+    if !dir.is_dir() {
+        anyhow::bail!("{} is not a directory", dir.display());
+    }
+
+    let probe = dir.join(".lance-bench-dry-run");
+    std::fs::File::create(&probe)
+        .map_err(|e| anyhow::anyhow!("{} is not writable: {}", dir.display(), e))?;
+    std::fs::remove_file(&probe)?;
+    Ok(())
+}
+
+/// Print the execution plan for `engines`, failing if any path can't be used.
+pub fn print_plan(engines: &[Arc<dyn Engine>], config: &Config) -> Result<()> {
+    println!("\n{}", "=".repeat(60));
+    println!("Dry Run: Execution Plan");
+    println!("{}", "=".repeat(60));
+
+    let mut problems = Vec::new();
+    for uri in &config.dataset_uri {
+        if !is_local_uri(uri) {
+            println!("\nNot checking object-store URI {}", uri);
+        } else if let Err(e) = check_writable(Path::new(local_path(uri))) {
+            problems.push(format!("Dataset URI {}: {}", uri, e));
+        }
+    }
+    if let Some(output) = &config.common.output {
+        let dir = output.parent().unwrap_or(Path::new("."));
+        if !is_local_uri(&output.to_string_lossy()) {
+            println!("\nNot checking object-store output {}", output.display());
+        } else if let Err(e) = check_writable(dir) {
+            problems.push(format!("Output {}: {}", output.display(), e));
+        }
+    }
+
+    // id, flag and vector; flags are bit-packed in practice, so this is an upper bound
+    let row_bytes = 8 + 1 + 4 * config.vector_dim;
+    let dataset_gb = (config.rows_per_dataset * row_bytes) as f64 / 1024.0 / 1024.0 / 1024.0;
+    println!(
+        "\nEstimated dataset size: {:.2} GB each before compression",
+        dataset_gb
+    );
+
+    let caches: &[&str] = match config.cache_mode {
+        CacheMode::DropOnce => &["drop-once"],
+        CacheMode::Hot => &["hot"],
+        CacheMode::Cold => &["cold"],
+        CacheMode::Both => &["hot", "cold"],
+    };
+    let run_length = match config.duration {
+        Some(duration) => format!("queries for {:?}", duration),
+        None => format!("{} queries", config.num_queries),
+    };
+
+    let mut to_create = 0;
+    let mut timed_phases = 0;
+    // Only known up front with --duration; ramp-up only applies to hot phases
+    let mut min_timed = Duration::ZERO;
+    for engine in engines {
+        println!("\nEngine: {}", engine.name());
+
+        let manifest = DatasetManifest::new(engine.as_ref(), config);
+        for uri in dataset_uris(engine.as_ref(), config) {
+            match stale_dataset_reason(engine.as_ref(), &manifest, &uri, config) {
+                Ok(None) => println!("  Reuse dataset {}", uri),
+                Ok(Some(reason)) => {
+                    println!("  Create dataset {} ({})", uri, reason);
+                    to_create += 1;
+                }
+                Err(e) => problems.push(format!("Dataset {}: {}", uri, e)),
+            }
+        }
+
+        for &rows_per_query in &config.rows_per_query {
+            if !config.skip_warmup {
+                println!(
+                    "  Warmup: {} rows/query, {} queries",
+                    rows_per_query, config.num_queries
+                );
+            }
+            for &concurrent_queries in &config.concurrent_queries {
+                for cache in caches {
+                    println!(
+                        "  Timed phase: {} rows/query, {} in flight, {} cache, {}",
+                        rows_per_query,
+                        config.num_runtimes * concurrent_queries,
+                        cache,
+                        run_length
+                    );
+                    timed_phases += 1;
+                    if let Some(duration) = config.duration {
+                        min_timed += duration;
+                        if *cache != "cold" {
+                            min_timed += config.ramp_up.unwrap_or(Duration::ZERO);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    println!(
+        "\nTotal: {} dataset(s) to create (~{:.2} GB), {} timed phase(s)",
+        to_create,
+        to_create as f64 * dataset_gb,
+        timed_phases
+    );
+    if config.duration.is_some() {
+        println!("  Timed phases alone take at least {:?}", min_timed);
+    }
+
+    if !problems.is_empty() {
+        println!("\nProblems:");
+        for problem in &problems {
+            println!("  {}", problem);
+        }
+        anyhow::bail!("Dry run found {} problem(s)", problems.len());
+    }
+    println!("\nDry run OK: nothing was written and no queries were run");
+    Ok(())
+}
@@ -0,0 +1,504 @@
+//! Plots of a run's results (`--plots`).
+//!
+//! Renders the results as charts that can be shared as they are:
+//! - a latency CDF per workload, a line per engine
+//! - throughput against queries in flight per query size and cache state,
+//!   when the run sweeps concurrency
+//! - dataset size against p50 latency per workload, a point per engine
+//! - a heatmap of p50 latency by engine and workload when the run sweeps
+//!   query sizes, concurrency, count selectivities or dataset configurations
+//!   (encodings, layouts, fragments), so a large sweep can be read at once
+
+use anyhow::Result;
+use clap::ValueEnum;
+use plotters::coord::Shift;
+use plotters::prelude::*;
+use plotters::style::text_anchor::{HPos, Pos, VPos};
+use std::path::{Path, PathBuf};
+
+use crate::results::{EngineResult, PhaseResult};
+
+/// Size of every plot but heatmaps, which grow with their cells, in pixels.
+const SIZE: (u32, u32) = (1024, 640);
+
+/// Image format of the plots.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlotFormat {
+    Svg,
+    /// Needs the system's fonts to draw text
+    Png,
+}
+
+impl PlotFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            PlotFormat::Svg => "svg",
+            PlotFormat::Png => "png",
+        }
+    }
+}
+
+/// A line or point of one engine.
+struct Series {
+    engine: String,
+    points: Vec<(f64, f64)>,
+}
+
+/// A chart of one series per engine.
+enum Plot {
+    /// Fraction of queries against latency (ms)
+    LatencyCdf { title: String, series: Vec<Series> },
+    /// Queries per second against queries in flight
+    Throughput { title: String, series: Vec<Series> },
+    /// p50 latency (ms) against dataset size (MB), one point per engine
+    SizeVsLatency { title: String, series: Vec<Series> },
+    /// p50 latency (ms) of each engine (row) at each workload (column)
+    Heatmap {
+        title: String,
+        rows: Vec<String>,
+        columns: Vec<String>,
+        cells: Vec<Vec<Option<f64>>>,
+    },
+}
+
+/// Heatmap cell size, and the width of a character of its labels, in pixels.
+const CELL: (u32, u32) = (84, 26);
+const CHAR_WIDTH: u32 = 8;
+
+impl Plot {
+    fn size(&self) -> (u32, u32) {
+        match self {
+            Plot::Heatmap { rows, columns, .. } => {
+                let label = rows.iter().map(|r| r.len()).max().unwrap_or(0) as u32;
+                let column = columns.iter().map(|c| c.len()).max().unwrap_or(0) as u32;
+                let cell_width = CELL.0.max(column * CHAR_WIDTH);
+                (
+                    (label * CHAR_WIDTH + 40 + cell_width * columns.len() as u32).max(640),
+                    110 + CELL.1 * rows.len() as u32,
+                )
+            }
+            _ => SIZE,
+        }
+    }
+}
+
+/// The workload of a phase, as (rows per query, queries in flight, cache).
+type WorkloadKey<'a> = (usize, usize, &'a str);
+
+fn workload(phase: &PhaseResult) -> WorkloadKey<'_> {
+    (
+        phase.rows_per_query,
+        phase.concurrency,
+        phase.cache.as_str(),
+    )
+}
+
+/// Every distinct value of `key` over the phases of `results`, in order of
+/// first appearance.
+fn distinct<'a, K: PartialEq>(
+    results: &'a [EngineResult],
+    key: impl Fn(&'a PhaseResult) -> K,
+) -> Vec<K> {
+    let mut keys = Vec::new();
+    for phase in results.iter().flat_map(|r| &r.phases) {
+        let k = key(phase);
+        if !keys.contains(&k) {
+            keys.push(k);
+        }
+    }
+    keys
+}
+
+/// Render the plots of `results` into `dir`, returning the files written.
+pub fn write_plots(
+    results: &[EngineResult],
+    dir: &Path,
+    format: PlotFormat,
+) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut written = Vec::new();
+
+    for (rows, concurrency, cache) in distinct(results, workload) {
+        let label = format!(
+            "{} rows/query, {} in flight, {} cache",
+            rows, concurrency, cache
+        );
+        let name = format!("{}rows-{}inflight-{}", rows, concurrency, cache);
+        let phases: Vec<(&EngineResult, &PhaseResult)> = results
+            .iter()
+            .filter_map(|r| {
+                let phase = r
+                    .phases
+                    .iter()
+                    .find(|p| workload(p) == (rows, concurrency, cache))?;
+                Some((r, phase))
+            })
+            .collect();
+
+        let cdfs: Vec<Series> = phases
+            .iter()
+            .filter(|(_, phase)| !phase.quantiles.is_empty())
+            .map(|(result, phase)| Series {
+                engine: result.engine.clone(),
+                points: phase
+                    .quantiles
+                    .iter()
+                    .enumerate()
+                    .map(|(percentile, latency)| (latency * 1000.0, percentile as f64 / 100.0))
+                    .collect(),
+            })
+            .collect();
+        if !cdfs.is_empty() {
+            let plot = Plot::LatencyCdf {
+                title: format!("Latency CDF, {}", label),
+                series: cdfs,
+            };
+            written.push(write_plot(
+                dir,
+                &format!("latency-cdf-{}", name),
+                format,
+                &plot,
+            )?);
+        }
+
+        let sizes: Vec<Series> = phases
+            .iter()
+            .filter(|(result, _)| result.dataset_bytes > 0)
+            .map(|(result, phase)| Series {
+                engine: result.engine.clone(),
+                points: vec![(
+                    result.dataset_bytes as f64 / 1_000_000.0,
+                    phase.stats.p50 * 1000.0,
+                )],
+            })
+            .collect();
+        if !sizes.is_empty() {
+            let plot = Plot::SizeVsLatency {
+                title: format!("Dataset size vs p50 latency, {}", label),
+                series: sizes,
+            };
+            written.push(write_plot(
+                dir,
+                &format!("size-vs-latency-{}", name),
+                format,
+                &plot,
+            )?);
+        }
+    }
+
+    if let Some(plot) = heatmap(results) {
+        written.push(write_plot(dir, "heatmap-p50", format, &plot)?);
+    }
+
+    if distinct(results, |p| p.concurrency).len() > 1 {
+        for (rows, cache) in distinct(results, |p| (p.rows_per_query, p.cache.as_str())) {
+            let series: Vec<Series> = results
+                .iter()
+                .map(|result| Series {
+                    engine: result.engine.clone(),
+                    points: result
+                        .phases
+                        .iter()
+                        .filter(|p| p.rows_per_query == rows && p.cache == cache)
+                        .map(|p| (p.concurrency as f64, p.throughput))
+                        .collect(),
+                })
+                .filter(|s| !s.points.is_empty())
+                .collect();
+            let plot = Plot::Throughput {
+                title: format!(
+                    "Throughput vs concurrency, {} rows/query, {} cache",
+                    rows, cache
+                ),
+                series,
+            };
+            let name = format!("throughput-{}rows-{}", rows, cache);
+            written.push(write_plot(dir, &name, format, &plot)?);
+        }
+    }
+    Ok(written)
+}
+
+/// p50 latency of every engine, and engine on a swept dataset configuration,
+/// at every workload and count selectivity of the run. None unless there are
+/// at least two of each to compare.
+fn heatmap(results: &[EngineResult]) -> Option<Plot> {
+    let workloads = distinct(results, workload);
+    let mut selectivities: Vec<f64> = Vec::new();
+    for count in results
+        .iter()
+        .flat_map(|r| &r.open_timings)
+        .flat_map(|t| &t.counts)
+    {
+        if !selectivities.contains(&count.selectivity) {
+            selectivities.push(count.selectivity);
+        }
+    }
+
+    let columns: Vec<String> = workloads
+        .iter()
+        .map(|(rows, concurrency, cache)| format!("{}r x{} {}", rows, concurrency, cache))
+        .chain(
+            selectivities
+                .iter()
+                .map(|s| format!("count {}%", s * 100.0)),
+        )
+        .collect();
+    let mut rows = Vec::new();
+    let mut cells = Vec::new();
+    for result in results {
+        let p50s = result.open_timings.iter().flat_map(|t| &t.counts);
+        let row: Vec<Option<f64>> = workloads
+            .iter()
+            .map(|&key| {
+                let phase = result.phases.iter().find(|p| workload(p) == key)?;
+                Some(phase.stats.p50)
+            })
+            .chain(selectivities.iter().map(|&selectivity| {
+                // Averaged over the datasets, as in the COUNT ROWS report
+                let counts: Vec<f64> = p50s
+                    .clone()
+                    .filter(|c| c.selectivity == selectivity)
+                    .map(|c| c.p50)
+                    .collect();
+                (!counts.is_empty()).then(|| counts.iter().sum::<f64>() / counts.len() as f64)
+            }))
+            .map(|p50| p50.filter(|p50| p50.is_finite() && *p50 > 0.0))
+            .collect();
+        if row.iter().any(Option::is_some) {
+            rows.push(result.engine.clone());
+            cells.push(row);
+        }
+    }
+
+    (rows.len() > 1 && columns.len() > 1).then(|| Plot::Heatmap {
+        title: "p50 latency (ms) by engine and workload".to_string(),
+        rows,
+        columns,
+        cells,
+    })
+}
+
+/// Draw `plot` into `dir/name`, with the extension of `format`.
+fn write_plot(dir: &Path, name: &str, format: PlotFormat, plot: &Plot) -> Result<PathBuf> {
+    let path = dir.join(format!("{}.{}", name, format.extension()));
+    let size = plot.size();
+    match format {
+        PlotFormat::Svg => plot.draw(SVGBackend::new(&path, size).into_drawing_area())?,
+        PlotFormat::Png => plot.draw(BitMapBackend::new(&path, size).into_drawing_area())?,
+    }
+    Ok(path)
+}
+
+/// (min, max) of `values`.
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+        (lo.min(v), hi.max(v))
+    })
+}
+
+impl Plot {
+    fn draw<DB: DrawingBackend>(&self, root: DrawingArea<DB, Shift>) -> Result<()>
+    where
+        DB::ErrorType: 'static,
+    {
+        root.fill(&WHITE)?;
+        let (title, series) = match self {
+            Plot::LatencyCdf { title, series }
+            | Plot::Throughput { title, series }
+            | Plot::SizeVsLatency { title, series } => (title, series),
+            Plot::Heatmap {
+                title,
+                rows,
+                columns,
+                cells,
+            } => {
+                draw_heatmap(&root, title, rows, columns, cells)?;
+                root.present()?;
+                return Ok(());
+            }
+        };
+        let points = || series.iter().flat_map(|s| s.points.iter());
+        let (x_min, x_max) = bounds(points().map(|&(x, _)| x));
+        let (_, y_max) = bounds(points().map(|&(_, y)| y));
+
+        let mut builder = ChartBuilder::on(&root);
+        builder
+            .caption(title, ("sans-serif", 22))
+            .margin(16)
+            .x_label_area_size(48)
+            .y_label_area_size(72);
+
+        match self {
+            Plot::LatencyCdf { .. } => {
+                // Engines can differ by orders of magnitude, so latency is on a log scale
+                let mut chart = builder
+                    .build_cartesian_2d((x_min.max(1e-3)..x_max * 1.1).log_scale(), 0.0..1.0)?;
+                chart
+                    .configure_mesh()
+                    .x_desc("Latency (ms)")
+                    .y_desc("Fraction of queries")
+                    .draw()?;
+                for (i, s) in series.iter().enumerate() {
+                    let color = Palette99::pick(i).mix(0.9);
+                    chart
+                        .draw_series(LineSeries::new(s.points.clone(), color.stroke_width(2)))?
+                        .label(&s.engine)
+                        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+                }
+                chart
+                    .configure_series_labels()
+                    .position(SeriesLabelPosition::LowerRight)
+                    .background_style(WHITE.mix(0.8))
+                    .border_style(BLACK)
+                    .draw()?;
+            }
+            Plot::Throughput { .. } => {
+                let mut chart =
+                    builder.build_cartesian_2d((x_min..x_max).log_scale(), 0.0..y_max * 1.1)?;
+                chart
+                    .configure_mesh()
+                    .x_desc("Queries in flight")
+                    .y_desc("Queries per second")
+                    .draw()?;
+                for (i, s) in series.iter().enumerate() {
+                    let color = Palette99::pick(i).mix(0.9);
+                    chart
+                        .draw_series(LineSeries::new(s.points.clone(), color.stroke_width(2)))?
+                        .label(&s.engine)
+                        .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+                    chart.draw_series(
+                        s.points
+                            .iter()
+                            .map(|&point| Circle::new(point, 4, color.filled())),
+                    )?;
+                }
+                chart
+                    .configure_series_labels()
+                    .position(SeriesLabelPosition::UpperLeft)
+                    .background_style(WHITE.mix(0.8))
+                    .border_style(BLACK)
+                    .draw()?;
+            }
+            Plot::SizeVsLatency { .. } => {
+                let mut chart = builder.build_cartesian_2d(0.0..x_max * 1.2, 0.0..y_max * 1.2)?;
+                chart
+                    .configure_mesh()
+                    .x_desc("Dataset size (MB)")
+                    .y_desc("p50 latency (ms)")
+                    .draw()?;
+                for (i, s) in series.iter().enumerate() {
+                    let color = Palette99::pick(i).mix(0.9);
+                    chart.draw_series(s.points.iter().map(|&point| {
+                        EmptyElement::at(point)
+                            + Circle::new((0, 0), 6, color.filled())
+                            + Text::new(s.engine.clone(), (10, -10), ("sans-serif", 14).into_font())
+                    }))?;
+                }
+            }
+        }
+        root.present()?;
+        Ok(())
+    }
+}
+
+/// Color of a cell `ratio` times slower than the fastest in its column:
+/// green for the fastest through yellow to red at 10x or slower.
+fn heat(ratio: f64) -> RGBColor {
+    let t = ratio.max(1.0).log10().min(1.0);
+    let mix = |from: (u8, u8, u8), to: (u8, u8, u8), t: f64| {
+        let channel = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        RGBColor(
+            channel(from.0, to.0),
+            channel(from.1, to.1),
+            channel(from.2, to.2),
+        )
+    };
+    const GREEN: (u8, u8, u8) = (26, 152, 80);
+    const YELLOW: (u8, u8, u8) = (254, 224, 139);
+    const RED: (u8, u8, u8) = (215, 48, 39);
+    if t < 0.5 {
+        mix(GREEN, YELLOW, t * 2.0)
+    } else {
+        mix(YELLOW, RED, (t - 0.5) * 2.0)
+    }
+}
+
+/// Draw a grid of `cells` (p50 seconds) with a row per engine and a column
+/// per workload, each colored by its p50 over the fastest in its column.
+fn draw_heatmap<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    title: &str,
+    rows: &[String],
+    columns: &[String],
+    cells: &[Vec<Option<f64>>],
+) -> Result<()>
+where
+    DB::ErrorType: 'static,
+{
+    let label_width =
+        rows.iter().map(|r| r.len()).max().unwrap_or(0) as i32 * CHAR_WIDTH as i32 + 20;
+    let column_width = columns.iter().map(|c| c.len()).max().unwrap_or(0) as u32 * CHAR_WIDTH;
+    let (cell_width, cell_height) = (CELL.0.max(column_width) as i32, CELL.1 as i32);
+    let (top, header) = (50, 30);
+    let centered = |size: u32| {
+        ("sans-serif", size)
+            .into_font()
+            .color(&BLACK)
+            .pos(Pos::new(HPos::Center, VPos::Center))
+    };
+
+    root.draw(&Text::new(
+        title.to_string(),
+        (20, 16),
+        ("sans-serif", 22).into_font(),
+    ))?;
+    for (column, name) in columns.iter().enumerate() {
+        let x = label_width + column as i32 * cell_width + cell_width / 2;
+        root.draw(&Text::new(
+            name.clone(),
+            (x, top + header / 2),
+            centered(13),
+        ))?;
+    }
+
+    let fastest: Vec<Option<f64>> = (0..columns.len())
+        .map(|column| cells.iter().filter_map(|row| row[column]).reduce(f64::min))
+        .collect();
+    for (row, name) in rows.iter().enumerate() {
+        let y = top + header + row as i32 * cell_height;
+        root.draw(&Text::new(
+            name.clone(),
+            (10, y + cell_height / 2),
+            ("sans-serif", 13)
+                .into_font()
+                .color(&BLACK)
+                .pos(Pos::new(HPos::Left, VPos::Center)),
+        ))?;
+        for (column, p50) in cells[row].iter().enumerate() {
+            let x = label_width + column as i32 * cell_width;
+            let (fill, text) = match (p50, fastest[column]) {
+                (Some(p50), Some(fastest)) => (heat(p50 / fastest), format!("{:.3}", p50 * 1000.0)),
+                _ => (RGBColor(235, 235, 235), "-".to_string()),
+            };
+            root.draw(&Rectangle::new(
+                [(x, y), (x + cell_width - 1, y + cell_height - 1)],
+                fill.filled(),
+            ))?;
+            root.draw(&Text::new(
+                text,
+                (x + cell_width / 2, y + cell_height / 2),
+                centered(12),
+            ))?;
+        }
+    }
+
+    let legend_y = top + header + rows.len() as i32 * cell_height + 16;
+    root.draw(&Text::new(
+        "Colored by p50 over the fastest in the column: green is fastest, red 10x or slower"
+            .to_string(),
+        (10, legend_y),
+        ("sans-serif", 13).into_font(),
+    ))?;
+    Ok(())
+}
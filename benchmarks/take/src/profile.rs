@@ -0,0 +1,203 @@
+//! Profiling of the timed phase (`--profile`).
+//!
+//! Heap profiles come from dhat, which has to be the global allocator; the
+//! binaries swap it in for jemalloc when built with the `dhat-heap` feature.
+//! dhat can only profile once per process, so the run must have a single
+//! timed phase.
+//!
+//! CPU profiles come from `perf record`, attached to this process for each
+//! engine's timed phase(s) of a workload. Every workload gets its own
+//! `perf.data` file and a text report in the `--profile-out` directory.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::Duration;
+
+use crate::{CacheMode, Config};
+
+/// What `--profile` records during the timed phase.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileMode {
+    /// Allocations by call stack, written as a dhat profile (needs the `dhat-heap` feature)
+    Heap,
+    /// On-CPU call stacks sampled with `perf record`, one profile per engine and workload
+    Perf,
+}
+
+impl ProfileMode {
+    /// Where the profile is written without `--profile-out`.
+    pub fn default_output(self) -> &'static str {
+        match self {
+            ProfileMode::Heap => "dhat-heap.json",
+            ProfileMode::Perf => "perf-profiles",
+        }
+    }
+}
+
+/// Check that `config` can be profiled with `mode`.
+pub fn check(mode: ProfileMode, config: &Config) -> Result<()> {
+    match mode {
+        ProfileMode::Heap => {
+            if !cfg!(feature = "dhat-heap") {
+                anyhow::bail!(
+                    "--profile heap needs the dhat allocator. Rebuild with `--features dhat-heap`"
+                );
+            }
+            let cache_phases = if config.cache_mode == CacheMode::Both {
+                2
+            } else {
+                1
+            };
+            let locations = if !config.fragments.is_empty() {
+                config.fragments.len()
+            } else if config.tmpfs_dir.is_some() {
+                2
+            } else {
+                1
+            };
+            let copies = (1
+                + config.lance_encodings.len()
+                + config.parquet_layouts.len()
+                + config.vortex_layouts.len())
+                * if config.compact { 2 } else { 1 }
+                * if config.compare_filter { 2 } else { 1 };
+            let phases = config.engines.len()
+                * config.rows_per_query.len()
+                * config.concurrent_queries.len()
+                * cache_phases
+                * locations
+                * copies;
+            if phases != 1 {
+                anyhow::bail!(
+                    "--profile heap profiles a single timed phase: give one engine, one --rows-per-query and one --concurrent-queries value, without --cache-mode both, --tmpfs-dir, --fragments, --lance-encoding, --parquet-layout, --vortex-layout, --compact or --compare-filter"
+                );
+            }
+            if config.worker_processes {
+                anyhow::bail!("--profile heap can't profile --worker-processes");
+            }
+        }
+        ProfileMode::Perf => {
+            let found = Command::new("perf")
+                .arg("--version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success());
+            if !found {
+                anyhow::bail!("--profile perf needs `perf` on the PATH");
+            }
+            // perf record -p doesn't follow processes started after it attaches
+            if config.worker_processes {
+                anyhow::bail!("--profile perf can't profile --worker-processes");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A running profiler, which writes its profile when dropped.
+pub struct Profiler {
+    #[cfg(feature = "dhat-heap")]
+    _dhat: Option<dhat::Profiler>,
+    _perf: Option<PerfRecord>,
+}
+
+/// Start profiling with `mode`, writing to `out`.
+///
+/// `label` names the profile of a perf run within the `out` directory.
+pub fn start(mode: ProfileMode, out: &Path, label: &str) -> Result<Profiler> {
+    match mode {
+        ProfileMode::Heap => {
+            println!("\nRecording a dhat heap profile to {}", out.display());
+            #[cfg(feature = "dhat-heap")]
+            return Ok(Profiler {
+                _dhat: Some(dhat::Profiler::builder().file_name(out).build()),
+                _perf: None,
+            });
+            #[cfg(not(feature = "dhat-heap"))]
+            anyhow::bail!("--profile heap needs the `dhat-heap` feature");
+        }
+        ProfileMode::Perf => {
+            std::fs::create_dir_all(out)?;
+            let perf = PerfRecord::start(out, label)?;
+            Ok(Profiler {
+                #[cfg(feature = "dhat-heap")]
+                _dhat: None,
+                _perf: Some(perf),
+            })
+        }
+    }
+}
+
+/// `perf record` attached to this process.
+struct PerfRecord {
+    child: Child,
+    data: PathBuf,
+    report: PathBuf,
+}
+
+impl PerfRecord {
+    fn start(dir: &Path, label: &str) -> Result<Self> {
+        let data = dir.join(format!("{}.perf.data", label));
+        let report = dir.join(format!("{}.txt", label));
+        println!("\nRecording a perf profile to {}", data.display());
+        let child = Command::new("perf")
+            .args(["record", "--call-graph", "dwarf", "-F", "999", "-p"])
+            .arg(std::process::id().to_string())
+            .arg("-o")
+            .arg(&data)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start perf record: {}", e))?;
+        // perf has no ready signal; give it time to attach before the phase starts
+        std::thread::sleep(Duration::from_millis(200));
+        Ok(Self {
+            child,
+            data,
+            report,
+        })
+    }
+
+    /// Stop recording and write the text report.
+    fn finish(&mut self) -> Result<()> {
+        // perf record only writes a complete file when interrupted
+        Command::new("kill")
+            .args(["-INT", &self.child.id().to_string()])
+            .status()?;
+        self.child.wait()?;
+
+        let output = Command::new("perf")
+            .args([
+                "report",
+                "--stdio",
+                "--no-children",
+                "--percent-limit",
+                "0.5",
+                "-i",
+            ])
+            .arg(&self.data)
+            .stderr(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            anyhow::bail!("perf report failed: {}", output.status);
+        }
+        std::fs::write(&self.report, output.stdout)?;
+        println!("perf report written to {}", self.report.display());
+        Ok(())
+    }
+}
+
+impl Drop for PerfRecord {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            println!(
+                "Warning: couldn't finish the perf profile {}: {:#}",
+                self.data.display(),
+                e
+            );
+        }
+    }
+}
@@ -0,0 +1,351 @@
+//! Console reporting: the configuration a run starts with, the comparison
+//! tables it ends with and the failures that fail it.
+
+use anyhow::Result;
+use std::sync::Arc;
+
+use crate::cache::CacheDropMode;
+use crate::engines::Engine;
+use crate::inject::Injection;
+#[cfg(feature = "plots")]
+use crate::plots;
+use crate::results::{
+    print_compaction_comparison, print_comparison, print_count_comparison, print_filter_comparison,
+    print_fragment_comparison, print_skipping_comparison, print_sweep_comparison,
+    print_tmpfs_comparison, EngineResult, COMPACTED_SUFFIX, POST_FILTER_SUFFIX, TMPFS_SUFFIX,
+};
+use crate::{Config, QueryBy, QueryPattern};
+
+/// Print the configuration of a run of `engines`.
+pub fn print_configuration(
+    engines: &[Arc<dyn Engine>],
+    config: &Config,
+    injection: Option<Injection>,
+) {
+    println!("{}", "=".repeat(60));
+    println!("Take Benchmark");
+    println!("{}", "=".repeat(60));
+    println!("\nConfiguration:");
+    println!(
+        "  Engines: {}",
+        engines
+            .iter()
+            .map(|e| e.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!("  Datasets: {}", config.dataset_uri.len());
+    println!("  Vector dimensions: {}", config.vector_dim);
+    println!("  Rows per dataset: {}", config.rows_per_dataset);
+    if let Some(deletions) = config.deletions() {
+        println!(
+            "  Deleted rows: {:.1}% ({} rows left)",
+            deletions.fraction() * 100.0,
+            config.live_rows()
+        );
+    }
+    match config.duration {
+        Some(duration) => println!("  Duration: {:?} per timed phase", duration),
+        None => println!("  Num queries: {}", config.num_queries),
+    }
+    if let Some(ramp_up) = config.ramp_up {
+        println!("  Ramp-up: {:?} (hot phases)", ramp_up);
+    }
+    println!(
+        "  Rows per query: {}",
+        config
+            .rows_per_query
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    match config.query_pattern {
+        QueryPattern::Random => println!("  Query pattern: random rows"),
+        QueryPattern::Ranges => println!(
+            "  Query pattern: {} contiguous range(s) per query",
+            config.ranges_per_query
+        ),
+    }
+    println!("  Take columns: {}", config.take_columns.join(", "));
+    println!("  Query by: {:?}", config.query_by);
+    if config.take_filter {
+        println!("  Take filter: flag = true");
+    }
+    if config.compare_filter {
+        println!(
+            "  Filter comparison: pushdown vs post-read (reported as <engine>{})",
+            POST_FILTER_SUFFIX
+        );
+    }
+    if !config.count_selectivities.is_empty() {
+        println!(
+            "  Row counts: {} per selectivity ({}), after every open",
+            config.count_iterations,
+            config
+                .count_selectivities
+                .iter()
+                .map(|s| s.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    println!("  Unique indices: {}", config.unique_indices);
+    if config.verify {
+        println!("  Verifying results (seed {})", config.common.seed);
+    }
+    println!(
+        "  Number of runtimes: {} ({})",
+        config.num_runtimes,
+        if config.worker_processes {
+            "one process each"
+        } else {
+            "threads"
+        }
+    );
+    println!("  Engine runtime: {}", config.runtime);
+    for (name, runtime) in &config.engine_runtimes {
+        println!("    {}: {}", name, runtime);
+    }
+    println!(
+        "  Concurrent queries per runtime: {}",
+        config
+            .concurrent_queries
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    match (&config.pin_cores, &config.pin_numa_nodes) {
+        (Some(cores), _) => println!("  Pinning: one core per runtime from {:?}", cores.0),
+        (None, Some(nodes)) => println!("  Pinning: runtimes across NUMA nodes {:?}", nodes.0),
+        (None, None) => println!("  Pinning: none"),
+    }
+    println!("  Cache mode: {:?}", config.cache_mode);
+    if config.cache_mode.drops_cache() {
+        println!("  Cache drop mode: {:?}", config.cache_drop_mode);
+        if config.cache_drop_mode == CacheDropMode::Cgroup {
+            println!(
+                "  Cold-phase cgroup memory.high: {} MiB",
+                config.cgroup_memory_mb
+            );
+        }
+    }
+    if let Some(dir) = &config.tmpfs_dir {
+        println!(
+            "  tmpfs copy: {} (reported as <engine>{})",
+            dir.display(),
+            TMPFS_SUFFIX
+        );
+    }
+    if !config.fragments.is_empty() {
+        println!(
+            "  Fragments per dataset: {} (reported as <engine>@<n>frags)",
+            config
+                .fragments
+                .iter()
+                .map(|n| n.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+    if config.lance_stable_row_ids {
+        println!("  Lance datasets: written with stable row ids");
+    }
+    if config.time_scan {
+        println!("  Scans: timed after every open");
+    }
+    for encoding in &config.lance_encodings {
+        println!(
+            "  Lance encoding: {} (reported as <engine>@{})",
+            encoding, encoding.label
+        );
+    }
+    for layout in &config.parquet_layouts {
+        println!(
+            "  Parquet layout: {} (reported as <engine>@{})",
+            layout, layout.label
+        );
+    }
+    for layout in &config.vortex_layouts {
+        println!(
+            "  Vortex layout: {} (reported as <engine>@{})",
+            layout, layout.label
+        );
+    }
+    if config.compact {
+        println!(
+            "  Compacted copy: every dataset (reported as <engine>{})",
+            COMPACTED_SUFFIX
+        );
+    }
+    if config.direct_io {
+        println!("  Direct I/O: O_DIRECT reads, bypassing the page cache");
+    }
+    if config.in_memory {
+        println!("  In memory: datasets loaded into memory when opened, isolating decode");
+    }
+    if let Some(injection) = injection {
+        println!("  Injected per read request: {}", injection);
+    }
+    if config.interleave {
+        println!("  Engine order: interleaved, one workload at a time");
+    }
+    if let Some(seed) = config.shuffle_seed {
+        println!("  Shuffled order: seed {}", seed);
+    }
+    match config.target_qps {
+        Some(target_qps) => println!("  Load: open-loop at {:.2} queries/sec", target_qps),
+        None => println!("  Load: closed-loop"),
+    }
+}
+
+/// Print the comparison tables for `results`, and where the outputs went.
+pub fn print_summary(results: &[EngineResult], config: &Config) {
+    if results.iter().map(|r| r.phases.len()).sum::<usize>() > 1 {
+        println!("\n{}", "=".repeat(60));
+        println!("ENGINE COMPARISON");
+        println!("{}", "=".repeat(60));
+        print_comparison(results);
+    }
+
+    if config.tmpfs_dir.is_some() {
+        println!("\n{}", "=".repeat(60));
+        println!("TMPFS VS DISK");
+        println!("{}", "=".repeat(60));
+        print_tmpfs_comparison(results);
+    }
+
+    if config.compare_filter {
+        println!("\n{}", "=".repeat(60));
+        println!("PUSHDOWN VS POST-READ FILTER");
+        println!("{}", "=".repeat(60));
+        print_filter_comparison(results);
+    }
+
+    if !config.count_selectivities.is_empty() {
+        println!("\n{}", "=".repeat(60));
+        println!("COUNT ROWS");
+        println!("{}", "=".repeat(60));
+        print_count_comparison(results, &config.count_selectivities);
+    }
+
+    let filtered = config.take_filter
+        || config.query_by == QueryBy::Key
+        || !config.count_selectivities.is_empty();
+    if filtered {
+        println!("\n{}", "=".repeat(60));
+        println!("DATA SKIPPING");
+        println!("{}", "=".repeat(60));
+        print_skipping_comparison(results);
+    }
+
+    if config.compact {
+        println!("\n{}", "=".repeat(60));
+        println!("BEFORE VS AFTER COMPACTION");
+        println!("{}", "=".repeat(60));
+        print_compaction_comparison(results);
+    }
+
+    if !config.lance_encodings.is_empty() {
+        println!("\n{}", "=".repeat(60));
+        println!("LANCE ENCODING SWEEP");
+        println!("{}", "=".repeat(60));
+        let labels: Vec<&str> = config
+            .lance_encodings
+            .iter()
+            .map(|e| e.label.as_str())
+            .collect();
+        print_sweep_comparison(results, &labels);
+    }
+
+    if !config.parquet_layouts.is_empty() {
+        println!("\n{}", "=".repeat(60));
+        println!("PARQUET LAYOUT SWEEP");
+        println!("{}", "=".repeat(60));
+        let labels: Vec<&str> = config
+            .parquet_layouts
+            .iter()
+            .map(|l| l.label.as_str())
+            .collect();
+        print_sweep_comparison(results, &labels);
+    }
+
+    if !config.vortex_layouts.is_empty() {
+        println!("\n{}", "=".repeat(60));
+        println!("VORTEX LAYOUT SWEEP");
+        println!("{}", "=".repeat(60));
+        let labels: Vec<&str> = config
+            .vortex_layouts
+            .iter()
+            .map(|l| l.label.as_str())
+            .collect();
+        print_sweep_comparison(results, &labels);
+    }
+
+    if !config.fragments.is_empty() {
+        println!("\n{}", "=".repeat(60));
+        println!("FRAGMENT SWEEP");
+        println!("{}", "=".repeat(60));
+        print_fragment_comparison(results, &config.fragments);
+    }
+
+    if let Some(output) = &config.common.output {
+        println!("\nResults written to {}", output.display());
+    }
+    #[cfg(feature = "plots")]
+    if let Some(dir) = &config.plots {
+        match plots::write_plots(results, dir, config.plot_format) {
+            Ok(written) => println!("{} plots written to {}", written.len(), dir.display()),
+            Err(e) => println!("Warning: failed to render plots: {:#}", e),
+        }
+    }
+    if let Some(trace_out) = &config.trace_out {
+        println!("Trace written to {}", trace_out.display());
+    }
+}
+
+/// Fail if any engine failed, or any phase's error rate is above
+/// `--max-error-rate`.
+pub fn check_failures(results: &[EngineResult], config: &Config) -> Result<()> {
+    let failed: Vec<&EngineResult> = results.iter().filter(|r| r.error.is_some()).collect();
+    if !failed.is_empty() {
+        println!("\n{}", "=".repeat(60));
+        println!("FAILED ENGINES");
+        println!("{}", "=".repeat(60));
+        for result in &failed {
+            println!(
+                "  {}: {}",
+                result.engine,
+                result.error.as_deref().unwrap_or_default()
+            );
+        }
+        anyhow::bail!(
+            "{} of {} engines failed: {}",
+            failed.len(),
+            results.len(),
+            failed
+                .iter()
+                .map(|r| r.engine.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    for result in results {
+        for phase in &result.phases {
+            if phase.error_rate() > config.max_error_rate {
+                anyhow::bail!(
+                    "{}: {:.2}% of queries failed ({} rows/query, {} in flight, {} cache), above --max-error-rate {:.2}%",
+                    result.engine,
+                    phase.error_rate() * 100.0,
+                    phase.rows_per_query,
+                    phase.concurrency,
+                    phase.cache,
+                    config.max_error_rate * 100.0
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,977 @@
+//! Per-engine benchmark results and cross-engine comparison reporting.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::blockdev::BlockStats;
+use crate::calibrate::HarnessOverhead;
+use crate::engines::RuntimeSpec;
+use crate::lance_trace::TraceCount;
+use crate::stats::Statistics;
+use crate::storage;
+use crate::threads::ThreadStates;
+
+/// Results of a single timed phase.
+#[derive(Serialize, Deserialize)]
+pub struct PhaseResult {
+    /// Page cache state during the phase ("drop-once", "hot" or "cold")
+    pub cache: String,
+    /// Number of rows requested by each query
+    pub rows_per_query: usize,
+    /// Total queries in flight across all runtimes (closed-loop)
+    pub concurrency: usize,
+    pub stats: Statistics,
+    /// Latency at every whole percentile from 0 to 100, for CDF plots
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quantiles: Vec<f64>,
+    /// Statistics for each dataset, keyed by index into `EngineResult::dataset_uris`
+    pub per_dataset: Vec<(usize, Statistics)>,
+    /// Queries per second over the whole phase
+    pub throughput: f64,
+    /// Total rows returned by all queries in the phase
+    pub rows_taken: usize,
+    /// Number of queries that completed successfully
+    pub queries: usize,
+    /// Number of queries that failed (excluded from `stats` and `throughput`)
+    pub errors: usize,
+    /// Queries with duplicate indices that returned a row per duplicate
+    pub duplicates_kept: usize,
+    /// Queries with duplicate indices that returned each distinct row once
+    pub duplicates_collapsed: usize,
+    /// Reads on the block devices holding the datasets, None if none were on one
+    #[serde(default)]
+    pub block_io: Option<BlockStats>,
+    /// Lance's tracing spans and events during the phase (`--trace-lance`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lance_trace: Vec<TraceCount>,
+    /// Samples of the process's thread states (`--sample-thread-states`)
+    #[serde(default)]
+    pub thread_states: Option<ThreadStates>,
+    /// Data the phase's queries pruned, if the engine counts it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipping: Option<Skipping>,
+}
+
+impl PhaseResult {
+    /// Fraction of the phase's queries that failed.
+    pub fn error_rate(&self) -> f64 {
+        let total = self.queries + self.errors;
+        if total == 0 {
+            0.0
+        } else {
+            self.errors as f64 / total as f64
+        }
+    }
+}
+
+/// Results of all timed phases for a single engine.
+#[derive(Serialize, Deserialize)]
+pub struct EngineResult {
+    pub engine: String,
+    /// Versions of the libraries the engine exercises, keyed by crate name
+    #[serde(default)]
+    pub versions: BTreeMap<String, String>,
+    /// Tokio runtime the engine ran on (missing from older results files)
+    #[serde(default)]
+    pub runtime: Option<RuntimeSpec>,
+    pub dataset_uris: Vec<String>,
+    /// Engine-specific write settings of the datasets, as in their manifests
+    #[serde(default)]
+    pub write_options: String,
+    /// Total size of the datasets on storage
+    #[serde(default)]
+    pub dataset_bytes: u64,
+    /// How long each dataset took to open, in `dataset_uris` order
+    #[serde(default)]
+    pub open_timings: Vec<OpenTimings>,
+    pub phases: Vec<PhaseResult>,
+    /// Harness time included in each query's latency
+    #[serde(default)]
+    pub harness_overhead: Option<HarnessOverhead>,
+    /// Compaction of every dataset, for the compacted copies of `--compact`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compaction: Option<Compaction>,
+    /// Why the engine failed to complete; a failed engine has no phases
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Compaction of an engine's datasets into fewer, larger fragments, with `--compact`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+pub struct Compaction {
+    /// Time to compact (seconds)
+    pub time: f64,
+    pub fragments_removed: usize,
+    pub fragments_added: usize,
+}
+
+impl Compaction {
+    /// Both compactions together, as of datasets compacted one after the other.
+    pub fn add(self, other: Self) -> Self {
+        Self {
+            time: self.time + other.time,
+            fragments_removed: self.fragments_removed + other.fragments_removed,
+            fragments_added: self.fragments_added + other.fragments_added,
+        }
+    }
+}
+
+/// Units of data (row groups, fragments) that queries could have read, and
+/// those they read after pruning the rest by position or statistics.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct Skipping {
+    /// What is counted, e.g. "row groups"
+    pub unit: String,
+    /// Units in the datasets queried, once per query
+    pub considered: u64,
+    /// Units queries read at least part of
+    pub read: u64,
+}
+
+impl Skipping {
+    /// Both counts together, as of queries to several datasets.
+    pub fn add(self, other: Self) -> Self {
+        Self {
+            unit: self.unit,
+            considered: self.considered + other.considered,
+            read: self.read + other.read,
+        }
+    }
+
+    /// What was counted since the counts were `before`.
+    pub fn since(&self, before: &Self) -> Self {
+        Self {
+            unit: self.unit.clone(),
+            considered: self.considered - before.considered,
+            read: self.read - before.read,
+        }
+    }
+
+    /// Fraction of the considered units that were never read.
+    pub fn pruned_fraction(&self) -> f64 {
+        if self.considered == 0 {
+            0.0
+        } else {
+            1.0 - self.read as f64 / self.considered as f64
+        }
+    }
+}
+
+/// Time to open one dataset and read from it for the first time (seconds).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct OpenTimings {
+    /// `Engine::open`, including `metadata_load`
+    pub open: f64,
+    /// Part of `open` spent loading metadata, if the engine reports it
+    pub metadata_load: Option<f64>,
+    /// A single-row query on the freshly opened dataset
+    pub first_query: f64,
+    /// A full scan of the take columns, with `--time-scan` or in `--fragments` sweeps
+    #[serde(default)]
+    pub scan: Option<f64>,
+    /// Filtered row counts, one per `--count-selectivity`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub counts: Vec<CountTiming>,
+}
+
+/// Counts of the rows matched by a filter selecting `selectivity` of them
+/// (seconds).
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CountTiming {
+    pub selectivity: f64,
+    /// Rows counted
+    pub rows: usize,
+    pub p50: f64,
+    pub p99: f64,
+    /// Data the counts pruned, if the engine counts it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skipping: Option<Skipping>,
+}
+
+/// Version of the results file layout written by [`write_results`].
+///
+/// Bump it when a change can't be read by older tooling through
+/// `#[serde(default)]` alone, and add a step to [`migrate`] that upgrades
+/// files of the previous version. Files without a version are version 1.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Fields of the results file written with `--output`, next to the results.
+#[derive(Serialize)]
+struct Metadata<'a> {
+    schema_version: u32,
+    /// CPUs each worker runtime was pinned to, if pinning was requested
+    worker_cpus: Option<&'a [Vec<usize>]>,
+    /// Seed of the data and the queries
+    seed: u64,
+    /// Seed of the engine and query order, if it was shuffled
+    shuffle_seed: Option<u64>,
+}
+
+/// Write all engine results to `path` as JSON, locally or to an object store.
+pub fn write_results(
+    path: &Path,
+    results: &[EngineResult],
+    worker_cpus: Option<&[Vec<usize>]>,
+    seed: u64,
+    shuffle_seed: Option<u64>,
+) -> Result<()> {
+    let metadata = Metadata {
+        schema_version: SCHEMA_VERSION,
+        worker_cpus,
+        seed,
+        shuffle_seed,
+    };
+    let json = bench_core::results::results_json("take", metadata, results)?;
+    storage::write_file(&path.to_string_lossy(), json)
+}
+
+/// The parts of a results file needed to compare runs.
+#[derive(Deserialize)]
+struct BenchmarkInput {
+    benchmark_type: String,
+    /// Unix time the file was written
+    #[serde(default)]
+    timestamp: u64,
+    results: Vec<EngineResult>,
+}
+
+/// Read the engine results from a file written by [`write_results`].
+///
+/// Returns None if the file doesn't exist.
+pub fn try_read_results(path: &Path) -> Result<Option<Vec<EngineResult>>> {
+    match storage::read_file(&path.to_string_lossy())? {
+        Some(contents) => Ok(Some(parse_results(path, &contents)?.results)),
+        None => Ok(None),
+    }
+}
+
+/// Read the engine results from a file written by [`write_results`], with
+/// the Unix time it was written.
+pub fn read_timestamped_results(path: &Path) -> Result<(u64, Vec<EngineResult>)> {
+    let contents = storage::read_file(&path.to_string_lossy())?
+        .ok_or_else(|| anyhow::anyhow!("{} not found", path.display()))?;
+    let input = parse_results(path, &contents)?;
+    Ok((input.timestamp, input.results))
+}
+
+/// Read the engine results from a file written by [`write_results`].
+pub fn read_results(path: &Path) -> Result<Vec<EngineResult>> {
+    try_read_results(path)?.ok_or_else(|| anyhow::anyhow!("{} not found", path.display()))
+}
+
+/// Upgrade a results file of `version` to [`SCHEMA_VERSION`] in place.
+fn migrate(file: &mut serde_json::Value, version: u32) -> Result<()> {
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "schema version {} is newer than this build reads ({}); update lance-bench",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    if version < 2 {
+        // Version 1 predates duplicate-index accounting
+        let engines = file.get_mut("results").and_then(|r| r.as_array_mut());
+        let phases = engines
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.get_mut("phases").and_then(|p| p.as_array_mut()));
+        for phase in phases.flatten() {
+            if let Some(phase) = phase.as_object_mut() {
+                for field in ["duplicates_kept", "duplicates_collapsed"] {
+                    phase.entry(field).or_insert(0.into());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_results(path: &Path, contents: &[u8]) -> Result<BenchmarkInput> {
+    let parse_error =
+        |e: &dyn std::fmt::Display| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e);
+    let mut file: serde_json::Value =
+        serde_json::from_slice(contents).map_err(|e| parse_error(&e))?;
+    let version = file
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1) as u32;
+    migrate(&mut file, version).map_err(|e| parse_error(&e))?;
+    let input: BenchmarkInput = serde_json::from_value(file).map_err(|e| parse_error(&e))?;
+    if input.benchmark_type != "take" {
+        anyhow::bail!(
+            "{} holds {} results, not take results",
+            path.display(),
+            input.benchmark_type
+        );
+    }
+    Ok(input)
+}
+
+/// Lance spans and events printed per phase; the results file has them all.
+const LANCE_TRACE_ROWS: usize = 15;
+
+/// Print the latency statistics and throughput for a single engine.
+pub fn print_engine_result(result: &EngineResult) {
+    if !result.open_timings.is_empty() {
+        println!("\nDataset open (ms):");
+        println!(
+            "  {:<40} {:>10} {:>10} {:>12}",
+            "Dataset", "Open", "Metadata", "First query"
+        );
+        for (uri, timings) in result.dataset_uris.iter().zip(&result.open_timings) {
+            let metadata = match timings.metadata_load {
+                Some(metadata_load) => format!("{:.3}", metadata_load * 1000.0),
+                None => "-".to_string(),
+            };
+            println!(
+                "  {:<40} {:>10.3} {:>10} {:>12.3}",
+                uri,
+                timings.open * 1000.0,
+                metadata,
+                timings.first_query * 1000.0
+            );
+        }
+    }
+
+    for phase in &result.phases {
+        let stats = &phase.stats;
+
+        println!(
+            "\nLatency Statistics, {} rows/query, {} in flight, {} cache (seconds):",
+            phase.rows_per_query, phase.concurrency, phase.cache
+        );
+        println!("  Mean:   {:.6}", stats.mean);
+        println!("  Std:    {:.6}", stats.std);
+        println!("  Min:    {:.6}", stats.min);
+        println!("  Max:    {:.6}", stats.max);
+        println!("  p50:    {:.6}", stats.p50);
+        println!("  p95:    {:.6}", stats.p95);
+        println!("  p99:    {:.6}", stats.p99);
+        if let Some(overhead) = &result.harness_overhead {
+            let per_query = overhead.per_query();
+            println!(
+                "  Harness overhead: {:.6} ({:.1}% of p50)",
+                per_query,
+                per_query / stats.p50 * 100.0
+            );
+        }
+        if let Some(skipping) = &phase.skipping {
+            println!(
+                "  Data skipping: read {} of {} {} ({:.1}% pruned)",
+                skipping.read,
+                skipping.considered,
+                skipping.unit,
+                skipping.pruned_fraction() * 100.0
+            );
+        }
+
+        if result.dataset_uris.len() > 1 {
+            println!("\nPer-dataset latency (ms):");
+            println!(
+                "  {:<40} {:>10} {:>10} {:>10} {:>10}",
+                "Dataset", "Mean", "p50", "p95", "p99"
+            );
+            for (dataset_idx, stats) in &phase.per_dataset {
+                println!(
+                    "  {:<40} {:>10.3} {:>10.3} {:>10.3} {:>10.3}",
+                    result.dataset_uris[*dataset_idx],
+                    stats.mean * 1000.0,
+                    stats.p50 * 1000.0,
+                    stats.p95 * 1000.0,
+                    stats.p99 * 1000.0
+                );
+            }
+        }
+
+        println!("\nThroughput: {:.2} queries/sec", phase.throughput);
+        println!("  Total rows taken: {}", phase.rows_taken);
+        println!(
+            "  Errors: {} of {} queries ({:.2}%)",
+            phase.errors,
+            phase.queries + phase.errors,
+            phase.error_rate() * 100.0
+        );
+        if phase.duplicates_kept + phase.duplicates_collapsed > 0 {
+            println!(
+                "  Queries with duplicate indices: {} ({} returned duplicates, {} collapsed them)",
+                phase.duplicates_kept + phase.duplicates_collapsed,
+                phase.duplicates_kept,
+                phase.duplicates_collapsed
+            );
+        }
+        if let Some(io) = &phase.block_io {
+            println!("\nBlock device reads ({}):", io.devices.join(", "));
+            println!(
+                "  Requests: {} ({} merged), {:.2} MB",
+                io.reads,
+                io.read_merges,
+                io.read_bytes as f64 / 1024.0 / 1024.0
+            );
+            println!(
+                "  Avg request: {:.1} KB, {:.3} ms",
+                io.avg_read_bytes() / 1024.0,
+                io.avg_read_ms()
+            );
+            println!(
+                "  Device busy: {} ms, time in queue: {} ms",
+                io.busy_ms, io.queue_time_ms
+            );
+        }
+        if let Some(threads) = &phase.thread_states {
+            println!("\nThread states ({} samples):", threads.samples);
+            println!(
+                "  On CPU: {:.1}%, I/O wait: {:.1}%, futex: {:.1}%, other sleep: {:.1}%",
+                threads.percent(threads.on_cpu),
+                threads.percent(threads.io_wait),
+                threads.percent(threads.futex),
+                threads.percent(threads.other_sleep)
+            );
+        }
+        if !phase.lance_trace.is_empty() {
+            println!("\nLance tracing (busiest spans, then most frequent events):");
+            println!(
+                "  {:<56} {:>10} {:>12}",
+                "Span or event", "Count", "Busy (ms)"
+            );
+            for trace in phase.lance_trace.iter().take(LANCE_TRACE_ROWS) {
+                let busy = match trace.busy {
+                    Some(busy) => format!("{:.3}", busy * 1000.0),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "  {:<56} {:>10} {:>12}",
+                    format!("{}::{}", trace.target, trace.name),
+                    trace.count,
+                    busy
+                );
+            }
+            if phase.lance_trace.len() > LANCE_TRACE_ROWS {
+                println!(
+                    "  ... {} more in the results file",
+                    phase.lance_trace.len() - LANCE_TRACE_ROWS
+                );
+            }
+        }
+    }
+}
+
+/// Print the throughput vs p99 latency curve across concurrency levels.
+///
+/// One curve is printed per query size and cache state.
+pub fn print_concurrency_curve(result: &EngineResult) {
+    let mut curves: Vec<(usize, &str)> = Vec::new();
+    for phase in &result.phases {
+        if !curves.contains(&(phase.rows_per_query, phase.cache.as_str())) {
+            curves.push((phase.rows_per_query, phase.cache.as_str()));
+        }
+    }
+
+    for (rows_per_query, cache) in curves {
+        println!(
+            "\nThroughput vs p99 latency: {}, {} rows/query, {} cache",
+            result.engine, rows_per_query, cache
+        );
+        println!("{:>10} {:>12} {:>10}", "In flight", "QPS", "p99(ms)");
+        for phase in result
+            .phases
+            .iter()
+            .filter(|p| p.rows_per_query == rows_per_query && p.cache == cache)
+        {
+            println!(
+                "{:>10} {:>12.2} {:>10.3}",
+                phase.concurrency,
+                phase.throughput,
+                phase.stats.p99 * 1000.0
+            );
+        }
+    }
+}
+
+/// Suffix of the engine name in results measured on the tmpfs copy of its datasets.
+pub const TMPFS_SUFFIX: &str = "@tmpfs";
+
+/// Print each engine's results next to those of its tmpfs copy.
+///
+/// The tmpfs latency approximates the engine's CPU cost; "I/O share" is the
+/// fraction of the disk p50 latency that goes away on tmpfs.
+pub fn print_tmpfs_comparison(results: &[EngineResult]) {
+    println!(
+        "\n{:<16} {:>8} {:>9} {:>6} {:>14} {:>15} {:>10} {:>11} {:>10}",
+        "Engine",
+        "Rows/q",
+        "In flight",
+        "Cache",
+        "Disk p50(ms)",
+        "tmpfs p50(ms)",
+        "Disk QPS",
+        "tmpfs QPS",
+        "I/O share"
+    );
+    println!("{}", "-".repeat(107));
+    for disk in results.iter().filter(|r| !r.engine.ends_with(TMPFS_SUFFIX)) {
+        let tmpfs_name = format!("{}{}", disk.engine, TMPFS_SUFFIX);
+        let Some(tmpfs) = results.iter().find(|r| r.engine == tmpfs_name) else {
+            continue;
+        };
+        for phase in &disk.phases {
+            let Some(tmpfs_phase) = tmpfs.phases.iter().find(|p| {
+                p.cache == phase.cache
+                    && p.rows_per_query == phase.rows_per_query
+                    && p.concurrency == phase.concurrency
+            }) else {
+                continue;
+            };
+            let io_share = if phase.stats.p50 > 0.0 {
+                (1.0 - tmpfs_phase.stats.p50 / phase.stats.p50).max(0.0)
+            } else {
+                0.0
+            };
+            println!(
+                "{:<16} {:>8} {:>9} {:>6} {:>14.3} {:>15.3} {:>10.2} {:>11.2} {:>9.1}%",
+                disk.engine,
+                phase.rows_per_query,
+                phase.concurrency,
+                phase.cache,
+                phase.stats.p50 * 1000.0,
+                tmpfs_phase.stats.p50 * 1000.0,
+                phase.throughput,
+                tmpfs_phase.throughput,
+                io_share * 100.0
+            );
+        }
+    }
+}
+
+/// Suffix of the engine name in results with the take filter applied after reading.
+pub const POST_FILTER_SUFFIX: &str = "@post-filter";
+
+/// Print each engine's filtered takes with the filter pushed into the reader
+/// next to the same takes filtered in memory after reading.
+///
+/// "Speedup" is the post-read p50 latency over the pushdown one.
+pub fn print_filter_comparison(results: &[EngineResult]) {
+    println!(
+        "\n{:<28} {:>8} {:>9} {:>6} {:>17} {:>15} {:>10} {:>10} {:>8}",
+        "Engine",
+        "Rows/q",
+        "In flight",
+        "Cache",
+        "Pushdown p50(ms)",
+        "Post p50(ms)",
+        "Push QPS",
+        "Post QPS",
+        "Speedup"
+    );
+    println!("{}", "-".repeat(119));
+    for pushdown in results
+        .iter()
+        .filter(|r| !r.engine.ends_with(POST_FILTER_SUFFIX))
+    {
+        let post_name = format!("{}{}", pushdown.engine, POST_FILTER_SUFFIX);
+        let Some(post) = results.iter().find(|r| r.engine == post_name) else {
+            continue;
+        };
+        for phase in &pushdown.phases {
+            let Some(post_phase) = post.phases.iter().find(|p| {
+                p.cache == phase.cache
+                    && p.rows_per_query == phase.rows_per_query
+                    && p.concurrency == phase.concurrency
+            }) else {
+                continue;
+            };
+            let speedup = if phase.stats.p50 > 0.0 {
+                post_phase.stats.p50 / phase.stats.p50
+            } else {
+                0.0
+            };
+            println!(
+                "{:<28} {:>8} {:>9} {:>6} {:>17.3} {:>15.3} {:>10.2} {:>10.2} {:>7.2}x",
+                pushdown.engine,
+                phase.rows_per_query,
+                phase.concurrency,
+                phase.cache,
+                phase.stats.p50 * 1000.0,
+                post_phase.stats.p50 * 1000.0,
+                phase.throughput,
+                post_phase.throughput,
+                speedup
+            );
+        }
+    }
+}
+
+/// Suffix of the engine name in results measured on compacted copies of its datasets.
+pub const COMPACTED_SUFFIX: &str = "@compacted";
+
+/// Print each engine's results next to those of its compacted datasets, with
+/// what compacting them cost.
+///
+/// "Change" is how much the p50 latency moved with compaction; negative is faster.
+pub fn print_compaction_comparison(results: &[EngineResult]) {
+    println!(
+        "\n{:<28} {:>8} {:>9} {:>6} {:>12} {:>11} {:>11} {:>10} {:>8}",
+        "Engine",
+        "Rows/q",
+        "In flight",
+        "Cache",
+        "Before(ms)",
+        "After(ms)",
+        "Before QPS",
+        "After QPS",
+        "Change"
+    );
+    println!("{}", "-".repeat(111));
+    let mut costs = Vec::new();
+    for compacted in results.iter() {
+        let Some(engine) = compacted.engine.strip_suffix(COMPACTED_SUFFIX) else {
+            continue;
+        };
+        let Some(before) = results.iter().find(|r| r.engine == engine) else {
+            continue;
+        };
+        for phase in &before.phases {
+            let Some(after) = compacted.phases.iter().find(|p| {
+                p.cache == phase.cache
+                    && p.rows_per_query == phase.rows_per_query
+                    && p.concurrency == phase.concurrency
+            }) else {
+                continue;
+            };
+            let change = if phase.stats.p50 > 0.0 {
+                format!("{:+.1}%", (after.stats.p50 / phase.stats.p50 - 1.0) * 100.0)
+            } else {
+                String::new()
+            };
+            println!(
+                "{:<28} {:>8} {:>9} {:>6} {:>12.3} {:>11.3} {:>11.2} {:>10.2} {:>8}",
+                engine,
+                phase.rows_per_query,
+                phase.concurrency,
+                phase.cache,
+                phase.stats.p50 * 1000.0,
+                after.stats.p50 * 1000.0,
+                phase.throughput,
+                after.throughput,
+                change
+            );
+        }
+        costs.push((engine, compacted.compaction));
+    }
+
+    println!("\nCompaction cost:");
+    for (engine, compaction) in costs {
+        match compaction {
+            Some(c) => println!(
+                "  {}: {} fragments into {} in {:.2}s",
+                engine, c.fragments_removed, c.fragments_added, c.time
+            ),
+            None => println!("  {}: not measured", engine),
+        }
+    }
+}
+
+/// Print each engine's p50 count latency at every `--count-selectivity`,
+/// averaged over its datasets.
+pub fn print_count_comparison(results: &[EngineResult], selectivities: &[f64]) {
+    print!("\n{:<28}", "Engine");
+    for selectivity in selectivities {
+        print!(" {:>14}", format!("{}% p50(ms)", selectivity * 100.0));
+    }
+    println!();
+    println!("{}", "-".repeat(28 + 15 * selectivities.len()));
+    for result in results {
+        print!("{:<28}", result.engine);
+        for &selectivity in selectivities {
+            let p50s: Vec<f64> = result
+                .open_timings
+                .iter()
+                .flat_map(|t| &t.counts)
+                .filter(|c| c.selectivity == selectivity)
+                .map(|c| c.p50)
+                .collect();
+            if p50s.is_empty() {
+                print!(" {:>14}", "-");
+            } else {
+                let p50 = p50s.iter().sum::<f64>() / p50s.len() as f64;
+                print!(" {:>14.3}", p50 * 1000.0);
+            }
+        }
+        println!();
+    }
+}
+
+/// Print how much data each engine's queries and counts pruned, next to
+/// their p50 latency.
+///
+/// "Pruned" is the fraction of the row groups or fragments the queries could
+/// have read that they never read. Engines that don't count it show "-".
+pub fn print_skipping_comparison(results: &[EngineResult]) {
+    println!(
+        "\n{:<28} {:<22} {:<10} {:>12} {:>12} {:>8} {:>10}",
+        "Engine", "Query", "Unit", "Considered", "Read", "Pruned", "p50(ms)"
+    );
+    println!("{}", "-".repeat(108));
+    let print_row =
+        |engine: &str, query: String, skipping: Option<&Skipping>, p50: f64| match skipping {
+            Some(skipping) => println!(
+                "{:<28} {:<22} {:<10} {:>12} {:>12} {:>7.1}% {:>10.3}",
+                engine,
+                query,
+                skipping.unit,
+                skipping.considered,
+                skipping.read,
+                skipping.pruned_fraction() * 100.0,
+                p50 * 1000.0
+            ),
+            None => println!(
+                "{:<28} {:<22} {:<10} {:>12} {:>12} {:>8} {:>10.3}",
+                engine,
+                query,
+                "-",
+                "-",
+                "-",
+                "-",
+                p50 * 1000.0
+            ),
+        };
+    for result in results {
+        for phase in &result.phases {
+            let query = format!("{} rows/q, {}", phase.rows_per_query, phase.cache);
+            print_row(
+                &result.engine,
+                query,
+                phase.skipping.as_ref(),
+                phase.stats.p50,
+            );
+        }
+        for count in result.open_timings.iter().flat_map(|t| &t.counts) {
+            let query = format!("count {}%", count.selectivity * 100.0);
+            print_row(&result.engine, query, count.skipping.as_ref(), count.p50);
+        }
+    }
+}
+
+/// Print each engine's size, scan and take latency per configuration of a
+/// `--lance-encoding` or `--parquet-layout` sweep (named by `labels`), next
+/// to those of the default configuration.
+///
+/// "vs default" is a phase's p50 latency over that of the same phase on the
+/// default configuration's datasets.
+pub fn print_sweep_comparison(results: &[EngineResult], labels: &[&str]) {
+    let mean = |values: Vec<f64>| values.iter().sum::<f64>() / values.len().max(1) as f64;
+    println!(
+        "\n{:<28} {:<12} {:>10} {:>10} {:>8} {:>9} {:>6} {:>10} {:>10} {:>10}",
+        "Engine",
+        "Config",
+        "Size(MB)",
+        "Scan(ms)",
+        "Rows/q",
+        "In flight",
+        "Cache",
+        "p50(ms)",
+        "p99(ms)",
+        "vs default"
+    );
+    println!("{}", "-".repeat(122));
+    for baseline in results {
+        let swept = labels.iter().filter_map(|&label| {
+            let name = format!("{}@{}", baseline.engine, label);
+            let result = results.iter().find(|r| r.engine == name)?;
+            Some((label, result))
+        });
+        let rows: Vec<(&str, &EngineResult)> = std::iter::once(("default", baseline))
+            .chain(swept)
+            .collect();
+        if rows.len() == 1 {
+            continue;
+        }
+        for (label, result) in rows {
+            let scan = mean(result.open_timings.iter().filter_map(|t| t.scan).collect());
+            for phase in &result.phases {
+                let vs_default = baseline
+                    .phases
+                    .iter()
+                    .find(|p| {
+                        p.cache == phase.cache
+                            && p.rows_per_query == phase.rows_per_query
+                            && p.concurrency == phase.concurrency
+                    })
+                    .filter(|p| p.stats.p50 > 0.0)
+                    .map(|p| format!("{:.2}x", phase.stats.p50 / p.stats.p50))
+                    .unwrap_or_default();
+                println!(
+                    "{:<28} {:<12} {:>10.1} {:>10.1} {:>8} {:>9} {:>6} {:>10.3} {:>10.3} {:>10}",
+                    baseline.engine,
+                    label,
+                    result.dataset_bytes as f64 / 1_000_000.0,
+                    scan * 1000.0,
+                    phase.rows_per_query,
+                    phase.concurrency,
+                    phase.cache,
+                    phase.stats.p50 * 1000.0,
+                    phase.stats.p99 * 1000.0,
+                    vs_default
+                );
+            }
+        }
+    }
+}
+
+/// Suffix of the engine name in results measured on datasets split into `fragments`.
+pub fn fragments_suffix(fragments: usize) -> String {
+    format!("@{}frags", fragments)
+}
+
+/// Print how each engine's open, scan and take latency change with the
+/// number of fragments (or files) its datasets are split into.
+///
+/// Open, first query and scan times are averaged over the datasets.
+/// "Slowdown" is a phase's p50 latency over that of the same phase with the
+/// fewest fragments.
+pub fn print_fragment_comparison(results: &[EngineResult], fragments: &[usize]) {
+    let mut counts = fragments.to_vec();
+    counts.sort_unstable();
+    counts.dedup();
+    let Some(&fewest) = counts.first() else {
+        return;
+    };
+    let mean = |values: Vec<f64>| values.iter().sum::<f64>() / values.len().max(1) as f64;
+
+    println!(
+        "\n{:<16} {:>6} {:>9} {:>10} {:>10} {:>8} {:>9} {:>6} {:>10} {:>10} {:>9}",
+        "Engine",
+        "Frags",
+        "Open(ms)",
+        "First(ms)",
+        "Scan(ms)",
+        "Rows/q",
+        "In flight",
+        "Cache",
+        "p50(ms)",
+        "p99(ms)",
+        "Slowdown"
+    );
+    println!("{}", "-".repeat(113));
+    let fewest_suffix = fragments_suffix(fewest);
+    for baseline in results
+        .iter()
+        .filter(|r| r.engine.ends_with(&fewest_suffix))
+    {
+        let engine = baseline.engine.trim_end_matches(&fewest_suffix);
+        for &count in &counts {
+            let name = format!("{}{}", engine, fragments_suffix(count));
+            let Some(result) = results.iter().find(|r| r.engine == name) else {
+                continue;
+            };
+            let timings = &result.open_timings;
+            let open = mean(timings.iter().map(|t| t.open).collect());
+            let first_query = mean(timings.iter().map(|t| t.first_query).collect());
+            let scan = mean(timings.iter().filter_map(|t| t.scan).collect());
+            for phase in &result.phases {
+                let slowdown = baseline
+                    .phases
+                    .iter()
+                    .find(|p| {
+                        p.cache == phase.cache
+                            && p.rows_per_query == phase.rows_per_query
+                            && p.concurrency == phase.concurrency
+                    })
+                    .filter(|p| p.stats.p50 > 0.0)
+                    .map(|p| format!("{:.2}x", phase.stats.p50 / p.stats.p50))
+                    .unwrap_or_default();
+                println!(
+                    "{:<16} {:>6} {:>9.2} {:>10.2} {:>10.1} {:>8} {:>9} {:>6} {:>10.3} {:>10.3} {:>9}",
+                    engine,
+                    count,
+                    open * 1000.0,
+                    first_query * 1000.0,
+                    scan * 1000.0,
+                    phase.rows_per_query,
+                    phase.concurrency,
+                    phase.cache,
+                    phase.stats.p50 * 1000.0,
+                    phase.stats.p99 * 1000.0,
+                    slowdown
+                );
+            }
+        }
+    }
+}
+
+/// Print a side-by-side comparison of all benchmarked engines and phases.
+///
+/// Latencies are shown in milliseconds. The last column is each row's
+/// throughput relative to the fastest row with the same query size,
+/// concurrency and cache state.
+pub fn print_comparison(results: &[EngineResult]) {
+    let best_throughput = |phase: &PhaseResult| {
+        results
+            .iter()
+            .flat_map(|r| r.phases.iter())
+            .filter(|p| {
+                p.cache == phase.cache
+                    && p.rows_per_query == phase.rows_per_query
+                    && p.concurrency == phase.concurrency
+            })
+            .map(|p| p.throughput)
+            .fold(f64::MIN, f64::max)
+    };
+
+    println!(
+        "\n{:<16} {:>8} {:>9} {:>6} {:>10} {:>10} {:>10} {:>10} {:>12} {:>8}",
+        "Engine",
+        "Rows/q",
+        "In flight",
+        "Cache",
+        "Mean(ms)",
+        "p50(ms)",
+        "p95(ms)",
+        "p99(ms)",
+        "QPS",
+        "vs best"
+    );
+    println!("{}", "-".repeat(108));
+    for result in results {
+        for phase in &result.phases {
+            let stats = &phase.stats;
+            println!(
+                "{:<16} {:>8} {:>9} {:>6} {:>10.3} {:>10.3} {:>10.3} {:>10.3} {:>12.2} {:>7.2}x",
+                result.engine,
+                phase.rows_per_query,
+                phase.concurrency,
+                phase.cache,
+                stats.mean * 1000.0,
+                stats.p50 * 1000.0,
+                stats.p95 * 1000.0,
+                stats.p99 * 1000.0,
+                phase.throughput,
+                phase.throughput / best_throughput(phase),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_version_1_results() {
+        let stats = r#"{"mean": 0.001, "std": 0.0, "min": 0.001, "max": 0.001,
+            "p50": 0.001, "p95": 0.001, "p99": 0.001}"#;
+        let v1 = format!(
+            r#"{{"benchmark_type": "take", "timestamp": 1, "worker_cpus": null,
+                "results": [{{"engine": "lance", "dataset_uris": ["/tmp/d"], "phases": [{{
+                    "cache": "hot", "rows_per_query": 10, "concurrency": 4,
+                    "stats": {stats}, "per_dataset": [[0, {stats}]], "throughput": 100.0,
+                    "rows_taken": 1000, "queries": 100, "errors": 0}}]}}]}}"#
+        );
+        let input = parse_results(Path::new("v1.json"), v1.as_bytes()).unwrap();
+        assert_eq!(input.results[0].phases[0].duplicates_kept, 0);
+
+        let future = format!(r#"{{"schema_version": {}}}"#, SCHEMA_VERSION + 1);
+        assert!(parse_results(Path::new("v3.json"), future.as_bytes()).is_err());
+    }
+}
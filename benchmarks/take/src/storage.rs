@@ -0,0 +1,179 @@
+//! URI handling shared by the engines, dataset manifests and results files.
+//!
+//! Anything that isn't a local path is resolved through `object_store`.
+
+use anyhow::Result;
+use futures::{Future, StreamExt, TryStreamExt};
+use object_store::aws::AmazonS3Builder;
+use object_store::azure::MicrosoftAzureBuilder;
+use object_store::gcp::GoogleCloudStorageBuilder;
+use object_store::local::LocalFileSystem;
+use object_store::memory::InMemory;
+use object_store::path::Path as ObjectPath;
+use object_store::ObjectStore;
+use std::sync::Arc;
+use url::Url;
+
+use crate::cache::is_local_uri;
+
+/// Filesystem path of a local URI (`file://`, `file+uring://` or a plain path).
+pub fn local_path(uri: &str) -> &str {
+    uri.strip_prefix("file+uring://")
+        .or_else(|| uri.strip_prefix("file://"))
+        .unwrap_or(uri)
+}
+
+/// Resolve an object-store URL to its store and the path within it.
+///
+/// Credentials come from each cloud's standard environment variables:
+///
+/// - `s3://`: `AWS_*`; set `AWS_ENDPOINT` (and `AWS_ALLOW_HTTP=true` for plain
+///   HTTP) to use an S3-compatible store such as MinIO
+/// - `gs://`: `GOOGLE_SERVICE_ACCOUNT` / `GOOGLE_APPLICATION_CREDENTIALS`
+/// - `az://`, `abfs(s)://`: `AZURE_STORAGE_ACCOUNT_NAME` and a key or token
+pub fn object_store_for(url: &Url) -> Result<(Arc<dyn ObjectStore>, ObjectPath)> {
+    let path = ObjectPath::from_url_path(url.path())?;
+    Ok(match url.scheme() {
+        "s3" | "s3a" => (
+            Arc::new(AmazonS3Builder::from_env().with_url(url.as_str()).build()?),
+            path,
+        ),
+        "gs" => (
+            Arc::new(
+                GoogleCloudStorageBuilder::from_env()
+                    .with_url(url.as_str())
+                    .build()?,
+            ),
+            path,
+        ),
+        "az" | "azure" | "abfs" | "abfss" => (
+            Arc::new(
+                MicrosoftAzureBuilder::from_env()
+                    .with_url(url.as_str())
+                    .build()?,
+            ),
+            path,
+        ),
+        _ => {
+            let (store, path) = object_store::parse_url(url)?;
+            (Arc::from(store), path)
+        }
+    })
+}
+
+/// Copy every file of the dataset at `uri` into a new in-memory store, for
+/// `--in-memory`.
+///
+/// Files keep the paths they have in their own store (local datasets are
+/// read through `LocalFileSystem`), so the returned store can stand in for
+/// it. Also returns the dataset's path within the store.
+pub async fn load_into_memory(uri: &str) -> Result<(Arc<InMemory>, ObjectPath)> {
+    let (store, prefix): (Arc<dyn ObjectStore>, ObjectPath) = if is_local_uri(uri) {
+        (
+            Arc::new(LocalFileSystem::new()),
+            ObjectPath::from_filesystem_path(local_path(uri))?,
+        )
+    } else {
+        object_store_for(&Url::parse(uri)?)?
+    };
+    let memory = InMemory::new();
+    let mut objects = store.list(Some(&prefix));
+    while let Some(meta) = objects.try_next().await? {
+        let bytes = store.get(&meta.location).await?.bytes().await?;
+        memory.put(&meta.location, bytes.into()).await?;
+    }
+    Ok((Arc::new(memory), prefix))
+}
+
+/// Delete everything under the dataset at `uri`, if anything is there.
+pub async fn remove_dataset(uri: &str) -> Result<()> {
+    if is_local_uri(uri) {
+        let path = std::path::Path::new(local_path(uri));
+        if path.exists() {
+            std::fs::remove_dir_all(path)?;
+        }
+        return Ok(());
+    }
+
+    let (store, prefix) = object_store_for(&Url::parse(uri)?)?;
+    let locations = store
+        .list(Some(&prefix))
+        .map_ok(|meta| meta.location)
+        .boxed();
+    store
+        .delete_stream(locations)
+        .try_collect::<Vec<_>>()
+        .await?;
+    Ok(())
+}
+
+/// Total size in bytes of the dataset at `uri`.
+///
+/// Local datasets are walked; object-store datasets are listed.
+pub async fn dataset_size(uri: &str) -> Result<u64> {
+    if is_local_uri(uri) {
+        let mut size = 0;
+        for entry in walkdir::WalkDir::new(local_path(uri)) {
+            let entry = entry?;
+            if entry.file_type().is_file() {
+                size += entry.metadata()?.len();
+            }
+        }
+        return Ok(size);
+    }
+
+    let (store, prefix) = object_store_for(&Url::parse(uri)?)?;
+    store
+        .list(Some(&prefix))
+        .try_fold(0, |size, meta| async move { Ok(size + meta.size) })
+        .await
+        .map_err(Into::into)
+}
+
+/// Run `future` on a throwaway runtime, for storage calls made outside any engine.
+fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    Ok(tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?
+        .block_on(future))
+}
+
+/// Read the file at `location`, a local path or an object-store URI.
+///
+/// Returns None if it doesn't exist.
+pub fn read_file(location: &str) -> Result<Option<Vec<u8>>> {
+    if is_local_uri(location) {
+        return match std::fs::read(local_path(location)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        };
+    }
+
+    block_on(get_object(location))?
+}
+
+async fn get_object(location: &str) -> Result<Option<Vec<u8>>> {
+    let (store, path) = object_store_for(&Url::parse(location)?)?;
+    match store.get(&path).await {
+        Ok(result) => Ok(Some(result.bytes().await?.to_vec())),
+        Err(object_store::Error::NotFound { .. }) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write `contents` to `location`, a local path or an object-store URI.
+pub fn write_file(location: &str, contents: Vec<u8>) -> Result<()> {
+    if is_local_uri(location) {
+        let path = std::path::Path::new(local_path(location));
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+        return Ok(());
+    }
+
+    let (store, path) = object_store_for(&Url::parse(location)?)?;
+    block_on(store.put(&path, contents.into()))??;
+    Ok(())
+}
@@ -0,0 +1,161 @@
+//! A metric's history across results files (`lance-bench trend`).
+//!
+//! Results files written by scheduled runs form the history: each file is
+//! one point, ordered by the time it was written. Every phase of the chosen
+//! engine gets its own series, and each series is searched for changepoints
+//! by binary segmentation: the split with the largest shift in mean relative
+//! to the noise on either side is kept if it is significant, and both halves
+//! are searched again.
+
+use crate::gate::{Metric, PhaseKey};
+use crate::results::EngineResult;
+
+/// Shift in means, in standard errors, that counts as a changepoint.
+const CHANGEPOINT_SCORE: f64 = 4.0;
+
+/// Fewest points on each side of a changepoint.
+const MIN_SEGMENT: usize = 2;
+
+/// One results file of the history.
+pub struct HistoryPoint {
+    /// Unix time the results were written
+    pub timestamp: u64,
+    /// Where the results came from, shown next to each point
+    pub label: String,
+    pub results: Vec<EngineResult>,
+}
+
+/// A metric of one phase over time.
+pub struct Series {
+    pub phase: PhaseKey,
+    /// (index into the history, value)
+    pub points: Vec<(usize, f64)>,
+}
+
+/// The series of `metric` for every phase of `engine`, in history order.
+///
+/// `history` must already be sorted by timestamp.
+pub fn series(history: &[HistoryPoint], engine: &str, metric: Metric) -> Vec<Series> {
+    let mut series: Vec<Series> = Vec::new();
+    for (index, point) in history.iter().enumerate() {
+        let Some(result) = point
+            .results
+            .iter()
+            .find(|r| r.engine == engine && r.error.is_none())
+        else {
+            continue;
+        };
+        for phase in &result.phases {
+            let key = PhaseKey::of(result, phase);
+            let value = (index, metric.value(phase));
+            match series.iter_mut().find(|s| s.phase == key) {
+                Some(s) => s.points.push(value),
+                None => series.push(Series {
+                    phase: key,
+                    points: vec![value],
+                }),
+            }
+        }
+    }
+    series
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+fn variance(values: &[f64]) -> f64 {
+    let mean = mean(values);
+    values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / values.len() as f64
+}
+
+/// The best split of `values` and its score (shift in means over its standard error).
+fn best_split(values: &[f64]) -> Option<(usize, f64)> {
+    (MIN_SEGMENT..=values.len().saturating_sub(MIN_SEGMENT))
+        .map(|split| {
+            let (before, after) = values.split_at(split);
+            let (n1, n2) = (before.len() as f64, after.len() as f64);
+            let pooled = (variance(before) * n1 + variance(after) * n2) / (n1 + n2);
+            // Floor the noise at 0.1% of the level so flat series don't score infinitely
+            let floor = (mean(values).abs() * 1e-3).powi(2);
+            let stderr = (pooled.max(floor) * (1.0 / n1 + 1.0 / n2)).sqrt();
+            let score = (mean(after) - mean(before)).abs() / stderr;
+            (split, score)
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// Indices into `values` where a new level starts, in order.
+pub fn changepoints(values: &[f64]) -> Vec<usize> {
+    let mut found = Vec::new();
+    let mut segments = vec![(0, values.len())];
+    while let Some((start, end)) = segments.pop() {
+        let Some((split, score)) = best_split(&values[start..end]) else {
+            continue;
+        };
+        if score < CHANGEPOINT_SCORE {
+            continue;
+        }
+        found.push(start + split);
+        segments.push((start, start + split));
+        segments.push((start + split, end));
+    }
+    found.sort_unstable();
+    found
+}
+
+/// Print each series with a bar per point, marking changepoints.
+pub fn print_trend(history: &[HistoryPoint], series: &[Series], metric: Metric) {
+    for s in series {
+        let values: Vec<f64> = s.points.iter().map(|(_, value)| *value).collect();
+        let changes = changepoints(&values);
+        let max = values.iter().copied().fold(0.0, f64::max);
+
+        println!("\n{} ({})", s.phase, metric.name());
+        for (i, (index, value)) in s.points.iter().enumerate() {
+            let point = &history[*index];
+            let bar = if max > 0.0 {
+                "#".repeat((value / max * 30.0).round() as usize)
+            } else {
+                String::new()
+            };
+            let marker = if changes.contains(&i) {
+                let before = mean(&values[..i]);
+                let change = (value - before) / before * 100.0;
+                let worse = if metric.is_latency() {
+                    change > 0.0
+                } else {
+                    change < 0.0
+                };
+                format!(
+                    "  <- {} ({:+.1}% vs before)",
+                    if worse { "regression" } else { "improvement" },
+                    change
+                )
+            } else {
+                String::new()
+            };
+            println!(
+                "  {:<32} {:>14} {:<30}{}",
+                point.label,
+                metric.format(*value),
+                bar,
+                marker
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changepoints() {
+        let flat = [10.0, 10.1, 9.9, 10.0, 10.2, 9.8];
+        assert!(changepoints(&flat).is_empty());
+
+        let step = [10.0, 10.1, 9.9, 10.0, 12.0, 12.1, 11.9, 12.0];
+        assert_eq!(changepoints(&step), vec![4]);
+    }
+}
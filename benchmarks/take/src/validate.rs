@@ -0,0 +1,307 @@
+//! Checks run before any data is written or queries are issued.
+//!
+//! Flags that can't be combined fail here, and engines that can't run the
+//! configuration are dropped with a warning.
+
+use anyhow::Result;
+#[cfg(feature = "tui")]
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::cache::CacheDropMode;
+use crate::data;
+use crate::engines::Engine;
+use crate::matrix::sweep_labels;
+use crate::profile;
+use crate::{CacheMode, Config, QueryBy, QueryPattern};
+
+/// Check that the flags in `config` can be combined, turning on the settings
+/// other flags imply.
+pub fn check_config(config: &mut Config) -> Result<()> {
+    if config.query_pattern == QueryPattern::Ranges
+        && (config.ranges_per_query == 0
+            || config
+                .rows_per_query
+                .iter()
+                .any(|&rows| config.ranges_per_query > rows || rows % config.ranges_per_query != 0))
+    {
+        anyhow::bail!(
+            "--ranges-per-query must be at least 1 and divide every --rows-per-query value"
+        );
+    }
+
+    if let Some(target_qps) = config.target_qps {
+        if target_qps <= 0.0 {
+            anyhow::bail!("--target-qps must be positive");
+        }
+        if config.cache_mode.runs_cold() {
+            anyhow::bail!("--target-qps is only supported with --cache-mode hot or drop-once");
+        }
+        if config.concurrent_queries.len() > 1 {
+            anyhow::bail!("--target-qps cannot be combined with a --concurrent-queries sweep");
+        }
+    }
+
+    if config.worker_processes && (config.target_qps.is_some() || config.cache_mode.runs_cold()) {
+        anyhow::bail!(
+            "--worker-processes only supports closed-loop --cache-mode hot or drop-once runs"
+        );
+    }
+
+    if config.worker_processes && config.trace_lance {
+        anyhow::bail!("--trace-lance can't see into --worker-processes");
+    }
+
+    if config.worker_processes && config.sample_thread_states {
+        anyhow::bail!("--sample-thread-states can't see into --worker-processes");
+    }
+
+    if config.cache_drop_mode == CacheDropMode::Cgroup && !config.cache_mode.runs_cold() {
+        anyhow::bail!("--cache-drop-mode cgroup only applies to cold phases, so it needs --cache-mode cold or both");
+    }
+
+    if config.ramp_up.is_some() && config.cache_mode == CacheMode::Cold {
+        anyhow::bail!("--ramp-up only applies to hot phases");
+    }
+
+    if config
+        .delete_fraction
+        .is_some_and(|fraction| !(0.0..1.0).contains(&fraction))
+    {
+        anyhow::bail!("--delete-fraction must be at least 0 and below 1");
+    }
+
+    if config.fragments.contains(&0) {
+        anyhow::bail!("--fragments values must be positive");
+    }
+    if let Some(&fragments) = config
+        .fragments
+        .iter()
+        .find(|&&fragments| config.rows_per_dataset % fragments != 0)
+    {
+        anyhow::bail!(
+            "--rows-per-dataset ({}) must split evenly into {} fragments",
+            config.rows_per_dataset,
+            fragments
+        );
+    }
+
+    if !(0.0..=1.0).contains(&config.max_error_rate) {
+        anyhow::bail!("--max-error-rate must be between 0 and 1");
+    }
+
+    if config.concurrent_queries.contains(&0) {
+        anyhow::bail!("--concurrent-queries values must be positive");
+    }
+
+    if let Some(mode) = config.profile {
+        profile::check(mode, config)?;
+    }
+
+    if config
+        .inject_failure_rate
+        .is_some_and(|rate| !(0.0..=1.0).contains(&rate))
+    {
+        anyhow::bail!("--inject-failure-rate must be between 0 and 1");
+    }
+
+    let schema = data::create_schema(config.vector_dim);
+    for column in &config.take_columns {
+        if schema.field_with_name(column).is_err() {
+            anyhow::bail!(
+                "Unknown take column '{}'. Available columns: {:?}",
+                column,
+                schema.fields().iter().map(|f| f.name()).collect::<Vec<_>>()
+            );
+        }
+    }
+
+    if !config.lance_builds.is_empty()
+        && !config.engines.iter().any(|name| name.starts_with("lance"))
+    {
+        anyhow::bail!("--lance-build needs a Lance engine in --engines");
+    }
+    let sweeps = !config.fragments.is_empty()
+        || !config.lance_encodings.is_empty()
+        || !config.parquet_layouts.is_empty()
+        || !config.vortex_layouts.is_empty();
+    if !config.lance_builds.is_empty() && (sweeps || config.compact) {
+        anyhow::bail!(
+            "--lance-build can't be combined with --fragments, --lance-encoding, --parquet-layout, --vortex-layout or --compact"
+        );
+    }
+    let mut labels = sweep_labels(config);
+    labels.sort_unstable();
+    if labels.windows(2).any(|pair| pair[0] == pair[1]) {
+        anyhow::bail!(
+            "--lance-encoding, --parquet-layout and --vortex-layout labels must be unique"
+        );
+    }
+    if !config.count_selectivities.is_empty() {
+        if config
+            .count_selectivities
+            .iter()
+            .any(|&s| !(s > 0.0 && s <= 1.0))
+        {
+            anyhow::bail!("--count-selectivity fractions must be in (0, 1]");
+        }
+        if config.count_iterations == 0 {
+            anyhow::bail!("--count-iterations must be positive");
+        }
+        // Counts are checked against the ids every dataset is written with
+        if config.delete_fraction.is_some() {
+            anyhow::bail!("--count-selectivity can't be combined with --delete-fraction");
+        }
+    }
+    if config.compare_filter {
+        if config.query_by == QueryBy::Key {
+            anyhow::bail!("--compare-filter compares filtered takes, not --query-by key");
+        }
+        if !config.lance_builds.is_empty() {
+            anyhow::bail!("--lance-build can't be combined with --compare-filter");
+        }
+        config.take_filter = true;
+    }
+    // Sweeps compare scans as well as takes, and scans from memory are
+    // the purest measure of decode throughput
+    if sweeps || config.in_memory {
+        config.time_scan = true;
+    }
+    for (name, _) in &config.engine_runtimes {
+        if !config.engines.contains(name) {
+            anyhow::bail!(
+                "--engine-runtime names '{}', which is not in --engines",
+                name
+            );
+        }
+    }
+
+    if config.verify
+        && config.query_by == QueryBy::Key
+        && !config.take_columns.iter().any(|c| c == "id")
+    {
+        anyhow::bail!("--verify with --query-by key needs id in --take-columns");
+    }
+
+    Ok(())
+}
+
+/// The `engines` that can run `config`.
+///
+/// Engines that can't run this configuration at all are skipped; missing
+/// filter pushdown or projection is made up for by `with_fallbacks`.
+pub fn supported_engines(
+    engines: Vec<Arc<dyn Engine>>,
+    config: &Config,
+    remote: bool,
+    injected: bool,
+) -> Result<Vec<Arc<dyn Engine>>> {
+    let engines: Vec<_> = engines
+        .into_iter()
+        .filter(|engine| {
+            let unsupported = match config.query_by {
+                QueryBy::Offset if !engine.supports_take() => Some("take by row offset"),
+                QueryBy::Key if !engine.supports_lookup() => Some("--query-by key"),
+                _ if remote && !engine.supports_object_store() => Some("object-store URIs"),
+                _ if injected && !engine.supports_object_store() => {
+                    Some("read request injection or throttling")
+                }
+                _ if config.direct_io && !engine.supports_direct_io() => Some("--direct-io"),
+                _ if config.in_memory && !engine.supports_in_memory() => Some("--in-memory"),
+                _ if config.delete_fraction.is_some() && !engine.supports_delete() => {
+                    Some("--delete-fraction")
+                }
+                _ if !config.fragments.is_empty() && !engine.supports_fragments() => {
+                    Some("--fragments")
+                }
+                _ if config.compact && !engine.supports_compaction() => Some("--compact"),
+                _ if !config.lance_encodings.is_empty() && !engine.supports_lance_encoding() => {
+                    Some("--lance-encoding")
+                }
+                _ if !config.parquet_layouts.is_empty() && !engine.supports_parquet_layout() => {
+                    Some("--parquet-layout")
+                }
+                _ if !config.vortex_layouts.is_empty() && !engine.supports_vortex_layout() => {
+                    Some("--vortex-layout")
+                }
+                _ if config.time_scan && !engine.supports_scan() => Some("scans"),
+                _ if !config.count_selectivities.is_empty() && !engine.supports_count() => {
+                    Some("--count-selectivity")
+                }
+                _ => None,
+            };
+            if let Some(unsupported) = unsupported {
+                println!(
+                    "Warning: skipping engine '{}', which does not support {}",
+                    engine.name(),
+                    unsupported
+                );
+            }
+            unsupported.is_none()
+        })
+        .collect();
+    if engines.is_empty() {
+        anyhow::bail!("None of the selected engines supports this configuration");
+    }
+    Ok(engines)
+}
+
+/// Check `config` against where the datasets live, warning about settings
+/// that won't have the usual effect there.
+pub fn check_environment(config: &mut Config, remote: bool, injected: bool) -> Result<()> {
+    if let Some(dir) = &config.tmpfs_dir {
+        if remote {
+            anyhow::bail!("--tmpfs-dir compares local datasets with a tmpfs copy, but the datasets are remote");
+        }
+        std::fs::create_dir_all(dir)?;
+        if !is_tmpfs(dir) {
+            println!(
+                "Warning: {} is not on a tmpfs mount, so the @tmpfs results may include device I/O",
+                dir.display()
+            );
+        }
+    }
+
+    if config.direct_io && (remote || injected) {
+        anyhow::bail!("--direct-io only applies to local files read without an object store");
+    }
+
+    if remote {
+        if config.cache_mode.runs_cold() {
+            println!("Warning: object-store datasets have no page cache to drop, so cold phases only add wave boundaries");
+        }
+    }
+    if config.in_memory && config.cache_mode.runs_cold() {
+        println!("Warning: --in-memory datasets aren't read through the page cache, so cold phases only add wave boundaries");
+    }
+    #[cfg(feature = "tui")]
+    if config.tui && !std::io::stdout().is_terminal() {
+        println!("Warning: stdout is not a terminal, so --tui falls back to the progress bar");
+        config.tui = false;
+    }
+
+    Ok(())
+}
+
+/// Whether `path` is on a tmpfs mount, according to `/proc/self/mounts`.
+fn is_tmpfs(path: &Path) -> bool {
+    let Ok(path) = path.canonicalize() else {
+        return false;
+    };
+    let Ok(mounts) = std::fs::read_to_string("/proc/self/mounts") else {
+        return false;
+    };
+    // The innermost mount containing the path decides
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?;
+            let fs_type = fields.next()?;
+            path.starts_with(mount_point)
+                .then_some((mount_point.len(), fs_type == "tmpfs"))
+        })
+        .max_by_key(|(len, _)| *len)
+        .is_some_and(|(_, tmpfs)| tmpfs)
+}
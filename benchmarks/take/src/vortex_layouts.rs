@@ -0,0 +1,121 @@
+//! Vortex write layouts swept with `--vortex-layout`.
+//!
+//! The Vortex engine writes with Vortex's default write strategy; a layout
+//! overrides how many rows each chunk of the layout holds and which
+//! compressor encodes them. Each one is benchmarked on its own copy of the
+//! datasets and reported as `<engine>@<label>`, next to the default.
+
+use std::fmt;
+
+use crate::encodings::check_label;
+
+/// Settings that can be given.
+pub const KEYS: &[&str] = &["row-block-size", "compressor"];
+
+/// How Vortex compresses the written arrays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VortexCompressor {
+    /// BtrBlocks-style cascading lightweight encodings (Vortex's default)
+    Btrblocks,
+    /// Lightweight encodings plus general-purpose compression, for smaller files
+    Compact,
+}
+
+impl VortexCompressor {
+    pub fn name(self) -> &'static str {
+        match self {
+            VortexCompressor::Btrblocks => "btrblocks",
+            VortexCompressor::Compact => "compact",
+        }
+    }
+}
+
+/// A named set of Vortex write settings; unset ones keep Vortex's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VortexLayout {
+    pub label: String,
+    /// Rows per chunk of the written layout
+    pub row_block_size: Option<usize>,
+    pub compressor: Option<VortexCompressor>,
+}
+
+impl fmt::Display for VortexLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut settings = Vec::new();
+        if let Some(row_block_size) = self.row_block_size {
+            settings.push(format!("row-block-size:{}", row_block_size));
+        }
+        if let Some(compressor) = self.compressor {
+            settings.push(format!("compressor:{}", compressor.name()));
+        }
+        write!(f, "{}={}", self.label, settings.join(","))
+    }
+}
+
+/// Parse `label=key:value[,key:value...]`, as given to `--vortex-layout`.
+pub fn parse_vortex_layout(s: &str) -> Result<VortexLayout, String> {
+    let (label, settings) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected <label>=<key>:<value>[,...], got '{}'", s))?;
+    check_label(label)?;
+    let mut layout = VortexLayout {
+        label: label.to_string(),
+        ..Default::default()
+    };
+    for setting in settings.split(',') {
+        let (key, value) = setting
+            .split_once(':')
+            .ok_or_else(|| format!("expected <key>:<value>, got '{}'", setting))?;
+        match key {
+            "row-block-size" => match value.parse::<usize>() {
+                Ok(rows) if rows > 0 => layout.row_block_size = Some(rows),
+                _ => {
+                    return Err(format!(
+                        "row-block-size must be a positive integer, got '{}'",
+                        value
+                    ))
+                }
+            },
+            "compressor" => {
+                layout.compressor = Some(match value {
+                    "btrblocks" => VortexCompressor::Btrblocks,
+                    "compact" => VortexCompressor::Compact,
+                    _ => {
+                        return Err(format!(
+                            "compressor must be btrblocks or compact, got '{}'",
+                            value
+                        ))
+                    }
+                })
+            }
+            _ => {
+                return Err(format!(
+                    "unknown layout setting '{}', expected one of {}",
+                    key,
+                    KEYS.join(", ")
+                ))
+            }
+        }
+    }
+    Ok(layout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vortex_layout() {
+        let layout =
+            parse_vortex_layout("compact=compressor:compact,row-block-size:65536").unwrap();
+        assert_eq!(layout.compressor, Some(VortexCompressor::Compact));
+        assert_eq!(layout.row_block_size, Some(65536));
+        assert_eq!(
+            layout.to_string(),
+            "compact=row-block-size:65536,compressor:compact"
+        );
+        assert!(parse_vortex_layout("x=row-block-size:0").is_err());
+        assert!(parse_vortex_layout("x=compressor:zstd").is_err());
+        assert!(parse_vortex_layout("x=page-size:8192").is_err());
+    }
+}
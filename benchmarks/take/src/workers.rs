@@ -0,0 +1,256 @@
+//! Multi-process worker mode for hot timed phases.
+//!
+//! Worker runtimes normally run as threads of one process, sharing its
+//! allocator and engine runtime. At high concurrency that contention can
+//! dominate what is being measured, so with `--worker-processes` each runtime
+//! instead runs in a child process (the same binary, re-executed) with its own
+//! runtime and dataset handles.
+//!
+//! The parent and each child talk over the child's stdin/stdout:
+//!
+//! 1. The child opens its datasets, warms up and prints `ready`.
+//! 2. Once every child is ready, the parent writes `go` to each of them.
+//! 3. The child runs its share of the phase and prints a [`WorkerReport`] as a
+//!    single JSON line, then exits.
+//!
+//! Children inherit stderr, so query failures are still logged.
+
+use anyhow::Result;
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::data;
+use crate::engines::{create_registry, set_direct_io, set_in_memory, with_fallbacks, Engine};
+use crate::inject;
+use crate::results::PhaseResult;
+use crate::stats::{compute_quantiles, compute_statistics};
+use crate::{
+    all_latencies, build_injection, build_pinning, dataset_uris, duration_progress_bar,
+    make_queries, per_dataset_statistics, run_queries, Config, QueryLatency, QuerySource, Record,
+    Workload, DUPLICATES_COLLAPSED, DUPLICATES_KEPT, ERROR_COUNTER, ROW_COUNTER,
+};
+
+/// What a worker process runs, passed to it via the hidden `--worker-process` flag.
+#[derive(Serialize, Deserialize)]
+struct WorkerSpec {
+    engine: String,
+    /// Worker index, used for CPU pinning
+    worker: usize,
+    rows_per_query: usize,
+    concurrent_queries: usize,
+    /// Number of queries for this worker when running a fixed query count
+    num_queries: usize,
+    /// Base dataset URIs, which differ from `--dataset-uri` for the tmpfs copy
+    dataset_uri: Vec<String>,
+    /// Whether the take filter is applied after reading, for `--compare-filter`
+    #[serde(default)]
+    post_filter: bool,
+}
+
+/// Results of one worker process, sent back to the parent.
+#[derive(Serialize, Deserialize)]
+struct WorkerReport {
+    latencies: Vec<QueryLatency>,
+    /// Measured time in seconds, excluding ramp-up
+    elapsed: f64,
+    rows_taken: usize,
+    errors: usize,
+    duplicates_kept: usize,
+    duplicates_collapsed: usize,
+}
+
+/// Run one hot timed phase with each worker runtime in its own process.
+pub fn run_hot_phase(
+    engine: &Arc<dyn Engine>,
+    num_datasets: usize,
+    workload: Workload,
+    config: &Config,
+) -> Result<PhaseResult> {
+    let exe = std::env::current_exe()?;
+    let args = config
+        .worker_args
+        .clone()
+        .unwrap_or_else(|| std::env::args().skip(1).collect());
+
+    let mut children = Vec::with_capacity(config.num_runtimes);
+    for worker in 0..config.num_runtimes {
+        // Split the fixed query count as evenly as possible
+        let num_queries = config.num_queries / config.num_runtimes
+            + usize::from(worker < config.num_queries % config.num_runtimes);
+        let spec = WorkerSpec {
+            engine: engine.name().to_string(),
+            worker,
+            rows_per_query: workload.rows_per_query,
+            concurrent_queries: workload.concurrent_queries,
+            num_queries,
+            dataset_uri: config.dataset_uri.clone(),
+            post_filter: config.post_filter,
+        };
+        let child = Command::new(&exe)
+            .args(&args)
+            .arg("--worker-process")
+            .arg(serde_json::to_string(&spec)?)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| anyhow::anyhow!("Failed to start worker process {}: {}", worker, e))?;
+        children.push(child);
+    }
+
+    // Don't start the clock until every worker has opened its datasets
+    let mut readers = Vec::with_capacity(children.len());
+    for (worker, child) in children.iter_mut().enumerate() {
+        let mut reader = BufReader::new(child.stdout.take().unwrap());
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        if line.trim() != "ready" {
+            anyhow::bail!("Worker process {} exited before becoming ready", worker);
+        }
+        readers.push(reader);
+    }
+    for child in &mut children {
+        writeln!(child.stdin.as_mut().unwrap(), "go")?;
+    }
+
+    let pb = duration_progress_bar("Timed queries (hot, worker processes)");
+    let mut reports = Vec::with_capacity(children.len());
+    for (worker, (child, mut reader)) in children.iter_mut().zip(readers).enumerate() {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let status = child.wait()?;
+        if !status.success() {
+            anyhow::bail!("Worker process {} failed: {}", worker, status);
+        }
+        let report: WorkerReport = serde_json::from_str(&line)
+            .map_err(|e| anyhow::anyhow!("Bad report from worker process {}: {}", worker, e))?;
+        pb.inc(report.latencies.len() as u64);
+        reports.push(report);
+    }
+    pb.finish();
+
+    let latencies: Vec<QueryLatency> = reports
+        .iter()
+        .flat_map(|r| r.latencies.iter().copied())
+        .collect();
+    // Workers start together, so the slowest one bounds the phase
+    let elapsed = reports.iter().map(|r| r.elapsed).fold(0.0, f64::max);
+
+    if latencies.is_empty() && config.ramp_up.is_some() {
+        println!("Warning: the phase finished during --ramp-up, so no queries were recorded");
+    }
+
+    let all = all_latencies(&latencies);
+    Ok(PhaseResult {
+        cache: config.cache_mode.warm_label().to_string(),
+        rows_per_query: workload.rows_per_query,
+        concurrency: config.num_runtimes * workload.concurrent_queries,
+        stats: compute_statistics(&all),
+        quantiles: compute_quantiles(&all),
+        per_dataset: per_dataset_statistics(&latencies, num_datasets),
+        throughput: latencies.len() as f64 / elapsed,
+        rows_taken: reports.iter().map(|r| r.rows_taken).sum(),
+        queries: latencies.len(),
+        errors: reports.iter().map(|r| r.errors).sum(),
+        duplicates_kept: reports.iter().map(|r| r.duplicates_kept).sum(),
+        duplicates_collapsed: reports.iter().map(|r| r.duplicates_collapsed).sum(),
+        block_io: None,
+        lance_trace: Vec::new(),
+        thread_states: None,
+        skipping: None,
+    })
+}
+
+/// Entry point of a worker process started by [`run_hot_phase`].
+pub fn run_worker(spec: &str, config: &Config) -> Result<()> {
+    let spec: WorkerSpec = serde_json::from_str(spec)?;
+    let engine = create_registry(config.runtime, &config.engine_runtimes)
+        .get(&spec.engine)
+        .ok_or_else(|| anyhow::anyhow!("Unknown engine '{}'", spec.engine))?;
+
+    // This process is a single worker runtime; threads spawned from here on
+    // inherit the worker's CPU affinity
+    let mut config = config.clone();
+    if let Some(pinning) = build_pinning(&config)? {
+        pinning.apply(spec.worker)?;
+    }
+    inject::set(build_injection(&config));
+    set_direct_io(config.direct_io);
+    set_in_memory(config.in_memory);
+    config.num_runtimes = 1;
+    config.dataset_uri = spec.dataset_uri;
+    config.post_filter = spec.post_filter;
+
+    // The parent has already created the datasets
+    let datasets = dataset_uris(engine.as_ref(), &config)
+        .iter()
+        .map(|uri| Ok(with_fallbacks(engine.as_ref(), engine.open(uri)?, &config)))
+        .collect::<Result<Vec<_>>>()?;
+    // Workers draw distinct queries, each reproducible from --seed
+    let stream = [
+        spec.rows_per_query as u64,
+        spec.concurrent_queries as u64,
+        spec.worker as u64,
+    ];
+    let seed = data::query_seed(config.common.seed, &stream);
+    let queries = make_queries(&config, spec.rows_per_query, spec.num_queries, seed);
+    let pb = ProgressBar::hidden();
+
+    if !config.skip_warmup {
+        run_queries(
+            datasets.clone(),
+            QuerySource::Fixed(queries.clone()),
+            Record::Nothing,
+            spec.concurrent_queries,
+            &config,
+            engine.runtime(),
+            &pb,
+        )?;
+    }
+
+    let mut stdout = std::io::stdout();
+    writeln!(stdout, "ready")?;
+    stdout.flush()?;
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+
+    let start = Instant::now();
+    let ramp_up = config.ramp_up.unwrap_or(Duration::ZERO);
+    let record = match config.ramp_up {
+        Some(ramp_up) => Record::IssuedFrom(start + ramp_up),
+        None => Record::All,
+    };
+    let latencies = run_queries(
+        datasets,
+        match config.duration {
+            Some(duration) => QuerySource::Until {
+                deadline: start + ramp_up + duration,
+                rows_per_query: spec.rows_per_query,
+                seed,
+            },
+            None => QuerySource::Fixed(queries),
+        },
+        record,
+        spec.concurrent_queries,
+        &config,
+        engine.runtime(),
+        &pb,
+    )?;
+    let elapsed = start.elapsed().saturating_sub(ramp_up);
+
+    let report = WorkerReport {
+        latencies,
+        elapsed: elapsed.as_secs_f64(),
+        rows_taken: ROW_COUNTER.load(Ordering::Relaxed),
+        errors: ERROR_COUNTER.load(Ordering::Relaxed),
+        duplicates_kept: DUPLICATES_KEPT.load(Ordering::Relaxed),
+        duplicates_collapsed: DUPLICATES_COLLAPSED.load(Ordering::Relaxed),
+    };
+    writeln!(stdout, "{}", serde_json::to_string(&report)?)?;
+    stdout.flush()?;
+    Ok(())
+}
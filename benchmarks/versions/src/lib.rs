@@ -0,0 +1,329 @@
+//! Versions Benchmark
+//!
+//! Measures how the cost of opening a Lance dataset grows with the number of
+//! versions it has. A dataset is committed to many times with small writes
+//! (up to tens of thousands of commits), and at each of a list of version
+//! counts the benchmark times:
+//! - opening the latest version with `Dataset::open`
+//! - checking out the first version from an open dataset
+//! - the commits made since the previous version count
+//!
+//! Each commit either appends, so the manifest grows by a fragment per
+//! commit, or overwrites, so only the number of versions grows. Datasets are
+//! written with the original (`v1`) manifest naming, where finding the
+//! latest version lists every manifest, and with the `v2` naming that
+//! avoids it.
+//!
+//! Runs as `lance-bench versions`.
+
+use anyhow::Result;
+use arrow::array::RecordBatchIterator;
+use bench_core::cache::drop_directory_cache;
+use bench_core::cli::CommonArgs;
+use bench_core::data::{create_schema, generate_vector_batch};
+use bench_core::stats::compute_statistics;
+use clap::{Parser, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use lance::dataset::{Dataset, InsertBuilder, WriteMode, WriteParams};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::runtime::Runtime;
+
+pub mod results;
+
+use results::{CheckpointResult, SeriesResult};
+
+/// How each commit after the first changes the dataset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CommitKind {
+    /// Append a fragment, so the manifest grows with every commit
+    Append,
+    /// Overwrite the data, so the manifest stays the same size
+    Overwrite,
+}
+
+impl CommitKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommitKind::Append => "append",
+            CommitKind::Overwrite => "overwrite",
+        }
+    }
+
+    fn write_mode(&self) -> WriteMode {
+        match self {
+            CommitKind::Append => WriteMode::Append,
+            CommitKind::Overwrite => WriteMode::Overwrite,
+        }
+    }
+}
+
+/// How the dataset's manifest files are named.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ManifestPaths {
+    /// `_versions/{version}.manifest`; the latest version is found by listing
+    V1,
+    /// Names that sort newest first, so the latest version is found without
+    /// listing every manifest
+    V2,
+}
+
+impl ManifestPaths {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ManifestPaths::V1 => "v1",
+            ManifestPaths::V2 => "v2",
+        }
+    }
+}
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "versions-benchmark")]
+#[command(about = "Benchmark how Lance dataset open cost grows with the number of versions")]
+pub struct Config {
+    /// Directory the datasets are written to, rewritten on every run
+    #[arg(long, default_value = "/tmp/versions-bench")]
+    pub data_dir: PathBuf,
+
+    /// Version counts to measure at (comma-separated); the dataset is
+    /// committed to until it has the largest
+    #[arg(long, value_delimiter = ',', default_value = "1,10,100,1000,10000")]
+    pub versions: Vec<u64>,
+
+    /// What every commit after the first does
+    #[arg(long, value_enum, default_value = "append")]
+    pub commit: CommitKind,
+
+    /// Manifest namings to benchmark (comma-separated), one dataset each
+    #[arg(long, value_enum, value_delimiter = ',', default_value = "v1,v2")]
+    pub manifest_paths: Vec<ManifestPaths>,
+
+    /// Rows written by each commit
+    #[arg(long, default_value_t = 100)]
+    pub rows_per_commit: usize,
+
+    /// Vector dimension
+    #[arg(long, default_value_t = 8)]
+    pub vector_dim: usize,
+
+    /// Timed opens and checkouts at each version count
+    #[arg(long, default_value_t = 10)]
+    pub iterations: usize,
+
+    /// Drop the dataset from the page cache before every timed open and checkout
+    #[arg(long)]
+    pub cold: bool,
+
+    #[command(flatten)]
+    pub common: CommonArgs,
+}
+
+impl Config {
+    fn validate(&mut self) -> Result<()> {
+        if self.common.resume {
+            anyhow::bail!("--resume is not supported by the versions benchmark");
+        }
+        if self.versions.is_empty() || self.versions.contains(&0) {
+            anyhow::bail!("--versions must list positive version counts");
+        }
+        if self.manifest_paths.is_empty() {
+            anyhow::bail!("--manifest-paths must name at least one naming");
+        }
+        if self.rows_per_commit == 0 || self.vector_dim == 0 || self.iterations == 0 {
+            anyhow::bail!("--rows-per-commit, --vector-dim and --iterations must be positive");
+        }
+        self.versions.sort_unstable();
+        self.versions.dedup();
+        Ok(())
+    }
+}
+
+fn print_plan(config: &Config) {
+    println!("{}", "=".repeat(60));
+    println!("Versions Benchmark");
+    println!("{}", "=".repeat(60));
+    println!("Data directory: {}", config.data_dir.display());
+    println!(
+        "Version counts: {}",
+        config
+            .versions
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!(
+        "Commits: {} of {} rows each (vector dim {})",
+        config.commit.name(),
+        config.rows_per_commit,
+        config.vector_dim
+    );
+    println!(
+        "Manifest paths: {}",
+        config
+            .manifest_paths
+            .iter()
+            .map(|m| m.name())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    println!(
+        "Timed: {} opens and checkouts per version count",
+        config.iterations
+    );
+    println!("Cache: {}", if config.cold { "cold" } else { "hot" });
+    println!("{}", "=".repeat(60));
+}
+
+/// Total size of the manifests (and transaction files) under `path`.
+fn manifests_size(path: &Path) -> u64 {
+    walkdir::WalkDir::new(path.join("_versions"))
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Commit the rows of commit `index` to `dataset` (or create it at `uri`).
+async fn commit(
+    config: &Config,
+    manifest_paths: ManifestPaths,
+    uri: &str,
+    dataset: Option<Arc<Dataset>>,
+    index: usize,
+) -> Result<Dataset> {
+    let schema = create_schema(config.vector_dim);
+    let batch = generate_vector_batch(
+        schema.clone(),
+        index * config.rows_per_commit,
+        config.rows_per_commit,
+        config.vector_dim,
+        config.common.seed,
+    )?;
+    Ok(match dataset {
+        None => {
+            let params = WriteParams {
+                mode: WriteMode::Create,
+                enable_v2_manifest_paths: manifest_paths == ManifestPaths::V2,
+                ..Default::default()
+            };
+            let reader = RecordBatchIterator::new(vec![Ok(batch)], schema);
+            Dataset::write(reader, uri, Some(params)).await?
+        }
+        Some(dataset) => {
+            let params = WriteParams {
+                mode: config.commit.write_mode(),
+                ..Default::default()
+            };
+            InsertBuilder::new(dataset)
+                .with_params(&params)
+                .execute(vec![batch])
+                .await?
+        }
+    })
+}
+
+/// Time opening the latest version and checking out the first one.
+async fn time_opens(config: &Config, path: &Path, uri: &str) -> Result<(Vec<f64>, Vec<f64>)> {
+    let mut opens = Vec::with_capacity(config.iterations);
+    let mut checkouts = Vec::with_capacity(config.iterations);
+    for _ in 0..config.iterations {
+        if config.cold {
+            drop_directory_cache(path)?;
+        }
+        let start = Instant::now();
+        let dataset = Dataset::open(uri).await?;
+        opens.push(start.elapsed().as_secs_f64());
+
+        if config.cold {
+            drop_directory_cache(path)?;
+        }
+        let start = Instant::now();
+        dataset.checkout_version(1).await?;
+        checkouts.push(start.elapsed().as_secs_f64());
+    }
+    Ok((opens, checkouts))
+}
+
+/// Commit to a new dataset with `manifest_paths` until it has every version
+/// count in the config, measuring at each.
+async fn bench_series(config: &Config, manifest_paths: ManifestPaths) -> Result<SeriesResult> {
+    println!("\n{} manifest paths", manifest_paths.name());
+    let path = config.data_dir.join(format!(
+        "{}-{}.lance",
+        config.commit.name(),
+        manifest_paths.name()
+    ));
+    if path.exists() {
+        std::fs::remove_dir_all(&path)?;
+    }
+    let uri = path.to_string_lossy().to_string();
+
+    let last = *config.versions.last().unwrap();
+    let pb = ProgressBar::new(last);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("  [{bar:40}] {pos}/{len} commits ({eta} remaining)")
+            .unwrap(),
+    );
+    let mut dataset: Option<Arc<Dataset>> = None;
+    let mut checkpoints = Vec::with_capacity(config.versions.len());
+    let mut commits = Vec::new();
+    for &versions in &config.versions {
+        while dataset.as_ref().map_or(0, |d| d.version().version) < versions {
+            let index = dataset.as_ref().map_or(0, |d| d.version().version) as usize;
+            let start = Instant::now();
+            let committed = commit(config, manifest_paths, &uri, dataset.take(), index).await?;
+            commits.push(start.elapsed().as_secs_f64());
+            dataset = Some(Arc::new(committed));
+            pb.inc(1);
+        }
+
+        let (opens, checkouts) = time_opens(config, &path, &uri).await?;
+        let fragments = dataset.as_ref().unwrap().get_fragments().len();
+        checkpoints.push(CheckpointResult {
+            versions,
+            fragments,
+            manifests_bytes: manifests_size(&path),
+            commit: compute_statistics(&commits),
+            open: compute_statistics(&opens),
+            checkout: compute_statistics(&checkouts),
+        });
+        commits.clear();
+    }
+    pb.finish_and_clear();
+
+    Ok(SeriesResult {
+        manifest_paths: manifest_paths.name().to_string(),
+        commit: config.commit.name().to_string(),
+        checkpoints,
+    })
+}
+
+/// Run the benchmark described by `config`, returning the results of every
+/// manifest naming.
+pub fn run(mut config: Config) -> Result<Vec<SeriesResult>> {
+    config.validate()?;
+    print_plan(&config);
+    if config.common.dry_run {
+        println!("\nDry run: nothing written or opened");
+        return Ok(Vec::new());
+    }
+
+    std::fs::create_dir_all(&config.data_dir)?;
+    let runtime = Runtime::new()?;
+    let mut results = Vec::new();
+    for &manifest_paths in &config.manifest_paths {
+        results.push(runtime.block_on(bench_series(&config, manifest_paths))?);
+    }
+    results::print_comparison(&results);
+
+    if let Some(output) = &config.common.output {
+        results::write_results(output, config.rows_per_commit, config.cold, &results)?;
+        println!("\nResults written to {}", output.display());
+    }
+    Ok(results)
+}
@@ -0,0 +1,99 @@
+//! Results of the versions benchmark, one series per manifest naming.
+
+use anyhow::Result;
+use bench_core::results::write_results as write_results_file;
+use bench_core::stats::Statistics;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Measurements once the dataset has `versions` versions.
+#[derive(Serialize, Deserialize)]
+pub struct CheckpointResult {
+    pub versions: u64,
+    /// Fragments in the latest version
+    pub fragments: usize,
+    /// Size of everything under `_versions`
+    pub manifests_bytes: u64,
+    /// Latency of the commits made since the previous version count, or
+    /// since the dataset was created (seconds)
+    pub commit: Statistics,
+    /// Latency of `Dataset::open` of the latest version (seconds)
+    pub open: Statistics,
+    /// Latency of checking out the first version from an open dataset (seconds)
+    pub checkout: Statistics,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SeriesResult {
+    pub manifest_paths: String,
+    /// What every commit after the first did
+    pub commit: String,
+    pub checkpoints: Vec<CheckpointResult>,
+}
+
+/// Fields of the results file written with `--output`, next to the results.
+#[derive(Serialize)]
+struct Metadata {
+    rows_per_commit: usize,
+    cold: bool,
+}
+
+/// Write all series to `path` as JSON.
+pub fn write_results(
+    path: &Path,
+    rows_per_commit: usize,
+    cold: bool,
+    results: &[SeriesResult],
+) -> Result<()> {
+    write_results_file(
+        path,
+        "versions",
+        Metadata {
+            rows_per_commit,
+            cold,
+        },
+        results,
+    )
+}
+
+/// Print one table per series, a row per version count.
+///
+/// "Growth" is the p50 open latency over that at the first version count.
+pub fn print_comparison(results: &[SeriesResult]) {
+    for series in results {
+        println!("\n{}", "=".repeat(60));
+        println!(
+            "{} commits, {} manifest paths",
+            series.commit, series.manifest_paths
+        );
+        println!("{}", "=".repeat(60));
+        println!(
+            "{:>9} {:>10} {:>14} {:>15} {:>13} {:>13} {:>17} {:>8}",
+            "Versions",
+            "Fragments",
+            "Manifests(MB)",
+            "Commit p50(ms)",
+            "Open p50(ms)",
+            "Open p99(ms)",
+            "Checkout p50(ms)",
+            "Growth"
+        );
+        println!("{}", "-".repeat(106));
+        let Some(first) = series.checkpoints.first() else {
+            continue;
+        };
+        for checkpoint in &series.checkpoints {
+            println!(
+                "{:>9} {:>10} {:>14.3} {:>15.3} {:>13.3} {:>13.3} {:>17.3} {:>7.2}x",
+                checkpoint.versions,
+                checkpoint.fragments,
+                checkpoint.manifests_bytes as f64 / 1_000_000.0,
+                checkpoint.commit.p50 * 1000.0,
+                checkpoint.open.p50 * 1000.0,
+                checkpoint.open.p99 * 1000.0,
+                checkpoint.checkout.p50 * 1000.0,
+                checkpoint.open.p50 / first.open.p50
+            );
+        }
+    }
+}
@@ -25,6 +25,37 @@ class Throughput:
     unit_system: UnitSystem
 
 
+@dataclass(frozen=True)
+class Metric:
+    """A named quantity a benchmark reports.
+
+    A benchmark can publish several metrics, one result per metric, all under
+    the same benchmark name. The dashboard shows one chart per benchmark with a
+    dropdown of its metrics instead of one chart per metric.
+    """
+
+    name: str
+    display_name: str
+    units: str
+    higher_is_better: bool = False
+
+
+LATENCY = Metric("latency", "Latency", "ns")
+LATENCY_P50 = Metric("latency_p50", "Latency (p50)", "ns")
+LATENCY_P99 = Metric("latency_p99", "Latency (p99)", "ns")
+THROUGHPUT = Metric("throughput", "Throughput", "queries/s", higher_is_better=True)
+PEAK_MEMORY = Metric("peak_memory", "Peak memory", "bytes")
+READ_IOPS = Metric("read_iops", "Read IOPS", "ops/s")
+BYTES_READ = Metric("bytes_read", "Bytes read", "bytes")
+BYTES_WRITTEN = Metric("bytes_written", "Bytes written", "bytes")
+RECALL = Metric("recall", "Recall", "recall", higher_is_better=True)
+
+METRICS = {
+    m.name: m
+    for m in [LATENCY, LATENCY_P50, LATENCY_P99, THROUGHPUT, PEAK_MEMORY, READ_IOPS, BYTES_READ, BYTES_WRITTEN, RECALL]
+}
+
+
 @dataclass
 class SummaryValues:
     """Statistical summary of benchmark values."""
@@ -72,6 +103,29 @@ class Result:
     throughput: Optional[Throughput]
     metadata: str
     timestamp: int
+    metric: Optional[str] = None
+
+    @staticmethod
+    def metric_of(row: dict) -> Metric:
+        """The metric of a stored result row.
+
+        Rows published before results named their metric were latencies, or
+        recalls for the vector search benchmarks, which their units tell apart.
+        """
+        name = row.get("metric")
+        if name is None:
+            return RECALL if row["units"] == "recall" else LATENCY
+        if name not in METRICS:
+            return Metric(name, name, row["units"])
+        return METRICS[name]
+
+    @staticmethod
+    def series_name(row: dict) -> str:
+        """Name of the history a stored result row belongs to: its benchmark, plus the metric unless it is latency."""
+        metric = Result.metric_of(row)
+        if metric == LATENCY:
+            return row["benchmark_name"]
+        return f"{row['benchmark_name']} ({metric.name})"
 
     @staticmethod
     def to_arrow_table(results: Iterable["Result"]) -> pa.Table:
@@ -136,6 +190,7 @@ class Result:
                 ("throughput", throughput_type),
                 ("metadata", pa.string()),
                 ("timestamp", pa.int64()),
+                ("metric", pa.string()),
             ]
         )
 
@@ -190,6 +245,7 @@ class Result:
             ],
             "metadata": [r.metadata for r in results_list],
             "timestamp": [r.timestamp for r in results_list],
+            "metric": [r.metric for r in results_list],
         }
 
         return pa.table(data, schema=schema)
@@ -199,6 +255,7 @@ class Result:
         """Open or create the results table in the database.
 
         If the table doesn't exist, it will be created with the appropriate schema.
+        Tables created before results named their metric get an empty `metric` column.
 
         Args:
             db: LanceDB database connection
@@ -207,8 +264,12 @@ class Result:
             lancedb.table.Table: The results table
         """
         try:
-            return db.open_table("results")
+            table = db.open_table("results")
         except Exception:
             # Table doesn't exist, create it with the schema
             empty_table = Result.to_arrow_table([])
             return db.create_table("results", empty_table)
+
+        if "metric" not in table.schema.names:
+            table.add_columns({"metric": "CAST(NULL AS STRING)"})
+        return table
@@ -23,7 +23,7 @@ def fetch_pr_results(pr_sha: str, local_results_path: Path) -> dict[str, dict]:
         local_results_path: Path to local results table
 
     Returns:
-        Dictionary mapping benchmark_name to result dict
+        Dictionary mapping each series name (see Result.series_name) to result dict
     """
     print(f"Reading PR results from local table: {local_results_path}")
     pr_results_df = lance.dataset(local_results_path).to_table().to_pandas()
@@ -36,22 +36,20 @@ def fetch_pr_results(pr_sha: str, local_results_path: Path) -> dict[str, dict]:
     # Convert to dict format
     pr_results_dict = pr_results_df.to_dict("records")
 
-    # Group by benchmark_name (should be unique for a single commit)
+    # Group by benchmark and metric (should be unique for a single commit)
     pr_by_benchmark = {}
     for result in pr_results_dict:
-        benchmark_name = result["benchmark_name"]
-        pr_by_benchmark[benchmark_name] = result
+        pr_by_benchmark[Result.series_name(result)] = result
 
     print(f"Found {len(pr_by_benchmark)} PR benchmark results")
     return pr_by_benchmark
 
 
-def fetch_historical_baseline(benchmark_name: str, limit: int = 20) -> list[dict]:
-    """Fetch N most recent historical results for a benchmark.
+def fetch_historical_baseline(pr_result: dict, limit: int = 20) -> list[dict]:
+    """Fetch N most recent historical results for the benchmark and metric of a PR result.
 
     Args:
-        benchmark_name: Name of the benchmark
-        exclude_sha: Commit SHA to exclude (the PR commit)
+        pr_result: PR result dict
         limit: Maximum number of historical results to fetch
 
     Returns:
@@ -60,8 +58,12 @@ def fetch_historical_baseline(benchmark_name: str, limit: int = 20) -> list[dict
     db = connect()
     results_table = Result.open_table(db)
 
-    # Get all results for this benchmark
+    # Get all results for this benchmark, then keep the PR result's metric
+    benchmark_name = pr_result["benchmark_name"]
     all_results_df = results_table.search().where(f"benchmark_name = '{benchmark_name}'").to_pandas()
+    if not all_results_df.empty:
+        series = Result.series_name(pr_result)
+        all_results_df = all_results_df[all_results_df.apply(lambda r: Result.series_name(r.to_dict()) == series, axis=1)]
 
     if all_results_df.empty:
         return []
@@ -101,9 +103,9 @@ def calculate_z_score(pr_value: float, historical_values: list[float]) -> float
 def determine_status(z_score: float | None, threshold: float = 2.0) -> tuple[str, str]:
     """Determine status based on z-score.
 
-    For benchmarks, lower values are better (faster execution time).
-    - Negative z-score: PR value < baseline mean → improvement (faster)
-    - Positive z-score: PR value > baseline mean → regression (slower)
+    The z-score is signed so that positive is worse (see main).
+    - Negative z-score: PR value better than baseline mean → improvement
+    - Positive z-score: PR value worse than baseline mean → regression
 
     Args:
         z_score: Calculated z-score (None if not calculable)
@@ -306,7 +308,7 @@ def main() -> None:
         print(f"Comparing {benchmark_name}...")
 
         # Fetch historical baseline
-        historical_results = fetch_historical_baseline(benchmark_name, args.baseline_limit)
+        historical_results = fetch_historical_baseline(pr_result, args.baseline_limit)
 
         # Extract values
         pr_value = pr_result["summary"]["mean"]
@@ -333,8 +335,10 @@ def main() -> None:
         baseline_mean = np.mean(historical_means)
         baseline_std = np.std(historical_means, ddof=1)
 
-        # Calculate z-score
+        # Calculate z-score, positive when the PR is worse
         z_score = calculate_z_score(pr_value, historical_means)
+        if z_score is not None and Result.metric_of(pr_result).higher_is_better:
+            z_score = -z_score
 
         if z_score is not None:
             print(f"  Z-score: {z_score:+.2f}")
@@ -365,7 +369,7 @@ def main() -> None:
         print("=" * 80)
 
     # Exit with non-zero if there are regressions
-    # Lower values are better, so positive z-score = regression
+    # Z-scores are signed so that positive = regression
     regressions = [c for c in comparisons if c["z_score"] is not None and c["z_score"] > args.threshold]
     if regressions:
         print(f"\n⚠️ Found {len(regressions)} potential regression(s)", file=sys.stderr)
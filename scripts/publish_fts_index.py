@@ -12,10 +12,10 @@ from pathlib import Path
 sys.path.insert(0, str(Path(__file__).parent.parent / "packages"))
 
 from lance_bench_db.dataset import connect, get_database_uri
-from lance_bench_db.models import DutBuild, Result, SummaryValues
+from lance_bench_db.models import LATENCY, PEAK_MEMORY, DutBuild, Result, SummaryValues
 
 # Import shared utilities
-from publish_util import get_test_bed
+from publish_util import get_test_bed, metric_result
 
 
 def parse_fts_index_output(
@@ -35,7 +35,7 @@ def parse_fts_index_output(
         dut_timestamp: Build timestamp of the device under test
 
     Returns:
-        List of Result instances
+        List of Result instances, a latency and a peak memory result per benchmark
     """
     # Create TestBed from current system
     test_bed = get_test_bed(testbed_name)
@@ -91,8 +91,12 @@ def parse_fts_index_output(
             throughput=None,
             metadata=json.dumps(metadata),
             timestamp=int(datetime.now().timestamp()),
+            metric=LATENCY.name,
         )
         results.append(result)
+        results.append(
+            metric_result(dut, test_bed, entry["benchmark_name"], PEAK_MEMORY, [entry["peak_rss_bytes"]], metadata)
+        )
 
     return results
 
@@ -0,0 +1,176 @@
+#!/usr/bin/env python3
+"""Publish take benchmark results (lance-bench take --output) to Lance dataset."""
+
+import argparse
+import json
+import sys
+from pathlib import Path
+
+# Add packages to path for imports
+sys.path.insert(0, str(Path(__file__).parent.parent / "packages"))
+
+from lance_bench_db.dataset import connect, get_database_uri
+from lance_bench_db.models import BYTES_READ, LATENCY_P50, LATENCY_P99, READ_IOPS, THROUGHPUT, DutBuild, Metric, Result
+
+# Import shared utilities
+from publish_util import get_test_bed, metric_result
+
+
+def parse_take_output(
+    output_path: Path,
+    testbed_name: str | None = None,
+    dut_name: str = "lance",
+    dut_version: str | None = None,
+    dut_timestamp: int | None = None,
+) -> list[Result]:
+    """Parse take benchmark JSON output and convert to Result instances.
+
+    Each timed phase is one benchmark, named after its engine, query size,
+    concurrency and cache state, with a result per metric: p50 and p99
+    latency, throughput and, when the datasets were on a block device, read
+    IOPS and bytes read.
+
+    Args:
+        output_path: Path to the take benchmark JSON output file
+        testbed_name: Optional testbed name. If not provided, uses hostname
+        dut_name: Name of the device under test
+        dut_version: Version of the device under test
+        dut_timestamp: Build timestamp of the device under test
+
+    Returns:
+        List of Result instances
+    """
+    # Create TestBed from current system
+    test_bed = get_test_bed(testbed_name)
+
+    # Validate DUT info
+    if not dut_version:
+        raise ValueError("DUT version is required. Provide --dut-version argument.")
+    if not dut_timestamp:
+        raise ValueError("DUT timestamp is required. Provide --dut-timestamp argument.")
+
+    # Create DutBuild
+    dut = DutBuild(name=dut_name, version=dut_version, timestamp=dut_timestamp)
+
+    # Parse JSON output
+    with open(output_path) as f:
+        data = json.load(f)
+    if data.get("benchmark_type") != "take":
+        raise ValueError(f"{output_path} does not hold take results")
+
+    results = []
+    for engine in data["results"]:
+        if engine.get("error"):
+            print(f"⚠️ Skipping {engine['engine']}: {engine['error']}")
+            continue
+        for phase in engine["phases"]:
+            benchmark_name = (
+                f"take_{engine['engine']}_{phase['rows_per_query']}rows_x{phase['concurrency']}_{phase['cache']}"
+            )
+            metadata = {
+                "engine": engine["engine"],
+                "versions": engine.get("versions", {}),
+                "rows_per_query": phase["rows_per_query"],
+                "concurrency": phase["concurrency"],
+                "cache": phase["cache"],
+                "queries": phase["queries"],
+                "errors": phase["errors"],
+            }
+            stats = phase["stats"]
+
+            def add(metric: Metric, value: float) -> None:
+                results.append(metric_result(dut, test_bed, benchmark_name, metric, [value], metadata))
+
+            # Latencies are recorded in seconds
+            add(LATENCY_P50, stats["p50"] * 1_000_000_000)
+            add(LATENCY_P99, stats["p99"] * 1_000_000_000)
+            add(THROUGHPUT, phase["throughput"])
+
+            block_io = phase.get("block_io")
+            if block_io and phase["throughput"] > 0:
+                phase_seconds = phase["queries"] / phase["throughput"]
+                add(READ_IOPS, block_io["reads"] / phase_seconds)
+                add(BYTES_READ, block_io["read_bytes"])
+
+    return results
+
+
+def upload_results(results: list[Result]) -> None:
+    """Upload results to the LanceDB table.
+
+    Args:
+        results: List of Result instances to upload
+    """
+    # Convert results to Arrow table using the Result class method
+    table = Result.to_arrow_table(results)
+
+    # Connect to database and open results table
+    db = connect()
+    results_table = Result.open_table(db)
+
+    # Add data to table
+    results_table.add(table)
+
+    print(f"✓ Uploaded {len(results)} benchmark results to <{get_database_uri()}>")
+
+
+def main() -> None:
+    parser = argparse.ArgumentParser(description="Publish take benchmark results to Lance dataset")
+    parser.add_argument(
+        "output_path",
+        type=Path,
+        help="Path to take benchmark JSON output file (lance-bench take --output)",
+    )
+    parser.add_argument(
+        "--testbed-name",
+        type=str,
+        default=None,
+        help="Name of the testbed (defaults to hostname)",
+    )
+    parser.add_argument(
+        "--dut-name",
+        type=str,
+        default="lance",
+        help="Name of the device under test (defaults to 'lance')",
+    )
+    parser.add_argument(
+        "--dut-version",
+        type=str,
+        required=True,
+        help="Version of the device under test (required)",
+    )
+    parser.add_argument(
+        "--dut-timestamp",
+        type=int,
+        required=True,
+        help="Build timestamp of the device under test (required, Unix timestamp)",
+    )
+
+    args = parser.parse_args()
+
+    if not args.output_path.exists():
+        print(f"❌ File not found: {args.output_path}", file=sys.stderr)
+        sys.exit(1)
+
+    # Parse results
+    print(f"ℹ️ Parsing take benchmark output from {args.output_path}...")
+    results = parse_take_output(
+        args.output_path,
+        args.testbed_name,
+        args.dut_name,
+        args.dut_version,
+        args.dut_timestamp,
+    )
+    print(f"ℹ️ Found {len(results)} benchmark results")
+
+    if not results:
+        print("❌ No benchmark results found in the input file", file=sys.stderr)
+        sys.exit(1)
+
+    # Upload results
+    print(f"ℹ️ Uploading results to LanceDB at <{get_database_uri()}>...")
+    upload_results(results)
+
+
+if __name__ == "__main__":
+    main()
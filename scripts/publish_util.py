@@ -1,14 +1,17 @@
 """Common utilities for publishing benchmark results."""
 
+import json
+import math
 import platform
 import sys
+import uuid
 from datetime import datetime
 from pathlib import Path
 
 # Add packages to path for imports
 sys.path.insert(0, str(Path(__file__).parent.parent / "packages"))
 
-from lance_bench_db.models import TestBed
+from lance_bench_db.models import DutBuild, Metric, Result, SummaryValues, TestBed
 
 
 def get_test_bed(name: str | None = None) -> TestBed:
@@ -29,3 +32,67 @@ def get_test_bed(name: str | None = None) -> TestBed:
         os=f"{platform.system()} {platform.release()}",
         created_at=int(datetime.now().timestamp()),
     )
+
+
+def summarize(values: list[float]) -> SummaryValues:
+    """Compute the summary statistics of a benchmark's measured values.
+
+    Args:
+        values: Measured values (at least one)
+
+    Returns:
+        SummaryValues of the values
+    """
+    if not values:
+        raise ValueError("Cannot summarize an empty list of values")
+    sorted_values = sorted(values)
+    n = len(sorted_values)
+    mean = sum(values) / n
+    return SummaryValues(
+        min=float(sorted_values[0]),
+        q1=float(sorted_values[n // 4]),
+        median=float(sorted_values[n // 2]),
+        q3=float(sorted_values[3 * n // 4]),
+        max=float(sorted_values[-1]),
+        mean=float(mean),
+        standard_deviation=float(math.sqrt(sum((x - mean) ** 2 for x in values) / n)),
+    )
+
+
+def metric_result(
+    dut: DutBuild,
+    test_bed: TestBed,
+    benchmark_name: str,
+    metric: Metric,
+    values: list[float],
+    metadata: dict,
+) -> Result:
+    """Create the result of one metric of a benchmark.
+
+    Every metric of a benchmark shares its benchmark name, so the dashboard
+    groups them into one chart with a metric dropdown.
+
+    Args:
+        dut: Device under test
+        test_bed: Test bed the benchmark ran on
+        benchmark_name: Name of the benchmark
+        metric: Metric measured, which sets the units
+        values: Measured values, in the metric's units
+        metadata: Benchmark details, stored as JSON
+
+    Returns:
+        Result for the metric
+    """
+    return Result(
+        id=str(uuid.uuid4()),
+        dut=dut,
+        test_bed=test_bed,
+        benchmark_name=benchmark_name,
+        values=[float(v) for v in values],
+        summary=summarize(values),
+        units=metric.units,
+        throughput=None,
+        metadata=json.dumps(metadata),
+        timestamp=int(datetime.now().timestamp()),
+        metric=metric.name,
+    )
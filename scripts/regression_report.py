@@ -42,19 +42,18 @@ def fetch_all_results() -> list[dict]:
 
 
 def group_and_sort_results(results: list[dict]) -> dict[str, list[dict]]:
-    """Group results by benchmark name and sort by timestamp.
+    """Group results by benchmark name and metric, and sort by timestamp.
 
     Args:
         results: List of result dictionaries
 
     Returns:
-        Dictionary mapping benchmark_name to sorted list of results
+        Dictionary mapping each series name (see Result.series_name) to sorted list of results
     """
     grouped = defaultdict(list)
 
     for result in results:
-        benchmark_name = result["benchmark_name"]
-        grouped[benchmark_name].append(result)
+        grouped[Result.series_name(result)].append(result)
 
     # Sort each group by dut.timestamp
     for benchmark_name in grouped: